@@ -119,11 +119,8 @@ fn main() {
     let mut person = ActivityFamily::automaton_with_mode(Box::new(Working { hours_worked: 0 }));
     
     for _age in 18..100 {
-        // Update the current Mode for the Automaton.
-        // NOTE: Using Deref coercion, we can call Activity::update() on the inner Mode through the Automaton itself.
-        person.update();
-
-        // Allow the Automaton to switch Modes.
-        Automaton::next(&mut person);
+        // Update the current Mode for the Automaton, and then allow the Automaton to switch Modes.
+        // NOTE: Using step_with() guarantees that update() is always followed by a call to Automaton::next().
+        Automaton::step_with(&mut person, |activity| activity.update());
     }
 }
\ No newline at end of file
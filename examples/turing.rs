@@ -26,7 +26,7 @@ enum PrintOp { Clear, Print }
 #[derive(Copy, Clone, Debug)]
 enum ShiftOp { Left,  Right }
 
-impl<'a> Mode for State {
+impl Mode for State {
     type Family = StateFamily;
     fn swap(self, mut tape : u16) -> (Self, u16) {
         use State::*;
@@ -62,13 +62,13 @@ impl<'a> Mode for State {
 
         if let Some((print_op, shift_op)) = op {
             match print_op {
-                Print => { tape = tape |  (1 << HEAD) },
-                Clear => { tape = tape & !(1 << HEAD) },
+                Print => { tape |=   1 << HEAD  },
+                Clear => { tape &= !(1 << HEAD) },
             }
 
             match shift_op {
-                Left  => { tape = tape << 1 },
-                Right => { tape = tape >> 1 },
+                Left  => { tape <<= 1 },
+                Right => { tape >>= 1 },
             }
         }
 
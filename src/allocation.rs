@@ -0,0 +1,278 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines a wrapping global allocator that counts heap allocations per thread, and a `Middleware` that uses it to
+//! account for the allocations made by each transition of an `Automaton`.
+//! 
+//! Once a [`CountingAllocator`](struct.CountingAllocator.html) has been installed as the `#[global_allocator]`, the
+//! number of allocations made by the current thread so far, and their total size, can be read at any time via
+//! [`current()`](fn.current.html). An [`AllocationTracker`](struct.AllocationTracker.html) can then be added to the
+//! [`Pipeline`](../struct.Pipeline.html) of an `Automaton` in order to attribute those allocations to each pair of
+//! `tag()`s that a swap went from and to, which helps find the swaps that are unexpectedly heap-heavy. Reallocations
+//! are counted as a single allocation of the new size. Deallocations are not counted.
+//! 
+//! **NOTE:** This module is only available when the `allocation` feature is enabled. If no `CountingAllocator` has
+//! been installed, every count reported by this module is zero. Since implementing `GlobalAlloc` requires `unsafe`
+//! code, this is the only module in the crate that contains any, and it only forwards each call to the wrapped
+//! allocator unchanged.
+//! 
+//! # Usage
+//! ```
+//! use mode::{allocation::{AllocationTracker, CountingAllocator}, Automaton, Family, Mode, Pipeline};
+//! 
+//! #[global_allocator]
+//! static ALLOCATOR : CountingAllocator = CountingAllocator::system();
+//! 
+//! struct BufferFamily;
+//! impl Family for BufferFamily {
+//!     type Base = Buffer;
+//!     type Mode = Buffer;
+//!     type Input = usize;
+//!     type Output = Buffer;
+//! }
+//! 
+//! enum Buffer { Empty, Filled(Vec<u8>) }
+//! impl Mode for Buffer {
+//!     type Family = BufferFamily;
+//!     fn swap(self, size : usize) -> Self {
+//!         match self { Buffer::Empty => Buffer::Filled(vec![0; size]), filled => filled }
+//!     }
+//!     fn tag(&self) -> &'static str {
+//!         match self { Buffer::Empty => "Empty", Buffer::Filled(_) => "Filled" }
+//!     }
+//! }
+//! 
+//! fn main() {
+//!     let tracker = AllocationTracker::new();
+//!     let mut pipeline = Pipeline::<BufferFamily>::new().with_layer(tracker.clone());
+//! 
+//!     let mut buffer = BufferFamily::automaton_with_mode(Buffer::Empty);
+//!     Automaton::next_with_pipeline(&mut buffer, &mut pipeline, 4096).unwrap();
+//!     Automaton::next_with_pipeline(&mut buffer, &mut pipeline, 4096).unwrap();
+//! 
+//!     let filling = tracker.get("Empty", "Filled").unwrap();
+//!     assert_eq!(filling.swaps, 1);
+//!     assert!(filling.total.bytes >= 4096);
+//! 
+//!     let idle = tracker.get("Filled", "Filled").unwrap();
+//!     assert_eq!(idle.total.count, 0);
+//!     assert_eq!(tracker.report()[0].0, "Empty");
+//! }
+//! ```
+//! 
+
+use crate::{Family, Middleware, Verdict};
+use std::{
+    alloc::{GlobalAlloc, Layout, System},
+    cell::Cell,
+    cmp::Reverse,
+    collections::BTreeMap,
+    fmt,
+    ops::Sub,
+    sync::{Arc, Mutex, MutexGuard, PoisonError},
+};
+
+thread_local! {
+    static COUNTERS : Cell<Allocations> = const { Cell::new(Allocations { count : 0, bytes : 0 }) };
+}
+
+/// Adds an allocation of `bytes` to the counters of the current thread, unless they have already been torn down.
+/// 
+fn count(bytes : usize) {
+    let _ =
+        COUNTERS.try_with(|counters| {
+            let Allocations { count, bytes : total } = counters.get();
+            counters.set(Allocations { count : count + 1, bytes : total + bytes as u64 });
+        });
+}
+
+/// A number of heap allocations and their total size in bytes.
+/// 
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Allocations {
+    /// The number of allocations.
+    pub count : u64,
+
+    /// The total number of bytes allocated.
+    pub bytes : u64,
+}
+
+impl Sub for Allocations {
+    type Output = Allocations;
+
+    fn sub(self, other : Allocations) -> Allocations {
+        Allocations { count : self.count.saturating_sub(other.count), bytes : self.bytes.saturating_sub(other.bytes) }
+    }
+}
+
+impl fmt::Display for Allocations {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} allocations, {} bytes", self.count, self.bytes)
+    }
+}
+
+/// Returns the number of heap allocations made by the current thread so far, and their total size, as counted by the
+/// [`CountingAllocator`](struct.CountingAllocator.html). The difference between two calls is the number of allocations
+/// made in between.
+/// 
+pub fn current() -> Allocations {
+    COUNTERS.try_with(Cell::get).unwrap_or_default()
+}
+
+/// Calls `f`, returning its result along with the heap allocations it made on the current thread.
+/// 
+pub fn measure<R>(f : impl FnOnce() -> R) -> (R, Allocations) {
+    let before = current();
+    let result = f();
+    (result, current() - before)
+}
+
+/// A `GlobalAlloc` that wraps another allocator, `A`, and counts every allocation made through it on the thread that
+/// made it. This must be installed as the `#[global_allocator]` in order for the counts reported by this module to be
+/// non-zero.
+/// 
+/// See the [module-level documentation](index.html) for an example.
+/// 
+#[derive(Debug, Default)]
+pub struct CountingAllocator<A = System> {
+    inner : A,
+}
+
+impl CountingAllocator<System> {
+    /// Creates a new `CountingAllocator` that wraps the `System` allocator.
+    /// 
+    pub const fn system() -> Self {
+        Self { inner : System }
+    }
+}
+
+impl<A> CountingAllocator<A> {
+    /// Creates a new `CountingAllocator` that wraps `inner`.
+    /// 
+    pub const fn new(inner : A) -> Self {
+        Self { inner }
+    }
+}
+
+unsafe impl<A> GlobalAlloc for CountingAllocator<A>
+    where A : GlobalAlloc
+{
+    unsafe fn alloc(&self, layout : Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { self.inner.alloc(layout) }
+    }
+
+    unsafe fn alloc_zeroed(&self, layout : Layout) -> *mut u8 {
+        count(layout.size());
+        unsafe { self.inner.alloc_zeroed(layout) }
+    }
+
+    unsafe fn dealloc(&self, ptr : *mut u8, layout : Layout) {
+        unsafe { self.inner.dealloc(ptr, layout) }
+    }
+
+    unsafe fn realloc(&self, ptr : *mut u8, layout : Layout, new_size : usize) -> *mut u8 {
+        count(new_size);
+        unsafe { self.inner.realloc(ptr, layout, new_size) }
+    }
+}
+
+/// The heap allocations made by every swap from one `tag()` to another, as recorded by an
+/// [`AllocationTracker`](struct.AllocationTracker.html).
+/// 
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct SwapAllocations {
+    /// The number of swaps that were recorded.
+    pub swaps : u64,
+
+    /// The heap allocations made by all of those swaps, in total.
+    pub total : Allocations,
+
+    /// The heap allocations made by the most allocation-heavy of those swaps, by bytes.
+    pub max : Allocations,
+}
+
+/// A `Middleware` that records the heap allocations made by each swap of an `Automaton`, keyed by the `tag()`s of the
+/// `Mode` that was current before the swap and the `Mode` that became current as a result.
+/// 
+/// An `AllocationTracker` is a handle to a shared set of records, so a clone of it can be added to a `Pipeline`, while
+/// the original is kept around in order to read the records back. Since the `after_swap()` function of each
+/// `Middleware` is called in reverse order, the `AllocationTracker` should be added to the end of the `Pipeline`, so
+/// that it only wraps the swap itself, and not the other `Middleware`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug, Default)]
+pub struct AllocationTracker {
+    records : Arc<Mutex<Records>>,
+}
+
+#[derive(Debug, Default)]
+struct Records {
+    before : Allocations,
+    swaps : BTreeMap<(&'static str, &'static str), SwapAllocations>,
+}
+
+impl AllocationTracker {
+    /// Creates a new `AllocationTracker` without any recorded swaps.
+    /// 
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn records(&self) -> MutexGuard<'_, Records> {
+        self.records.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Returns the heap allocations made by swaps from the `Mode` with `tag()` `from` to the `Mode` with `tag()`
+    /// `to`, or `None` if no such swap has been recorded.
+    /// 
+    pub fn get(&self, from : &str, to : &str) -> Option<SwapAllocations> {
+        self.records().swaps.iter()
+            .find(|((swap_from, swap_to), _)| *swap_from == from && *swap_to == to)
+            .map(|(_, allocations)| *allocations)
+    }
+
+    /// Returns the `tag()`s and heap allocations of every kind of swap that has been recorded, ordered from the most
+    /// total bytes allocated to the fewest.
+    /// 
+    pub fn report(&self) -> Vec<(&'static str, &'static str, SwapAllocations)> {
+        let mut report : Vec<_> =
+            self.records().swaps.iter()
+                .map(|((from, to), allocations)| (*from, *to, *allocations))
+                .collect();
+        report.sort_by_key(|(_, _, allocations)| Reverse(allocations.total.bytes));
+        report
+    }
+
+    /// Removes every recorded swap.
+    /// 
+    pub fn clear(&self) {
+        self.records().swaps.clear();
+    }
+}
+
+impl<F, R> Middleware<F, R> for AllocationTracker
+    where F : Family + ?Sized
+{
+    fn before_swap(&mut self, _current : &F::Base, _input : &mut F::Input) -> Verdict<R> {
+        self.records().before = current();
+        Verdict::Proceed
+    }
+
+    fn after_swap_with_tags(&mut self, from : &'static str, to : &'static str, _current : &F::Base) {
+        let after = current();
+        let mut records = self.records();
+        let allocations = after - records.before;
+        let swap = records.swaps.entry((from, to)).or_default();
+        swap.swaps += 1;
+        swap.total.count += allocations.count;
+        swap.total.bytes += allocations.bytes;
+        if allocations.bytes >= swap.max.bytes {
+            swap.max = allocations;
+        }
+    }
+}
@@ -4,14 +4,28 @@
 // MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
 // modified, or distributed except according to those terms.
 
-use crate::{Family, Mode};
+use crate::{boxed, goap, guards, Blackboard, CancellationToken, Dwell, EnterFrom, EventLog, Family, Field, FinalMode, Finish, FromInputs, FinishError, Footprint, Inbox, Inputs, IsrQueue, Lend, Mode, Outcome, Packed, Phase, Pipeline, Poisoned, Record, ReplayError, SwapRef, TransitionEvent, TransitionRequest, Trigger, Utility, View, Watchdog};
 use std::{
+    any::Any,
     convert::{AsRef, AsMut},
     borrow::{Borrow, BorrowMut},
     fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{mpsc::{self, Receiver, Sender}, Arc},
+    task::{Context, Poll, Waker},
+    time::{Duration, Instant},
 };
 use std::ops::{Deref, DerefMut};
 
+type Factory<F> = dyn Fn() -> <F as Family>::Mode + Send + Sync;
+type Fallback<F> = dyn Fn(<F as Family>::Mode) -> <F as Family>::Mode + Send + Sync;
+type ProgressFn<F> = dyn Fn(&<F as Family>::Mode) -> bool + Send + Sync;
+type AwaitedFn<F> = dyn FnOnce(<F as Family>::Mode) -> <F as Family>::Mode + Send + Sync;
+type Awaiting<F> = Pin<Box<dyn Future<Output = Box<AwaitedFn<F>>> + Send + Sync>>;
+type OutputSink<Output> = dyn FnMut(Output) + Send + Sync;
+
 /// Represents a state machine over a set of `Mode`s within the same `Family`.
 /// 
 /// The `Automaton` contains a single, active `Mode` that represents the current state of the state machine. The current
@@ -62,9 +76,21 @@ use std::ops::{Deref, DerefMut};
 /// automaton.borrow_mode_mut().some_mut_fn();
 /// 
 /// // Let the Automaton handle transitions.
-/// Automaton::next(&mut automaton);
+/// automaton.step_modes();
 /// ```
 /// 
+/// # Method-style stepping
+/// Most functions on `Automaton` are associated functions, e.g. `Automaton::next(&mut automaton)`, so that they can
+/// never shadow a member of `F::Base` that is reachable through `Deref`. The most common ones also have method-style
+/// equivalents with names that are unlikely to collide with anything in `F::Base`, which are the recommended way of
+/// stepping an `Automaton`:
+/// 
+/// - [`automaton.step_modes()`](#method.step_modes) is the same as `Automaton::next(&mut automaton)`.
+/// - [`automaton.step_modes_with(input)`](#method.step_modes_with) is the same as
+///   `Automaton::next_with_input(&mut automaton, input)`.
+/// 
+/// If `F::Base` does happen to define a method with the same name, the associated-function form can still be used.
+/// 
 /// # The `F` parameter
 /// 
 /// One important thing to note about the `F` generic parameter it that it is **not** the base `Mode` type that will be
@@ -156,27 +182,2560 @@ use std::ops::{Deref, DerefMut};
 /// }
 /// ```
 /// 
+/// # Panics
+/// The current `Mode` is moved out of the `Automaton` for the duration of each swap. If user code called during a swap
+/// panics, e.g. `Mode::swap()`, `Mode::enter()`, or `Mode::exit()`, the `Automaton` is left without a current `Mode`,
+/// and is said to be [poisoned](struct.Poisoned.html). Every function that accesses the current `Mode` of a poisoned
+/// `Automaton` panics, including `Deref` and `DerefMut`.
+/// 
+/// Apart from that, `Automaton::next()`, `Automaton::next_with_input()`, and the other `Automaton::next*()` functions
+/// never panic, and neither do any of the functions that only query or update the bookkeeping of the `Automaton`, e.g.
+/// `Automaton::steps()` or `Automaton::pause()`. The only other functions that can panic are `Automaton::reset()`,
+/// which panics if the `Automaton` was not created with a factory for its initial `Mode`, and
+/// [`Automaton::into_output()`](#method.into_output), which panics if a final `Mode` breaks the contract of
+/// [`FinalMode`](trait.FinalMode.html) by not producing an output. Callers that need a guarantee that the `Automaton`
+/// itself never panics can use [`Automaton::try_next()`](#method.try_next),
+/// [`Automaton::try_next_with_input()`](#method.try_next_with_input),
+/// [`try_borrow_mode()`](#method.try_borrow_mode), and [`try_borrow_mode_mut()`](#method.try_borrow_mode_mut), which
+/// return `Err(Poisoned)` instead of panicking, together with [`Automaton::can_reset()`](#method.can_reset).
+/// 
 /// For more on the `Base` and `Mode` parameters, see [`Family`](trait.Family.html).
 /// 
 pub struct Automaton<F>
     where F : Family + ?Sized
 {
-    mode : Option<F::Mode>,
+    mode : Option<F::Mode>,
+    phase : Phase,
+    factory : Option<Box<Factory<F>>>,
+    blackboard : Blackboard,
+    subscribers : Vec<Sender<TransitionEvent>>,
+    #[cfg(feature = "tokio")]
+    watch : Option<tokio::sync::watch::Sender<&'static str>>,
+    steps : u64,
+    steps_in_mode : u64,
+    entered_at : Option<Instant>,
+    paused_at : Option<Instant>,
+    on_resume : Option<Waker>,
+    min_dwell : Option<Dwell>,
+    sink : Option<Box<dyn Any + Send + Sync>>,
+    requests : Vec<TransitionRequest<F>>,
+    awaiting : Vec<Awaiting<F>>,
+    timeouts : Vec<Timeout<F>>,
+    watchdog : Option<Watchdog>,
+    stall_limit : Option<StallLimit<F>>,
+    cancellation : Option<Cancellation<F>>,
+    name : Option<String>,
+    parent : Option<String>,
+    path : Option<Arc<str>>,
+    #[cfg(feature = "debug")]
+    debug : Option<crate::debug::Handle>,
+}
+
+struct Timeout<F>
+    where F : Family + ?Sized
+{
+    tag : &'static str,
+    after : Duration,
+    fallback : Box<Fallback<F>>,
+}
+
+struct StallLimit<F>
+    where F : Family + ?Sized
+{
+    steps : u64,
+    expects_progress : Box<ProgressFn<F>>,
+    on_stall : Box<Fallback<F>>,
+}
+
+struct Cancellation<F>
+    where F : Family + ?Sized
+{
+    token : CancellationToken,
+    on_cancel : Box<Fallback<F>>,
+}
+
+/// Describes what should happen to the `Input` of a single step, as decided by `Automaton::prepare()`.
+/// 
+enum Step {
+    /// The current `Mode` should `swap()` with the `Input`.
+    Swap,
+
+    /// The `Input` should be discarded, e.g. because the `Automaton` is paused or finished.
+    Held,
+
+    /// A hook took the place of `swap()`, so the `Input` should be discarded.
+    Hooked(Trigger),
+}
+
+impl<F> Automaton<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Automaton` with the specified `mode`, which will be the initial active `Mode` for the `Automaton`
+    /// that is returned.
+    /// 
+    /// **NOTE:** If `F::Base` is a type that implements `Default`, [`new()`](struct.Automaton.html#method.new) can be
+    /// used instead.
+    /// 
+    /// Since the `F` parameter cannot be determined automatically, using this function usually requires the use of the
+    /// turbofish, e.g. `Automaton::<SomeFamily>::with_mode()`. To avoid that, `Family` provides an
+    /// `automaton_with_mode()` associated function that can be used instead. See
+    /// [`Family::automaton_with_mode()`](trait.Family.html#method.automaton_with_mode) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = SomeMode;
+    ///     type Mode = SomeMode;
+    ///     type Input = ();
+    ///     type Output = SomeMode;
+    /// }
+    /// 
+    /// enum SomeMode { A, B, C };
+    /// impl Mode for SomeMode {
+    ///     type Family = SomeFamily;
+    ///     fn swap(mut self, _input : ()) -> Self {
+    ///         // TODO: Logic for transitioning between states goes here.
+    ///         self
+    ///     }
+    /// }
+    /// 
+    /// // Create an Automaton with A as the initial Mode.
+    /// // NOTE: We could alternatively use SomeFamily::automaton_with_mode() here to shorten this.
+    /// let mut automaton = Automaton::<SomeFamily>::with_mode(SomeMode::A);
+    /// ```
+    /// 
+    /// # Static allocation
+    /// This is a `const fn` that does not allocate, so an `Automaton` can be created in a `const` or `static` context,
+    /// e.g. in order to place it in statically-allocated memory on an embedded target.
+    /// 
+    /// ```
+    /// use mode::*;
+    /// use std::sync::Mutex;
+    /// 
+    /// struct LedFamily;
+    /// impl Family for LedFamily {
+    ///     type Base = Led;
+    ///     type Mode = Led;
+    ///     type Input = ();
+    ///     type Output = Led;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Led { Off, On }
+    /// impl Mode for Led {
+    ///     type Family = LedFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         match self { Led::Off => Led::On, Led::On => Led::Off }
+    ///     }
+    /// }
+    /// 
+    /// static LED : Mutex<Automaton<LedFamily>> = Mutex::new(Automaton::with_mode(Led::Off));
+    /// 
+    /// Automaton::next(&mut LED.lock().unwrap());
+    /// assert_eq!(**LED.lock().unwrap(), Led::On);
+    /// ```
+    /// 
+    pub const fn with_mode(mode : F::Mode) -> Self {
+        Self {
+            mode : Some(mode),
+            phase : Phase::Idle,
+            factory : None,
+            blackboard : Blackboard::new(),
+            subscribers : Vec::new(),
+            #[cfg(feature = "tokio")]
+            watch : None,
+            steps : 0,
+            steps_in_mode : 0,
+            entered_at : None,
+            paused_at : None,
+            on_resume : None,
+            min_dwell : None,
+            sink : None,
+            requests : Vec::new(),
+            awaiting : Vec::new(),
+            timeouts : Vec::new(),
+            watchdog : None,
+            stall_limit : None,
+            cancellation : None,
+            name : None,
+            parent : None,
+            path : None,
+            #[cfg(feature = "debug")]
+            debug : None,
+        }
+    }
+
+    /// Returns `true` if the `Automaton` no longer has a current `Mode`, because user code panicked during a swap.
+    /// 
+    /// See [`Poisoned`](struct.Poisoned.html) for more details.
+    /// 
+    pub fn is_poisoned(this : &Self) -> bool {
+        this.mode.is_none()
+    }
+
+    /// Returns the total number of calls to any of the `Automaton::next*()` functions since this `Automaton` was
+    /// created.
+    /// 
+    pub fn steps(this : &Self) -> u64 {
+        this.steps
+    }
+
+    /// Returns the number of calls to any of the `Automaton::next*()` functions since the current `Mode` became current.
+    /// This is reset to `0` every time a `Mode` with a different [`tag()`](trait.Mode.html#method.tag) is swapped in.
+    /// 
+    /// This can be used to implement frame-based delays without requiring each `Mode` to count frames itself, e.g. by
+    /// passing it into `swap()` as the `input` parameter. (See example below.)
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Light;
+    ///     type Mode = Light;
+    ///     type Input = u64;
+    ///     type Output = Light;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Light { Green, Red }
+    /// impl Mode for Light {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, steps_in_mode : u64) -> Self {
+    ///         // Stay green for three steps before turning red.
+    ///         if self == Light::Green && steps_in_mode >= 3 { Light::Red } else { self }
+    ///     }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Light::Green => "Green", Light::Red => "Red" }
+    ///     }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Light::Green);
+    /// for _ in 0..4 {
+    ///     assert_eq!(*automaton, Light::Green);
+    ///     let steps = Automaton::steps_in_mode(&automaton);
+    ///     Automaton::next_with_input(&mut automaton, steps);
+    /// }
+    /// 
+    /// assert_eq!(*automaton, Light::Red);
+    /// assert_eq!(Automaton::steps(&automaton), 4);
+    /// assert_eq!(Automaton::steps_in_mode(&automaton), 0);
+    /// ```
+    /// 
+    pub fn steps_in_mode(this : &Self) -> u64 {
+        this.steps_in_mode
+    }
+
+    /// Returns the amount of real time that has passed since the current `Mode` became current, not counting any time
+    /// during which the `Automaton` was paused. This is always zero until the `Automaton` has been started.
+    /// 
+    pub fn time_in_mode(this : &Self) -> Duration {
+        match this.entered_at {
+            Some(entered_at) => Self::clock(this).duration_since(entered_at),
+            None => Duration::ZERO,
+        }
+    }
+
+    /// Returns the point in time from which `time_in_mode()` is measured, i.e. the moment that the `Automaton` was
+    /// paused, if it is paused, or the current time otherwise. Stamping a new `Mode` with this keeps `time_in_mode()`
+    /// at zero until the `Automaton` is resumed.
+    /// 
+    fn clock(this : &Self) -> Instant {
+        this.paused_at.unwrap_or_else(Instant::now)
+    }
+
+    /// Starts the `Automaton`, and then adjusts its clock so that `time_in_mode()` reports `elapsed`, as if the current
+    /// `Mode` had become current `elapsed` ago. This is used to carry timing across a restart.
+    /// 
+    #[cfg(feature = "serde")]
+    pub(crate) fn resume_after(this : &mut Self, elapsed : Duration) {
+        Self::start(this);
+
+        let now = Instant::now();
+        this.entered_at = Some(now.checked_sub(elapsed).unwrap_or(now));
+        if this.paused_at.is_some() {
+            this.paused_at = Some(now);
+        }
+    }
+
+    /// Pauses the `Automaton`. While paused, calls to `Automaton::next()`, `Automaton::next_with_input()`,
+    /// `Automaton::try_next_with_output()` and `Automaton::try_next_with_input_and_output()` do nothing, are **not**
+    /// counted as steps, and the time returned by `Automaton::time_in_mode()` stops accumulating. Calling `pause()` on an
+    /// `Automaton` that is already paused has no effect.
+    /// 
+    /// **NOTE:** Since `Automaton::next_with_output()` and `Automaton::next_with_input_and_output()` must always return
+    /// a value produced by `swap()`, these functions will still call `swap()` while the `Automaton` is paused. Use the
+    /// `try_` variants instead.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Counter;
+    ///     type Mode = Counter;
+    ///     type Input = ();
+    ///     type Output = Counter;
+    /// }
+    /// 
+    /// struct Counter(u32);
+    /// impl Mode for Counter {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { Counter(self.0 + 1) }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Counter(0));
+    /// 
+    /// Automaton::pause(&mut automaton);
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(automaton.0, 0);
+    /// 
+    /// Automaton::resume(&mut automaton);
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(automaton.0, 1);
+    /// ```
+    /// 
+    pub fn pause(this : &mut Self) {
+        if this.paused_at.is_none() {
+            this.paused_at = Some(Instant::now());
+        }
+    }
+
+    /// Resumes an `Automaton` that was paused via [`Automaton::pause()`](#method.pause). Calling `resume()` on an
+    /// `Automaton` that is not paused has no effect.
+    /// 
+    pub fn resume(this : &mut Self) {
+        if let Some(paused_at) = this.paused_at.take() {
+            if let Some(entered_at) = this.entered_at.as_mut() {
+                *entered_at += paused_at.elapsed();
+            }
+
+            if let Some(waker) = this.on_resume.take() {
+                waker.wake();
+            }
+        }
+    }
+
+    /// Registers `waker` to be woken the next time that the `Automaton` is [resumed](#method.resume), replacing any
+    /// `Waker` that was registered before.
+    /// 
+    pub(crate) fn wake_on_resume(this : &mut Self, waker : &Waker) {
+        this.on_resume = Some(waker.clone());
+    }
+
+    /// Returns the point in time at which the minimum dwell time stops holding the current `Mode` in place, if it does,
+    /// or at which the timeout for the current `Mode` expires, if that comes first. Returns `None` if stepping the
+    /// `Automaton` right away could make progress, e.g. because it has no minimum dwell time, or because it has
+    /// asynchronous guards that need to be polled. Unlike the remaining time, this only changes when the current `Mode`,
+    /// the minimum dwell time, or the timeouts change, so it can be used to decide when to re-arm a timer.
+    /// 
+    pub(crate) fn release_at(this : &Self) -> Option<Instant> {
+        let min_dwell =
+            match this.min_dwell {
+                Some(Dwell::Time(min_dwell)) => min_dwell,
+                _ => return None,
+            };
+
+        if Self::is_paused(this) || Self::is_finished(this) || !this.awaiting.is_empty() || !this.requests.is_empty() {
+            return None;
+        }
+
+        let tag = this.borrow_current().tag();
+        let hold =
+            this.timeouts.iter()
+                .filter(|timeout| timeout.tag == tag)
+                .map(|timeout| timeout.after)
+                .fold(min_dwell, Duration::min);
+
+        let release_at = this.entered_at?.checked_add(hold)?;
+        if release_at > Instant::now() { Some(release_at) } else { None }
+    }
+
+    /// Queues a request to transition out of the current `Mode`, e.g. into an "interrupt" state such as being stunned or
+    /// playing a cutscene. This allows several external systems to compete for control over the `Automaton` between
+    /// steps without needing to coordinate with each other.
+    /// 
+    /// Pending requests are applied at the beginning of the next step, unless the `Automaton` is paused, and take the
+    /// place of `swap()` for that step, so the `Mode` that a request returns is not swapped until the step after it
+    /// became current. The same goes for timeouts, stall limits, asynchronous guards, and cancellation, and at most one
+    /// of them is applied per step. The request with the highest `priority` wins, with ties being broken in favor of
+    /// the request that was made first, and the current `Mode` is passed by value into its `build` function, so that
+    /// state can be moved into the `Mode` it returns, just as with `Mode::swap()`. Every other pending request is
+    /// rejected. A callback can be attached to the request that is returned, via
+    /// [`TransitionRequest::on_rejected()`](struct.TransitionRequest.html#method.on_rejected), in order to find out
+    /// whether it was rejected.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+    /// 
+    /// struct HeroFamily;
+    /// impl Family for HeroFamily {
+    ///     type Base = Hero;
+    ///     type Mode = Hero;
+    ///     type Input = ();
+    ///     type Output = Hero;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Hero { Idle, Stunned, Dead }
+    /// impl Mode for Hero {
+    ///     type Family = HeroFamily;
+    ///     fn swap(self, _input : ()) -> Self { self }
+    /// }
+    /// 
+    /// let stun_rejected = Arc::new(AtomicBool::new(false));
+    /// let rejected = stun_rejected.clone();
+    /// 
+    /// let mut hero = HeroFamily::automaton_with_mode(Hero::Idle);
+    /// Automaton::request_transition(&mut hero, 1, |_| Hero::Stunned)
+    ///     .on_rejected(move || rejected.store(true, Ordering::Relaxed));
+    /// Automaton::request_transition(&mut hero, 10, |_| Hero::Dead);
+    /// 
+    /// Automaton::next(&mut hero);
+    /// assert_eq!(*hero, Hero::Dead);
+    /// assert!(stun_rejected.load(Ordering::Relaxed));
+    /// ```
+    /// 
+    pub fn request_transition<B>(this : &mut Self, priority : i32, build : B) -> &mut TransitionRequest<F>
+        where B : FnOnce(F::Mode) -> F::Mode + Send + Sync + 'static
+    {
+        this.requests.push(TransitionRequest::new(priority, Box::new(build)));
+        this.requests.last_mut().unwrap()
+    }
+
+    /// Same as [`Automaton::request_transition()`](#method.request_transition), except that the `Mode` that becomes
+    /// current if the request wins is created via [`EnterFrom::enter_from()`](trait.EnterFrom.html#tymethod.enter_from)
+    /// from the current `Mode` and `args`.
+    /// 
+    /// See [`EnterFrom`](trait.EnterFrom.html) for more details.
+    /// 
+    pub fn request<T, Args>(this : &mut Self, priority : i32, args : Args) -> &mut TransitionRequest<F>
+        where
+            T : EnterFrom<F, Args>,
+            Args : Send + Sync + 'static,
+    {
+        Self::request_transition(this, priority, move |current| T::enter_from(current, args).into())
+    }
+
+    /// Returns the number of transition requests that have not been applied or rejected yet.
+    /// 
+    /// See [`Automaton::request_transition()`](#method.request_transition) for more details.
+    /// 
+    pub fn pending_requests(this : &Self) -> usize {
+        this.requests.len()
+    }
+
+    /// Applies the pending transition request with the highest priority immediately, and rejects all others, instead of
+    /// waiting for the next step. If the `Automaton` has finished, all pending requests are rejected. Returns `true`
+    /// if a request was applied.
+    /// 
+    /// See [`Automaton::request_transition()`](#method.request_transition) for more details.
+    /// 
+    pub fn apply_requests(this : &mut Self) -> bool {
+        if this.requests.is_empty() {
+            return false;
+        }
+
+        let mut requests = mem::take(&mut this.requests);
+        let winner =
+            if Self::is_finished(this) {
+                None
+            }
+            else {
+                let mut best = 0;
+                for (index, request) in requests.iter().enumerate() {
+                    if request.priority() > requests[best].priority() {
+                        best = index;
+                    }
+                }
+                Some(requests.remove(best))
+            };
+
+        for request in requests {
+            request.reject();
+        }
+
+        match winner {
+            Some(winner) => {
+                Self::replace_current(this, winner.into_builder());
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Arms an asynchronous guard for the current `Mode`, which is resolved once `future` completes. The `Automaton`
+    /// keeps `future` pending between steps, and polls it once at the beginning of every step, so that the current
+    /// `Mode` doesn't have to store any `Pin<Box<dyn Future>>` fields of its own. Once `future` resolves, the current
+    /// `Mode` is passed by value into `build` along with the value that `future` produced, and the `Mode` it returns
+    /// becomes current in place of `swap()` for that step, e.g. "transition once this oneshot channel receives a reply"
+    /// or "transition after this timer fires". If several guards resolve during the same step, the one that was armed
+    /// first wins.
+    /// 
+    /// Guards are scoped to the `Mode` that was current when they were armed. As soon as the `tag()` of the current
+    /// `Mode` changes, every pending guard is dropped without being polled again.
+    /// 
+    /// **NOTE:** `future` is polled with a no-op waker, so it only makes progress when the `Automaton` is stepped, e.g.
+    /// via [`runtime::tick_every()`](runtime/fn.tick_every.html). Any `Future` that relies on the runtime it was created
+    /// in, e.g. a timer, must be polled from within that runtime.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::{future, sync::{atomic::{AtomicU32, Ordering}, Arc}, task::Poll};
+    /// 
+    /// struct RequestFamily;
+    /// impl Family for RequestFamily {
+    ///     type Base = Request;
+    ///     type Mode = Request;
+    ///     type Input = ();
+    ///     type Output = Request;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Request { Waiting { swaps : u32 }, Answered(u32) }
+    /// impl Mode for Request {
+    ///     type Family = RequestFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         match self { Request::Waiting { swaps } => Request::Waiting { swaps : swaps + 1 }, other => other }
+    ///     }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Request::Waiting { .. } => "Waiting", Request::Answered(_) => "Answered" }
+    ///     }
+    /// }
+    /// 
+    /// // The reply arrives on the third poll.
+    /// let polls = Arc::new(AtomicU32::new(0));
+    /// let reply = {
+    ///     let polls = polls.clone();
+    ///     future::poll_fn(move |_| {
+    ///         if polls.fetch_add(1, Ordering::SeqCst) == 2 { Poll::Ready(42) } else { Poll::Pending }
+    ///     })
+    /// };
+    /// 
+    /// let mut request = RequestFamily::automaton_with_mode(Request::Waiting { swaps : 0 });
+    /// Automaton::transition_when(&mut request, reply, |_, answer| Request::Answered(answer));
+    /// 
+    /// // The guard is polled exactly once per step.
+    /// for step in 1..=2 {
+    ///     Automaton::next(&mut request);
+    ///     assert_eq!(polls.load(Ordering::SeqCst), step);
+    ///     assert_eq!(*request, Request::Waiting { swaps : step });
+    /// }
+    /// assert_eq!(Automaton::pending_guards(&request), 1);
+    /// 
+    /// // Once the guard resolves, it takes the place of swap() for that step.
+    /// Automaton::next(&mut request);
+    /// assert_eq!(polls.load(Ordering::SeqCst), 3);
+    /// assert_eq!(*request, Request::Answered(42));
+    /// assert_eq!(Automaton::steps(&request), 3);
+    /// assert_eq!(Automaton::pending_guards(&request), 0);
+    /// ```
+    /// 
+    pub fn transition_when<T, B>(this : &mut Self, future : impl Future<Output = T> + Send + Sync + 'static, build : B)
+        where
+            T : Send + Sync + 'static,
+            B : FnOnce(F::Mode, T) -> F::Mode + Send + Sync + 'static,
+    {
+        this.awaiting.push(Box::pin(async move {
+            let value = future.await;
+            Box::new(move |mode| build(mode, value)) as Box<AwaitedFn<F>>
+        }));
+    }
+
+    /// Returns the number of asynchronous guards armed via
+    /// [`Automaton::transition_when()`](#method.transition_when) that have not resolved or been dropped yet.
+    /// 
+    pub fn pending_guards(this : &Self) -> usize {
+        this.awaiting.len()
+    }
+
+    /// Drops every pending asynchronous guard without applying it.
+    /// 
+    /// See [`Automaton::transition_when()`](#method.transition_when) for more details.
+    /// 
+    pub fn clear_pending_guards(this : &mut Self) {
+        this.awaiting.clear();
+    }
+
+    /// Polls every pending asynchronous guard once, and applies the first one that has resolved, if any. Returns `true`
+    /// if a guard was applied.
+    /// 
+    fn apply_awaiting(this : &mut Self) -> bool {
+        if this.awaiting.is_empty() || Self::is_finished(this) {
+            return false;
+        }
+
+        let mut context = Context::from_waker(Waker::noop());
+        let mut awaiting = mem::take(&mut this.awaiting);
+        let resolved =
+            awaiting.iter_mut().enumerate().find_map(|(index, future)| {
+                match future.as_mut().poll(&mut context) {
+                    Poll::Ready(build) => Some((index, build)),
+                    Poll::Pending => None,
+                }
+            });
+
+        match resolved {
+            Some((index, build)) => {
+                drop(awaiting.remove(index));
+                this.awaiting = awaiting;
+                Self::replace_current(this, build);
+                true
+            },
+            None => {
+                this.awaiting = awaiting;
+                false
+            },
+        }
+    }
+
+    /// Sets a timeout for every `Mode` with the specified `tag()`. If such a `Mode` is still current once `after` has
+    /// passed, as reported by [`Automaton::time_in_mode()`](#method.time_in_mode), the current `Mode` is passed by value
+    /// into `fallback` at the beginning of the next step, and the `Mode` it returns becomes current in place of `swap()`
+    /// for that step. This replaces any timeout previously set for the same `tag`.
+    /// 
+    /// Timeouts are not checked while the `Automaton` is paused, and time spent paused does not count towards them.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::time::Duration;
+    /// 
+    /// struct ConnectionFamily;
+    /// impl Family for ConnectionFamily {
+    ///     type Base = Connection;
+    ///     type Mode = Connection;
+    ///     type Input = ();
+    ///     type Output = Connection;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Connection { Connecting { attempt : u32 }, Offline }
+    /// impl Mode for Connection {
+    ///     type Family = ConnectionFamily;
+    ///     fn swap(self, _input : ()) -> Self { self }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Connection::Connecting { .. } => "Connecting", Connection::Offline => "Offline" }
+    ///     }
+    /// }
+    /// 
+    /// let mut connection = ConnectionFamily::automaton_with_mode(Connection::Connecting { attempt : 1 });
+    /// Automaton::set_timeout(&mut connection, "Connecting", Duration::from_millis(10), |_| Connection::Offline);
+    /// 
+    /// Automaton::next(&mut connection);
+    /// assert_eq!(*connection, Connection::Connecting { attempt : 1 });
+    /// 
+    /// std::thread::sleep(Duration::from_millis(20));
+    /// Automaton::next(&mut connection);
+    /// assert_eq!(*connection, Connection::Offline);
+    /// ```
+    /// 
+    pub fn set_timeout<B>(this : &mut Self, tag : &'static str, after : Duration, fallback : B)
+        where B : Fn(F::Mode) -> F::Mode + Send + Sync + 'static
+    {
+        Self::clear_timeout(this, tag);
+        this.timeouts.push(Timeout { tag, after, fallback : Box::new(fallback) });
+    }
+
+    /// Removes the timeout for `Mode`s with the specified `tag()`, if any.
+    /// 
+    /// See [`Automaton::set_timeout()`](#method.set_timeout) for more details.
+    /// 
+    pub fn clear_timeout(this : &mut Self, tag : &'static str) {
+        this.timeouts.retain(|timeout| timeout.tag != tag);
+    }
+
+    /// Applies the fallback for the current `Mode` if its timeout has expired. Returns `true` if the fallback was
+    /// applied.
+    /// 
+    fn apply_timeout(this : &mut Self) -> bool {
+        if this.timeouts.is_empty() || Self::is_finished(this) {
+            return false;
+        }
+
+        let tag = this.borrow_current().tag();
+        let elapsed = Self::time_in_mode(this);
+        let timeouts = mem::take(&mut this.timeouts);
+        let expired =
+            timeouts.iter()
+                .find(|timeout| timeout.tag == tag && elapsed >= timeout.after);
+
+        let applied =
+            match expired {
+                Some(timeout) => {
+                    Self::replace_current(this, &*timeout.fallback);
+                    true
+                },
+                None => false,
+            };
+
+        this.timeouts = timeouts;
+        applied
+    }
+
+    /// Attaches `watchdog` to the `Automaton`, replacing any `Watchdog` that was attached before. From then on, every
+    /// call to one of the `Automaton::next*()` functions records a heartbeat that `watchdog` can check.
+    /// 
+    /// See [`Watchdog`](struct.Watchdog.html) for more details.
+    /// 
+    pub fn attach_watchdog(this : &mut Self, watchdog : &Watchdog) {
+        watchdog.beat();
+        this.watchdog = Some(watchdog.clone());
+    }
+
+    /// Detaches the `Watchdog` from the `Automaton`, if any.
+    /// 
+    pub fn detach_watchdog(this : &mut Self) {
+        this.watchdog = None;
+    }
+
+    /// Records a heartbeat for the attached `Watchdog`, if any.
+    /// 
+    fn beat(this : &Self) {
+        if let Some(watchdog) = &this.watchdog {
+            watchdog.beat();
+        }
+    }
+
+    /// Sets a limit on the number of steps that the current `Mode` can stay current while `expects_progress` returns
+    /// `true` for it. Once the current `Mode` has been current for at least `steps` steps, as reported by
+    /// [`Automaton::steps_in_mode()`](#method.steps_in_mode), and `expects_progress` returns `true`, the current `Mode`
+    /// is passed by value into `on_stall` at the beginning of the next step, and the `Mode` it returns becomes current
+    /// in place of `swap()` for that step, e.g. an error state. This replaces any stall limit that was set before.
+    /// 
+    /// If `on_stall` returns a `Mode` with the same `tag()`, e.g. because it only reports the stall and then returns
+    /// the current `Mode` unchanged, it will be called again on every step for as long as the stall lasts.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+    /// 
+    /// struct JobFamily;
+    /// impl Family for JobFamily {
+    ///     type Base = Job;
+    ///     type Mode = Job;
+    ///     type Input = ();
+    ///     type Output = Job;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Job { Waiting, Working, Failed }
+    /// impl Mode for Job {
+    ///     type Family = JobFamily;
+    ///     fn swap(self, _input : ()) -> Self { self }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Job::Waiting => "Waiting", Job::Working => "Working", Job::Failed => "Failed" }
+    ///     }
+    /// }
+    /// 
+    /// let mut job = JobFamily::automaton_with_mode(Job::Working);
+    /// Automaton::set_stall_limit(&mut job, 3, |job| *job == Job::Working, |_| Job::Failed);
+    /// 
+    /// for _ in 0..3 { Automaton::next(&mut job); }
+    /// assert_eq!(*job, Job::Working);
+    /// 
+    /// Automaton::next(&mut job);
+    /// assert_eq!(*job, Job::Failed);
+    /// 
+    /// // An on_stall function that only reports the stall is called exactly once per step while the stall lasts.
+    /// let reports = Arc::new(AtomicU32::new(0));
+    /// let mut job = JobFamily::automaton_with_mode(Job::Working);
+    /// Automaton::set_stall_limit(&mut job, 1, |job| *job == Job::Working, {
+    ///     let reports = reports.clone();
+    ///     move |job| { reports.fetch_add(1, Ordering::SeqCst); job }
+    /// });
+    /// 
+    /// for _ in 0..4 { Automaton::next(&mut job); }
+    /// assert_eq!(reports.load(Ordering::SeqCst), 3);
+    /// assert_eq!(Automaton::steps(&job), 4);
+    /// ```
+    /// 
+    pub fn set_stall_limit<P, S>(this : &mut Self, steps : u64, expects_progress : P, on_stall : S)
+        where
+            P : Fn(&F::Mode) -> bool + Send + Sync + 'static,
+            S : Fn(F::Mode) -> F::Mode + Send + Sync + 'static,
+    {
+        this.stall_limit =
+            Some(StallLimit { steps, expects_progress : Box::new(expects_progress), on_stall : Box::new(on_stall) });
+    }
+
+    /// Removes the stall limit of the `Automaton`, if any.
+    /// 
+    /// See [`Automaton::set_stall_limit()`](#method.set_stall_limit) for more details.
+    /// 
+    pub fn clear_stall_limit(this : &mut Self) {
+        this.stall_limit = None;
+    }
+
+    /// Makes the `Automaton` observe `token`, replacing any `CancellationToken` that it was observing before. At the
+    /// beginning of the first step after `token` is cancelled, [`Mode::exit()`](trait.Mode.html#method.exit) is called
+    /// on the current `Mode`, which is then passed by value into `on_cancel`, and the `Mode` it returns becomes current
+    /// in place of `swap()` for that step, e.g. a cancellation state that shuts down gracefully before finishing. This
+    /// happens at most once per call to `set_cancellation()`, and takes precedence over pending transition requests,
+    /// timeouts, and the stall limit. If the `Automaton` has already finished, nothing happens.
+    /// 
+    /// See [`CancellationToken`](struct.CancellationToken.html) for more details.
+    /// 
+    pub fn set_cancellation<C>(this : &mut Self, token : &CancellationToken, on_cancel : C)
+        where C : Fn(F::Mode) -> F::Mode + Send + Sync + 'static
+    {
+        this.cancellation = Some(Cancellation { token : token.clone(), on_cancel : Box::new(on_cancel) });
+    }
+
+    /// Stops the `Automaton` from observing its `CancellationToken`, if any.
+    /// 
+    /// See [`Automaton::set_cancellation()`](#method.set_cancellation) for more details.
+    /// 
+    pub fn clear_cancellation(this : &mut Self) {
+        this.cancellation = None;
+    }
+
+    /// Returns the `CancellationToken` observed by the `Automaton`, if it has not been applied yet.
+    /// 
+    pub fn cancellation_token(this : &Self) -> Option<&CancellationToken> {
+        this.cancellation.as_ref().map(|cancellation| &cancellation.token)
+    }
+
+    /// Exits the current `Mode` and routes the `Automaton` into its cancellation state if its `CancellationToken` has
+    /// been cancelled. Returns `true` if the cancellation state was entered.
+    /// 
+    fn apply_cancellation(this : &mut Self) -> bool {
+        let cancelled = this.cancellation.as_ref().is_some_and(|cancellation| cancellation.token.is_cancelled());
+        if Self::is_finished(this) || !cancelled {
+            return false;
+        }
+
+        let cancellation = this.cancellation.take().unwrap();
+        Self::replace_current(this, |mut current| {
+            current.exit();
+            (cancellation.on_cancel)(current)
+        });
+        true
+    }
+
+    /// Calls the `on_stall` function of the stall limit if the current `Mode` has stalled. Returns `true` if `on_stall`
+    /// was called.
+    /// 
+    fn apply_stall_limit(this : &mut Self) -> bool {
+        if Self::is_finished(this) {
+            return false;
+        }
+
+        let mut applied = false;
+        if let Some(limit) = this.stall_limit.take() {
+            if this.steps_in_mode >= limit.steps && (limit.expects_progress)(this.borrow_current()) {
+                Self::replace_current(this, &*limit.on_stall);
+                applied = true;
+            }
+            this.stall_limit = Some(limit);
+        }
+        applied
+    }
+
+    /// Passes the current `Mode` into `build` and makes the `Mode` it returns current, without counting a step.
+    /// 
+    fn replace_current(this : &mut Self, build : impl FnOnce(F::Mode) -> F::Mode) {
+        Self::start(this);
+        let current =
+            this.mode.take()
+                .expect("Cannot replace current Mode because another swap is already taking place!");
+        let from = current.tag();
+        this.blackboard.begin_scope();
+        let next = build(current);
+        let to = next.tag();
+        this.mode = Some(next);
+
+        if from != to {
+            Self::transitioned(this, from, to);
+        }
+
+        Self::finish_if_final(this);
+    }
+
+    /// Returns `true` if the `Automaton` is currently paused.
+    /// 
+    pub fn is_paused(this : &Self) -> bool {
+        this.paused_at.is_some()
+    }
+
+    /// Returns the minimum dwell time set on this `Automaton`, if any.
+    /// 
+    pub fn min_dwell(this : &Self) -> Option<Dwell> {
+        this.min_dwell
+    }
+
+    /// Sets the minimum amount of time for which every `Mode` must remain current before it is allowed to `swap()`. Until
+    /// the current `Mode` has been current for at least `min_dwell`, calls to `Automaton::next()`,
+    /// `Automaton::next_with_input()`, `Automaton::try_next_with_output()` and
+    /// `Automaton::try_next_with_input_and_output()` will **not** call `swap()` on the current `Mode`, although they
+    /// will still count as steps. Passing `None` removes the minimum dwell time.
+    /// 
+    /// **NOTE:** Since `Automaton::next_with_output()` and `Automaton::next_with_input_and_output()` must always return
+    /// a value produced by `swap()`, these functions ignore the minimum dwell time. Use the `try_` variants instead.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Toggle;
+    ///     type Mode = Toggle;
+    ///     type Input = ();
+    ///     type Output = Toggle;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Toggle { On, Off }
+    /// impl Mode for Toggle {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         match self { Toggle::On => Toggle::Off, Toggle::Off => Toggle::On }
+    ///     }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Toggle::On => "On", Toggle::Off => "Off" }
+    ///     }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Toggle::On);
+    /// Automaton::set_min_dwell(&mut automaton, Some(Dwell::Steps(2)));
+    /// 
+    /// Automaton::next(&mut automaton);
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(*automaton, Toggle::On);
+    /// 
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(*automaton, Toggle::Off);
+    /// ```
+    /// 
+    pub fn set_min_dwell(this : &mut Self, min_dwell : Option<Dwell>) {
+        this.min_dwell = min_dwell;
+    }
+
+    /// Starts the step, and returns `true` if the current `Mode` should not be allowed to `swap()` during it, e.g.
+    /// because a pending transition request or timeout has already replaced the current `Mode` for this step. Unless
+    /// the `Automaton` is paused, the call is still counted as a step.
+    /// 
+    fn hold(this : &mut Self) -> bool {
+        !matches!(Self::prepare(this), Step::Swap)
+    }
+
+    /// Same as `hold()`, except that it also reports whether a hook took the place of `swap()`, and which one.
+    /// 
+    fn prepare(this : &mut Self) -> Step {
+        Self::start(this);
+        Self::beat(this);
+
+        if Self::is_paused(this) {
+            return Step::Held;
+        }
+
+        if let Some(trigger) = Self::apply_hooks(this) {
+            return Step::Hooked(trigger);
+        }
+
+        if Self::is_finished(this) {
+            return Step::Held;
+        }
+
+        let held =
+            match this.min_dwell {
+                Some(Dwell::Steps(steps)) => this.steps_in_mode < steps,
+                Some(Dwell::Time(time)) => Self::time_in_mode(this) < time,
+                None => false,
+            };
+
+        if held {
+            this.steps += 1;
+            this.steps_in_mode += 1;
+            return Step::Held;
+        }
+
+        Step::Swap
+    }
+
+    /// Applies the first of the cancellation token, the asynchronous guards, the pending transition requests, the
+    /// timeout, and the stall limit of the `Automaton` that wants to replace the current `Mode`, in that order. If one
+    /// of them does, it takes the place of `swap()` for this step, and the step is counted. Returns the hook that
+    /// replaced the current `Mode`, if any.
+    /// 
+    fn apply_hooks(this : &mut Self) -> Option<Trigger> {
+        let from = this.borrow_current().tag();
+        let trigger =
+            if Self::apply_cancellation(this) {
+                Some(Trigger::Cancellation)
+            }
+            else if Self::apply_awaiting(this) {
+                Some(Trigger::Awaiting)
+            }
+            else if Self::apply_requests(this) {
+                Some(Trigger::Request)
+            }
+            else if Self::apply_timeout(this) {
+                Some(Trigger::Timeout)
+            }
+            else if Self::apply_stall_limit(this) {
+                Some(Trigger::StallLimit)
+            }
+            else {
+                None
+            };
+
+        if trigger.is_some() {
+            Self::count_hook(this, from);
+        }
+        trigger
+    }
+
+    /// Fires the hook identified by `trigger` unconditionally, as if its condition held, e.g. as if the timeout for the
+    /// current `Mode` had expired. Returns `false` if the `Automaton` has no such hook for the current `Mode`, or if the
+    /// hook cannot be fired on demand.
+    /// 
+    fn force_hook(this : &mut Self, trigger : Trigger) -> bool {
+        let from = this.borrow_current().tag();
+        let applied =
+            match trigger {
+                Trigger::Cancellation => {
+                    match this.cancellation.take() {
+                        Some(cancellation) => {
+                            Self::replace_current(this, |mut current| {
+                                current.exit();
+                                (cancellation.on_cancel)(current)
+                            });
+                            true
+                        },
+                        None => false,
+                    }
+                },
+                // NOTE: Requests and asynchronous guards are one-off closures, so they can't be fired again.
+                Trigger::Awaiting | Trigger::Request => false,
+                Trigger::Timeout => {
+                    let timeouts = mem::take(&mut this.timeouts);
+                    let applied =
+                        match timeouts.iter().find(|timeout| timeout.tag == from) {
+                            Some(timeout) => {
+                                Self::replace_current(this, &*timeout.fallback);
+                                true
+                            },
+                            None => false,
+                        };
+                    this.timeouts = timeouts;
+                    applied
+                },
+                Trigger::StallLimit => {
+                    match this.stall_limit.take() {
+                        Some(limit) => {
+                            Self::replace_current(this, &*limit.on_stall);
+                            this.stall_limit = Some(limit);
+                            true
+                        },
+                        None => false,
+                    }
+                },
+            };
+
+        if applied {
+            Self::count_hook(this, from);
+        }
+        applied
+    }
+
+    /// Counts a step in which a hook took the place of `swap()`, given the `tag()` of the `Mode` it replaced.
+    /// 
+    fn count_hook(this : &mut Self, from : &'static str) {
+        this.steps += 1;
+        if this.borrow_current().tag() == from {
+            this.steps_in_mode += 1;
+        }
+    }
+
+    /// Creates a new `Automaton` whose initial `Mode` is created by calling `factory`. The `Automaton` holds on to
+    /// `factory`, so that it can be called again to recreate the initial `Mode` whenever
+    /// [`Automaton::reset()`](#method.reset) is called.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Counter;
+    ///     type Mode = Counter;
+    ///     type Input = ();
+    ///     type Output = Counter;
+    /// }
+    /// 
+    /// struct Counter(u32);
+    /// impl Mode for Counter {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { Counter(self.0 + 1) }
+    /// }
+    /// 
+    /// let start = 10;
+    /// let mut automaton = Automaton::<SomeFamily>::with_factory(move || Counter(start));
+    /// 
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(automaton.0, 11);
+    /// 
+    /// Automaton::reset(&mut automaton);
+    /// assert_eq!(automaton.0, 10);
+    /// assert_eq!(Automaton::steps_in_mode(&automaton), 0);
+    /// assert_eq!(Automaton::steps(&automaton), 1);
+    /// ```
+    /// 
+    pub fn with_factory<G>(factory : G) -> Self
+        where G : Fn() -> F::Mode + Send + Sync + 'static
+    {
+        let mut automaton = Self::with_mode(factory());
+        automaton.factory = Some(Box::new(factory));
+        automaton
+    }
+
+    /// Returns the [`tag()`](trait.Mode.html#method.tag) of the current `Mode`.
+    /// 
+    pub fn tag(this : &Self) -> &'static str {
+        this.borrow_current().tag()
+    }
+
+    /// Returns the [`fields()`](trait.Mode.html#method.fields) of the current `Mode`.
+    /// 
+    pub fn fields(this : &Self) -> Vec<Field> {
+        this.borrow_current().fields()
+    }
+
+    /// Returns the amount of memory used by the current `Mode`, i.e. the size of the `F::Mode` stored inline within the
+    /// `Automaton`, and the heap memory that it owns, as reported by
+    /// [`Mode::heap_size()`](trait.Mode.html#method.heap_size).
+    /// 
+    /// See [`Footprint`](struct.Footprint.html) for more details.
+    /// 
+    pub fn footprint(this : &Self) -> Footprint {
+        Footprint { inline : std::mem::size_of::<F::Mode>(), heap : this.borrow_current().heap_size() }
+    }
+
+    /// Returns the name of the `Automaton`, if it has been given one via [`set_name()`](#method.set_name).
+    /// 
+    pub fn name(this : &Self) -> Option<&str> {
+        this.name.as_deref()
+    }
+
+    /// Sets the name of the `Automaton`, which is used to build its [`path()`](#method.path). The path identifies the
+    /// `Automaton` in diagnostics, e.g. its `Debug` output, and is attached to every
+    /// [`TransitionEvent`](struct.TransitionEvent.html) it publishes, so that observers and exporters can tell many
+    /// `Automaton`s apart.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct WeaponFamily;
+    /// impl Family for WeaponFamily {
+    ///     type Base = Weapon;
+    ///     type Mode = Weapon;
+    ///     type Input = ();
+    ///     type Output = Weapon;
+    /// }
+    /// 
+    /// enum Weapon { Ready, Reload }
+    /// impl Mode for Weapon {
+    ///     type Family = WeaponFamily;
+    ///     fn swap(self, _input : ()) -> Self { Weapon::Reload }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Weapon::Ready => "Ready", Weapon::Reload => "Reload" }
+    ///     }
+    /// }
+    /// 
+    /// struct PlayerFamily;
+    /// impl Family for PlayerFamily {
+    ///     type Base = Player;
+    ///     type Mode = Player;
+    ///     type Input = ();
+    ///     type Output = Player;
+    /// }
+    /// 
+    /// struct Player { weapon : Automaton<WeaponFamily> }
+    /// impl Mode for Player {
+    ///     type Family = PlayerFamily;
+    ///     fn swap(mut self, _input : ()) -> Self {
+    ///         Automaton::next(&mut self.weapon);
+    ///         self
+    ///     }
+    ///     fn set_path(&mut self, path : &str) {
+    ///         // Pass the path of the parent Automaton on to the nested one.
+    ///         Automaton::set_parent(&mut self.weapon, Some(path));
+    ///     }
+    /// }
+    /// 
+    /// let mut weapon = WeaponFamily::automaton_with_mode(Weapon::Ready);
+    /// Automaton::set_name(&mut weapon, "weapon");
+    /// assert_eq!(Automaton::path(&weapon), Some("weapon"));
+    /// 
+    /// let mut player = PlayerFamily::automaton_with_mode(Player { weapon });
+    /// Automaton::set_name(&mut player, "player");
+    /// assert_eq!(Automaton::path(&player.weapon), Some("player/weapon"));
+    /// 
+    /// let events = Automaton::subscribe(&mut player.weapon);
+    /// Automaton::next(&mut player);
+    /// assert_eq!(events.try_recv().unwrap().path.as_deref(), Some("player/weapon"));
+    /// ```
+    /// 
+    pub fn set_name(this : &mut Self, name : impl Into<String>) {
+        this.name = Some(name.into());
+        Self::update_path(this);
+    }
+
+    /// Sets the path of the parent of this `Automaton`, which prefixes its [`path()`](#method.path), or removes it if
+    /// `parent` is `None`. This is usually called from [`Mode::set_path()`](trait.Mode.html#method.set_path) by a
+    /// `Mode` that owns this `Automaton`.
+    /// 
+    /// See [`set_name()`](#method.set_name) for more details.
+    /// 
+    pub fn set_parent(this : &mut Self, parent : Option<&str>) {
+        this.parent = parent.map(str::to_owned);
+        Self::update_path(this);
+    }
+
+    /// Returns the path of the `Automaton`, i.e. the path of its parent, if any, followed by a `/` and its
+    /// [`name()`](#method.name), e.g. `player/weapon/reload`. Returns `None` if the `Automaton` has not been named.
+    /// 
+    /// See [`set_name()`](#method.set_name) for more details.
+    /// 
+    pub fn path(this : &Self) -> Option<&str> {
+        this.path.as_deref()
+    }
+
+    /// Rebuilds the path of the `Automaton` after its name or parent changed, and passes it on to the current `Mode`.
+    /// 
+    fn update_path(this : &mut Self) {
+        let parent = &this.parent;
+        this.path =
+            this.name.as_ref()
+                .map(|name| {
+                    match parent {
+                        Some(parent) => format!("{}/{}", parent, name).into(),
+                        None => name.as_str().into(),
+                    }
+                });
+
+        #[cfg(feature = "debug")]
+        {
+            match (&this.path, &this.debug) {
+                (Some(path), Some(debug)) => debug.set_path(path.clone()),
+                (Some(path), None) => {
+                    let tag = this.mode.as_ref().map_or("", |mode| mode.tag());
+                    this.debug = Some(crate::debug::Handle::register(path.clone(), tag));
+                },
+                (None, _) => this.debug = None,
+            }
+        }
+
+        Self::propagate_path(this);
+    }
+
+    /// Passes the path of the `Automaton`, if any, to the current `Mode` via `Mode::set_path()`.
+    /// 
+    fn propagate_path(this : &mut Self) {
+        if let (Some(path), Some(mode)) = (&this.path, this.mode.as_mut()) {
+            mode.set_path(path);
+        }
+    }
+
+    /// Returns the current lifecycle [`Phase`](enum.Phase.html) of the `Automaton`.
+    /// 
+    pub fn phase(this : &Self) -> Phase {
+        this.phase
+    }
+
+    /// Returns `true` if the `Automaton` has been started, i.e. if the initial `Mode` has been entered.
+    /// 
+    pub fn is_started(this : &Self) -> bool {
+        this.phase != Phase::Idle
+    }
+
+    /// Returns `true` if the `Automaton` has finished, either because [`Automaton::stop()`](#method.stop) was called,
+    /// or because a [final](trait.Mode.html#method.is_final) `Mode` became current. Once finished, the gated
+    /// `Automaton::next*()` functions become no-ops.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Countdown;
+    ///     type Mode = Countdown;
+    ///     type Input = ();
+    ///     type Output = Countdown;
+    /// }
+    /// 
+    /// struct Countdown(u32);
+    /// impl Mode for Countdown {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { Countdown(self.0 - 1) }
+    ///     fn is_final(&self) -> bool { self.0 == 0 }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Countdown(2));
+    /// while !Automaton::is_finished(&automaton) {
+    ///     Automaton::next(&mut automaton);
+    /// }
+    /// assert_eq!(automaton.0, 0);
+    /// 
+    /// // Further calls to next() have no effect.
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(automaton.0, 0);
+    /// ```
+    /// 
+    pub fn is_finished(this : &Self) -> bool {
+        this.phase == Phase::Finished
+    }
+
+    /// Returns `true` if the `Automaton` has finished because a [final](trait.Mode.html#method.is_final) `Mode` became
+    /// current, as opposed to having been [stopped](#method.stop) while a non-final `Mode` was current. Only a complete
+    /// `Automaton` can be consumed via [`Automaton::finish()`](#method.finish).
+    /// 
+    pub fn is_complete(this : &Self) -> bool {
+        Self::is_finished(this) && this.mode.as_ref().is_some_and(|mode| mode.is_final())
+    }
+
+    /// Starts the `Automaton`, calling [`Mode::enter()`](trait.Mode.html#method.enter) on the initial `Mode` and moving
+    /// the `Automaton` into the `Running` phase. Calling `start()` on an `Automaton` that has already been started has
+    /// no effect.
+    /// 
+    /// **NOTE:** It is not strictly necessary to call this function, since all of the `Automaton::next*()` functions
+    /// will start the `Automaton` implicitly if it has not been started yet. However, calling `start()` explicitly
+    /// allows the initial `Mode` to be entered before the first step.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Door;
+    ///     type Mode = Door;
+    ///     type Input = ();
+    ///     type Output = Door;
+    /// }
+    /// 
+    /// struct Door { entered : bool }
+    /// impl Mode for Door {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { self }
+    ///     fn enter(&mut self) { self.entered = true; }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Door { entered : false });
+    /// assert_eq!(Automaton::phase(&automaton), Phase::Idle);
+    /// assert!(!automaton.entered);
+    /// 
+    /// Automaton::start(&mut automaton);
+    /// assert_eq!(Automaton::phase(&automaton), Phase::Running);
+    /// assert!(automaton.entered);
+    /// 
+    /// Automaton::stop(&mut automaton);
+    /// assert!(Automaton::is_finished(&automaton));
+    /// ```
+    /// 
+    pub fn start(this : &mut Self) {
+        if this.phase == Phase::Idle {
+            this.phase = Phase::Running;
+            this.entered_at = Some(Self::clock(this));
+            this.mode.as_mut()
+                .expect("Cannot start Automaton because another swap is already taking place!")
+                .enter();
+            Self::finish_if_final(this);
+        }
+    }
+
+    /// Creates a new `Automaton` that has already been started with `mode` as its current `Mode`, without calling
+    /// `Mode::enter()` on it, since it was already entered elsewhere, e.g. by a [`Persistent`](struct.Persistent.html).
+    /// 
+    pub(crate) fn with_entered_mode(mode : F::Mode) -> Self {
+        let mut automaton = Self::with_mode(mode);
+        automaton.phase = Phase::Running;
+        automaton.entered_at = Some(Instant::now());
+        Self::finish_if_final(&mut automaton);
+        automaton
+    }
+
+    /// Stops the `Automaton`, calling [`Mode::exit()`](trait.Mode.html#method.exit) on the current `Mode` and moving the
+    /// `Automaton` into the `Finished` phase. Once finished, the `Automaton::next*()` functions will no longer allow the
+    /// current `Mode` to `swap()`, although the current `Mode` can still be borrowed. Calling `stop()` on an `Automaton`
+    /// that has already finished has no effect.
+    /// 
+    pub fn stop(this : &mut Self) {
+        if this.phase != Phase::Finished {
+            this.phase = Phase::Finished;
+            this.mode.as_mut()
+                .expect("Cannot stop Automaton because another swap is already taking place!")
+                .exit();
+        }
+    }
+
+    /// Returns `true` if this `Automaton` knows how to recreate its initial `Mode`, i.e. if it was created via
+    /// `Automaton::with_factory()`.
+    /// 
+    pub fn can_reset(this : &Self) -> bool {
+        this.factory.is_some()
+    }
+
+    /// Tears down the current `Mode`, calling [`Mode::exit()`](trait.Mode.html#method.exit) on it first, and replaces
+    /// it with a freshly created initial `Mode`, which is then entered. [`steps_in_mode()`](#method.steps_in_mode) is
+    /// reset to `0`, scoped `Blackboard` entries are dropped, and subscribers are notified of the transition if the
+    /// `tag()` of the current `Mode` changes. If the `Automaton` had finished, it is moved back into the `Running`
+    /// phase, and `exit()` is not called a second time. If the `Automaton` has not been started yet, the initial `Mode`
+    /// is simply replaced, and will be entered once the `Automaton` is started. All other state, e.g. subscribers, the
+    /// rest of the `Blackboard`, and the total number of [`steps()`](#method.steps) taken, is preserved.
+    /// 
+    /// # Panics
+    /// Panics if the `Automaton` was not created via `Automaton::with_factory()`. Use
+    /// [`Automaton::can_reset()`](#method.can_reset) to check beforehand.
+    /// 
+    pub fn reset(this : &mut Self) {
+        let initial =
+            this.factory.as_ref()
+                .expect("Cannot reset an Automaton that was not created with an initial Mode factory!")();
+
+        let mut current =
+            this.mode.take()
+                .expect("Cannot reset Automaton because another swap is already taking place!");
+
+        match this.phase {
+            Phase::Idle => { },
+            Phase::Running => current.exit(),
+            // The current Mode has already exited, so don't call exit() on it again.
+            Phase::Finished => this.phase = Phase::Running,
+        }
+
+        let from = current.tag();
+        drop(current);
+
+        let to = initial.tag();
+        this.mode = Some(initial);
+        this.blackboard.begin_scope();
+        Self::transitioned(this, from, to);
+        Self::finish_if_final(this);
+    }
+
+    /// Returns an immutable reference to the [`Blackboard`](struct.Blackboard.html) owned by this `Automaton`.
+    /// 
+    pub fn blackboard(this : &Self) -> &Blackboard {
+        &this.blackboard
+    }
+
+    /// Returns a mutable reference to the [`Blackboard`](struct.Blackboard.html) owned by this `Automaton`.
+    /// 
+    pub fn blackboard_mut(this : &mut Self) -> &mut Blackboard {
+        &mut this.blackboard
+    }
+
+    /// Returns a `Receiver` that will be sent a [`TransitionEvent`](struct.TransitionEvent.html) every time the
+    /// current `Mode` swaps in a `Mode` with a different [`tag()`](trait.Mode.html#method.tag). Any number of
+    /// `Receiver`s can be subscribed to the same `Automaton`, and each will receive its own copy of every event.
+    /// Dropping a `Receiver` will automatically unsubscribe it.
+    /// 
+    /// **NOTE:** Events are only sent when the `tag()` changes. A `Mode` that swaps in itself, or another `Mode` with
+    /// the same `tag()`, does not produce an event. Since the default `tag()` is the name of the `Self` type, this
+    /// includes every transition between two variants of an `enum` that does not override `tag()`. Override `tag()` to
+    /// tell such `Mode`s apart.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = SomeMode;
+    ///     type Mode = SomeMode;
+    ///     type Input = ();
+    ///     type Output = SomeMode;
+    /// }
+    /// 
+    /// enum SomeMode { A, B }
+    /// impl Mode for SomeMode {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { SomeMode::B }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { SomeMode::A => "A", SomeMode::B => "B" }
+    ///     }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(SomeMode::A);
+    /// let events = Automaton::subscribe(&mut automaton);
+    /// 
+    /// Automaton::next(&mut automaton); // A -> B
+    /// Automaton::next(&mut automaton); // B -> B, which is not a transition.
+    /// 
+    /// let event = events.try_recv().unwrap();
+    /// assert_eq!((event.from, event.to), ("A", "B"));
+    /// assert!(events.try_recv().is_err());
+    /// ```
+    /// 
+    pub fn subscribe(this : &mut Self) -> Receiver<TransitionEvent> {
+        let (sender, receiver) = mpsc::channel();
+        this.subscribers.push(sender);
+        receiver
+    }
+
+    /// Returns a `tokio::sync::watch::Receiver` that always holds the [`tag()`](trait.Mode.html#method.tag) of the
+    /// current `Mode`, and is updated every time the current `Mode` swaps in a `Mode` with a different `tag()`. This
+    /// allows async tasks to `changed().await` on the state of the `Automaton` without polling it, or holding a lock on
+    /// it. Any number of `Receiver`s can be created, and they can be cloned freely.
+    /// 
+    /// **NOTE:** This function is only available when the `tokio` feature is enabled.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = SomeMode;
+    ///     type Mode = SomeMode;
+    ///     type Input = ();
+    ///     type Output = SomeMode;
+    /// }
+    /// 
+    /// enum SomeMode { A, B }
+    /// impl Mode for SomeMode {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { SomeMode::B }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { SomeMode::A => "A", SomeMode::B => "B" }
+    ///     }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(SomeMode::A);
+    /// let mut state = Automaton::watch(&mut automaton);
+    /// assert_eq!(*state.borrow_and_update(), "A");
+    /// 
+    /// Automaton::next(&mut automaton);
+    /// assert!(state.has_changed().unwrap());
+    /// assert_eq!(*state.borrow_and_update(), "B");
+    /// ```
+    /// 
+    #[cfg(feature = "tokio")]
+    pub fn watch(this : &mut Self) -> tokio::sync::watch::Receiver<&'static str> {
+        match &this.watch {
+            Some(sender) => sender.subscribe(),
+            None => {
+                let (sender, receiver) = tokio::sync::watch::channel(this.borrow_current().tag());
+                this.watch = Some(sender);
+                receiver
+            },
+        }
+    }
+
+    /// Returns an immutable reference to the current `Mode` as a `&V`, for any `V` that `F::Mode` can be viewed as. This
+    /// allows a `Family` to expose more than one interface to its `Mode`s besides `F::Base`.
+    /// 
+    /// See [`View`](trait.View.html) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct ShipFamily;
+    /// impl Family for ShipFamily {
+    ///     type Base = Ship;
+    ///     type Mode = Ship;
+    ///     type Input = ();
+    ///     type Output = Ship;
+    /// }
+    /// 
+    /// trait Render { fn sprite(&self) -> &'static str; }
+    /// trait Update { fn update(&mut self); }
+    /// 
+    /// struct Ship { speed : u32 }
+    /// impl Render for Ship { fn sprite(&self) -> &'static str { "ship.png" } }
+    /// impl Update for Ship { fn update(&mut self) { self.speed += 1; } }
+    /// 
+    /// impl Mode for Ship {
+    ///     type Family = ShipFamily;
+    ///     fn swap(self, _input : ()) -> Self { self }
+    /// }
+    /// 
+    /// impl View<dyn Render> for Ship {
+    ///     fn view(&self) -> &(dyn Render + 'static) { self }
+    ///     fn view_mut(&mut self) -> &mut (dyn Render + 'static) { self }
+    /// }
+    /// 
+    /// impl View<dyn Update> for Ship {
+    ///     fn view(&self) -> &(dyn Update + 'static) { self }
+    ///     fn view_mut(&mut self) -> &mut (dyn Update + 'static) { self }
+    /// }
+    /// 
+    /// let mut automaton = ShipFamily::automaton_with_mode(Ship { speed : 0 });
+    /// Automaton::as_view_mut::<dyn Update>(&mut automaton).update();
+    /// assert_eq!(Automaton::as_view::<dyn Render>(&automaton).sprite(), "ship.png");
+    /// assert_eq!(automaton.speed, 1);
+    /// ```
+    /// 
+    pub fn as_view<V>(this : &Self) -> &V
+        where
+            V : ?Sized,
+            F::Mode : View<V>,
+    {
+        this.borrow_current().view()
+    }
+
+    /// Returns a mutable reference to the current `Mode` as a `&mut V`, for any `V` that `F::Mode` can be viewed as.
+    /// 
+    /// See [`Automaton::as_view()`](#method.as_view) for more details.
+    /// 
+    pub fn as_view_mut<V>(this : &mut Self) -> &mut V
+        where
+            V : ?Sized,
+            F::Mode : View<V>,
+    {
+        this.mode.as_mut()
+            .expect("Cannot borrow current Mode because another swap is already taking place!")
+            .view_mut()
+    }
+
+    /// Returns a reference to the current `Mode` as it is stored in the `Automaton`.
+    /// 
+    pub(crate) fn borrow_current(&self) -> &F::Mode {
+        self.mode.as_ref()
+            .expect("Cannot borrow current Mode because another swap is already taking place!")
+    }
+
+    /// Moves the current `Mode` into `swap`, making the `Mode` it returns current, and updates the step counters. If
+    /// the `tag()` of the current `Mode` changed as a result, all scoped `Blackboard` entries inserted before `swap`
+    /// was called are dropped and all subscribers are notified. If the new `Mode` is a final state, the `Automaton` is
+    /// stopped.
+    /// 
+    fn swap_mode<R>(this : &mut Self, swap : impl FnOnce(F::Mode, &mut Blackboard) -> (F::Mode, R)) -> R {
+        let current =
+            this.mode.take()
+                .expect("Cannot swap to next Mode because another swap is already taking place!");
+        let from = current.tag();
+        this.blackboard.begin_scope();
+        let (next, result) = swap(current, &mut this.blackboard);
+        let to = next.tag();
+        this.mode = Some(next);
+        this.steps += 1;
+
+        if from == to {
+            this.steps_in_mode += 1;
+        }
+        else {
+            Self::transitioned(this, from, to);
+        }
+
+        Self::finish_if_final(this);
+        result
+    }
+
+    /// Stops the `Automaton` if it is running and the current `Mode` is a final state.
+    /// 
+    fn finish_if_final(this : &mut Self) {
+        if this.phase == Phase::Running && this.borrow_current().is_final() {
+            Self::stop(this);
+        }
+    }
+
+    /// Updates the bookkeeping for the `Automaton` after a new `Mode` has become current, enters the new `Mode`, and
+    /// notifies all subscribers if the `tag()` of the current `Mode` changed.
+    /// 
+    fn transitioned(this : &mut Self, from : &'static str, to : &'static str) {
+        this.steps_in_mode = 0;
+        this.entered_at =
+            match this.phase {
+                Phase::Idle => None,
+                _ => Some(Self::clock(this)),
+            };
+        this.blackboard.clear_scoped();
+        this.awaiting.clear();
+        Self::propagate_path(this);
+
+        if this.phase != Phase::Idle {
+            if let Some(mode) = this.mode.as_mut() {
+                mode.enter();
+            }
+        }
+
+        if from != to && !this.subscribers.is_empty() {
+            let fields = this.borrow_current().fields();
+            let event = TransitionEvent { from, to, at: Instant::now(), fields, path : this.path.clone() };
+            this.subscribers.retain(|subscriber| subscriber.send(event.clone()).is_ok());
+        }
+
+        #[cfg(feature = "tokio")]
+        {
+            if let Some(watch) = &this.watch {
+                if from != to {
+                    watch.send_replace(to);
+                }
+            }
+        }
+
+        #[cfg(feature = "debug")]
+        {
+            if let Some(debug) = &this.debug {
+                debug.set_tag(to);
+            }
+        }
+    }
+}
+
+impl<F> Automaton<F>
+    where
+        F : Family + ?Sized,
+        F::Mode : Borrow<F::Base>,
+{
+    /// Returns an immutable reference to the current `Mode` as an `&F::Base`, allowing immutable functions to be called
+    /// on the inner `Mode`.
+    /// 
+    /// **NOTE:** `Automaton` also implements `Deref<Target = F::Base>`, allowing all `Base` members to be accessed via
+    /// a reference to the `Automaton`. Hence, you can usually leave the `borrow_mode()` out and simply treat the
+    /// `Automaton` as if it were an object of type `Base`.
+    /// 
+    pub fn borrow_mode(&self) -> &F::Base {
+        self.mode.as_ref()
+            .expect("Cannot borrow current Mode because another swap is already taking place!")
+            .borrow()
+    }
+
+    /// Same as [`borrow_mode()`](#method.borrow_mode), except that it returns `Err(Poisoned)` instead of panicking if
+    /// the `Automaton` is [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn try_borrow_mode(&self) -> Result<&F::Base, Poisoned> {
+        self.mode.as_ref().map(Borrow::borrow).ok_or(Poisoned)
+    }
+}
+
+impl<F> Automaton<F>
+    where
+        F : Family + ?Sized,
+        F::Mode : BorrowMut<F::Base>,
+{
+    /// Returns a mutable reference to the current `Mode` as a `&mut F::Base`, allowing mutable functions to be called
+    /// on the inner `Mode`.
+    /// 
+    /// **NOTE:** `Automaton` also implements `DerefMut<Target = Base>`, allowing all `Base` members to be accessed via
+    /// a reference to the `Automaton`. Hence, you can usually leave the `borrow_mode_mut()` out and simply treat the
+    /// `Automaton` as if it were an object of type `Base`.
+    /// 
+    pub fn borrow_mode_mut(&mut self) -> &mut F::Base {
+        self.mode.as_mut()
+            .expect("Cannot borrow current Mode because another swap is already taking place!")
+            .borrow_mut()
+    }
+
+    /// Same as [`borrow_mode_mut()`](#method.borrow_mode_mut), except that it returns `Err(Poisoned)` instead of
+    /// panicking if the `Automaton` is [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn try_borrow_mode_mut(&mut self) -> Result<&mut F::Base, Poisoned> {
+        self.mode.as_mut().map(BorrowMut::borrow_mut).ok_or(Poisoned)
+    }
+}
+
+impl<F, M> Automaton<F>
+    where
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Calls `swap()` on the current `Mode` to determine whether it wants to transition out, swapping in whatever
+    /// `Mode` it returns as a result. Calling this function *may* change the current `Mode`, but not necessarily.
+    /// 
+    /// See [`Mode::swap()`](trait.Mode.html#tymethod.swap) for more details.
+    /// 
+    pub fn next(this : &mut Self) {
+        Self::next_with_input(this, ());
+    }
+
+    /// Same as `Automaton::next()`, except that it returns `Err(Poisoned)` instead of panicking if the `Automaton` is
+    /// [poisoned](struct.Poisoned.html). Panics raised by the current `Mode` itself are not caught.
+    /// 
+    pub fn try_next(this : &mut Self) -> Result<(), Poisoned> {
+        Self::try_next_with_input(this, ())
+    }
+
+    /// The method-style equivalent of [`Automaton::next()`](#method.next), i.e. `automaton.step_modes()` is the same as
+    /// `Automaton::next(&mut automaton)`.
+    /// 
+    /// See [Method-style stepping](#method-style-stepping) for more details.
+    /// 
+    pub fn step_modes(&mut self) {
+        Self::next(self);
+    }
+
+    /// Calls `update` on the current `Mode` as an `&mut F::Base`, and then immediately calls `swap()` on the current
+    /// `Mode` to determine whether it wants to transition out, exactly as `Automaton::next()` would. Whatever `update`
+    /// returns will be returned from this function.
+    /// 
+    /// This is a convenience for the common pattern of updating the current `Mode` and then allowing it to transition
+    /// once per frame, and guarantees that the two calls always happen together and in the correct order.
+    /// 
+    /// See [`Automaton::next()`](#method.next) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Counter;
+    ///     type Mode = Counter;
+    ///     type Input = ();
+    ///     type Output = Counter;
+    /// }
+    /// 
+    /// enum Counter { Counting(u32), Done }
+    /// impl Mode for Counter {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         match self {
+    ///             Counter::Counting(count) if count >= 3 => Counter::Done,
+    ///             _ => self,
+    ///         }
+    ///     }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Counter::Counting(0));
+    /// 
+    /// // Update the current Mode and let it transition, all in one call.
+    /// while let Counter::Counting(_) = *automaton {
+    ///     Automaton::step_with(&mut automaton, |counter| {
+    ///         if let Counter::Counting(count) = counter { *count += 1; }
+    ///     });
+    /// }
+    /// ```
+    /// 
+    pub fn step_with<R>(this : &mut Self, update : impl FnOnce(&mut F::Base) -> R) -> R
+        where M : BorrowMut<F::Base>
+    {
+        let result = update(this.borrow_mode_mut());
+        Self::next(this);
+        result
+    }
+
+    /// Scores the current `Mode` against all candidates in `utility`, transitioning to the highest-scoring candidate if
+    /// its score exceeds the `threshold` of the `Utility`. If no candidate is selected, or the selected candidate has
+    /// the same `tag()` as the current `Mode`, this behaves exactly like [`Automaton::next()`](#method.next).
+    /// 
+    /// See [`Utility`](struct.Utility.html) for more details.
+    /// 
+    pub fn next_with_utility(this : &mut Self, utility : &Utility<F>)
+        where M : Borrow<F::Base>
+    {
+        if Self::hold(this) {
+            return;
+        }
+
+        let current_tag = this.borrow_current().tag();
+        let winner =
+            utility.winner(this.borrow_mode())
+                .filter(|(tag, _)| *tag != current_tag);
+
+        match winner {
+            Some((_, enter)) => Self::swap_mode(this, |mode, _| (enter(mode), ())),
+            None => Self::swap_mode(this, |mode, _| (mode.swap(()), ())),
+        }
+    }
+
+    /// Executes the next step of `plan`, which was created by `planner`. If no `Action` in `plan` is in progress, or the
+    /// `Action` in progress has finished, the next `Action` is started by passing the current `Mode` into its `enter`
+    /// function and making the `Mode` it returns current. Otherwise, this behaves exactly like
+    /// [`Automaton::next()`](#method.next), allowing the `Action` in progress to run.
+    /// 
+    /// See the [`goap`](goap/index.html) module for more details.
+    /// 
+    pub fn next_with_plan(this : &mut Self, planner : &goap::Planner<F>, plan : &mut goap::Plan)
+        where M : Borrow<F::Base>
+    {
+        if Self::hold(this) {
+            return;
+        }
+
+        if planner.is_ready(plan, this.borrow_mode()) {
+            if let Some(enter) = planner.advance(plan) {
+                Self::swap_mode(this, |mode, _| (enter(mode), ()));
+                return;
+            }
+        }
+
+        Self::swap_mode(this, |mode, _| (mode.swap(()), ()));
+    }
+}
+
+impl<F, M> Automaton<F>
+    where
+        F : Family<Mode = M, Output = M> + ?Sized,
+        M : SwapRef<Family = F>,
+{
+    /// Same as `Automaton::next()`, except that it calls [`SwapRef::swap_ref()`](trait.SwapRef.html#tymethod.swap_ref)
+    /// on the current `Mode` instead of `swap()`, borrowing `context` mutably for the duration of the call.
+    /// 
+    /// See [`SwapRef`](trait.SwapRef.html) for more details.
+    /// 
+    pub fn next_with_ref(this : &mut Self, context : &mut M::Context) {
+        if !Self::hold(this) {
+            Self::swap_mode(this, |mode, _| (mode.swap_ref(context), ()));
+        }
+    }
+}
+
+impl<F, M, Input> Automaton<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Same as `Automaton::next()`, except that it passes `input` into the `swap()` function.
+    /// 
+    /// See [`Automaton::next()`](#method.next) for more details.
+    /// 
+    pub fn next_with_input(this : &mut Self, input : Input) {
+        if !Self::hold(this) {
+            Self::swap_mode(this, |mode, _| (mode.swap(input), ()));
+        }
+    }
+
+    /// Same as `Automaton::next_with_input()`, except that it returns `Err(Poisoned)` instead of panicking if the
+    /// `Automaton` is [poisoned](struct.Poisoned.html). Panics raised by the current `Mode` itself are not caught.
+    /// 
+    pub fn try_next_with_input(this : &mut Self, input : Input) -> Result<(), Poisoned> {
+        if Self::is_poisoned(this) {
+            return Err(Poisoned);
+        }
+
+        Self::next_with_input(this, input);
+        Ok(())
+    }
+
+    /// Creates a new `Automaton` with `mode` as its initial `Mode` and starts it immediately, treating the initial
+    /// transition as a real transition. The initial `Mode` is [entered](trait.Mode.html#method.enter) first, and is
+    /// then allowed to `swap()` once with `initial_input`, so that it can transition straight into another `Mode`,
+    /// which will also be entered. If the initial `Mode` is a final state, it does not `swap()`.
+    /// 
+    /// By contrast, `Automaton::with_mode()` installs the initial `Mode` silently, and defers calling `enter()` on it
+    /// until the `Automaton` is started or stepped for the first time.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct PlayerFamily;
+    /// impl Family for PlayerFamily {
+    ///     type Base = Player;
+    ///     type Mode = Player;
+    ///     type Input = bool;
+    ///     type Output = Player;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Player { Booting { entered : u32 }, Menu { entered : u32 }, Playing { entered : u32 } }
+    /// 
+    /// impl Mode for Player {
+    ///     type Family = PlayerFamily;
+    ///     fn swap(self, has_save : bool) -> Self {
+    ///         match self {
+    ///             Player::Booting { entered } if has_save => Player::Playing { entered },
+    ///             Player::Booting { entered } => Player::Menu { entered },
+    ///             other => other,
+    ///         }
+    ///     }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Player::Booting { .. } => "Booting", Player::Menu { .. } => "Menu", _ => "Playing" }
+    ///     }
+    ///     fn enter(&mut self) {
+    ///         let (Player::Booting { entered } | Player::Menu { entered } | Player::Playing { entered }) = self;
+    ///         *entered += 1;
+    ///     }
+    /// }
+    /// 
+    /// let automaton = Automaton::<PlayerFamily>::start_with(Player::Booting { entered : 0 }, true);
+    /// assert_eq!(Automaton::phase(&automaton), Phase::Running);
+    /// assert_eq!(*automaton, Player::Playing { entered : 2 });
+    /// ```
+    /// 
+    pub fn start_with(mode : M, initial_input : Input) -> Self {
+        let mut automaton = Self::with_mode(mode);
+        Self::start(&mut automaton);
+        Self::next_with_input(&mut automaton, initial_input);
+        automaton
+    }
+
+    /// The method-style equivalent of [`Automaton::next_with_input()`](#method.next_with_input), i.e.
+    /// `automaton.step_modes_with(input)` is the same as `Automaton::next_with_input(&mut automaton, input)`.
+    /// 
+    /// See [Method-style stepping](#method-style-stepping) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct CounterFamily;
+    /// impl Family for CounterFamily {
+    ///     type Base = Counter;
+    ///     type Mode = Counter;
+    ///     type Input = u32;
+    ///     type Output = Counter;
+    /// }
+    /// 
+    /// struct Counter { total : u32 }
+    /// impl Mode for Counter {
+    ///     type Family = CounterFamily;
+    ///     fn swap(self, amount : u32) -> Self { Counter { total : self.total + amount } }
+    /// }
+    /// 
+    /// let mut counter = CounterFamily::automaton_with_mode(Counter { total : 0 });
+    /// counter.step_modes_with(2);
+    /// counter.step_modes_with(3);
+    /// assert_eq!(counter.total, 5);
+    /// ```
+    /// 
+    pub fn step_modes_with(&mut self, input : Input) {
+        Self::next_with_input(self, input);
+    }
+
+    /// Same as `Automaton::next_with_input()`, except that `swap()` is only called if `guard` passes for the current
+    /// `Mode` and `input`. Returns `true` if `swap()` was called. If the `Automaton` holds the current `Mode` in place,
+    /// e.g. because it is paused, `guard` is not checked. A call in which `guard` fails is still counted as a step.
+    /// 
+    /// See the [`guards`](guards/index.html) module for more details.
+    /// 
+    pub fn next_if(this : &mut Self, guard : &guards::Guard<F>, input : Input) -> bool
+        where M : Borrow<F::Base>
+    {
+        if Self::hold(this) {
+            return false;
+        }
+
+        let passed = {
+            let context =
+                guards::Context::new(
+                    this.borrow_current().borrow(),
+                    &input,
+                    this.steps_in_mode,
+                    Self::time_in_mode(this));
+            guard.check(&context)
+        };
+
+        if passed {
+            Self::swap_mode(this, |mode, _| (mode.swap(input), ()));
+        }
+        else {
+            this.steps += 1;
+            this.steps_in_mode += 1;
+        }
+
+        passed
+    }
+
+    /// Same as `Automaton::next_with_input()`, except that each swap is wrapped by the `Middleware` in `pipeline`.
+    /// Returns `Ok(true)` if `swap()` was called, or `Ok(false)` if the `Automaton` held the current `Mode` in place,
+    /// e.g. because it is paused. If a `Middleware` vetoes the swap, the reason it gave is returned as an `Err`. A
+    /// vetoed swap is guaranteed to leave the current `Mode` untouched, and is not counted as a step.
+    /// 
+    /// Pending transition requests, timeouts, stall limits, asynchronous guards, and cancellation are applied before
+    /// `pipeline` is consulted. If one of them replaces the current `Mode`, it takes the place of `swap()` for this
+    /// step, so `Ok(false)` is returned without running `pipeline` at all. Otherwise, `pipeline` always sees the same
+    /// `Mode` that `swap()` is called on.
+    /// 
+    /// See [`Middleware`](trait.Middleware.html) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct PlayerFamily;
+    /// impl Family for PlayerFamily {
+    ///     type Base = Player;
+    ///     type Mode = Player;
+    ///     type Input = u32;
+    ///     type Output = Player;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Player { Walking { speed : u32 }, Flying }
+    /// impl Mode for Player {
+    ///     type Family = PlayerFamily;
+    ///     fn swap(self, speed : u32) -> Self {
+    ///         if speed > 100 { Player::Flying } else { Player::Walking { speed } }
+    ///     }
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Refusal { Frozen }
+    /// 
+    /// let mut pipeline =
+    ///     Pipeline::<PlayerFamily, Refusal>::new()
+    ///         // Cheat detection: nobody can run faster than 20.
+    ///         .with_fn(|_, speed| { *speed = (*speed).min(20); Verdict::Proceed })
+    ///         // Refuse to move at all while speed 0 is requested.
+    ///         .with_fn(|_, speed| if *speed == 0 { Verdict::Veto(Refusal::Frozen) } else { Verdict::Proceed });
+    /// 
+    /// let mut player = PlayerFamily::automaton_with_mode(Player::Walking { speed : 1 });
+    /// assert_eq!(Automaton::next_with_pipeline(&mut player, &mut pipeline, 500), Ok(true));
+    /// assert_eq!(*player, Player::Walking { speed : 20 });
+    /// 
+    /// assert_eq!(Automaton::next_with_pipeline(&mut player, &mut pipeline, 0), Err(Refusal::Frozen));
+    /// assert_eq!(*player, Player::Walking { speed : 20 });
+    /// 
+    /// // A queued request takes the place of the swap, so the pipeline is not consulted for that step.
+    /// Automaton::request_transition(&mut player, 0, |_| Player::Walking { speed : 5 });
+    /// assert_eq!(Automaton::next_with_pipeline(&mut player, &mut pipeline, 0), Ok(false));
+    /// assert_eq!(*player, Player::Walking { speed : 5 });
+    /// 
+    /// // The next veto leaves the Mode returned by the request untouched.
+    /// assert_eq!(Automaton::next_with_pipeline(&mut player, &mut pipeline, 0), Err(Refusal::Frozen));
+    /// assert_eq!(*player, Player::Walking { speed : 5 });
+    /// assert_eq!(Automaton::pending_requests(&player), 0);
+    /// ```
+    /// 
+    pub fn next_with_pipeline<R>(this : &mut Self, pipeline : &mut Pipeline<F, R>, mut input : Input) -> Result<bool, R>
+        where M : Borrow<F::Base>
+    {
+        if Self::hold(this) {
+            return Ok(false);
+        }
+
+        pipeline.before_swap(this.borrow_current().borrow(), &mut input)?;
+
+        let from = this.borrow_current().tag();
+        Self::swap_mode(this, |mode, _| (mode.swap(input), ()));
+        let current = this.borrow_current();
+        pipeline.after_swap(from, current.tag(), current.borrow());
+        Ok(true)
+    }
+
+    /// Same as `Automaton::next_with_input()`, except that `log` receives a `Record` of what happened during the step.
+    /// If `input` reaches the `swap()` function of the current `Mode`, a copy of it is appended first. If a hook takes
+    /// the place of `swap()`, a `Hook` record is appended instead, and if the step is held, e.g. because the
+    /// `Automaton` is paused, nothing is appended at all. Either way, a `Transition` record follows if the current
+    /// `Mode` was replaced by a `Mode` with a different `tag()`.
+    /// 
+    /// See [`EventLog`](trait.EventLog.html) for more details.
+    /// 
+    pub fn next_logged(this : &mut Self, input : Input, log : &mut impl EventLog<Input>)
+        where Input : Clone
+    {
+        let from = this.borrow_current().tag();
+        match Self::prepare(this) {
+            Step::Swap => {
+                log.append(Record::Input(input.clone()));
+                Self::swap_mode(this, |mode, _| (mode.swap(input), ()));
+            },
+            Step::Hooked(trigger) => log.append(Record::Hook(trigger)),
+            Step::Held => return,
+        }
+
+        let to = this.borrow_current().tag();
+        if from != to {
+            let fields =
+                this.borrow_current().fields().into_iter()
+                    .map(|field| (field.name.to_owned(), field.value))
+                    .collect();
+            log.append(Record::Transition { from : from.to_owned(), to : to.to_owned(), fields });
+        }
+    }
+
+    /// Replays every `Record` in `log` into this `Automaton`, in order, in order to reproduce the state of the
+    /// `Automaton` that wrote it via [`next_logged()`](#method.next_logged). Each `Input` is passed straight into the
+    /// `swap()` function of the current `Mode`, and each `Hook` record forces the same hook of this `Automaton` to
+    /// fire, e.g. the timeout for the current `Mode`, without checking its condition. For this to work, this
+    /// `Automaton` should be in the same state as the original was when it started logging, and its timeouts, stall
+    /// limit, and cancellation should be set up in the same way. None of the hooks fire on their own during a replay.
+    /// 
+    /// Replaying stops with an `Err` if a `Hook` record can't be reproduced, or if the `tag()` of the current `Mode`
+    /// ever differs from what the `Transition` records say it should be. Since requests and asynchronous guards are
+    /// one-off closures that are not part of the log, a log containing a `Request` or `Awaiting` hook can't be
+    /// replayed past that point.
+    /// 
+    /// See [`EventLog`](trait.EventLog.html) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::{thread, time::Duration};
+    /// 
+    /// struct DoorFamily;
+    /// impl Family for DoorFamily {
+    ///     type Base = Door;
+    ///     type Mode = Door;
+    ///     type Input = &'static str;
+    ///     type Output = Door;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Door { Closed, Open, Locked }
+    /// impl Mode for Door {
+    ///     type Family = DoorFamily;
+    ///     fn swap(self, input : &'static str) -> Self {
+    ///         match (self, input) {
+    ///             (Door::Closed, "open") => Door::Open,
+    ///             (Door::Closed, "lock") => Door::Locked,
+    ///             (door, _) => door,
+    ///         }
+    ///     }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Door::Closed => "Closed", Door::Open => "Open", Door::Locked => "Locked" }
+    ///     }
+    /// }
+    /// 
+    /// fn door() -> Automaton<DoorFamily> {
+    ///     let mut door = DoorFamily::automaton_with_mode(Door::Closed);
+    ///     Automaton::set_timeout(&mut door, "Open", Duration::from_millis(10), |_| Door::Closed);
+    ///     door
+    /// }
+    /// 
+    /// let mut log = Vec::new();
+    /// let mut original = door();
+    /// Automaton::next_logged(&mut original, "open", &mut log);
+    /// 
+    /// // The door swings shut on its own, discarding the next Input, which is logged as a Timeout instead.
+    /// thread::sleep(Duration::from_millis(20));
+    /// Automaton::next_logged(&mut original, "ignored", &mut log);
+    /// Automaton::next_logged(&mut original, "lock", &mut log);
+    /// assert_eq!(*original, Door::Locked);
+    /// assert!(log.contains(&Record::Hook(Trigger::Timeout)));
+    /// 
+    /// // The replica closes the door at the same point, even though no time passes while replaying.
+    /// let mut replica = door();
+    /// Automaton::replay(&mut replica, log.clone()).unwrap();
+    /// assert_eq!(Automaton::tag(&replica), Automaton::tag(&original));
+    /// 
+    /// // Without the timeout, the replay can't close the door.
+    /// let error = Automaton::<DoorFamily>::rehydrate(Door::Closed, log).unwrap_err();
+    /// assert_eq!(error, ReplayError::MissingHook { index : 2, trigger : Trigger::Timeout });
+    /// ```
+    /// 
+    pub fn replay(this : &mut Self, log : impl IntoIterator<Item = Record<Input>>) -> Result<(), ReplayError> {
+        Self::start(this);
+
+        let mut expected = this.borrow_current().tag().to_owned();
+        let mut len = 0;
+        for (index, record) in log.into_iter().enumerate() {
+            len = index + 1;
+            match record {
+                Record::Input(input) => {
+                    Self::check_replay(this, index, &expected)?;
+                    Self::swap_mode(this, |mode, _| (mode.swap(input), ()));
+                },
+                Record::Hook(trigger) => {
+                    Self::check_replay(this, index, &expected)?;
+                    if !Self::force_hook(this, trigger) {
+                        return Err(ReplayError::MissingHook { index, trigger });
+                    }
+                },
+                Record::Transition { to, .. } => expected = to,
+            }
+        }
+
+        Self::check_replay(this, len, &expected)
+    }
+
+    /// Returns an `Err` if the `tag()` of the current `Mode` is not `expected` before the `Record` at `index` is
+    /// replayed.
+    /// 
+    fn check_replay(this : &Self, index : usize, expected : &str) -> Result<(), ReplayError> {
+        let actual = this.borrow_current().tag();
+        if actual == expected {
+            Ok(())
+        }
+        else {
+            Err(ReplayError::Diverged { index, expected : expected.to_owned(), actual })
+        }
+    }
+
+    /// Creates a new `Automaton` with `initial` as its initial `Mode`, and then reconstructs its current state by
+    /// passing `log` into [`Automaton::replay()`](#method.replay). Since the new `Automaton` has no hooks, this fails
+    /// with `ReplayError::MissingHook` if `log` contains any `Hook` records. In order to replay those, set up the hooks
+    /// of an `Automaton` first and call `replay()` instead.
+    /// 
+    /// See [`EventLog`](trait.EventLog.html) for more details.
+    /// 
+    pub fn rehydrate(initial : F::Mode, log : impl IntoIterator<Item = Record<Input>>) -> Result<Self, ReplayError> {
+        let mut automaton = Self::with_mode(initial);
+        Self::replay(&mut automaton, log)?;
+        Ok(automaton)
+    }
+
+    /// Assembles the `Input` for the current `Mode` from a tuple of several logical inputs via
+    /// [`FromInputs`](trait.FromInputs.html), and then passes it into `Automaton::next_with_input()`.
+    /// 
+    /// See [`Inputs`](struct.Inputs.html) for more details.
+    /// 
+    pub fn next_with_inputs<T>(this : &mut Self, inputs : Inputs<T>)
+        where Input : FromInputs<T>
+    {
+        Self::next_with_input(this, Input::from_inputs(inputs.build()));
+    }
+
+    /// Drains all pending `Input`s from `inbox`, passing each one into `Automaton::next_with_input()` in the order in
+    /// which they were received, and returns the number of `Input`s that were processed. Draining stops early if the
+    /// `Automaton` is paused or finishes, leaving any remaining `Input`s in the `inbox`.
+    /// 
+    /// **NOTE:** As with `next_with_input()`, an `Input` that arrives while the current `Mode` is still held by the
+    /// minimum dwell time is discarded without calling `swap()`.
+    /// 
+    /// See [`Inbox`](struct.Inbox.html) for more details.
+    /// 
+    pub fn next_with_inbox(this : &mut Self, inbox : &Inbox<Input>) -> usize {
+        let mut processed = 0;
+        while !Self::is_paused(this) && !Self::is_finished(this) {
+            match inbox.try_recv() {
+                Some(input) => Self::next_with_input(this, input),
+                None => break,
+            }
+            processed += 1;
+        }
+        processed
+    }
+
+    /// Same as [`next_with_inbox()`](#method.next_with_inbox), except that `Input`s are drained from an
+    /// [`IsrQueue`](struct.IsrQueue.html), which can be filled from an interrupt handler without locking or
+    /// allocating. Returns the number of `Input`s that were processed.
+    /// 
+    /// See [`IsrQueue`](struct.IsrQueue.html) for more details.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct MotorFamily;
+    /// impl Family for MotorFamily {
+    ///     type Base = Motor;
+    ///     type Mode = Motor;
+    ///     type Input = Event;
+    ///     type Output = Motor;
+    /// }
+    /// 
+    /// #[derive(Clone, Copy, Debug, PartialEq)]
+    /// enum Event { Fault, Clear }
+    /// impl Packed for Event {
+    ///     fn pack(self) -> u32 { self as u32 }
+    ///     fn unpack(bits : u32) -> Self { if bits == 0 { Event::Fault } else { Event::Clear } }
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum Motor { Running, Faulted }
+    /// impl Mode for Motor {
+    ///     type Family = MotorFamily;
+    ///     fn swap(self, event : Event) -> Self {
+    ///         match event { Event::Fault => Motor::Faulted, Event::Clear => Motor::Running }
+    ///     }
+    ///     fn tag(&self) -> &'static str {
+    ///         match self { Motor::Running => "Running", Motor::Faulted => "Faulted" }
+    ///     }
+    /// }
+    /// 
+    /// static EVENTS : IsrQueue<Event, 4> = IsrQueue::new();
+    /// 
+    /// // Called from the overcurrent interrupt handler.
+    /// fn on_overcurrent() {
+    ///     let _ = EVENTS.push(Event::Fault);
+    /// }
+    /// 
+    /// let mut motor = MotorFamily::automaton_with_mode(Motor::Running);
+    /// on_overcurrent();
+    /// assert_eq!(Automaton::next_with_queue(&mut motor, &EVENTS), 1);
+    /// assert_eq!(*motor, Motor::Faulted);
+    /// ```
+    /// 
+    pub fn next_with_queue<const N : usize>(this : &mut Self, queue : &IsrQueue<Input, N>) -> usize
+        where Input : Packed
+    {
+        let mut processed = 0;
+        while !Self::is_paused(this) && !Self::is_finished(this) {
+            match queue.pop() {
+                Some(input) => Self::next_with_input(this, input),
+                None => break,
+            }
+            processed += 1;
+        }
+        processed
+    }
+}
+
+impl<F, M, Output> Automaton<F>
+    where
+        F : Family<Mode = M, Input = (), Output = (M, Output)> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// For `Mode` implementations that return a tuple with a `Mode` and some other parameter, calls `swap()` on the
+    /// current `Mode` to determine whether it wants to transition out. Whatever `Mode` was returned as the first tuple
+    /// parameter will be switched in as active, and the second tuple parameter will be returned from the function.
+    /// Calling this function *may* change the current `Mode`, but not necessarily.
+    /// 
+    /// **NOTE:** Since an `Output` must always be returned, this function calls `swap()` even if the `Automaton` is
+    /// paused or [finished](#method.is_finished), and never applies pending transition requests, timeouts, stall
+    /// limits, asynchronous guards, or cancellation, since those replace the call to `swap()` for the step in which
+    /// they are applied. Use [`try_next_with_output()`](#method.try_next_with_output) to respect all of those instead.
+    /// 
+    /// See [`Mode::swap()`](trait.Mode.html#tymethod.swap) for more details.
+    /// 
+    pub fn next_with_output(this : &mut Self) -> Output {
+        Self::next_with_input_and_output(this, ())
+    }
+
+    /// Same as `Automaton::next_with_output()`, except that it respects the minimum dwell time of the `Automaton`,
+    /// returning `None` without calling `swap()` if the current `Mode` is not allowed to `swap()` yet, or if the
+    /// `Automaton` is paused or finished.
+    /// 
+    /// See [`Automaton::set_min_dwell()`](#method.set_min_dwell) for more details.
+    /// 
+    pub fn try_next_with_output(this : &mut Self) -> Option<Output> {
+        Self::try_next_with_input_and_output(this, ())
+    }
+
+    /// Same as `Automaton::try_next_with_output()`, except that the `Output` is passed into the output sink of the
+    /// `Automaton` instead of being returned. Returns `true` if `swap()` was called.
+    /// 
+    /// See [`Automaton::set_output_sink()`](#method.set_output_sink) for more details.
+    /// 
+    pub fn next_to_sink(this : &mut Self) -> bool
+        where Output : 'static
+    {
+        Self::next_with_input_to_sink(this, ())
+    }
+}
+
+impl<F, M, Input, Output> Automaton<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = (M, Output)> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Same as `Automaton::next_with_output()`, except that it passes `input` into the `swap()` function.
+    /// 
+    /// See [`Automaton::next()`](#method.next_with_output) for more details.
+    /// 
+    pub fn next_with_input_and_output(this : &mut Self, input : Input) -> Output {
+        Self::start(this);
+        Self::beat(this);
+        Self::swap_mode(this, |mode, _| mode.swap(input))
+    }
+
+    /// Same as `Automaton::next_with_input_and_output()`, except that the `Output` returned by `swap()` is passed into
+    /// [`Lend::lend()`](trait.Lend.html#tymethod.lend) on the new current `Mode`, and the resulting `Loan` is returned.
+    /// The `Loan` may borrow from the current `Mode`, and therefore keeps the `Automaton` borrowed for as long as it is
+    /// in use.
+    /// 
+    /// See [`Lend`](trait.Lend.html) for more details.
+    /// 
+    pub fn next_lending(this : &mut Self, input : Input) -> M::Loan<'_>
+        where M : Lend<Output>
+    {
+        let key = Self::next_with_input_and_output(this, input);
+        this.borrow_current().lend(key)
+    }
+
+    /// Same as `Automaton::next_with_input_and_output()`, except that it respects the minimum dwell time of the
+    /// `Automaton`, returning `None` without calling `swap()` if the current `Mode` is not allowed to `swap()` yet, or
+    /// if the `Automaton` is paused or finished.
+    /// 
+    /// See [`Automaton::set_min_dwell()`](#method.set_min_dwell) for more details.
+    /// 
+    pub fn try_next_with_input_and_output(this : &mut Self, input : Input) -> Option<Output> {
+        if Self::hold(this) {
+            None
+        }
+        else {
+            Some(Self::swap_mode(this, |mode, _| mode.swap(input)))
+        }
+    }
+
+    /// Registers `sink` as the output sink of the `Automaton`, replacing any sink that was registered before. Each
+    /// `Output` produced by [`Automaton::next_to_sink()`](#method.next_to_sink) or
+    /// [`Automaton::next_with_input_to_sink()`](#method.next_with_input_to_sink) is passed into `sink`, so that the
+    /// loop driving the `Automaton` does not have to forward it to whatever consumes it.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::sync::mpsc;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Ticker;
+    ///     type Mode = Ticker;
+    ///     type Input = u32;
+    ///     type Output = (Ticker, String);
+    /// }
+    /// 
+    /// struct Ticker;
+    /// impl Mode for Ticker {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, tick : u32) -> (Self, String) { (self, format!("tick {}", tick)) }
+    /// }
+    /// 
+    /// let (sender, receiver) = mpsc::channel();
+    /// let mut automaton = SomeFamily::automaton_with_mode(Ticker);
+    /// Automaton::set_output_sink(&mut automaton, move |message| sender.send(message).unwrap());
+    /// 
+    /// for tick in 0..2 {
+    ///     Automaton::next_with_input_to_sink(&mut automaton, tick);
+    /// }
+    /// 
+    /// assert_eq!(receiver.try_iter().collect::<Vec<_>>(), vec!["tick 0", "tick 1"]);
+    /// ```
+    /// 
+    pub fn set_output_sink(this : &mut Self, sink : impl FnMut(Output) + Send + Sync + 'static)
+        where Output : 'static
+    {
+        let sink : Box<OutputSink<Output>> = Box::new(sink);
+        this.sink = Some(Box::new(sink));
+    }
+
+    /// Removes the output sink of the `Automaton`, if any. Once removed, `Output`s produced by
+    /// `Automaton::next_with_input_to_sink()` are dropped.
+    /// 
+    pub fn clear_output_sink(this : &mut Self) {
+        this.sink = None;
+    }
+
+    /// Same as `Automaton::try_next_with_input_and_output()`, except that the `Output` is passed into the output sink of
+    /// the `Automaton` instead of being returned. If no output sink was registered, the `Output` is dropped. Returns
+    /// `true` if `swap()` was called.
+    /// 
+    /// See [`Automaton::set_output_sink()`](#method.set_output_sink) for more details.
+    /// 
+    pub fn next_with_input_to_sink(this : &mut Self, input : Input) -> bool
+        where Output : 'static
+    {
+        match Self::try_next_with_input_and_output(this, input) {
+            Some(output) => {
+                let sink =
+                    this.sink.as_mut()
+                        .and_then(|sink| sink.downcast_mut::<Box<OutputSink<Output>>>());
+                if let Some(sink) = sink {
+                    sink(output);
+                }
+                true
+            },
+            None => false,
+        }
+    }
 }
 
-impl<F> Automaton<F>
-    where F : Family + ?Sized
+impl<F, M> Automaton<F>
+    where
+        F : Family<Mode = M, Input = Blackboard, Output = (M, Blackboard)> + ?Sized,
+        M : Mode<Family = F>,
 {
-    /// Creates a new `Automaton` with the specified `mode`, which will be the initial active `Mode` for the `Automaton`
-    /// that is returned.
+    /// For a `Family` that passes a [`Blackboard`](struct.Blackboard.html) into `Mode::swap()` and returns it back out
+    /// again, moves the `Blackboard` owned by this `Automaton` into the `swap()` function of the current `Mode`, and
+    /// stores the `Blackboard` that is returned back in the `Automaton`. This allows the current `Mode` to read and
+    /// modify the shared `Blackboard` while deciding whether to transition. Scoped entries that `swap()` inserts belong
+    /// to the `Mode` it returns, as described in [Scoped entries](struct.Blackboard.html#scoped-entries).
     /// 
-    /// **NOTE:** If `F::Base` is a type that implements `Default`, [`new()`](struct.Automaton.html#method.new) can be
-    /// used instead.
+    /// **NOTE:** Since the `Blackboard` is moved into `swap()`, it is dropped along with the current `Mode` if `swap()`
+    /// panics. Besides being [`Poisoned`](struct.Poisoned.html), the `Automaton` is then left with an empty
+    /// `Blackboard`.
     /// 
-    /// Since the `F` parameter cannot be determined automatically, using this function usually requires the use of the
-    /// turbofish, e.g. `Automaton::<SomeFamily>::with_mode()`. To avoid that, `Family` provides an
-    /// `automaton_with_mode()` associated function that can be used instead. See
-    /// [`Family::automaton_with_mode()`](trait.Family.html#method.automaton_with_mode) for more details.
+    /// See [`Automaton::next()`](#method.next) for more details.
     /// 
     /// # Usage
     /// ```
@@ -186,135 +2745,360 @@ impl<F> Automaton<F>
     /// impl Family for SomeFamily {
     ///     type Base = SomeMode;
     ///     type Mode = SomeMode;
-    ///     type Input = ();
-    ///     type Output = SomeMode;
+    ///     type Input = Blackboard;
+    ///     type Output = (SomeMode, Blackboard);
     /// }
     /// 
-    /// enum SomeMode { A, B, C };
+    /// struct Alarm;
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// enum SomeMode { Patrolling, Alerted }
     /// impl Mode for SomeMode {
     ///     type Family = SomeFamily;
-    ///     fn swap(mut self, _input : ()) -> Self {
-    ///         // TODO: Logic for transitioning between states goes here.
-    ///         self
+    ///     fn swap(self, blackboard : Blackboard) -> (Self, Blackboard) {
+    ///         let next = if blackboard.contains::<Alarm>() { SomeMode::Alerted } else { self };
+    ///         (next, blackboard)
     ///     }
     /// }
     /// 
-    /// // Create an Automaton with A as the initial Mode.
-    /// // NOTE: We could alternatively use SomeFamily::automaton_with_mode() here to shorten this.
-    /// let mut automaton = Automaton::<SomeFamily>::with_mode(SomeMode::A);
+    /// let mut automaton = SomeFamily::automaton_with_mode(SomeMode::Patrolling);
+    /// 
+    /// Automaton::next_with_blackboard(&mut automaton);
+    /// assert_eq!(*automaton, SomeMode::Patrolling);
+    /// 
+    /// Automaton::blackboard_mut(&mut automaton).insert(Alarm);
+    /// Automaton::next_with_blackboard(&mut automaton);
+    /// assert_eq!(*automaton, SomeMode::Alerted);
     /// ```
     /// 
-    pub fn with_mode(mode : F::Mode) -> Self {
-        Self {
-            mode : Some(mode),
+    pub fn next_with_blackboard(this : &mut Self) {
+        if Self::hold(this) {
+            return;
         }
+
+        Self::swap_mode(this, |mode, blackboard| {
+            let (next, returned) = mode.swap(mem::take(blackboard));
+            *blackboard = returned;
+            (next, ())
+        })
+    }
+
+    /// Calls `update` with the current `Mode` as an `&mut F::Base` and the `Blackboard` owned by this `Automaton`, and
+    /// then immediately calls [`Automaton::next_with_blackboard()`](#method.next_with_blackboard). Whatever `update`
+    /// returns will be returned from this function.
+    /// 
+    /// See [`Automaton::step_with()`](#method.step_with) for more details.
+    /// 
+    pub fn step_with_blackboard<R>(this : &mut Self, update : impl FnOnce(&mut F::Base, &mut Blackboard) -> R) -> R
+        where M : BorrowMut<F::Base>
+    {
+        let mode =
+            this.mode.as_mut()
+                .expect("Cannot borrow current Mode because another swap is already taking place!")
+                .borrow_mut();
+        let result = update(mode, &mut this.blackboard);
+        Self::next_with_blackboard(this);
+        result
     }
 }
 
 impl<F> Automaton<F>
     where
         F : Family + ?Sized,
-        F::Mode : Borrow<F::Base>,
+        F::Mode : Finish,
 {
-    /// Returns an immutable reference to the current `Mode` as an `&F::Base`, allowing immutable functions to be called
-    /// on the inner `Mode`.
+    /// Consumes a finished `Automaton`, returning the `Outcome` produced by its final `Mode`. If the `Automaton` is not
+    /// [complete](#method.is_complete), i.e. if it has not finished yet, or if it was [stopped](#method.stop) while a
+    /// non-final `Mode` was current, a [`FinishError`](struct.FinishError.html) is returned instead, from which the
+    /// `Automaton` can be recovered. Hence, `Finish::finish()` is only ever called on a final `Mode`.
     /// 
-    /// **NOTE:** `Automaton` also implements `Deref<Target = F::Base>`, allowing all `Base` members to be accessed via
-    /// a reference to the `Automaton`. Hence, you can usually leave the `borrow_mode()` out and simply treat the
-    /// `Automaton` as if it were an object of type `Base`.
+    /// # Usage
+    /// ```
+    /// use mode::*;
     /// 
-    pub fn borrow_mode(&self) -> &F::Base {
-        self.mode.as_ref()
-            .expect("Cannot borrow current Mode because another swap is already taking place!")
-            .borrow()
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Download;
+    ///     type Mode = Download;
+    ///     type Input = ();
+    ///     type Output = Download;
+    /// }
+    /// 
+    /// enum Download { Fetching { attempts : u32 }, Done(Vec<u8>), Failed }
+    /// 
+    /// impl Mode for Download {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         match self {
+    ///             Download::Fetching { attempts } if attempts < 2 => Download::Fetching { attempts : attempts + 1 },
+    ///             Download::Fetching { .. } => Download::Done(vec![1, 2, 3]),
+    ///             other => other,
+    ///         }
+    ///     }
+    ///     fn is_final(&self) -> bool { !matches!(self, Download::Fetching { .. }) }
+    /// }
+    /// 
+    /// impl Finish for Download {
+    ///     type Outcome = Result<Vec<u8>, ()>;
+    ///     fn finish(self) -> Self::Outcome {
+    ///         match self { Download::Done(bytes) => Ok(bytes), _ => Err(()) }
+    ///     }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Download::Fetching { attempts : 0 });
+    /// 
+    /// // Calling finish() too early returns the Automaton as part of the error.
+    /// automaton = Automaton::finish(automaton).unwrap_err().into_inner();
+    /// 
+    /// while !Automaton::is_finished(&automaton) {
+    ///     Automaton::next(&mut automaton);
+    /// }
+    /// assert_eq!(Automaton::finish(automaton).unwrap(), Ok(vec![1, 2, 3]));
+    /// 
+    /// // An Automaton that was stopped before reaching a final Mode cannot be finished either.
+    /// let mut stopped = SomeFamily::automaton_with_mode(Download::Fetching { attempts : 0 });
+    /// Automaton::stop(&mut stopped);
+    /// assert!(Automaton::is_finished(&stopped) && !Automaton::is_complete(&stopped));
+    /// assert!(Automaton::finish(stopped).is_err());
+    /// ```
+    /// 
+    #[allow(clippy::result_large_err)]
+    pub fn finish(mut this : Self) -> Result<<F::Mode as Finish>::Outcome, FinishError<F>> {
+        if !Self::is_complete(&this) {
+            return Err(FinishError::new(this));
+        }
+
+        let mode =
+            this.mode.take()
+                .expect("Cannot finish Automaton because another swap is already taking place!");
+        Ok(mode.finish())
+    }
+
+    /// Consumes the `Automaton`, returning the [`Outcome`](enum.Outcome.html) of the terminal state that its current
+    /// `Mode` maps into via [`Finish::finish()`](trait.Finish.html#tymethod.finish). If the `Automaton` has not reached
+    /// a terminal state, i.e. one for which [`Mode::is_final()`](trait.Mode.html#method.is_final) returns `true`, it is
+    /// [stopped](#method.stop) if it hasn't been already, and `Outcome::Cancelled` is returned instead.
+    /// 
+    /// The `Finish::Outcome` of the current `Mode` can be either an `Outcome` or a `Result`.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = Upload;
+    ///     type Mode = Upload;
+    ///     type Input = ();
+    ///     type Output = Upload;
+    /// }
+    /// 
+    /// enum Upload { Sending, Sent, Rejected }
+    /// 
+    /// impl Mode for Upload {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         match self { Upload::Sending => Upload::Sent, other => other }
+    ///     }
+    ///     fn is_final(&self) -> bool { !matches!(self, Upload::Sending) }
+    /// }
+    /// 
+    /// impl Finish for Upload {
+    ///     type Outcome = Result<u32, &'static str>;
+    ///     fn finish(self) -> Self::Outcome {
+    ///         match self { Upload::Rejected => Err("rejected"), _ => Ok(200) }
+    ///     }
+    /// }
+    /// 
+    /// let automaton = SomeFamily::automaton_with_mode(Upload::Sending);
+    /// assert_eq!(Automaton::into_outcome(automaton), Outcome::Cancelled);
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Upload::Sending);
+    /// Automaton::next(&mut automaton);
+    /// assert_eq!(Automaton::into_outcome(automaton), Outcome::Succeeded(200));
+    /// ```
+    /// 
+    pub fn into_outcome<T, E>(mut this : Self) -> Outcome<T, E>
+        where <F::Mode as Finish>::Outcome : Into<Outcome<T, E>>
+    {
+        if !Self::is_complete(&this) {
+            Self::stop(&mut this);
+            return Outcome::Cancelled;
+        }
+
+        let mode =
+            this.mode.take()
+                .expect("Cannot finish Automaton because another swap is already taking place!");
+        mode.finish().into()
     }
 }
 
 impl<F> Automaton<F>
     where
         F : Family + ?Sized,
-        F::Mode : BorrowMut<F::Base>,
+        F::Mode : FinalMode,
 {
-    /// Returns a mutable reference to the current `Mode` as a `&mut F::Base`, allowing mutable functions to be called
-    /// on the inner `Mode`.
+    /// Consumes a finished `Automaton` whose current `Mode` is a terminal state, returning the value that it computed.
+    /// If the `Automaton` has not [finished](#method.is_finished) yet, or if it was stopped in a `Mode` that is not a
+    /// terminal state, i.e. one for which [`Mode::is_final()`](trait.Mode.html#method.is_final) returns `false`, a
+    /// [`FinishError`](struct.FinishError.html) is returned instead, from which the `Automaton` can be recovered.
     /// 
-    /// **NOTE:** `Automaton` also implements `DerefMut<Target = Base>`, allowing all `Base` members to be accessed via
-    /// a reference to the `Automaton`. Hence, you can usually leave the `borrow_mode_mut()` out and simply treat the
-    /// `Automaton` as if it were an object of type `Base`.
+    /// See [`FinalMode`](trait.FinalMode.html) for more details.
     /// 
-    pub fn borrow_mode_mut(&mut self) -> &mut F::Base {
-        self.mode.as_mut()
-            .expect("Cannot borrow current Mode because another swap is already taking place!")
-            .borrow_mut()
+    /// # Panics
+    /// If the current `Mode` is a terminal state, but its [`into_output()`](trait.FinalMode.html#method.into_output)
+    /// function returns `None`, which breaks the contract of `FinalMode`.
+    /// 
+    #[allow(clippy::result_large_err)]
+    pub fn into_output(mut this : Self) -> Result<<F::Mode as FinalMode>::Output, FinishError<F>> {
+        if !Self::is_complete(&this) {
+            return Err(FinishError::new(this));
+        }
+
+        let mode =
+            this.mode.take()
+                .expect("Cannot take output of Automaton because another swap is already taking place!");
+        Ok(mode.into_output().expect("The final Mode of the Automaton did not produce an output!"))
     }
 }
 
-impl<F, M> Automaton<F>
+impl<F> Automaton<F>
     where
-        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
-        M : Mode<Family = F>,
+        F : Family + ?Sized,
+        F::Mode : Clone,
 {
-    /// Calls `swap()` on the current `Mode` to determine whether it wants to transition out, swapping in whatever
-    /// `Mode` it returns as a result. Calling this function *may* change the current `Mode`, but not necessarily.
+    /// Creates a new `Automaton` with a clone of the current `Mode`, which can be stepped speculatively without
+    /// affecting the original. The fork starts out in the same [`Phase`](enum.Phase.html), with the same step counts
+    /// and time in the current `Mode`, but without any of the subscribers, timeouts, pending requests, output sink,
+    /// watchdog, stall limit, cancellation token, name, or `Blackboard` of the original.
     /// 
-    /// See [`Mode::swap()`](trait.Mode.html#tymethod.swap) for more details.
+    /// Large state payloads stored inside of the `Mode` can be wrapped in a [`CowState`](struct.CowState.html), so that
+    /// forking the `Automaton` doesn't copy them until the fork actually writes to them.
     /// 
-    pub fn next(this : &mut Self) {
-        Self::next_with_input(this, ());
+    /// # Panics
+    /// If the current `Mode` is being swapped, or if the `Automaton` has been [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn fork(this : &Self) -> Self {
+        let mode =
+            this.mode.as_ref()
+                .expect("Cannot fork Automaton because it has no current Mode!")
+                .clone();
+
+        let mut fork = Self::with_mode(mode);
+        fork.phase = this.phase;
+        fork.steps = this.steps;
+        fork.steps_in_mode = this.steps_in_mode;
+        fork.entered_at = this.entered_at;
+        fork.paused_at = this.paused_at;
+        fork.min_dwell = this.min_dwell;
+        fork
     }
 }
 
-impl<F, M, Input> Automaton<F>
-    where
-        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
-        M : Mode<Family = F>,
+impl<F> Automaton<F>
+    where F : Family + ?Sized
 {
-    /// Same as `Automaton::next()`, except that it passes `input` into the `swap()` function.
+    /// Consumes a [complete](#method.is_complete) `Automaton`, passing its final `Mode` into `convert` in order to create
+    /// a new `Automaton` over `G`, which inherits the `Blackboard`, name, and parent of the old one.
     /// 
-    /// See [`Automaton::next()`](#method.next) for more details.
+    /// See [`Bridge`](struct.Bridge.html) for more details.
     /// 
-    pub fn next_with_input(this : &mut Self, input : Input) {
-        let next =
+    #[allow(clippy::result_large_err)]
+    pub(crate) fn hand_off<G>(mut this : Self, convert : &dyn Fn(F::Mode) -> G::Mode)
+        -> Result<Automaton<G>, FinishError<F>>
+        where G : Family + ?Sized
+    {
+        if !Self::is_complete(&this) {
+            return Err(FinishError::new(this));
+        }
+
+        let mode =
             this.mode.take()
-                .expect("Cannot swap to next Mode because another swap is already taking place!")
-                .swap(input);
-        this.mode = Some(next);
+                .expect("Cannot hand off Automaton because another swap is already taking place!");
+
+        let mut next = Automaton::with_mode(convert(mode));
+        next.blackboard = std::mem::take(&mut this.blackboard);
+        next.name = this.name.take();
+        next.parent = this.parent.take();
+        Automaton::update_path(&mut next);
+        Ok(next)
     }
 }
 
-impl<F, M, Output> Automaton<F>
+impl<F, B> Automaton<F>
     where
-        F : Family<Mode = M, Input = (), Output = (M, Output)> + ?Sized,
-        M : Mode<Family = F>,
+        F : Family<Base = B, Mode = Box<B>> + ?Sized,
+        B : boxed::AsAny + ?Sized,
 {
-    /// For `Mode` implementations that return a tuple with a `Mode` and some other parameter, calls `swap()` on the
-    /// current `Mode` to determine whether it wants to transition out. Whatever `Mode` was returned as the first tuple
-    /// parameter will be switched in as active, and the second tuple parameter will be returned from the function.
-    /// Calling this function *may* change the current `Mode`, but not necessarily.
+    /// For a `Family` that stores its `Mode`s in a `Box` and whose `Base` type extends
+    /// [`boxed::AsAny`](boxed/trait.AsAny.html), consumes the `Automaton` and returns the current `Mode` as a `Box<T>`
+    /// if it is an instance of the concrete type `T`. Otherwise, the `Automaton` is returned unchanged as an `Err`.
     /// 
-    /// See [`Mode::swap()`](trait.Mode.html#tymethod.swap) for more details.
+    /// This can be used to harvest the data stored in the final `Mode` of a state machine once it has finished.
     /// 
-    pub fn next_with_output(this : &mut Self) -> Output {
-        Self::next_with_input_and_output(this, ())
-    }
-}
-
-impl<F, M, Input, Output> Automaton<F>
-    where
-        F : Family<Mode = M, Input = Input, Output = (M, Output)> + ?Sized,
-        M : Mode<Family = F>,
-{
-    /// Same as `Automaton::next_with_output()`, except that it passes `input` into the `swap()` function.
+    /// # Usage
+    /// ```
+    /// use mode::*;
     /// 
-    /// See [`Automaton::next()`](#method.next_with_output) for more details.
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = dyn SomeTrait;
+    ///     type Mode = Box<dyn SomeTrait>;
+    ///     type Input = ();
+    ///     type Output = Box<dyn SomeTrait>;
+    /// }
     /// 
-    pub fn next_with_input_and_output(this : &mut Self, input : Input) -> Output {
-        let (next, result) =
-            this.mode.take()
-                .expect("Cannot swap to next Mode because another swap is already taking place!")
-                .swap(input);
-        this.mode = Some(next);
-        result
+    /// trait SomeTrait : boxed::Mode<Family = SomeFamily> + boxed::AsAny { }
+    /// 
+    /// struct Running { count : u32 }
+    /// impl SomeTrait for Running { }
+    /// impl boxed::Mode for Running {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self : Box<Self>, _input : ()) -> Box<dyn SomeTrait> {
+    ///         if self.count >= 3 { Box::new(Finished { total : self.count }) }
+    ///         else { Box::new(Running { count : self.count + 1 }) }
+    ///     }
+    /// }
+    /// 
+    /// struct Finished { total : u32 }
+    /// impl SomeTrait for Finished { }
+    /// impl boxed::Mode for Finished {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self : Box<Self>, _input : ()) -> Box<dyn SomeTrait> { self }
+    /// }
+    /// 
+    /// let mut automaton = SomeFamily::automaton_with_mode(Box::new(Running { count : 0 }));
+    /// 
+    /// // The current Mode is not a Finished yet, so we get the Automaton back.
+    /// automaton =
+    ///     match Automaton::try_into_mode::<Finished>(automaton) {
+    ///         Ok(_) => unreachable!(),
+    ///         Err(automaton) => automaton,
+    ///     };
+    /// 
+    /// for _ in 0..4 { Automaton::next(&mut automaton); }
+    /// 
+    /// let finished = Automaton::try_into_mode::<Finished>(automaton).ok().unwrap();
+    /// assert_eq!(finished.total, 3);
+    /// ```
+    /// 
+    #[allow(clippy::result_large_err)]
+    pub fn try_into_mode<T>(mut this : Self) -> Result<Box<T>, Self>
+        where T : Any
+    {
+        let is_match =
+            this.mode.as_ref()
+                .expect("Cannot take current Mode because another swap is already taking place!")
+                .as_any()
+                .is::<T>();
+
+        if is_match {
+            let mode = this.mode.take().unwrap();
+            Ok(mode.into_any().downcast::<T>().unwrap())
+        }
+        else {
+            Err(this)
+        }
     }
 }
 
@@ -432,9 +3216,7 @@ impl<F> Automaton<F>
     /// ```
     /// 
     pub fn new() -> Self {
-        Self {
-            mode : Some(Default::default()),
-        }
+        Self::with_mode(Default::default())
     }
 }
 
@@ -494,11 +3276,30 @@ impl<F> fmt::Debug for Automaton<F>
 {
     fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
         formatter.debug_struct("Automaton")
+            .field("path", &self.path)
             .field("mode", &self.borrow_mode())
             .finish()
     }
 }
 
+/// When the `defmt` feature is enabled, `Automaton` implements `defmt::Format` for every `Family`, and will print its
+/// [`path`](struct.Automaton.html#method.path), its [`Phase`](enum.Phase.html), and the `tag()` of its current `Mode`,
+/// which is `None` if the `Automaton` is [poisoned](struct.Poisoned.html).
+/// 
+#[cfg(feature = "defmt")]
+impl<F> defmt::Format for Automaton<F>
+    where F : Family + ?Sized
+{
+    fn format(&self, formatter : defmt::Formatter) {
+        defmt::write!(
+            formatter,
+            "Automaton {{ path: {}, phase: {}, tag: {} }}",
+            self.path.as_deref(),
+            self.phase,
+            self.mode.as_ref().map(|mode| mode.tag()))
+    }
+}
+
 /// If `Base` implements `std::fmt::Display`, `Automaton` also implements `Display`, and will print its current `mode`.
 /// 
 /// # Usage
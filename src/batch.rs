@@ -0,0 +1,316 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode};
+use std::fmt;
+
+/// A lightweight container that stores the current `Mode` of many independent state machines within the same `Family`
+/// contiguously in memory, and steps all of them in a single, tight loop.
+/// 
+/// Unlike a `Vec<Automaton<F>>`, a `Batch` stores its state as a structure of arrays: one contiguous array for the
+/// current `Mode` of every machine, and a separate array for the number of steps each machine has spent in its current
+/// `Mode`. For `Family`s whose `Mode` is an `enum` stored inline, this keeps all machine states side by side, which is
+/// much friendlier to the cache than stepping many separately allocated `Automaton`s, e.g. when simulating a crowd of
+/// thousands of agents.
+/// 
+/// In order to keep the stepping loop tight, a `Batch` only calls `Mode::swap()` on each machine, and skips any machine
+/// whose current `Mode` is [final](trait.Mode.html#method.is_final). None of the other hooks or bookkeeping provided by
+/// `Automaton` are supported. Machines are identified by their index in the `Batch`, which is returned by
+/// [`push()`](#method.push).
+/// 
+/// Each machine is swapped in place, one at a time. If `swap()` panics, only the machine that panicked is lost: as with
+/// [`swap_remove()`](#method.swap_remove), the last machine in the `Batch` is moved into its index, and every other
+/// machine keeps its current `Mode`.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct AgentFamily;
+/// impl Family for AgentFamily {
+///     type Base = Agent;
+///     type Mode = Agent;
+///     type Input = ();
+///     type Output = Agent;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Agent { Idle, Walking { distance : u32 } }
+/// impl Mode for Agent {
+///     type Family = AgentFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Agent::Idle => Agent::Walking { distance : 0 },
+///             Agent::Walking { distance } => Agent::Walking { distance : distance + 1 },
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Agent::Idle => "Idle", Agent::Walking { .. } => "Walking" }
+///     }
+/// }
+/// 
+/// let mut crowd = Batch::<AgentFamily>::with_capacity(1000);
+/// for _ in 0..1000 { crowd.push(Agent::Idle); }
+/// 
+/// crowd.step_all();
+/// crowd.step_all();
+/// 
+/// assert!(crowd.modes().iter().all(|agent| *agent == Agent::Walking { distance : 1 }));
+/// assert_eq!(crowd.steps_in_mode(0), Some(1));
+/// ```
+/// 
+pub struct Batch<F>
+    where F : Family + ?Sized
+{
+    modes : Vec<F::Mode>,
+    steps_in_mode : Vec<u64>,
+}
+
+impl<F> Default for Batch<F>
+    where F : Family + ?Sized
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> Batch<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new, empty `Batch`.
+    /// 
+    pub fn new() -> Self {
+        Self { modes : Vec::new(), steps_in_mode : Vec::new() }
+    }
+
+    /// Creates a new, empty `Batch` with room for at least `capacity` machines before reallocating.
+    /// 
+    pub fn with_capacity(capacity : usize) -> Self {
+        Self { modes : Vec::with_capacity(capacity), steps_in_mode : Vec::with_capacity(capacity) }
+    }
+
+    /// Adds a new machine to the `Batch` with `mode` as its initial `Mode`, returning the index of the new machine.
+    /// 
+    pub fn push(&mut self, mode : F::Mode) -> usize {
+        self.modes.push(mode);
+        self.steps_in_mode.push(0);
+        self.modes.len() - 1
+    }
+
+    /// Removes the machine at `index` from the `Batch` and returns its current `Mode`. The last machine in the `Batch`
+    /// is moved into its place, so this does not preserve ordering, but is `O(1)`.
+    /// 
+    /// # Panics
+    /// Panics if `index` is out of bounds.
+    /// 
+    pub fn swap_remove(&mut self, index : usize) -> F::Mode {
+        self.steps_in_mode.swap_remove(index);
+        self.modes.swap_remove(index)
+    }
+
+    /// Releases any spare capacity left behind by machines that have been removed from the `Batch`, so that memory
+    /// usage stays bounded over long sessions. Since this may reallocate, it is best called during periods of low
+    /// activity, e.g. after unloading a level.
+    /// 
+    pub fn compact(&mut self) {
+        self.modes.shrink_to_fit();
+        self.steps_in_mode.shrink_to_fit();
+    }
+
+    /// Returns the number of machines in the `Batch`.
+    /// 
+    pub fn len(&self) -> usize {
+        self.modes.len()
+    }
+
+    /// Returns `true` if the `Batch` contains no machines.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.modes.is_empty()
+    }
+
+    /// Returns an immutable reference to the current `Mode` of the machine at `index`, if any.
+    /// 
+    pub fn get(&self, index : usize) -> Option<&F::Mode> {
+        self.modes.get(index)
+    }
+
+    /// Returns a mutable reference to the current `Mode` of the machine at `index`, if any.
+    /// 
+    pub fn get_mut(&mut self, index : usize) -> Option<&mut F::Mode> {
+        self.modes.get_mut(index)
+    }
+
+    /// Returns the number of steps that the machine at `index` has spent in its current `Mode`, if any. As with
+    /// `Automaton::steps_in_mode()`, this is reset to `0` whenever the `tag()` of the current `Mode` changes.
+    /// 
+    pub fn steps_in_mode(&self, index : usize) -> Option<u64> {
+        self.steps_in_mode.get(index).copied()
+    }
+
+    /// Returns the current `Mode` of every machine in the `Batch` as a contiguous slice, ordered by index.
+    /// 
+    pub fn modes(&self) -> &[F::Mode] {
+        &self.modes
+    }
+
+    /// Returns the current `Mode` of every machine in the `Batch` as a contiguous, mutable slice, ordered by index.
+    /// 
+    pub fn modes_mut(&mut self) -> &mut [F::Mode] {
+        &mut self.modes
+    }
+
+    /// Swaps every machine in the `Batch` that is not in a final `Mode`, using `swap` to produce the next `Mode`.
+    /// 
+    fn swap_all(&mut self, mut swap : impl FnMut(usize, F::Mode) -> F::Mode) {
+        for index in 0..self.modes.len() {
+            if self.modes[index].is_final() {
+                continue;
+            }
+
+            // NOTE: The machine is moved out via swap_remove() and moved back into place once it has swapped, so that
+            // if swap() panics, only the panicking machine is lost, exactly as if swap_remove() had been called on it.
+            let mode = self.modes.swap_remove(index);
+            let steps_in_mode = self.steps_in_mode.swap_remove(index);
+
+            let from = mode.tag();
+            let next = swap(index, mode);
+            let steps_in_mode = if next.tag() == from { steps_in_mode + 1 } else { 0 };
+
+            let last = self.modes.len();
+            self.modes.push(next);
+            self.modes.swap(index, last);
+            self.steps_in_mode.push(steps_in_mode);
+            self.steps_in_mode.swap(index, last);
+        }
+    }
+}
+
+impl<F, M> Batch<F>
+    where
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Calls `swap()` on the current `Mode` of every machine in the `Batch`, in order.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// use std::panic::{self, AssertUnwindSafe};
+    /// 
+    /// struct FuseFamily;
+    /// impl Family for FuseFamily {
+    ///     type Base = Fuse;
+    ///     type Mode = Fuse;
+    ///     type Input = ();
+    ///     type Output = Fuse;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq)]
+    /// struct Fuse(u32);
+    /// impl Mode for Fuse {
+    ///     type Family = FuseFamily;
+    ///     fn swap(self, _input : ()) -> Self {
+    ///         if self.0 == 0 { panic!("Boom!"); }
+    ///         Fuse(self.0 - 1)
+    ///     }
+    /// }
+    /// 
+    /// let mut fuses = Batch::<FuseFamily>::new();
+    /// fuses.push(Fuse(3));
+    /// fuses.push(Fuse(0));
+    /// fuses.push(Fuse(5));
+    /// 
+    /// // The second Fuse panics, so the last Fuse takes its place, without having been stepped.
+    /// let result = panic::catch_unwind(AssertUnwindSafe(|| fuses.step_all()));
+    /// assert!(result.is_err());
+    /// assert_eq!(fuses.modes(), &[Fuse(2), Fuse(5)]);
+    /// assert_eq!(fuses.steps_in_mode(1), Some(0));
+    /// ```
+    /// 
+    pub fn step_all(&mut self) {
+        self.swap_all(|_, mode| mode.swap(()));
+    }
+}
+
+impl<F, M, Input> Batch<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Calls `swap()` on the current `Mode` of every machine in the `Batch`, in order, passing in the `Input` returned
+    /// by calling `input` with the index of each machine.
+    /// 
+    pub fn step_all_with_input(&mut self, mut input : impl FnMut(usize) -> Input) {
+        self.swap_all(|index, mode| mode.swap(input(index)));
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<F, M, Input> Batch<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F> + Send,
+{
+    /// Same as [`step_all_with_input()`](#method.step_all_with_input), except that the machines in the `Batch` are
+    /// stepped in parallel on the `rayon` thread pool. Since `input` may be called from multiple threads at once, it
+    /// must be `Sync`.
+    /// 
+    /// **NOTE:** Since every machine is in flight at once, a panic in `swap()` drops every machine in the `Batch`,
+    /// leaving it empty, unlike `step_all_with_input()`.
+    /// 
+    /// **NOTE:** This function is only available when the `rayon` feature is enabled.
+    /// 
+    pub fn par_step_all_with_input(&mut self, input : impl Fn(usize) -> Input + Sync) {
+        use rayon::prelude::*;
+        use std::mem;
+
+        // NOTE: Both arrays are moved out together, so that a panic leaves the Batch empty rather than inconsistent.
+        (self.modes, self.steps_in_mode) =
+            mem::take(&mut self.modes).into_par_iter()
+                .zip(mem::take(&mut self.steps_in_mode))
+                .enumerate()
+                .map(|(index, (mode, steps_in_mode))| {
+                    if mode.is_final() {
+                        return (mode, steps_in_mode);
+                    }
+
+                    let from = mode.tag();
+                    let next = mode.swap(input(index));
+                    let steps_in_mode = if next.tag() == from { steps_in_mode + 1 } else { 0 };
+                    (next, steps_in_mode)
+                })
+                .unzip();
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<F, M> Batch<F>
+    where
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F> + Send,
+{
+    /// Same as [`step_all()`](#method.step_all), except that the machines in the `Batch` are stepped in parallel on the
+    /// `rayon` thread pool.
+    /// 
+    /// **NOTE:** This function is only available when the `rayon` feature is enabled.
+    /// 
+    pub fn par_step_all(&mut self) {
+        self.par_step_all_with_input(|_| ());
+    }
+}
+
+impl<F> fmt::Debug for Batch<F>
+    where
+        F : Family + ?Sized,
+        F::Mode : fmt::Debug,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Batch")
+            .field("modes", &self.modes)
+            .finish()
+    }
+}
@@ -0,0 +1,100 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! A command-line companion for validating and rendering data-driven `mode::table` definitions.
+//! 
+//! ```shell
+//! mode-cli validate <FILE> [--guard <NAME>]...
+//! mode-cli render <FILE> [--format dot|plantuml|scxml]
+//! ```
+//! 
+//! The format of `<FILE>` is chosen by its extension, which must be one of `.json`, `.yaml`, `.yml`, or `.toml`.
+//! `validate` exits with a non-zero status if the definition is malformed, refers to a guard that was not passed via
+//! `--guard`, or contains unreachable or dead states.
+//! 
+
+use mode::table::{Definition, Guards};
+use std::{env, path::Path, process};
+
+const USAGE : &str = "\
+Usage:
+    mode-cli validate <FILE> [--guard <NAME>]...
+    mode-cli render <FILE> [--format dot|plantuml|scxml]";
+
+fn main() {
+    let args : Vec<String> = env::args().skip(1).collect();
+    match run(&args) {
+        Ok(output) => print!("{}", output),
+        Err(message) => {
+            eprintln!("{}", message);
+            process::exit(1);
+        },
+    }
+}
+
+fn run(args : &[String]) -> Result<String, String> {
+    let (command, path, options) =
+        match args {
+            [command, path, options @ ..] => (command.as_str(), path, options),
+            _ => return Err(USAGE.to_owned()),
+        };
+
+    let definition = read(Path::new(path))?;
+    match command {
+        "validate" => validate(&definition, options),
+        "render" => render(&definition, options),
+        _ => Err(USAGE.to_owned()),
+    }
+}
+
+fn read(path : &Path) -> Result<Definition, String> {
+    Definition::from_file(path).map_err(|error| format!("{}: {}", path.display(), error))
+}
+
+fn validate(definition : &Definition, options : &[String]) -> Result<String, String> {
+    let mut guards = Guards::new();
+    let mut options = options.iter();
+    while let Some(option) = options.next() {
+        match (option.as_str(), options.next()) {
+            ("--guard", Some(name)) => guards.add_guard(name.as_str(), |_, _| true),
+            _ => return Err(USAGE.to_owned()),
+        }
+    }
+
+    let mut problems = Vec::new();
+    if let Err(error) = definition.validate_with(&guards) {
+        problems.push(error.to_string());
+    }
+    for name in definition.unreachable_states() {
+        problems.push(format!("State \"{}\" is unreachable from the initial state!", name));
+    }
+    for name in definition.dead_states() {
+        problems.push(format!("State \"{}\" is not final, but has no transitions out of it!", name));
+    }
+
+    if problems.is_empty() {
+        Ok(format!("OK: {} states, {} transitions\n", definition.states.len(), definition.transitions.len()))
+    }
+    else {
+        Err(problems.join("\n"))
+    }
+}
+
+fn render(definition : &Definition, options : &[String]) -> Result<String, String> {
+    let format =
+        match options {
+            [] => "dot",
+            [option, format] if option == "--format" => format.as_str(),
+            _ => return Err(USAGE.to_owned()),
+        };
+
+    match format {
+        "dot" => Ok(definition.to_dot()),
+        "plantuml" => Ok(definition.to_plantuml()),
+        "scxml" => Ok(definition.to_scxml()),
+        _ => Err(format!("Unknown format \"{}\"!\n\n{}", format, USAGE)),
+    }
+}
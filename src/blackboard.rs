@@ -0,0 +1,259 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{
+    any::{Any, TypeId},
+    collections::BTreeMap,
+    fmt,
+};
+
+/// A collection of values keyed by type, allowing `Mode`s within the same `Automaton` to share data with each other
+/// without needing to move it from one `Mode` to the next when transitioning.
+/// 
+/// Every `Automaton` owns a single `Blackboard`, which can be accessed via `Automaton::blackboard()` and
+/// `Automaton::blackboard_mut()`. The `Blackboard` can also be passed into `Mode::swap()` by setting `Family::Input`
+/// to `Blackboard` and `Family::Output` to `(Family::Mode, Blackboard)`, and then calling
+/// [`Automaton::next_with_blackboard()`](struct.Automaton.html#method.next_with_blackboard). Since the `Blackboard` is
+/// moved into and out of `swap()`, this is no more expensive than passing a reference.
+/// 
+/// At most one value of each type can be stored in the `Blackboard` at a time. In order to store several values of the
+/// same type, wrap each one in a distinct newtype `struct`. All values must be `Send + Sync`, so that storing them in
+/// the `Automaton` does not prevent it from being shared between threads.
+/// 
+/// # Usage
+/// ```
+/// use mode::Blackboard;
+/// 
+/// struct Score(u32);
+/// 
+/// let mut blackboard = Blackboard::new();
+/// blackboard.insert(Score(10));
+/// 
+/// blackboard.get_mut::<Score>().unwrap().0 += 5;
+/// assert_eq!(blackboard.get::<Score>().unwrap().0, 15);
+/// 
+/// assert_eq!(blackboard.remove::<Score>().unwrap().0, 15);
+/// assert!(!blackboard.contains::<Score>());
+/// ```
+/// 
+/// # Scoped entries
+/// Values stored via [`insert_scoped()`](#method.insert_scoped) are tied to the lifetime of the `Mode` that is current
+/// when they are inserted. As soon as the `Automaton` that owns the `Blackboard` transitions to a `Mode` with a
+/// different [`tag()`](trait.Mode.html#method.tag), all scoped entries are dropped. This prevents data that only makes
+/// sense for a single state from leaking into the states that follow it.
+/// 
+/// Scoped entries inserted while the current `Mode` is swapping, e.g. by a `swap()` function that receives the
+/// `Blackboard` via [`Automaton::next_with_blackboard()`](struct.Automaton.html#method.next_with_blackboard), belong
+/// to the `Mode` that `swap()` returns, and are only dropped once that `Mode` transitions out in turn. This allows a
+/// `Mode` to hand per-state data to the `Mode` it transitions into.
+/// 
+/// ```
+/// use mode::*;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = SomeMode;
+///     type Mode = SomeMode;
+///     type Input = ();
+///     type Output = SomeMode;
+/// }
+/// 
+/// enum SomeMode { A, B }
+/// impl Mode for SomeMode {
+///     type Family = SomeFamily;
+///     fn swap(self, _input : ()) -> Self { SomeMode::B }
+///     fn tag(&self) -> &'static str {
+///         match self { SomeMode::A => "A", SomeMode::B => "B" }
+///     }
+/// }
+/// 
+/// struct Shared;
+/// struct OnlyForA;
+/// 
+/// let mut automaton = SomeFamily::automaton_with_mode(SomeMode::A);
+/// Automaton::blackboard_mut(&mut automaton).insert(Shared);
+/// Automaton::blackboard_mut(&mut automaton).insert_scoped(OnlyForA);
+/// 
+/// Automaton::next(&mut automaton); // A -> B
+/// 
+/// assert!(Automaton::blackboard(&automaton).contains::<Shared>());
+/// assert!(!Automaton::blackboard(&automaton).contains::<OnlyForA>());
+/// ```
+/// 
+/// ```
+/// use mode::*;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = SomeMode;
+///     type Mode = SomeMode;
+///     type Input = Blackboard;
+///     type Output = (SomeMode, Blackboard);
+/// }
+/// 
+/// enum SomeMode { A, B, C }
+/// impl Mode for SomeMode {
+///     type Family = SomeFamily;
+///     fn swap(self, mut blackboard : Blackboard) -> (Self, Blackboard) {
+///         match self {
+///             SomeMode::A => {
+///                 // Hand a value to B that only B should see.
+///                 blackboard.insert_scoped(OnlyForB);
+///                 (SomeMode::B, blackboard)
+///             },
+///             _ => (SomeMode::C, blackboard),
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { SomeMode::A => "A", SomeMode::B => "B", SomeMode::C => "C" }
+///     }
+/// }
+/// 
+/// struct OnlyForB;
+/// 
+/// let mut automaton = SomeFamily::automaton_with_mode(SomeMode::A);
+/// 
+/// Automaton::next_with_blackboard(&mut automaton); // A -> B
+/// assert!(Automaton::blackboard(&automaton).contains::<OnlyForB>());
+/// 
+/// Automaton::next_with_blackboard(&mut automaton); // B -> C
+/// assert!(!Automaton::blackboard(&automaton).contains::<OnlyForB>());
+/// ```
+/// 
+#[derive(Default)]
+pub struct Blackboard {
+    entries : BTreeMap<TypeId, Entry>,
+    scope : u64,
+}
+
+struct Entry {
+    value : Box<dyn Any + Send + Sync>,
+    scope : Option<u64>,
+}
+
+impl Blackboard {
+    /// Creates a new, empty `Blackboard`.
+    /// 
+    pub const fn new() -> Self {
+        Self { entries : BTreeMap::new(), scope : 0 }
+    }
+
+    /// Stores `value` in the `Blackboard`, returning the value of the same type that was previously stored, if any.
+    /// 
+    pub fn insert<T>(&mut self, value : T) -> Option<T>
+        where T : Any + Send + Sync
+    {
+        self.insert_entry(value, None)
+    }
+
+    /// Stores `value` in the `Blackboard` as a scoped entry, returning the value of the same type that was previously
+    /// stored, if any. The value will be dropped automatically as soon as the current `Mode` of the `Automaton` that
+    /// owns this `Blackboard` transitions out.
+    /// 
+    /// See [Scoped entries](#scoped-entries) for more details.
+    /// 
+    pub fn insert_scoped<T>(&mut self, value : T) -> Option<T>
+        where T : Any + Send + Sync
+    {
+        self.insert_entry(value, Some(self.scope))
+    }
+
+    fn insert_entry<T>(&mut self, value : T, scope : Option<u64>) -> Option<T>
+        where T : Any + Send + Sync
+    {
+        self.entries.insert(TypeId::of::<T>(), Entry { value : Box::new(value), scope })
+            .map(|previous| *previous.value.downcast::<T>().unwrap())
+    }
+
+    /// Returns an immutable reference to the value of type `T` stored in the `Blackboard`, if any.
+    /// 
+    pub fn get<T>(&self) -> Option<&T>
+        where T : Any + Send + Sync
+    {
+        self.entries.get(&TypeId::of::<T>())
+            .map(|entry| entry.value.downcast_ref::<T>().unwrap())
+    }
+
+    /// Returns a mutable reference to the value of type `T` stored in the `Blackboard`, if any.
+    /// 
+    pub fn get_mut<T>(&mut self) -> Option<&mut T>
+        where T : Any + Send + Sync
+    {
+        self.entries.get_mut(&TypeId::of::<T>())
+            .map(|entry| entry.value.downcast_mut::<T>().unwrap())
+    }
+
+    /// Returns a mutable reference to the value of type `T` stored in the `Blackboard`, first inserting the value
+    /// returned by `default` if no such value exists.
+    /// 
+    pub fn get_or_insert_with<T>(&mut self, default : impl FnOnce() -> T) -> &mut T
+        where T : Any + Send + Sync
+    {
+        self.entries.entry(TypeId::of::<T>())
+            .or_insert_with(|| Entry { value : Box::new(default()), scope : None })
+            .value
+            .downcast_mut::<T>()
+            .unwrap()
+    }
+
+    /// Removes the value of type `T` from the `Blackboard`, returning it if it was present.
+    /// 
+    pub fn remove<T>(&mut self) -> Option<T>
+        where T : Any + Send + Sync
+    {
+        self.entries.remove(&TypeId::of::<T>())
+            .map(|entry| *entry.value.downcast::<T>().unwrap())
+    }
+
+    /// Returns `true` if a value of type `T` is stored in the `Blackboard`.
+    /// 
+    pub fn contains<T>(&self) -> bool
+        where T : Any + Send + Sync
+    {
+        self.entries.contains_key(&TypeId::of::<T>())
+    }
+
+    /// Returns the number of values stored in the `Blackboard`.
+    /// 
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if no values are stored in the `Blackboard`.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Removes all values from the `Blackboard`.
+    /// 
+    pub fn clear(&mut self) {
+        self.entries.clear();
+    }
+
+    /// Starts a new scope, so that scoped entries inserted from now on belong to whichever `Mode` becomes current next.
+    /// This is called by the `Automaton` immediately before the current `Mode` swaps.
+    /// 
+    pub(crate) fn begin_scope(&mut self) {
+        self.scope += 1;
+    }
+
+    /// Drops all scoped entries that were inserted before the current scope began, leaving all other values in place.
+    /// This is called by the `Automaton` whenever the current `Mode` transitions out.
+    /// 
+    pub(crate) fn clear_scoped(&mut self) {
+        let scope = self.scope;
+        self.entries.retain(|_, entry| entry.scope.is_none_or(|inserted| inserted >= scope));
+    }
+}
+
+impl fmt::Debug for Blackboard {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Blackboard")
+            .field("len", &self.entries.len())
+            .finish()
+    }
+}
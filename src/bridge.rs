@@ -0,0 +1,142 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, FinishError};
+use std::fmt;
+
+type ConvertFn<A, B> = dyn Fn(<A as Family>::Mode) -> <B as Family>::Mode + Send + Sync;
+
+/// Encapsulates the hand-off from a finished state machine over the `Family` `A` into a new state machine over a
+/// different `Family` `B`, e.g. between the phases of a large application whose `Mode`s expose different `Base`
+/// interfaces.
+/// 
+/// When [`cross()`](#method.cross) is called with an `Automaton` over `A` whose current `Mode` is
+/// [final](trait.Mode.html#method.is_final), the `Automaton` is consumed, and its final `Mode` is passed by value into
+/// the `convert` function of the `Bridge`. The `Mode` that it returns becomes the initial `Mode` of a new `Automaton`
+/// over `B`, so that state can be moved directly from one state machine into the next. The
+/// [`Blackboard`](struct.Blackboard.html), [`name()`](struct.Automaton.html#method.name), and parent path of the
+/// first `Automaton` are carried over into the new one. Everything else, e.g. subscribers, timeouts, and counters,
+/// starts fresh, just as with [`Automaton::with_mode()`](struct.Automaton.html#method.with_mode).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct LoadingFamily;
+/// impl Family for LoadingFamily {
+///     type Base = Loading;
+///     type Mode = Loading;
+///     type Input = ();
+///     type Output = Loading;
+/// }
+/// 
+/// enum Loading { Reading { chunks : Vec<u8> }, Loaded { level : Vec<u8> } }
+/// impl Mode for Loading {
+///     type Family = LoadingFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Loading::Reading { mut chunks } if chunks.len() < 3 => {
+///                 chunks.push(chunks.len() as u8);
+///                 Loading::Reading { chunks }
+///             },
+///             Loading::Reading { chunks } => Loading::Loaded { level : chunks },
+///             loaded => loaded,
+///         }
+///     }
+///     fn is_final(&self) -> bool { matches!(self, Loading::Loaded { .. }) }
+/// }
+/// 
+/// struct PlayingFamily;
+/// impl Family for PlayingFamily {
+///     type Base = Playing;
+///     type Mode = Playing;
+///     type Input = ();
+///     type Output = Playing;
+/// }
+/// 
+/// struct Playing { level : Vec<u8>, turns : u32 }
+/// impl Mode for Playing {
+///     type Family = PlayingFamily;
+///     fn swap(self, _input : ()) -> Self { Playing { turns : self.turns + 1, ..self } }
+/// }
+/// 
+/// let bridge =
+///     Bridge::<LoadingFamily, PlayingFamily>::new(|loading| {
+///         match loading {
+///             Loading::Loaded { level } => Playing { level, turns : 0 },
+///             Loading::Reading { .. } => unreachable!(),
+///         }
+///     });
+/// 
+/// let mut loading = LoadingFamily::automaton_with_mode(Loading::Reading { chunks : Vec::new() });
+/// Automaton::set_name(&mut loading, "game");
+/// 
+/// // Crossing too early returns the Automaton as part of the error.
+/// loading =
+///     match bridge.cross(loading) {
+///         Ok(_) => unreachable!(),
+///         Err(error) => error.into_inner(),
+///     };
+/// 
+/// while !Automaton::is_finished(&loading) {
+///     Automaton::next(&mut loading);
+/// }
+/// 
+/// let mut playing = bridge.cross(loading).ok().unwrap();
+/// Automaton::next(&mut playing);
+/// assert_eq!(playing.level, vec![0, 1, 2]);
+/// assert_eq!(playing.turns, 1);
+/// assert_eq!(Automaton::path(&playing), Some("game"));
+/// 
+/// // An Automaton that was stopped before reaching a final Mode cannot cross the Bridge either.
+/// let mut stopped = LoadingFamily::automaton_with_mode(Loading::Reading { chunks : Vec::new() });
+/// Automaton::stop(&mut stopped);
+/// assert!(bridge.cross(stopped).is_err());
+/// ```
+/// 
+pub struct Bridge<A, B>
+    where
+        A : Family + ?Sized,
+        B : Family + ?Sized,
+{
+    convert : Box<ConvertFn<A, B>>,
+}
+
+impl<A, B> Bridge<A, B>
+    where
+        A : Family + ?Sized,
+        B : Family + ?Sized,
+{
+    /// Creates a new `Bridge` that converts the final `Mode` of a state machine over `A` into the initial `Mode` of a
+    /// new state machine over `B` via `convert`.
+    /// 
+    pub fn new<C>(convert : C) -> Self
+        where C : Fn(A::Mode) -> B::Mode + Send + Sync + 'static
+    {
+        Self { convert : Box::new(convert) }
+    }
+
+    /// Consumes `automaton` and returns a new `Automaton` over `B`, whose initial `Mode` is converted from the final
+    /// `Mode` of `automaton`. If `automaton` is not [complete](struct.Automaton.html#method.is_complete), i.e. if it has
+    /// not finished yet, or if it was [stopped](struct.Automaton.html#method.stop) while a non-final `Mode` was current,
+    /// a [`FinishError`](struct.FinishError.html) is returned instead, from which the `Automaton` can be recovered.
+    /// Hence, `convert` is only ever called with a final `Mode`.
+    /// 
+    #[allow(clippy::result_large_err)]
+    pub fn cross(&self, automaton : Automaton<A>) -> Result<Automaton<B>, FinishError<A>> {
+        Automaton::hand_off(automaton, &*self.convert)
+    }
+}
+
+impl<A, B> fmt::Debug for Bridge<A, B>
+    where
+        A : Family + ?Sized,
+        B : Family + ?Sized,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Bridge").finish_non_exhaustive()
+    }
+}
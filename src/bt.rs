@@ -0,0 +1,186 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines types that allow an `Automaton` to be used as a task within a behavior tree, and vice versa.
+//! 
+//! Behavior trees are made up of `Task`s that are ticked repeatedly, each of which reports whether it is still
+//! `Running`, or has finished with either `Success` or `Failure`. This module provides a minimal `Task` interface that
+//! can be implemented by the nodes of any behavior tree library, along with two adapters:
+//! 
+//! - [`Leaf`](struct.Leaf.html), which wraps an `Automaton` so that it can be ticked as a leaf `Task` in a behavior
+//!   tree, and
+//! - the `Task` trait itself, which can be stored inside a `Mode` and ticked from `Mode::swap()` in order to host a
+//!   behavior tree inside a state. (See example below.)
+//! 
+//! # Hosting a behavior tree inside a `Mode`
+//! ```
+//! use mode::{bt::{Status, Task}, Family, Mode};
+//! 
+//! struct AgentFamily;
+//! impl Family for AgentFamily {
+//!     type Base = Agent;
+//!     type Mode = Agent;
+//!     type Input = ();
+//!     type Output = Agent;
+//! }
+//! 
+//! enum Agent {
+//!     Searching { tree : Box<dyn Task> },
+//!     Found,
+//!     GaveUp,
+//! }
+//! 
+//! impl Mode for Agent {
+//!     type Family = AgentFamily;
+//!     fn swap(self, _input : ()) -> Self {
+//!         match self {
+//!             Agent::Searching { mut tree } => {
+//!                 match tree.tick() {
+//!                     Status::Running => Agent::Searching { tree },
+//!                     Status::Success => Agent::Found,
+//!                     Status::Failure => Agent::GaveUp,
+//!                 }
+//!             },
+//!             other => other,
+//!         }
+//!     }
+//! }
+//! 
+//! let mut attempts = 0;
+//! let tree = move || { attempts += 1; if attempts < 3 { Status::Running } else { Status::Success } };
+//! 
+//! let mut automaton = AgentFamily::automaton_with_mode(Agent::Searching { tree : Box::new(tree) });
+//! for _ in 0..3 { mode::Automaton::next(&mut automaton); }
+//! assert!(matches!(*automaton, Agent::Found));
+//! ```
+//! 
+
+use crate::{Automaton, Family, Mode};
+use std::borrow::Borrow;
+
+/// The result of ticking a behavior tree `Task`.
+/// 
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Status {
+    /// The `Task` has not finished yet, and should be ticked again.
+    Running,
+
+    /// The `Task` finished successfully.
+    Success,
+
+    /// The `Task` finished unsuccessfully.
+    Failure,
+}
+
+impl Status {
+    /// Returns `true` if the `Task` has not finished yet.
+    /// 
+    pub fn is_running(self) -> bool {
+        self == Status::Running
+    }
+}
+
+/// A single node in a behavior tree, which can be ticked repeatedly until it reports that it is no longer `Running`.
+/// 
+/// This is implemented for all `FnMut() -> Status` closures, as well as for [`Leaf`](struct.Leaf.html).
+/// 
+pub trait Task {
+    /// Advances the `Task` by one step, returning its current `Status`.
+    /// 
+    fn tick(&mut self) -> Status;
+}
+
+impl<T> Task for T
+    where T : FnMut() -> Status
+{
+    fn tick(&mut self) -> Status {
+        self()
+    }
+}
+
+/// Adapter that allows an `Automaton` to be ticked as a leaf `Task` in a behavior tree.
+/// 
+/// Every time the `Leaf` is ticked, it calls `Automaton::next()` on the wrapped `Automaton`, and then calls the
+/// `status` function with the current `Mode` to determine whether the state machine is still `Running` or has finished
+/// with `Success` or `Failure`.
+/// 
+/// # Usage
+/// ```
+/// use mode::{bt::{Leaf, Status, Task}, Family, Mode};
+/// 
+/// struct DoorFamily;
+/// impl Family for DoorFamily {
+///     type Base = Door;
+///     type Mode = Door;
+///     type Input = ();
+///     type Output = Door;
+/// }
+/// 
+/// #[derive(Copy, Clone, PartialEq)]
+/// enum Door { Closed, Opening, Open }
+/// impl Mode for Door {
+///     type Family = DoorFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self { Door::Closed => Door::Opening, _ => Door::Open }
+///     }
+/// }
+/// 
+/// let mut open_door =
+///     Leaf::new(DoorFamily::automaton_with_mode(Door::Closed), |door : &Door| {
+///         if *door == Door::Open { Status::Success } else { Status::Running }
+///     });
+/// 
+/// assert_eq!(open_door.tick(), Status::Running);
+/// assert_eq!(open_door.tick(), Status::Success);
+/// ```
+/// 
+pub struct Leaf<F, S>
+    where F : Family + ?Sized
+{
+    automaton : Automaton<F>,
+    status : S,
+}
+
+impl<F, S> Leaf<F, S>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Leaf` that steps `automaton` each time it is ticked, using `status` to determine the `Status` of
+    /// the `Task` from the current `Mode`.
+    /// 
+    pub fn new(automaton : Automaton<F>, status : S) -> Self {
+        Self { automaton, status }
+    }
+
+    /// Returns an immutable reference to the wrapped `Automaton`.
+    /// 
+    pub fn automaton(&self) -> &Automaton<F> {
+        &self.automaton
+    }
+
+    /// Returns a mutable reference to the wrapped `Automaton`.
+    /// 
+    pub fn automaton_mut(&mut self) -> &mut Automaton<F> {
+        &mut self.automaton
+    }
+
+    /// Consumes the `Leaf`, returning the wrapped `Automaton`.
+    /// 
+    pub fn into_inner(self) -> Automaton<F> {
+        self.automaton
+    }
+}
+
+impl<F, M, S> Task for Leaf<F, S>
+    where
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F> + Borrow<F::Base>,
+        S : FnMut(&F::Base) -> Status,
+{
+    fn tick(&mut self) -> Status {
+        Automaton::next(&mut self.automaton);
+        (self.status)(self.automaton.borrow_mode())
+    }
+}
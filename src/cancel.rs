@@ -0,0 +1,110 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{
+    fmt,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc,
+    },
+};
+
+/// A flag that can be triggered from anywhere in order to request the cooperative shutdown of one or more state
+/// machines that are still in progress.
+/// 
+/// All clones of a `CancellationToken` share the same flag, so a token can be handed to a shutdown handler, the
+/// `Mode`s of an `Automaton`, and the loop driving it at the same time. Once [`cancel()`](#method.cancel) has been
+/// called, the token stays cancelled forever.
+/// 
+/// An `Automaton` observes a `CancellationToken` via
+/// [`Automaton::set_cancellation()`](struct.Automaton.html#method.set_cancellation). At the beginning of the first step
+/// after the token is cancelled, the current `Mode` is [exited](trait.Mode.html#method.exit) and passed into the
+/// `on_cancel` function, which routes the `Automaton` into a designated cancellation state, e.g. one that drains
+/// in-flight work before finishing, instead of the `Automaton` simply being dropped mid-flight. `Mode`s can also hold
+/// a clone of the token and check [`is_cancelled()`](#method.is_cancelled) themselves, and
+/// [`driver::Pool`](driver/struct.Pool.html) can observe a token on behalf of every state machine it hosts.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct JobFamily;
+/// impl Family for JobFamily {
+///     type Base = Job;
+///     type Mode = Job;
+///     type Input = ();
+///     type Output = Job;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Job { Working { done : u32 }, ShuttingDown { done : u32 }, Stopped }
+/// 
+/// impl Mode for Job {
+///     type Family = JobFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Job::Working { done } => Job::Working { done : done + 1 },
+///             Job::ShuttingDown { .. } => Job::Stopped,
+///             stopped => stopped,
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Job::Working { .. } => "Working", Job::ShuttingDown { .. } => "ShuttingDown", _ => "Stopped" }
+///     }
+///     fn is_final(&self) -> bool { *self == Job::Stopped }
+/// }
+/// 
+/// let token = CancellationToken::new();
+/// let mut job = JobFamily::automaton_with_mode(Job::Working { done : 0 });
+/// Automaton::set_cancellation(&mut job, &token, |job| match job {
+///     Job::Working { done } => Job::ShuttingDown { done },
+///     other => other,
+/// });
+/// 
+/// Automaton::next(&mut job);
+/// assert_eq!(*job, Job::Working { done : 1 });
+/// 
+/// token.clone().cancel();
+/// Automaton::next(&mut job);
+/// assert_eq!(*job, Job::ShuttingDown { done : 1 });
+/// 
+/// Automaton::next(&mut job);
+/// assert!(Automaton::is_finished(&job));
+/// assert_eq!(*job, Job::Stopped);
+/// ```
+/// 
+#[derive(Clone, Default)]
+pub struct CancellationToken {
+    cancelled : Arc<AtomicBool>,
+}
+
+impl CancellationToken {
+    /// Creates a new `CancellationToken` that has not been cancelled.
+    /// 
+    pub fn new() -> Self {
+        Self { cancelled : Arc::new(AtomicBool::new(false)) }
+    }
+
+    /// Cancels the token, and every clone of it. Calling this more than once has no further effect.
+    /// 
+    pub fn cancel(&self) {
+        self.cancelled.store(true, Ordering::Release);
+    }
+
+    /// Returns `true` if [`cancel()`](#method.cancel) has been called on this token, or on any clone of it.
+    /// 
+    pub fn is_cancelled(&self) -> bool {
+        self.cancelled.load(Ordering::Acquire)
+    }
+}
+
+impl fmt::Debug for CancellationToken {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("CancellationToken")
+            .field("cancelled", &self.is_cancelled())
+            .finish()
+    }
+}
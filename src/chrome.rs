@@ -0,0 +1,352 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines an exporter that writes the transition histories of one or more `Automaton`s in the Chrome trace-event
+//! format.
+//! 
+//! A [`ChromeTrace`](struct.ChromeTrace.html) keeps one track per `Automaton`, and records each state that the
+//! `Automaton` was in as a duration slice on that track, named after the [`tag()`](../trait.Mode.html#method.tag) of
+//! the `Mode`. The [`fields()`](../trait.Mode.html#method.fields) of each state are exported as the `args` of its
+//! slice. The resulting JSON can be loaded into `chrome://tracing` or the [Perfetto UI](https://ui.perfetto.dev),
+//! e.g. next to a frame profile captured in the same format.
+//! 
+//! The same histories can also be rendered as a self-contained SVG timeline via
+//! [`to_svg()`](struct.ChromeTrace.html#method.to_svg), with one row per track, each state drawn as a colored band
+//! over time, and each transition marked by a vertical line. Unlike a textual log, this makes periodic behavior and
+//! oscillation between states easy to spot, e.g. when attached to a bug report.
+//! 
+//! Transitions are recorded from [`TransitionEvent`](../struct.TransitionEvent.html)s, so an `Automaton` can either be
+//! attached directly via [`attach()`](struct.ChromeTrace.html#method.attach), in which case the `ChromeTrace`
+//! subscribes to it and collects its events whenever [`poll()`](struct.ChromeTrace.html#method.poll) is called, or its
+//! events can be passed in by hand via [`record()`](struct.ChromeTrace.html#method.record).
+//! 
+//! # Usage
+//! ```
+//! use mode::{chrome::ChromeTrace, Automaton, Family, Mode};
+//! 
+//! struct LightFamily;
+//! impl Family for LightFamily {
+//!     type Base = Light;
+//!     type Mode = Light;
+//!     type Input = ();
+//!     type Output = Light;
+//! }
+//! 
+//! #[derive(Debug, PartialEq)]
+//! enum Light { Red, Green }
+//! impl Mode for Light {
+//!     type Family = LightFamily;
+//!     fn swap(self, _input : ()) -> Self {
+//!         match self { Light::Red => Light::Green, Light::Green => Light::Red }
+//!     }
+//!     fn tag(&self) -> &'static str {
+//!         match self { Light::Red => "Red", Light::Green => "Green" }
+//!     }
+//! }
+//! 
+//! let mut trace = ChromeTrace::new();
+//! let mut light = LightFamily::automaton_with_mode(Light::Red);
+//! trace.attach("light", &mut light);
+//! 
+//! Automaton::next(&mut light);
+//! Automaton::next(&mut light);
+//! trace.poll();
+//! 
+//! let json = trace.to_json();
+//! assert!(json.contains(r#""name":"thread_name","ph":"M","pid":1,"tid":1,"args":{"name":"light"}"#));
+//! assert_eq!(json.matches(r#""ph":"X""#).count(), 3);
+//! 
+//! let svg = trace.to_svg();
+//! assert!(svg.starts_with("<svg"));
+//! assert_eq!(svg.matches("<title>Red").count(), 2);
+//! assert_eq!(svg.matches(r#"class="transition""#).count(), 2);
+//! ```
+//! 
+
+use crate::{table::escape_xml, Automaton, Family, Field, TransitionEvent};
+use std::{io, sync::mpsc::Receiver, time::Instant};
+
+/// A single state on a track, from the time it was entered until the time it was left.
+/// 
+#[derive(Clone, Debug)]
+struct Slice {
+    name : &'static str,
+    fields : Vec<Field>,
+    start : Instant,
+    end : Instant,
+}
+
+#[derive(Debug)]
+struct Track {
+    name : String,
+    current : Option<(&'static str, Vec<Field>, Instant)>,
+    slices : Vec<Slice>,
+    events : Option<Receiver<TransitionEvent>>,
+}
+
+/// Records the transition histories of any number of `Automaton`s, and exports them as Chrome trace-event JSON.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Debug)]
+pub struct ChromeTrace {
+    epoch : Instant,
+    tracks : Vec<Track>,
+}
+
+impl Default for ChromeTrace {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl ChromeTrace {
+    /// Creates a new `ChromeTrace` with no tracks. All timestamps are exported relative to the time at which the
+    /// `ChromeTrace` was created.
+    /// 
+    pub fn new() -> Self {
+        Self { epoch : Instant::now(), tracks : Vec::new() }
+    }
+
+    /// Adds a new track called `name`, without any states, and returns its index. Use this together with
+    /// [`record()`](#method.record) in order to record `TransitionEvent`s by hand.
+    /// 
+    pub fn add_track(&mut self, name : impl Into<String>) -> usize {
+        self.tracks.push(Track { name : name.into(), current : None, slices : Vec::new(), events : None });
+        self.tracks.len() - 1
+    }
+
+    /// Adds a new track called `name` for `automaton`, subscribes to its `TransitionEvent`s, and returns the index of
+    /// the track. The current `Mode` of `automaton` is treated as having been entered just now. Transitions are only
+    /// collected once [`poll()`](#method.poll) is called.
+    /// 
+    pub fn attach<F>(&mut self, name : impl Into<String>, automaton : &mut Automaton<F>) -> usize
+        where F : Family + ?Sized
+    {
+        let track = self.add_track(name);
+        self.tracks[track].current = Some((Automaton::tag(automaton), Automaton::fields(automaton), Instant::now()));
+        self.tracks[track].events = Some(Automaton::subscribe(automaton));
+        track
+    }
+
+    /// Records `event` on the track with index `track`, closing the slice for the state being left and opening a new
+    /// one for the state being entered. If no state has been recorded on the track yet, the state being left is
+    /// treated as having been entered when the `ChromeTrace` was created.
+    /// 
+    /// # Panics
+    /// Panics if there is no track with index `track`.
+    /// 
+    pub fn record(&mut self, track : usize, event : &TransitionEvent) {
+        let epoch = self.epoch;
+        let track = &mut self.tracks[track];
+        let (name, fields, start) = track.current.take().unwrap_or((event.from, Vec::new(), epoch));
+        track.slices.push(Slice { name, fields, start, end : event.at });
+        track.current = Some((event.to, event.fields.clone(), event.at));
+    }
+
+    /// Collects every pending `TransitionEvent` from each `Automaton` that was attached via
+    /// [`attach()`](#method.attach).
+    /// 
+    pub fn poll(&mut self) {
+        for index in 0..self.tracks.len() {
+            let events : Vec<TransitionEvent> =
+                match &self.tracks[index].events {
+                    Some(events) => events.try_iter().collect(),
+                    None => continue,
+                };
+
+            for event in &events {
+                self.record(index, event);
+            }
+        }
+    }
+
+    /// Returns the recorded histories as a Chrome trace-event JSON document. Each track is exported as a separate
+    /// thread of a single process, and the state that each track is currently in is exported as a slice ending now.
+    /// 
+    pub fn to_json(&self) -> String {
+        let now = Instant::now();
+        let micros = |instant : Instant| instant.saturating_duration_since(self.epoch).as_micros();
+
+        let mut events = Vec::new();
+        for (index, track) in self.tracks.iter().enumerate() {
+            let tid = index + 1;
+            events.push(format!(
+                r#"{{"name":"thread_name","ph":"M","pid":1,"tid":{},"args":{{"name":"{}"}}}}"#,
+                tid,
+                escape(&track.name)));
+
+            let current =
+                track.current.as_ref()
+                    .map(|(name, fields, start)| Slice { name, fields : fields.clone(), start : *start, end : now });
+            for slice in track.slices.iter().chain(current.as_ref()) {
+                let args : Vec<String> =
+                    slice.fields.iter()
+                        .map(|field| format!(r#""{}":"{}""#, escape(field.name), escape(&field.value)))
+                        .collect();
+                events.push(format!(
+                    r#"{{"name":"{}","cat":"mode","ph":"X","ts":{},"dur":{},"pid":1,"tid":{},"args":{{{}}}}}"#,
+                    escape(slice.name),
+                    micros(slice.start),
+                    micros(slice.end).saturating_sub(micros(slice.start)),
+                    tid,
+                    args.join(",")));
+            }
+        }
+
+        format!(r#"{{"traceEvents":[{}],"displayTimeUnit":"ms"}}"#, events.join(","))
+    }
+
+    /// Writes the JSON document returned by [`to_json()`](#method.to_json) to `writer`.
+    /// 
+    pub fn write_to(&self, mut writer : impl io::Write) -> io::Result<()> {
+        writer.write_all(self.to_json().as_bytes())
+    }
+
+    /// Returns the recorded histories as a self-contained SVG timeline. Each track is drawn as a separate row, labeled
+    /// with its name, on which every state is drawn as a band colored by its `tag()`, spanning the time during which it
+    /// was current. Each transition is marked by a vertical line of class `transition`, and hovering over a band shows
+    /// its `tag()`, duration, and fields. The state that each track is currently in is drawn as a band ending now. A
+    /// time axis is drawn below the rows, followed by a legend of every `tag()` that appears on the timeline.
+    /// 
+    pub fn to_svg(&self) -> String {
+        let now = Instant::now();
+        let micros = |instant : Instant| instant.saturating_duration_since(self.epoch).as_micros() as f64;
+        let total = micros(now).max(1.0);
+        let x = |instant : Instant| LABEL_WIDTH as f64 + micros(instant) / total * PLOT_WIDTH as f64;
+
+        let rows = self.tracks.len() as u32;
+        let plot_height = rows * (ROW_HEIGHT + ROW_GAP);
+        let width = LABEL_WIDTH + PLOT_WIDTH + ROW_GAP;
+        let height = plot_height + AXIS_HEIGHT + LEGEND_HEIGHT + ROW_GAP;
+
+        let mut svg = format!(
+            r#"<svg xmlns="http://www.w3.org/2000/svg" width="{}" height="{}" font-family="sans-serif" font-size="12">"#,
+            width,
+            height);
+        let mut legend : Vec<&'static str> = Vec::new();
+
+        for (index, track) in self.tracks.iter().enumerate() {
+            let top = index as u32 * (ROW_HEIGHT + ROW_GAP) + ROW_GAP / 2;
+            svg.push_str(&format!(
+                r#"<text x="4" y="{}" dominant-baseline="middle">{}</text>"#,
+                top + ROW_HEIGHT / 2,
+                escape_xml(&track.name)));
+
+            let current =
+                track.current.as_ref()
+                    .map(|(name, fields, start)| Slice { name, fields : fields.clone(), start : *start, end : now });
+            for (position, slice) in track.slices.iter().chain(current.as_ref()).enumerate() {
+                if !legend.contains(&slice.name) {
+                    legend.push(slice.name);
+                }
+
+                let (left, right) = (x(slice.start), x(slice.end));
+                let fields : Vec<String> = slice.fields.iter().map(ToString::to_string).collect();
+                svg.push_str(&format!(
+                    concat!(
+                        r#"<rect x="{:.1}" y="{}" width="{:.1}" height="{}" fill="{}">"#,
+                        r#"<title>{} ({:.3} ms){}{}</title></rect>"#),
+                    left,
+                    top,
+                    (right - left).max(0.5),
+                    ROW_HEIGHT,
+                    color(slice.name),
+                    escape_xml(slice.name),
+                    (micros(slice.end) - micros(slice.start)) / 1000.0,
+                    if fields.is_empty() { "" } else { " " },
+                    escape_xml(&fields.join(", "))));
+
+                if position > 0 {
+                    svg.push_str(&format!(
+                        r##"<line class="transition" x1="{:.1}" y1="{}" x2="{:.1}" y2="{}" stroke="#000"/>"##,
+                        left,
+                        top,
+                        left,
+                        top + ROW_HEIGHT));
+                }
+            }
+        }
+
+        svg.push_str(&format!(
+            r##"<line x1="{}" y1="{}" x2="{}" y2="{}" stroke="#000"/>"##,
+            LABEL_WIDTH,
+            plot_height,
+            LABEL_WIDTH + PLOT_WIDTH,
+            plot_height));
+        for tick in 0..=4 {
+            let offset = LABEL_WIDTH + PLOT_WIDTH * tick / 4;
+            let anchor = match tick { 0 => "start", 4 => "end", _ => "middle" };
+            svg.push_str(&format!(
+                r#"<text x="{}" y="{}" text-anchor="{}">{:.1} ms</text>"#,
+                offset,
+                plot_height + AXIS_HEIGHT - 6,
+                anchor,
+                total / 1000.0 * tick as f64 / 4.0));
+        }
+
+        let mut offset = LABEL_WIDTH;
+        let top = plot_height + AXIS_HEIGHT;
+        for name in legend {
+            svg.push_str(&format!(
+                r#"<rect x="{}" y="{}" width="12" height="12" fill="{}"/><text x="{}" y="{}">{}</text>"#,
+                offset,
+                top,
+                color(name),
+                offset + 16,
+                top + 10,
+                escape_xml(name)));
+            offset += 16 + 8 * name.chars().count() as u32 + 16;
+        }
+
+        svg.push_str("</svg>");
+        svg
+    }
+
+    /// Writes the SVG timeline returned by [`to_svg()`](#method.to_svg) to `writer`.
+    /// 
+    pub fn write_svg_to(&self, mut writer : impl io::Write) -> io::Result<()> {
+        writer.write_all(self.to_svg().as_bytes())
+    }
+}
+
+/// The colors used for the states on an SVG timeline, chosen per `tag()`.
+/// 
+const PALETTE : [&str; 10] =
+    ["#4e79a7", "#f28e2b", "#e15759", "#76b7b2", "#59a14f", "#edc948", "#b07aa1", "#ff9da7", "#9c755f", "#bab0ac"];
+
+/// The layout of an SVG timeline, in pixels.
+/// 
+const LABEL_WIDTH : u32 = 160;
+const PLOT_WIDTH : u32 = 960;
+const ROW_HEIGHT : u32 = 24;
+const ROW_GAP : u32 = 8;
+const AXIS_HEIGHT : u32 = 24;
+const LEGEND_HEIGHT : u32 = 20;
+
+/// Returns the color for the state called `name`, which is stable across runs.
+/// 
+fn color(name : &str) -> &'static str {
+    let hash =
+        name.bytes()
+            .fold(0xcbf2_9ce4_8422_2325u64, |hash, byte| (hash ^ byte as u64).wrapping_mul(0x100_0000_01b3));
+    PALETTE[(hash % PALETTE.len() as u64) as usize]
+}
+
+/// Escapes `text` so that it can be embedded in a JSON string.
+/// 
+fn escape(text : &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            c if (c as u32) < 0x20 => escaped.push_str(&format!("\\u{:04x}", c as u32)),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
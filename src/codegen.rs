@@ -0,0 +1,346 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Generates Rust source code implementing `Family` and `Mode` from a [`table::Definition`](../table/struct.Definition.html),
+//! so that a data-driven definition and a statically-dispatched state machine can come from a single source of truth.
+//! 
+//! Given a `Definition` and a `name`, [`generate()`](fn.generate.html) produces:
+//! 
+//! - an `enum` called `name`, with one unit variant per state, which implements `Mode`,
+//! - an `enum` called `{name}Event`, with one unit variant per distinct event,
+//! - a `struct` called `{name}Guards`, with one `bool` field per distinct guard, if any transitions are guarded, and
+//! - a `struct` called `{name}Family`, which implements `Family`.
+//! 
+//! State and event names are converted to `CamelCase`, and guard names to `snake_case`. Names that turn into Rust
+//! keywords are emitted as raw identifiers, e.g. `r#loop`, except for `self`, `Self`, `super`, and `crate`, which cannot
+//! be raw identifiers and get a trailing `_` instead. If the `Definition` has no guards, the `Input` of the generated
+//! `Family` is `{name}Event`. Otherwise, it is `({name}Event, {name}Guards)`, and the caller is expected to evaluate
+//! each guard before passing in the event. The initial state is available as the `INITIAL` associated constant of the
+//! generated `enum`.
+//! 
+//! # Build scripts
+//! When the `serde` feature is enabled, [`generate_file()`](fn.generate_file.html) can be called from `build.rs` in
+//! order to generate code from a spec file in any format supported by
+//! [`Definition::from_file()`](../table/struct.Definition.html#method.from_file):
+//! 
+//! ```no_run
+//! // build.rs
+//! # #[cfg(feature = "serde")]
+//! fn main() {
+//!     let out_dir = std::env::var("OUT_DIR").unwrap();
+//!     mode::codegen::generate_file("machines/door.json", "Door", format!("{}/door.rs", out_dir)).unwrap();
+//! }
+//! # #[cfg(not(feature = "serde"))] fn main() { }
+//! ```
+//! 
+//! The generated file can then be included in the crate via `include!(concat!(env!("OUT_DIR"), "/door.rs"));`.
+//! 
+//! # Usage
+//! ```
+//! use mode::{codegen, table::Definition};
+//! 
+//! let definition =
+//!     Definition::new("closed")
+//!         .with_state("closed")
+//!         .with_state("open")
+//!         .with_guarded_transition("closed", "push", "open", "is-unlocked")
+//!         .with_transition("open", "pull", "closed");
+//! 
+//! let code = codegen::generate(&definition, "Door").unwrap();
+//! assert!(code.contains("pub enum Door {\n    Closed,\n    Open,\n}"));
+//! assert!(code.contains("pub struct DoorGuards {\n    pub is_unlocked : bool,\n}"));
+//! assert!(code.contains("(Door::Closed, DoorEvent::Push) if guards.is_unlocked => Door::Open,"));
+//! ```
+//! 
+
+use crate::table::{Definition, DefinitionError, LoadError};
+use std::{collections::HashMap, error::Error, fmt, fmt::Write, io};
+
+/// An error describing why code could not be generated from a `Definition`.
+/// 
+#[derive(Debug)]
+pub enum CodegenError {
+    /// The `Definition` could not be loaded.
+    Load(LoadError),
+
+    /// Two different names in the `Definition` map onto the same Rust identifier.
+    DuplicateIdentifier {
+        /// The Rust identifier.
+        identifier : String,
+
+        /// The first name.
+        first : String,
+
+        /// The second name.
+        second : String,
+    },
+
+    /// A name in the `Definition` does not contain any characters that can be used in a Rust identifier.
+    InvalidName(String),
+
+    /// The generated code could not be written.
+    Io(io::Error),
+}
+
+impl fmt::Display for CodegenError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CodegenError::Load(error) => write!(formatter, "{}", error),
+            CodegenError::DuplicateIdentifier { identifier, first, second } => {
+                write!(formatter, "Both \"{}\" and \"{}\" map onto the identifier `{}`!", first, second, identifier)
+            },
+            CodegenError::InvalidName(name) => write!(formatter, "\"{}\" cannot be turned into an identifier!", name),
+            CodegenError::Io(error) => write!(formatter, "Failed to write the generated code: {}", error),
+        }
+    }
+}
+
+impl Error for CodegenError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CodegenError::Load(error) => Some(error),
+            CodegenError::Io(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<LoadError> for CodegenError {
+    fn from(error : LoadError) -> Self {
+        CodegenError::Load(error)
+    }
+}
+
+impl From<DefinitionError> for CodegenError {
+    fn from(error : DefinitionError) -> Self {
+        CodegenError::Load(LoadError::Definition(error))
+    }
+}
+
+/// Splits `name` into words, separated by any character that cannot appear in an identifier, or by `_`.
+/// 
+fn words(name : &str) -> impl Iterator<Item = &str> {
+    name.split(|c : char| !c.is_ascii_alphanumeric()).filter(|word| !word.is_empty())
+}
+
+fn to_camel_case(name : &str) -> Result<String, CodegenError> {
+    let mut identifier = String::new();
+    for word in words(name) {
+        let mut chars = word.chars();
+        identifier.extend(chars.next().map(|c| c.to_ascii_uppercase()));
+        identifier.extend(chars);
+    }
+    finish_identifier(name, identifier)
+}
+
+fn to_snake_case(name : &str) -> Result<String, CodegenError> {
+    let identifier = words(name).map(|word| word.to_ascii_lowercase()).collect::<Vec<_>>().join("_");
+    finish_identifier(name, identifier)
+}
+
+/// Every keyword that is reserved in any edition of Rust, except for `self`, `Self`, `super`, and `crate`, which cannot
+/// be used as raw identifiers.
+/// 
+const KEYWORDS : &[&str] = &[
+    "abstract", "as", "async", "await", "become", "box", "break", "const", "continue", "do", "dyn", "else", "enum",
+    "extern", "false", "final", "fn", "for", "gen", "if", "impl", "in", "let", "loop", "macro", "match", "mod", "move",
+    "mut", "override", "priv", "pub", "ref", "return", "static", "struct", "trait", "true", "try", "type", "typeof",
+    "unsafe", "unsized", "use", "virtual", "where", "while", "yield",
+];
+
+fn finish_identifier(name : &str, identifier : String) -> Result<String, CodegenError> {
+    match identifier.as_str() {
+        "" => Err(CodegenError::InvalidName(name.to_owned())),
+        "self" | "Self" | "super" | "crate" => Ok(format!("{}_", identifier)),
+        keyword if KEYWORDS.contains(&keyword) => Ok(format!("r#{}", identifier)),
+        _ if identifier.starts_with(|c : char| c.is_ascii_digit()) => Ok(format!("_{}", identifier)),
+        _ => Ok(identifier),
+    }
+}
+
+/// Maps every distinct name in `names` onto a unique identifier via `convert`, preserving the order in which the
+/// names first appear.
+/// 
+fn identifiers<'a>(
+    names : impl IntoIterator<Item = &'a str>,
+    convert : fn(&str) -> Result<String, CodegenError>)
+    -> Result<Vec<(&'a str, String)>, CodegenError>
+{
+    let mut result : Vec<(&str, String)> = Vec::new();
+    let mut owners : HashMap<String, &str> = HashMap::new();
+    for name in names {
+        if result.iter().any(|(existing, _)| *existing == name) {
+            continue;
+        }
+
+        let identifier = convert(name)?;
+        if let Some(first) = owners.insert(identifier.clone(), name) {
+            return Err(CodegenError::DuplicateIdentifier {
+                identifier,
+                first : first.to_owned(),
+                second : name.to_owned(),
+            });
+        }
+        result.push((name, identifier));
+    }
+    Ok(result)
+}
+
+/// Generates Rust source code implementing `Family` and `Mode` for `definition`, using `name` as the name of the
+/// generated `Mode` type. The `Definition` is validated first.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub fn generate(definition : &Definition, name : &str) -> Result<String, CodegenError> {
+    definition.validate()?;
+
+    let states = identifiers(definition.states.iter().map(|state| state.name.as_str()), to_camel_case)?;
+    let events = identifiers(definition.transitions.iter().map(|transition| transition.event.as_str()), to_camel_case)?;
+    let guards =
+        identifiers(
+            definition.transitions.iter().filter_map(|transition| transition.guard.as_deref()),
+            to_snake_case)?;
+
+    let lookup = |identifiers : &[(&str, String)], name : &str| {
+        identifiers.iter().find(|(existing, _)| *existing == name).map(|(_, identifier)| identifier.clone()).unwrap()
+    };
+
+    let event = format!("{}Event", name);
+    let guards_type = format!("{}Guards", name);
+    let family = format!("{}Family", name);
+    let input = if guards.is_empty() { event.clone() } else { format!("({}, {})", event, guards_type) };
+
+    let write = || -> Result<String, fmt::Error> {
+        let mut code = String::new();
+        writeln!(code, "// Generated by mode::codegen. Do not edit.")?;
+        writeln!(code)?;
+
+        writeln!(code, "#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]")?;
+        writeln!(code, "pub enum {} {{", name)?;
+        for (_, state) in &states {
+            writeln!(code, "    {},", state)?;
+        }
+        writeln!(code, "}}")?;
+        writeln!(code)?;
+
+        writeln!(code, "impl {} {{", name)?;
+        writeln!(code, "    /// The initial state.")?;
+        writeln!(code, "    pub const INITIAL : Self = {}::{};", name, lookup(&states, &definition.initial))?;
+        writeln!(code, "}}")?;
+        writeln!(code)?;
+
+        writeln!(code, "#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]")?;
+        writeln!(code, "pub enum {} {{", event)?;
+        for (_, variant) in &events {
+            writeln!(code, "    {},", variant)?;
+        }
+        writeln!(code, "}}")?;
+        writeln!(code)?;
+
+        if !guards.is_empty() {
+            writeln!(code, "#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]")?;
+            writeln!(code, "pub struct {} {{", guards_type)?;
+            for (_, field) in &guards {
+                writeln!(code, "    pub {} : bool,", field)?;
+            }
+            writeln!(code, "}}")?;
+            writeln!(code)?;
+        }
+
+        writeln!(code, "pub struct {};", family)?;
+        writeln!(code)?;
+        writeln!(code, "impl ::mode::Family for {} {{", family)?;
+        writeln!(code, "    type Base = {};", name)?;
+        writeln!(code, "    type Mode = {};", name)?;
+        writeln!(code, "    type Input = {};", input)?;
+        writeln!(code, "    type Output = {};", name)?;
+        writeln!(code, "}}")?;
+        writeln!(code)?;
+
+        writeln!(code, "impl ::mode::Mode for {} {{", name)?;
+        writeln!(code, "    type Family = {};", family)?;
+        writeln!(code)?;
+        if guards.is_empty() {
+            writeln!(code, "    fn swap(self, event : {}) -> Self {{", input)?;
+        }
+        else {
+            writeln!(code, "    fn swap(self, (event, guards) : {}) -> Self {{", input)?;
+        }
+        // NOTE: A transition without a guard is only taken if none of the guarded transitions for the same state and
+        // event pass, so its arm has to come after theirs.
+        let mut transitions : Vec<_> = definition.transitions.iter().collect();
+        transitions.sort_by_key(|transition| transition.guard.is_none());
+
+        writeln!(code, "        match (self, event) {{")?;
+        for transition in transitions {
+            let condition =
+                transition.guard.as_deref()
+                    .map(|guard| format!(" if guards.{}", lookup(&guards, guard)))
+                    .unwrap_or_default();
+            writeln!(code,
+                "            ({}::{}, {}::{}){} => {}::{},",
+                name, lookup(&states, &transition.from),
+                event, lookup(&events, &transition.event),
+                condition,
+                name, lookup(&states, &transition.to))?;
+        }
+        writeln!(code, "            #[allow(unreachable_patterns)]")?;
+        writeln!(code, "            (state, _) => state,")?;
+        writeln!(code, "        }}")?;
+        writeln!(code, "    }}")?;
+        writeln!(code)?;
+
+        writeln!(code, "    fn tag(&self) -> &'static str {{")?;
+        writeln!(code, "        match self {{")?;
+        for (state, variant) in &states {
+            writeln!(code, "            {}::{} => {:?},", name, variant, state)?;
+        }
+        writeln!(code, "        }}")?;
+        writeln!(code, "    }}")?;
+        writeln!(code)?;
+
+        let finals : Vec<_> =
+            definition.states.iter()
+                .filter(|state| state.is_final)
+                .map(|state| format!("{}::{}", name, lookup(&states, &state.name)))
+                .collect();
+        writeln!(code, "    fn is_final(&self) -> bool {{")?;
+        if finals.is_empty() {
+            writeln!(code, "        false")?;
+        }
+        else {
+            writeln!(code, "        matches!(self, {})", finals.join(" | "))?;
+        }
+        writeln!(code, "    }}")?;
+        writeln!(code, "}}")?;
+
+        Ok(code)
+    };
+
+    Ok(write().expect("Writing to a String cannot fail!"))
+}
+
+/// Reads a `Definition` from the spec file at `spec` via
+/// [`Definition::from_file()`](../table/struct.Definition.html#method.from_file), generates code for it via
+/// [`generate()`](fn.generate.html), and writes the code to the file at `out`. This is intended to be called from a
+/// build script, so it also tells Cargo to re-run the build script whenever `spec` changes.
+/// 
+/// **NOTE:** This function is only available when the `serde` feature is enabled.
+/// 
+#[cfg(feature = "serde")]
+pub fn generate_file(
+    spec : impl AsRef<std::path::Path>,
+    name : &str,
+    out : impl AsRef<std::path::Path>)
+    -> Result<(), CodegenError>
+{
+    let spec = spec.as_ref();
+    println!("cargo:rerun-if-changed={}", spec.display());
+
+    let definition = Definition::from_file(spec)?;
+    let code = generate(&definition, name)?;
+    std::fs::write(out, code).map_err(CodegenError::Io)
+}
@@ -0,0 +1,168 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{
+    fmt,
+    hash::{Hash, Hasher},
+    ops::{Deref, DerefMut},
+    sync::Arc,
+};
+
+/// A clone-on-write wrapper around a large state payload that is stored inside of a `Mode`, so that the `Mode` can be
+/// cloned cheaply, e.g. in order to [fork](struct.Automaton.html#method.fork) an `Automaton` for speculative stepping,
+/// or by an [`Explorer`](explore/struct.Explorer.html) while searching the state space of a state machine.
+/// 
+/// Cloning a `CowState` only clones an `Arc` pointing to the payload, which is then shared between every clone. The
+/// payload is only deep-copied the first time one of the clones writes to it via `DerefMut` or
+/// [`make_mut()`](#method.make_mut) while it is still shared, and only for that clone. Reading from a `CowState`
+/// through `Deref` never copies the payload.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct EditorFamily;
+/// impl Family for EditorFamily {
+///     type Base = Editor;
+///     type Mode = Editor;
+///     type Input = char;
+///     type Output = Editor;
+/// }
+/// 
+/// #[derive(Clone)]
+/// struct Editor { document : CowState<String> }
+/// impl Mode for Editor {
+///     type Family = EditorFamily;
+///     fn swap(mut self, input : char) -> Self {
+///         self.document.push(input);
+///         self
+///     }
+/// }
+/// 
+/// let mut editor = EditorFamily::automaton_with_mode(Editor { document : CowState::new("draft".into()) });
+/// 
+/// // Forking the Automaton shares the document instead of copying it.
+/// let mut speculative = Automaton::fork(&editor);
+/// assert!(CowState::ptr_eq(&editor.document, &speculative.document));
+/// 
+/// // The document is only copied once the fork writes to it.
+/// Automaton::next_with_input(&mut speculative, '!');
+/// assert!(!CowState::ptr_eq(&editor.document, &speculative.document));
+/// assert_eq!(*speculative.document, "draft!");
+/// assert_eq!(*editor.document, "draft");
+/// 
+/// // The original still owns its copy uniquely, so writing to it does not copy anything.
+/// assert!(CowState::is_unique(&editor.document));
+/// Automaton::next_with_input(&mut editor, '?');
+/// assert_eq!(*editor.document, "draft?");
+/// ```
+/// 
+pub struct CowState<T> {
+    state : Arc<T>,
+}
+
+impl<T> CowState<T> {
+    /// Creates a new `CowState` that owns `state` uniquely.
+    /// 
+    pub fn new(state : T) -> Self {
+        Self { state : Arc::new(state) }
+    }
+
+    /// Returns `true` if no other `CowState` shares the payload of `this`, i.e. if writing to it would not copy it.
+    /// 
+    pub fn is_unique(this : &Self) -> bool {
+        Arc::strong_count(&this.state) == 1
+    }
+
+    /// Returns `true` if `this` and `other` share the same payload.
+    /// 
+    pub fn ptr_eq(this : &Self, other : &Self) -> bool {
+        Arc::ptr_eq(&this.state, &other.state)
+    }
+
+    /// Returns the payload of `this` by value, cloning it only if it is still shared with another `CowState`.
+    /// 
+    pub fn into_inner(this : Self) -> T
+        where T : Clone
+    {
+        Arc::try_unwrap(this.state).unwrap_or_else(|state| (*state).clone())
+    }
+}
+
+impl<T> CowState<T>
+    where T : Clone
+{
+    /// Returns a mutable reference to the payload of `this`, cloning it first if it is still shared with another
+    /// `CowState`.
+    /// 
+    pub fn make_mut(this : &mut Self) -> &mut T {
+        Arc::make_mut(&mut this.state)
+    }
+}
+
+impl<T> Clone for CowState<T> {
+    fn clone(&self) -> Self {
+        Self { state : Arc::clone(&self.state) }
+    }
+}
+
+impl<T> Default for CowState<T>
+    where T : Default
+{
+    fn default() -> Self {
+        Self::new(T::default())
+    }
+}
+
+impl<T> From<T> for CowState<T> {
+    fn from(state : T) -> Self {
+        Self::new(state)
+    }
+}
+
+impl<T> Deref for CowState<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.state
+    }
+}
+
+impl<T> DerefMut for CowState<T>
+    where T : Clone
+{
+    fn deref_mut(&mut self) -> &mut T {
+        Self::make_mut(self)
+    }
+}
+
+impl<T> fmt::Debug for CowState<T>
+    where T : fmt::Debug
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        fmt::Debug::fmt(&*self.state, formatter)
+    }
+}
+
+impl<T> PartialEq for CowState<T>
+    where T : PartialEq
+{
+    fn eq(&self, other : &Self) -> bool {
+        Self::ptr_eq(self, other) || *self.state == *other.state
+    }
+}
+
+impl<T> Eq for CowState<T>
+    where T : Eq
+{ }
+
+impl<T> Hash for CowState<T>
+    where T : Hash
+{
+    fn hash<H : Hasher>(&self, state : &mut H) {
+        self.state.hash(state)
+    }
+}
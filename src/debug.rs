@@ -0,0 +1,157 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines a global registry of every live, named `Automaton`, which can be queried at runtime in order to find out
+//! what every state machine in the program is doing right now, e.g. when a game soft-locks.
+//! 
+//! As soon as an `Automaton` is given a name via [`Automaton::set_name()`](../struct.Automaton.html#method.set_name),
+//! it adds itself to the registry under its [`path`](../struct.Automaton.html#method.path), and keeps the registry up
+//! to date with the [`tag()`](../trait.Mode.html#method.tag) of its current `Mode` on every transition. The entry is
+//! removed again when the `Automaton` is dropped. Unnamed `Automaton`s are never registered.
+//! 
+//! **NOTE:** This module is only available when the `debug` feature is enabled. Since every transition of a named
+//! `Automaton` briefly locks the registry, this feature is intended for debug builds.
+//! 
+//! # Usage
+//! ```
+//! use mode::{debug, Automaton, Family, Mode};
+//! 
+//! struct DoorFamily;
+//! impl Family for DoorFamily {
+//!     type Base = Door;
+//!     type Mode = Door;
+//!     type Input = ();
+//!     type Output = Door;
+//! }
+//! 
+//! enum Door { Open, Closed }
+//! impl Mode for Door {
+//!     type Family = DoorFamily;
+//!     fn swap(self, _input : ()) -> Self {
+//!         match self { Door::Open => Door::Closed, Door::Closed => Door::Open }
+//!     }
+//!     fn tag(&self) -> &'static str {
+//!         match self { Door::Open => "Open", Door::Closed => "Closed" }
+//!     }
+//! }
+//! 
+//! let mut front = DoorFamily::automaton_with_mode(Door::Closed);
+//! Automaton::set_name(&mut front, "doors/front");
+//! let mut back = DoorFamily::automaton_with_mode(Door::Closed);
+//! Automaton::set_name(&mut back, "doors/back");
+//! Automaton::next(&mut back);
+//! 
+//! let doors : Vec<_> = debug::live().into_iter().filter(|live| live.path.starts_with("doors/")).collect();
+//! assert_eq!(doors.len(), 2);
+//! assert_eq!((doors[0].path.as_str(), doors[0].tag), ("doors/back", "Open"));
+//! assert_eq!((doors[1].path.as_str(), doors[1].tag), ("doors/front", "Closed"));
+//! assert!(debug::dump().contains("doors/front: Closed\n"));
+//! 
+//! drop(back);
+//! assert!(debug::live().iter().all(|live| live.path != "doors/back"));
+//! ```
+//! 
+
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, Mutex,
+    },
+};
+
+/// A snapshot of a single live, named `Automaton`, as returned by [`live()`](fn.live.html).
+/// 
+#[derive(Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Live {
+    /// The [`path`](../struct.Automaton.html#method.path) of the `Automaton`.
+    pub path : String,
+
+    /// The `tag()` of the current `Mode` of the `Automaton`.
+    pub tag : &'static str,
+}
+
+impl fmt::Display for Live {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}: {}", self.path, self.tag)
+    }
+}
+
+static NEXT_ID : AtomicU64 = AtomicU64::new(0);
+static LIVE : Mutex<BTreeMap<u64, (Arc<str>, &'static str)>> = Mutex::new(BTreeMap::new());
+
+fn with_live<T>(f : impl FnOnce(&mut BTreeMap<u64, (Arc<str>, &'static str)>) -> T) -> T {
+    // A panic while the lock is held cannot leave the map in an inconsistent state, so poisoning can be ignored.
+    let mut live = LIVE.lock().unwrap_or_else(|error| error.into_inner());
+    f(&mut live)
+}
+
+/// Returns a snapshot of every live, named `Automaton`, sorted by path. `Automaton`s that share the same path are
+/// listed in the order in which they were named.
+/// 
+pub fn live() -> Vec<Live> {
+    let mut snapshot : Vec<Live> =
+        with_live(|live| {
+            live.values()
+                .map(|(path, tag)| Live { path : path.to_string(), tag })
+                .collect()
+        });
+    snapshot.sort_by(|a, b| a.path.cmp(&b.path));
+    snapshot
+}
+
+/// Returns a human-readable listing of every live, named `Automaton`, with one `path: tag` line per `Automaton`,
+/// sorted by path.
+/// 
+pub fn dump() -> String {
+    live().iter()
+        .map(|live| format!("{}\n", live))
+        .collect()
+}
+
+/// The entry of a single named `Automaton` in the registry, which is removed when the `Handle` is dropped.
+/// 
+#[derive(Debug)]
+pub(crate) struct Handle {
+    id : u64,
+}
+
+impl Handle {
+    /// Adds a new entry for an `Automaton` with the specified `path`, currently in the state with the specified `tag`.
+    /// 
+    pub(crate) fn register(path : Arc<str>, tag : &'static str) -> Self {
+        let id = NEXT_ID.fetch_add(1, Ordering::Relaxed);
+        with_live(|live| live.insert(id, (path, tag)));
+        Self { id }
+    }
+
+    /// Updates the path of the entry.
+    /// 
+    pub(crate) fn set_path(&self, path : Arc<str>) {
+        with_live(|live| {
+            if let Some(entry) = live.get_mut(&self.id) {
+                entry.0 = path;
+            }
+        });
+    }
+
+    /// Updates the `tag()` of the current state of the entry.
+    /// 
+    pub(crate) fn set_tag(&self, tag : &'static str) {
+        with_live(|live| {
+            if let Some(entry) = live.get_mut(&self.id) {
+                entry.1 = tag;
+            }
+        });
+    }
+}
+
+impl Drop for Handle {
+    fn drop(&mut self) {
+        with_live(|live| live.remove(&self.id));
+    }
+}
@@ -0,0 +1,690 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines operations from formal language theory over [`table`](../table/index.html) definitions.
+//! 
+//! A [`Dfa`](struct.Dfa.html) is a deterministic finite automaton built from a table
+//! [`Definition`](../table/struct.Definition.html) that is used as an acceptor: the events named by its transitions form
+//! the alphabet, and its final states are its accepting states. A sequence of events is accepted if it leads from the
+//! initial state to an accepting state. Missing transitions lead to an implicit, non-accepting sink state, which is
+//! only added if it is needed.
+//! 
+//! `Dfa`s can be combined via [`union()`](struct.Dfa.html#method.union),
+//! [`intersection()`](struct.Dfa.html#method.intersection), and [`complement()`](struct.Dfa.html#method.complement),
+//! minimized via [`minimize()`](struct.Dfa.html#method.minimize), compared via
+//! [`distinguish()`](struct.Dfa.html#method.distinguish), and turned back into a `Definition` via
+//! [`to_definition()`](struct.Dfa.html#method.to_definition) so that they can be driven by a
+//! [`table::Machine`](../table/struct.Machine.html).
+//! 
+//! Since a `Machine` halts as soon as it enters a final state, accepting states are not mapped onto final states when
+//! converting a `Dfa` back into a `Definition`, as the `Machine` would otherwise stop at the first accepted prefix.
+//! Instead, `to_definition()` returns an [`Acceptor`](struct.Acceptor.html), which keeps the names of the accepting
+//! states alongside a `Definition` without any final states. A sequence of events is accepted if the `Machine`
+//! driven by it ends up in one of them.
+//! 
+//! # Usage
+//! ```
+//! use mode::{dfa::Dfa, table::{Definition, Machine, TableFamily}, Automaton, Family};
+//! 
+//! // Accepts any sequence of "a"s and "b"s that ends with an "a".
+//! let ends_with_a =
+//!     Definition::new("Other")
+//!         .with_state("Other")
+//!         .with_final_state("A")
+//!         .with_transition("Other", "a", "A")
+//!         .with_transition("Other", "b", "Other")
+//!         .with_transition("A", "a", "A")
+//!         .with_transition("A", "b", "Other");
+//! 
+//! // Accepts any sequence containing an even number of "a"s.
+//! let even_as =
+//!     Definition::new("Even")
+//!         .with_final_state("Even")
+//!         .with_state("Odd")
+//!         .with_transition("Even", "a", "Odd")
+//!         .with_transition("Even", "b", "Even")
+//!         .with_transition("Odd", "a", "Even")
+//!         .with_transition("Odd", "b", "Odd");
+//! 
+//! let ends_with_a = Dfa::from_definition(&ends_with_a).unwrap();
+//! let even_as = Dfa::from_definition(&even_as).unwrap();
+//! 
+//! let both = ends_with_a.intersection(&even_as);
+//! assert!(both.accepts(["a", "b", "a"]));
+//! assert!(!both.accepts(["b", "a"]));
+//! 
+//! let either = ends_with_a.union(&even_as);
+//! assert!(either.accepts(["b", "a"]));
+//! assert!(either.accepts(["a", "a", "b"]));
+//! assert!(!either.accepts(["a", "b"]));
+//! 
+//! let neither = either.complement();
+//! assert!(neither.accepts(["a", "b"]));
+//! assert!(!neither.accepts(["c"]));
+//! 
+//! // The result can be driven like any other table definition, and reports whether the events seen so far are accepted.
+//! let acceptor = ends_with_a.to_definition();
+//! let machine = Machine::new(acceptor.definition.clone()).unwrap();
+//! let mut automaton = TableFamily::automaton_with_mode(machine);
+//! for event in ["a", "b", "a"] {
+//!     Automaton::next_with_input(&mut automaton, event.to_owned());
+//! }
+//! assert!(acceptor.is_accepting(automaton.state()));
+//! 
+//! Automaton::next_with_input(&mut automaton, "b".to_owned());
+//! assert!(!acceptor.is_accepting(automaton.state()));
+//! assert_eq!(Dfa::from_acceptor(&acceptor).unwrap(), ends_with_a);
+//! ```
+//! 
+
+use crate::table::{Definition, DefinitionError, StateDef};
+use std::{
+    collections::{hash_map::Entry, BTreeMap, BTreeSet, HashMap, VecDeque},
+    error::Error,
+    fmt,
+};
+
+/// An error describing why a `Definition` could not be turned into a `Dfa`.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DfaError {
+    /// The `Definition` is not well-formed.
+    Definition(DefinitionError),
+
+    /// A transition is guarded, so whether it is taken cannot be decided from the event alone.
+    Guarded {
+        /// The name of the state.
+        from : String,
+
+        /// The name of the event.
+        event : String,
+    },
+}
+
+impl fmt::Display for DfaError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DfaError::Definition(error) => write!(formatter, "{}", error),
+            DfaError::Guarded { from, event } => {
+                write!(formatter, "State \"{}\" has a guarded transition on event \"{}\"!", from, event)
+            },
+        }
+    }
+}
+
+impl Error for DfaError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            DfaError::Definition(error) => Some(error),
+            DfaError::Guarded { .. } => None,
+        }
+    }
+}
+
+impl From<DefinitionError> for DfaError {
+    fn from(error : DefinitionError) -> Self {
+        DfaError::Definition(error)
+    }
+}
+
+/// A table [`Definition`](../table/struct.Definition.html) used as an acceptor, together with the names of its
+/// accepting states, as returned by [`Dfa::to_definition()`](struct.Dfa.html#method.to_definition).
+/// 
+/// The `definition` has no final states, so that a [`table::Machine`](../table/struct.Machine.html) driven by it
+/// consumes every event instead of halting at the first accepted prefix. A sequence of events is accepted if the
+/// state that the `Machine` ends up in [`is_accepting()`](#method.is_accepting).
+/// 
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Acceptor {
+    /// The states and transitions of the acceptor, none of which are final.
+    pub definition : Definition,
+
+    /// The names of the accepting states.
+    pub accepting : BTreeSet<String>,
+}
+
+impl Acceptor {
+    /// Returns `true` if the state called `state` is an accepting state.
+    /// 
+    pub fn is_accepting(&self, state : &str) -> bool {
+        self.accepting.contains(state)
+    }
+}
+
+/// A deterministic finite automaton with a complete transition function over a finite alphabet of named events.
+/// 
+/// States and events are identified by their index in [`states()`](#method.states) and
+/// [`alphabet()`](#method.alphabet), respectively. The alphabet is always sorted.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Dfa {
+    states : Vec<String>,
+    accepting : Vec<bool>,
+    alphabet : Vec<String>,
+    initial : usize,
+    delta : Vec<Vec<usize>>,
+}
+
+impl Dfa {
+    /// Builds a `Dfa` from `definition`, which is validated first. Every final state becomes an accepting state. If
+    /// any state has no transition for some event in the alphabet, a non-accepting sink state is added, which every
+    /// missing transition leads to. Guarded transitions are rejected.
+    /// 
+    pub fn from_definition(definition : &Definition) -> Result<Self, DfaError> {
+        Self::build(definition, |state| state.is_final)
+    }
+
+    /// Builds a `Dfa` from an [`Acceptor`](struct.Acceptor.html), e.g. one returned by
+    /// [`to_definition()`](#method.to_definition). Same as [`from_definition()`](#method.from_definition), except that
+    /// the accepting states are the ones named by `acceptor`, rather than the final states of its `Definition`.
+    /// 
+    pub fn from_acceptor(acceptor : &Acceptor) -> Result<Self, DfaError> {
+        Self::build(&acceptor.definition, |state| acceptor.is_accepting(&state.name))
+    }
+
+    /// Builds a `Dfa` from `definition`, with the states for which `is_accepting` returns `true` as accepting states.
+    /// 
+    fn build(definition : &Definition, is_accepting : impl Fn(&StateDef) -> bool) -> Result<Self, DfaError> {
+        definition.validate()?;
+        if let Some(transition) = definition.transitions.iter().find(|transition| transition.guard.is_some()) {
+            return Err(DfaError::Guarded { from : transition.from.clone(), event : transition.event.clone() });
+        }
+
+        let states : Vec<String> = definition.states.iter().map(|state| state.name.clone()).collect();
+        let accepting = definition.states.iter().map(&is_accepting).collect();
+        let alphabet : Vec<String> =
+            definition.transitions.iter()
+                .map(|transition| transition.event.clone())
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+
+        let index_of = |name : &str| states.iter().position(|state| state == name).unwrap();
+        let mut delta = vec![vec![None; alphabet.len()]; states.len()];
+        for transition in &definition.transitions {
+            let symbol = alphabet.binary_search(&transition.event).unwrap();
+            delta[index_of(&transition.from)][symbol] = Some(index_of(&transition.to));
+        }
+
+        let initial = index_of(&definition.initial);
+        let mut dfa = Self { states, accepting, alphabet, initial, delta : Vec::new() };
+        dfa.delta = dfa.complete(delta);
+        Ok(dfa)
+    }
+
+    /// Fills every missing transition in `delta` with a transition into a new sink state, if necessary.
+    /// 
+    fn complete(&mut self, delta : Vec<Vec<Option<usize>>>) -> Vec<Vec<usize>> {
+        if delta.iter().all(|row| row.iter().all(Option::is_some)) {
+            return delta.into_iter().map(|row| row.into_iter().map(Option::unwrap).collect()).collect();
+        }
+
+        let sink = self.add_state("∅".to_owned(), false);
+        let mut complete : Vec<Vec<usize>> =
+            delta.into_iter()
+                .map(|row| row.into_iter().map(|to| to.unwrap_or(sink)).collect())
+                .collect();
+        complete.push(vec![sink; self.alphabet.len()]);
+        complete
+    }
+
+    /// Adds a new state whose name is based on `name`, but does not clash with any existing state, and returns its
+    /// index. The transitions out of the new state must be added by the caller.
+    /// 
+    fn add_state(&mut self, mut name : String, accepting : bool) -> usize {
+        while self.states.contains(&name) {
+            name.push('\'');
+        }
+        self.states.push(name);
+        self.accepting.push(accepting);
+        self.states.len() - 1
+    }
+
+    /// Returns an [`Acceptor`](struct.Acceptor.html) equivalent to this `Dfa`. Every state of its `Definition` is
+    /// non-final, so that a `Machine` driven by it never halts, and every transition is included, including those into
+    /// and out of the sink state, if any. The accepting states are listed separately.
+    /// 
+    pub fn to_definition(&self) -> Acceptor {
+        let mut definition = Definition::new(self.states[self.initial].clone());
+        for name in &self.states {
+            definition = definition.with_state(name.clone());
+        }
+
+        for (from, row) in self.delta.iter().enumerate() {
+            for (symbol, to) in row.iter().enumerate() {
+                definition =
+                    definition.with_transition(
+                        self.states[from].clone(),
+                        self.alphabet[symbol].clone(),
+                        self.states[*to].clone());
+            }
+        }
+
+        let accepting =
+            self.states.iter().zip(&self.accepting)
+                .filter(|(_, accepting)| **accepting)
+                .map(|(name, _)| name.clone())
+                .collect();
+        Acceptor { definition, accepting }
+    }
+
+    /// Returns the names of all states, in order of their indices.
+    /// 
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    /// Returns the alphabet of events, in sorted order.
+    /// 
+    pub fn alphabet(&self) -> &[String] {
+        &self.alphabet
+    }
+
+    /// Returns the index of the initial state.
+    /// 
+    pub fn initial(&self) -> usize {
+        self.initial
+    }
+
+    /// Returns the index of the state called `name`, if any.
+    /// 
+    pub fn state_index(&self, name : &str) -> Option<usize> {
+        self.states.iter().position(|state| state == name)
+    }
+
+    /// Returns `true` if the state at index `state` is accepting.
+    /// 
+    pub fn is_accepting(&self, state : usize) -> bool {
+        self.accepting[state]
+    }
+
+    /// Returns the index of the state reached from the state at index `state` when `event` arrives, or `None` if
+    /// `event` is not part of the alphabet.
+    /// 
+    pub fn next(&self, state : usize, event : &str) -> Option<usize> {
+        self.alphabet.binary_search_by(|symbol| symbol.as_str().cmp(event))
+            .ok()
+            .map(|symbol| self.delta[state][symbol])
+    }
+
+    /// Returns `true` if the sequence of `events` leads from the initial state to an accepting state. Any sequence
+    /// containing an event that is not part of the alphabet is rejected.
+    /// 
+    pub fn accepts<I>(&self, events : I) -> bool
+        where
+            I : IntoIterator,
+            I::Item : AsRef<str>,
+    {
+        let mut state = self.initial;
+        for event in events {
+            match self.next(state, event.as_ref()) {
+                Some(next) => state = next,
+                None => return false,
+            }
+        }
+        self.accepting[state]
+    }
+
+    /// Returns a `Dfa` that accepts exactly the sequences over the same alphabet that this `Dfa` rejects.
+    /// 
+    /// **NOTE:** Since the complement is taken with respect to the alphabet of this `Dfa`, sequences containing events
+    /// outside of it are still rejected. Use [`with_alphabet()`](#method.with_alphabet) first in order to take the
+    /// complement with respect to a larger alphabet.
+    /// 
+    pub fn complement(&self) -> Self {
+        let mut complement = self.clone();
+        for accepting in &mut complement.accepting {
+            *accepting = !*accepting;
+        }
+        complement
+    }
+
+    /// Returns a `Dfa` that accepts the same sequences as this one, but whose alphabet also includes every event in
+    /// `events`. Each added event leads to a non-accepting sink state.
+    /// 
+    pub fn with_alphabet<I>(&self, events : I) -> Self
+        where
+            I : IntoIterator,
+            I::Item : Into<String>,
+    {
+        let alphabet : Vec<String> =
+            self.alphabet.iter().cloned()
+                .chain(events.into_iter().map(Into::into))
+                .collect::<BTreeSet<_>>()
+                .into_iter()
+                .collect();
+        if alphabet == self.alphabet {
+            return self.clone();
+        }
+
+        let delta =
+            self.delta.iter()
+                .map(|row| {
+                    alphabet.iter()
+                        .map(|event| self.alphabet.binary_search(event).ok().map(|symbol| row[symbol]))
+                        .collect()
+                })
+                .collect();
+
+        let mut extended =
+            Self {
+                states : self.states.clone(),
+                accepting : self.accepting.clone(),
+                alphabet,
+                initial : self.initial,
+                delta : Vec::new(),
+            };
+        extended.delta = extended.complete(delta);
+        extended
+    }
+
+    /// Returns a `Dfa` that accepts every sequence accepted by either this `Dfa` or `other`. The alphabet of the result
+    /// is the union of both alphabets.
+    /// 
+    pub fn union(&self, other : &Dfa) -> Self {
+        self.product(other, |left, right| left || right)
+    }
+
+    /// Returns a `Dfa` that accepts every sequence accepted by both this `Dfa` and `other`. The alphabet of the result
+    /// is the union of both alphabets.
+    /// 
+    pub fn intersection(&self, other : &Dfa) -> Self {
+        self.product(other, |left, right| left && right)
+    }
+
+    /// Returns the indices of all states that are reachable from the initial state, in breadth-first order.
+    /// 
+    fn reachable(&self) -> Vec<usize> {
+        let mut reached = vec![false; self.states.len()];
+        let mut order = vec![self.initial];
+        reached[self.initial] = true;
+
+        let mut next = 0;
+        while next < order.len() {
+            for to in &self.delta[order[next]] {
+                if !reached[*to] {
+                    reached[*to] = true;
+                    order.push(*to);
+                }
+            }
+            next += 1;
+        }
+
+        order
+    }
+
+    /// Returns the smallest `Dfa` that accepts the same sequences as this one, along with a mapping from the index of
+    /// each state in this `Dfa` to the index of the state it was merged into, or `None` if it was dropped because it is
+    /// unreachable from the initial state.
+    /// 
+    /// States are merged using Hopcroft's partition refinement algorithm. The initial state of the result always has
+    /// index `0`, and the remaining states are numbered in breadth-first order. A state that was not merged with any
+    /// other keeps its name, while a merged state is named after its members, e.g. `{A, B}`.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{dfa::Dfa, table::Definition};
+    /// 
+    /// // "B" and "C" both accept everything from here on, so they can be merged. "D" is unreachable.
+    /// let definition =
+    ///     Definition::new("A")
+    ///         .with_state("A")
+    ///         .with_final_state("B")
+    ///         .with_final_state("C")
+    ///         .with_state("D")
+    ///         .with_transition("A", "x", "B")
+    ///         .with_transition("A", "y", "C")
+    ///         .with_transition("B", "x", "C")
+    ///         .with_transition("B", "y", "B")
+    ///         .with_transition("C", "x", "B")
+    ///         .with_transition("C", "y", "C")
+    ///         .with_transition("D", "x", "A")
+    ///         .with_transition("D", "y", "A");
+    /// 
+    /// let dfa = Dfa::from_definition(&definition).unwrap();
+    /// let (minimal, mapping) = dfa.minimize();
+    /// 
+    /// assert_eq!(minimal.states(), ["A", "{B, C}"]);
+    /// assert_eq!(mapping, vec![Some(0), Some(1), Some(1), None]);
+    /// assert!(minimal.accepts(["y", "x", "x"]));
+    /// assert!(!minimal.accepts(Vec::<&str>::new()));
+    /// ```
+    /// 
+    pub fn minimize(&self) -> (Self, Vec<Option<usize>>) {
+        let reachable = self.reachable();
+
+        // Start with the accepting and non-accepting states in separate blocks.
+        let mut class_of = vec![usize::MAX; self.states.len()];
+        let mut blocks : Vec<Vec<usize>> = Vec::new();
+        for accepting in [true, false] {
+            let block : Vec<usize> =
+                reachable.iter().copied()
+                    .filter(|state| self.accepting[*state] == accepting)
+                    .collect();
+            if !block.is_empty() {
+                for state in &block {
+                    class_of[*state] = blocks.len();
+                }
+                blocks.push(block);
+            }
+        }
+
+        let mut inverse = vec![vec![Vec::new(); self.states.len()]; self.alphabet.len()];
+        for from in &reachable {
+            for (symbol, to) in self.delta[*from].iter().enumerate() {
+                inverse[symbol][*to].push(*from);
+            }
+        }
+
+        let mut pending : Vec<usize> = (0..blocks.len()).collect();
+        let mut is_pending = vec![true; blocks.len()];
+        while let Some(splitter) = pending.pop() {
+            is_pending[splitter] = false;
+            let splitter = blocks[splitter].clone();
+            for predecessors in &inverse {
+                // Group every state that leads into the splitter on this symbol by the block it belongs to.
+                let mut touched : BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+                for to in &splitter {
+                    for from in &predecessors[*to] {
+                        touched.entry(class_of[*from]).or_default().push(*from);
+                    }
+                }
+
+                for (block, members) in touched {
+                    if members.len() == blocks[block].len() {
+                        continue;
+                    }
+
+                    // NOTE: Reassigning the members first lets the block be filtered in linear time via class_of.
+                    let split = blocks.len();
+                    for state in &members {
+                        class_of[*state] = split;
+                    }
+                    blocks[block].retain(|state| class_of[*state] == block);
+                    blocks.push(members);
+
+                    if is_pending[block] || blocks[split].len() <= blocks[block].len() {
+                        pending.push(split);
+                        is_pending.push(true);
+                    }
+                    else {
+                        pending.push(block);
+                        is_pending[block] = true;
+                        is_pending.push(false);
+                    }
+                }
+            }
+        }
+
+        // Renumber the blocks in breadth-first order, starting with the block containing the initial state.
+        let mut renumbered = vec![usize::MAX; blocks.len()];
+        let mut order = Vec::new();
+        for state in &reachable {
+            if renumbered[class_of[*state]] == usize::MAX {
+                renumbered[class_of[*state]] = order.len();
+                order.push(class_of[*state]);
+            }
+        }
+
+        let states =
+            order.iter()
+                .map(|block| {
+                    let mut members = blocks[*block].clone();
+                    members.sort_unstable();
+                    if members.len() == 1 {
+                        return self.states[members[0]].clone();
+                    }
+
+                    let names : Vec<&str> = members.iter().map(|state| self.states[*state].as_str()).collect();
+                    format!("{{{}}}", names.join(", "))
+                })
+                .collect();
+
+        let minimal =
+            Self {
+                states,
+                accepting : order.iter().map(|block| self.accepting[blocks[*block][0]]).collect(),
+                alphabet : self.alphabet.clone(),
+                initial : 0,
+                delta :
+                    order.iter()
+                        .map(|block| {
+                            self.delta[blocks[*block][0]].iter()
+                                .map(|to| renumbered[class_of[*to]])
+                                .collect()
+                        })
+                        .collect(),
+            };
+
+        let mapping =
+            class_of.iter()
+                .map(|block| if *block == usize::MAX { None } else { Some(renumbered[*block]) })
+                .collect();
+
+        (minimal, mapping)
+    }
+
+    /// Returns `true` if this `Dfa` and `other` accept exactly the same sequences of events.
+    /// 
+    /// See [`distinguish()`](#method.distinguish) for more details.
+    /// 
+    pub fn is_equivalent(&self, other : &Dfa) -> bool {
+        self.distinguish(other).is_none()
+    }
+
+    /// Returns the shortest sequence of events that is accepted by exactly one of this `Dfa` and `other`, or `None` if
+    /// both accept the same sequences. This can be used to check a hand-written state machine against a specification,
+    /// e.g. after refactoring it. Events that are only part of one of the two alphabets are rejected by the other.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{dfa::Dfa, table::Definition};
+    /// 
+    /// // Specification: the door must be unlocked before it is opened.
+    /// let spec =
+    ///     Definition::new("Locked")
+    ///         .with_state("Locked")
+    ///         .with_state("Unlocked")
+    ///         .with_final_state("Open")
+    ///         .with_transition("Locked", "unlock", "Unlocked")
+    ///         .with_transition("Unlocked", "open", "Open");
+    /// 
+    /// // A refactored version that accidentally allows opening a locked door.
+    /// let refactored =
+    ///     Definition::new("Locked")
+    ///         .with_state("Locked")
+    ///         .with_state("Unlocked")
+    ///         .with_final_state("Open")
+    ///         .with_transition("Locked", "unlock", "Unlocked")
+    ///         .with_transition("Locked", "open", "Open")
+    ///         .with_transition("Unlocked", "open", "Open");
+    /// 
+    /// let spec = Dfa::from_definition(&spec).unwrap();
+    /// let refactored = Dfa::from_definition(&refactored).unwrap();
+    /// 
+    /// assert_eq!(spec.distinguish(&refactored), Some(vec!["open".to_owned()]));
+    /// assert!(spec.is_equivalent(&spec.minimize().0));
+    /// ```
+    /// 
+    pub fn distinguish(&self, other : &Dfa) -> Option<Vec<String>> {
+        type Pair = (usize, usize);
+
+        let left = self.with_alphabet(other.alphabet.iter().cloned());
+        let right = other.with_alphabet(self.alphabet.iter().cloned());
+
+        // Search the product breadth-first, remembering how each pair of states was first reached.
+        let start = (left.initial, right.initial);
+        let mut parents : HashMap<Pair, Option<(Pair, usize)>> = HashMap::new();
+        let mut pending = VecDeque::new();
+        parents.insert(start, None);
+        pending.push_back(start);
+
+        while let Some((l, r)) = pending.pop_front() {
+            if left.accepting[l] != right.accepting[r] {
+                let mut events = Vec::new();
+                let mut pair = (l, r);
+                while let Some(Some((parent, symbol))) = parents.get(&pair) {
+                    events.push(left.alphabet[*symbol].clone());
+                    pair = *parent;
+                }
+                events.reverse();
+                return Some(events);
+            }
+
+            for symbol in 0..left.alphabet.len() {
+                let next = (left.delta[l][symbol], right.delta[r][symbol]);
+                if let Entry::Vacant(entry) = parents.entry(next) {
+                    entry.insert(Some(((l, r), symbol)));
+                    pending.push_back(next);
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Builds the product of this `Dfa` and `other` over the union of their alphabets, keeping only the states that are
+    /// reachable from the initial state. A state of the product is accepting if `accept` returns `true` for the
+    /// acceptance of its two component states.
+    /// 
+    fn product(&self, other : &Dfa, accept : impl Fn(bool, bool) -> bool) -> Self {
+        let left = self.with_alphabet(other.alphabet.iter().cloned());
+        let right = other.with_alphabet(self.alphabet.iter().cloned());
+
+        let mut indices = HashMap::new();
+        let mut pairs = Vec::new();
+        let mut pending = VecDeque::new();
+        let mut visit = |pair : (usize, usize), pairs : &mut Vec<(usize, usize)>, pending : &mut VecDeque<usize>| {
+            *indices.entry(pair).or_insert_with(|| {
+                pairs.push(pair);
+                pending.push_back(pairs.len() - 1);
+                pairs.len() - 1
+            })
+        };
+
+        let initial = visit((left.initial, right.initial), &mut pairs, &mut pending);
+        let mut delta = Vec::new();
+        while let Some(index) = pending.pop_front() {
+            let (l, r) = pairs[index];
+            let row : Vec<usize> =
+                (0..left.alphabet.len())
+                    .map(|symbol| visit((left.delta[l][symbol], right.delta[r][symbol]), &mut pairs, &mut pending))
+                    .collect();
+            // States are visited in the order in which they were discovered, so each row belongs at the end.
+            delta.push(row);
+        }
+
+        Self {
+            states : pairs.iter().map(|(l, r)| format!("({}, {})", left.states[*l], right.states[*r])).collect(),
+            accepting : pairs.iter().map(|(l, r)| accept(left.accepting[*l], right.accepting[*r])).collect(),
+            alphabet : left.alphabet,
+            initial,
+            delta,
+        }
+    }
+}
@@ -0,0 +1,499 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines helpers for driving an `Automaton` from a game loop or other external update loop.
+//! 
+//! # Fixed time steps
+//! Most simulations want their state machines to be stepped at a fixed rate, regardless of how quickly frames are
+//! actually being rendered. [`FixedStep`](struct.FixedStep.html) accumulates the real time that has elapsed between
+//! frames and steps the `Automaton` once for every full time step that has accumulated, carrying any remainder over to
+//! the next frame. The fraction of a time step left over is exposed via
+//! [`FixedStep::alpha()`](struct.FixedStep.html#method.alpha), so that renderers can interpolate between the previous
+//! and current simulation states.
+//! 
+//! ```
+//! use mode::{driver, Family, Mode};
+//! use std::time::Duration;
+//! 
+//! struct CounterFamily;
+//! impl Family for CounterFamily {
+//!     type Base = Counter;
+//!     type Mode = Counter;
+//!     type Input = ();
+//!     type Output = Counter;
+//! }
+//! 
+//! struct Counter(u32);
+//! impl Mode for Counter {
+//!     type Family = CounterFamily;
+//!     fn swap(self, _input : ()) -> Self { Counter(self.0 + 1) }
+//! }
+//! 
+//! let mut automaton = CounterFamily::automaton_with_mode(Counter(0));
+//! let mut timestep = driver::fixed_step(Duration::from_millis(10));
+//! 
+//! // 25 ms have passed since the last frame, so the Automaton is stepped twice, with half a step left over.
+//! assert_eq!(timestep.next(&mut automaton, Duration::from_millis(25)), 2);
+//! assert_eq!(automaton.0, 2);
+//! assert!((timestep.alpha() - 0.5).abs() < 1e-6);
+//! ```
+//! 
+//! # Large populations
+//! When the `rayon` feature is enabled, [`Pool`](struct.Pool.html) can be used to drive hundreds of thousands of
+//! lightweight, input-driven state machines at once, by sharding them across the `rayon` thread pool. Inputs can be
+//! sent to any state machine in the `Pool` from any thread via an [`Injector`](struct.Injector.html), with
+//! backpressure applied once the input queue for a shard fills up.
+//! 
+
+use crate::{Automaton, Family, Mode};
+use std::time::{Duration, Instant};
+
+/// Returns a new [`FixedStep`](struct.FixedStep.html) that steps at a fixed rate of once every `step`. This is
+/// shorthand for `FixedStep::new()`.
+/// 
+pub fn fixed_step(step : Duration) -> FixedStep {
+    FixedStep::new(step)
+}
+
+/// Accumulates elapsed time and converts it into a whole number of fixed-length simulation steps.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug)]
+pub struct FixedStep {
+    step : Duration,
+    accumulator : Duration,
+    max_steps : u32,
+    last_tick : Option<Instant>,
+}
+
+impl FixedStep {
+    /// The default maximum number of steps that will be taken per call to `advance()`.
+    /// 
+    pub const DEFAULT_MAX_STEPS : u32 = 8;
+
+    /// Creates a new `FixedStep` that steps once for every `step` of accumulated time.
+    /// 
+    /// # Panics
+    /// Panics if `step` is zero.
+    /// 
+    pub fn new(step : Duration) -> Self {
+        assert!(step > Duration::from_secs(0), "Fixed time step must be greater than zero!");
+
+        Self {
+            step,
+            accumulator : Duration::from_secs(0),
+            max_steps : Self::DEFAULT_MAX_STEPS,
+            last_tick : None,
+        }
+    }
+
+    /// Sets the maximum number of steps that will be taken per call to `advance()`. If more time than this has
+    /// accumulated, e.g. because the process was suspended, the excess time is discarded. This prevents a slow frame
+    /// from causing ever more steps to be taken on each subsequent frame.
+    /// 
+    pub fn with_max_steps(mut self, max_steps : u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+
+    /// Returns the length of a single step.
+    /// 
+    pub fn step(&self) -> Duration {
+        self.step
+    }
+
+    /// Returns the fraction of a step that has accumulated but not yet been simulated, between `0.0` and `1.0`. This
+    /// can be used to interpolate between the previous and current simulation states when rendering.
+    /// 
+    pub fn alpha(&self) -> f32 {
+        self.accumulator.as_secs_f32() / self.step.as_secs_f32()
+    }
+
+    /// Adds `elapsed` to the accumulated time, and then calls `step` once for every full step that has accumulated,
+    /// returning the number of steps that were taken.
+    /// 
+    pub fn advance(&mut self, elapsed : Duration, mut step : impl FnMut()) -> u32 {
+        self.accumulator += elapsed;
+
+        let mut steps = 0;
+        while self.accumulator >= self.step {
+            if steps == self.max_steps {
+                self.accumulator = Duration::from_secs(0);
+                break;
+            }
+
+            self.accumulator -= self.step;
+            step();
+            steps += 1;
+        }
+
+        steps
+    }
+
+    /// Adds `elapsed` to the accumulated time, and then calls `Automaton::next()` on `automaton` once for every full
+    /// step that has accumulated, returning the number of steps that were taken. If `automaton` is paused or finished,
+    /// no time is accumulated and no steps are taken.
+    /// 
+    pub fn next<F, M>(&mut self, automaton : &mut Automaton<F>, elapsed : Duration) -> u32
+        where
+            F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+            M : Mode<Family = F>,
+    {
+        if Automaton::is_paused(automaton) || Automaton::is_finished(automaton) {
+            return 0;
+        }
+
+        self.advance(elapsed, || Automaton::next(automaton))
+    }
+
+    /// Measures the real time that has elapsed since the last call to `tick()`, and then steps `automaton` as in
+    /// [`next()`](#method.next). The first call to `tick()` only starts the clock, and never steps the `Automaton`.
+    /// 
+    pub fn tick<F, M>(&mut self, automaton : &mut Automaton<F>) -> u32
+        where
+            F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+            M : Mode<Family = F>,
+    {
+        let now = Instant::now();
+        let elapsed = self.last_tick.map_or(Duration::from_secs(0), |last_tick| now - last_tick);
+        self.last_tick = Some(now);
+        self.next(automaton, elapsed)
+    }
+}
+
+#[cfg(feature = "rayon")]
+mod pool {
+    use crate::{CancellationToken, Family, Mode};
+    use std::sync::{
+        atomic::{AtomicU64, Ordering},
+        mpsc::{self, Receiver, SyncSender, TrySendError},
+    };
+
+    type CancelFn<M> = dyn Fn(M) -> M + Send + Sync;
+
+    static NEXT_POOL_ID : AtomicU64 = AtomicU64::new(0);
+
+    /// Identifies a single state machine within a [`Pool`](struct.Pool.html). A `Handle` is only valid for the `Pool` that
+    /// issued it.
+    /// 
+    #[derive(Copy, Clone, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+    pub struct Handle {
+        pool : u64,
+        shard : usize,
+        index : usize,
+    }
+
+    impl Handle {
+        /// Returns the index of the shard that owns the state machine.
+        /// 
+        pub fn shard(self) -> usize {
+            self.shard
+        }
+    }
+
+    struct Shard<M, Input> {
+        modes : Vec<Option<M>>,
+        receiver : Receiver<(usize, Input)>,
+    }
+
+    /// Drives a large population of lightweight, input-driven state machines by sharding them across the `rayon`
+    /// thread pool.
+    /// 
+    /// Each state machine is assigned to one of a fixed number of shards when it is inserted, and each shard has its own
+    /// bounded input queue. Inputs can be sent to any state machine from any thread via an
+    /// [`Injector`](struct.Injector.html). Every call to [`step()`](#method.step) processes the pending inputs of all
+    /// shards in parallel, with idle worker threads stealing shards from busy ones. Each input is passed into the
+    /// `swap()` function of the state machine it was addressed to. State machines that have no pending inputs are not
+    /// stepped, and state machines in a [final](../trait.Mode.html#method.is_final) `Mode` ignore their inputs.
+    /// 
+    /// Since each shard queue is bounded, producers that send inputs faster than the `Pool` can step its state machines
+    /// experience backpressure: [`Injector::send()`](struct.Injector.html#method.send) blocks until there is room in the
+    /// queue, while [`Injector::try_send()`](struct.Injector.html#method.try_send) returns the input to the caller.
+    /// 
+    /// **NOTE:** This type is only available when the `rayon` feature is enabled.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{driver::Pool, Family, Mode};
+    /// 
+    /// struct CounterFamily;
+    /// impl Family for CounterFamily {
+    ///     type Base = Counter;
+    ///     type Mode = Counter;
+    ///     type Input = u32;
+    ///     type Output = Counter;
+    /// }
+    /// 
+    /// struct Counter(u32);
+    /// impl Mode for Counter {
+    ///     type Family = CounterFamily;
+    ///     fn swap(self, input : u32) -> Self { Counter(self.0 + input) }
+    /// }
+    /// 
+    /// let mut pool = Pool::<CounterFamily>::new(4, 1024);
+    /// let handles : Vec<_> = (0..100).map(|_| pool.insert(Counter(0))).collect();
+    /// 
+    /// let injector = pool.injector();
+    /// std::thread::spawn(move || {
+    ///     for handle in handles { injector.send(handle, 2); }
+    /// }).join().unwrap();
+    /// 
+    /// assert_eq!(pool.step(), 100);
+    /// assert!(pool.modes().all(|counter| counter.0 == 2));
+    /// ```
+    /// 
+    pub struct Pool<F>
+        where F : Family + ?Sized
+    {
+        id : u64,
+        shards : Vec<Shard<F::Mode, F::Input>>,
+        senders : Vec<SyncSender<(usize, F::Input)>>,
+        capacity : usize,
+        next_shard : usize,
+        cancellation : Option<(CancellationToken, Box<CancelFn<F::Mode>>)>,
+    }
+
+    impl<F> Pool<F>
+        where F : Family + ?Sized
+    {
+        /// Creates a new, empty `Pool` with the specified number of `shards`, each of which can queue up to `capacity`
+        /// inputs before producers experience backpressure.
+        /// 
+        /// # Panics
+        /// Panics if `shards` or `capacity` is zero.
+        /// 
+        pub fn new(shards : usize, capacity : usize) -> Self {
+            assert!(shards > 0, "A Pool must have at least one shard!");
+            assert!(capacity > 0, "Shard capacity must be greater than zero!");
+
+            let (senders, shards) =
+                (0..shards)
+                    .map(|_| {
+                        let (sender, receiver) = mpsc::sync_channel(capacity);
+                        (sender, Shard { modes : Vec::new(), receiver })
+                    })
+                    .unzip();
+
+            let id = NEXT_POOL_ID.fetch_add(1, Ordering::Relaxed);
+            Self { id, shards, senders, capacity, next_shard : 0, cancellation : None }
+        }
+
+        /// Adds a new state machine to the `Pool` with `mode` as its initial `Mode`, returning a `Handle` that can be
+        /// used to address inputs to it. State machines are distributed across shards in round-robin order.
+        /// 
+        pub fn insert(&mut self, mode : F::Mode) -> Handle {
+            let shard = self.next_shard;
+            self.next_shard = (self.next_shard + 1) % self.shards.len();
+
+            let modes = &mut self.shards[shard].modes;
+            modes.push(Some(mode));
+            Handle { pool : self.id, shard, index : modes.len() - 1 }
+        }
+
+        /// Returns the current `Mode` of the state machine identified by `handle`, if any. Returns `None` if `handle` was
+        /// issued by a different `Pool`.
+        /// 
+        pub fn get(&self, handle : Handle) -> Option<&F::Mode> {
+            if handle.pool != self.id {
+                return None;
+            }
+
+            self.shards.get(handle.shard)
+                .and_then(|shard| shard.modes.get(handle.index))
+                .and_then(Option::as_ref)
+        }
+
+        /// Returns an iterator over the current `Mode` of every state machine in the `Pool`, ordered by shard.
+        /// 
+        pub fn modes(&self) -> impl Iterator<Item = &F::Mode> + '_ {
+            self.shards.iter()
+                .flat_map(|shard| shard.modes.iter().flatten())
+        }
+
+        /// Returns the total number of state machines in the `Pool`.
+        /// 
+        pub fn len(&self) -> usize {
+            self.shards.iter().map(|shard| shard.modes.len()).sum()
+        }
+
+        /// Returns `true` if the `Pool` contains no state machines.
+        /// 
+        pub fn is_empty(&self) -> bool {
+            self.len() == 0
+        }
+
+        /// Releases any spare capacity in each shard, so that memory usage stays bounded over long sessions. Existing
+        /// `Handle`s remain valid. See [`Batch::compact()`](../struct.Batch.html#method.compact) for when to call this.
+        /// 
+        pub fn compact(&mut self) {
+            for shard in &mut self.shards {
+                shard.modes.shrink_to_fit();
+            }
+        }
+
+        /// Makes the `Pool` observe `token` on behalf of every state machine it hosts, replacing any
+        /// `CancellationToken` that it was observing before. At the beginning of the first call to
+        /// [`step()`](#method.step) after `token` is cancelled, every state machine that is not in a final `Mode` has
+        /// [`Mode::exit()`](../trait.Mode.html#method.exit) called on its current `Mode`, which is then passed by value
+        /// into `on_cancel`, and the `Mode` it returns becomes current. This happens at most once per call to
+        /// `set_cancellation()`.
+        /// 
+        /// See [`CancellationToken`](../struct.CancellationToken.html) for more details.
+        /// 
+        pub fn set_cancellation<C>(&mut self, token : &CancellationToken, on_cancel : C)
+            where C : Fn(F::Mode) -> F::Mode + Send + Sync + 'static
+        {
+            self.cancellation = Some((token.clone(), Box::new(on_cancel)));
+        }
+
+        /// Returns a new `Injector` that can be used to send inputs to the state machines in this `Pool` from any
+        /// thread.
+        /// 
+        pub fn injector(&self) -> Injector<F::Input> {
+            Injector { pool : self.id, senders : self.senders.clone() }
+        }
+    }
+
+    impl<F, M, Input> Pool<F>
+        where
+            F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+            M : Mode<Family = F> + Send,
+            Input : Send,
+    {
+        /// Processes the pending inputs of every shard in parallel, returning the total number of inputs that were
+        /// processed. At most `capacity` inputs are processed per shard, so that a producer that sends inputs
+        /// continuously cannot prevent this function from returning.
+        /// 
+        pub fn step(&mut self) -> usize {
+            use rayon::prelude::*;
+
+            if self.cancellation.as_ref().is_some_and(|(token, _)| token.is_cancelled()) {
+                let (_, on_cancel) = self.cancellation.take().unwrap();
+                self.shards.par_iter_mut()
+                    .flat_map(|shard| shard.modes.par_iter_mut())
+                    .for_each(|slot| {
+                        let mut mode = slot.take().expect("Cannot cancel a Mode that panicked during swap()!");
+                        if !mode.is_final() {
+                            mode.exit();
+                            mode = on_cancel(mode);
+                        }
+                        *slot = Some(mode);
+                    });
+            }
+
+            let capacity = self.capacity;
+            self.shards.par_iter_mut()
+                .map(|shard| {
+                    let mut processed = 0;
+                    for (index, input) in shard.receiver.try_iter().take(capacity) {
+                        if let Some(slot) = shard.modes.get_mut(index) {
+                            let mode = slot.take().expect("Cannot step a Mode that panicked during swap()!");
+                            *slot = Some(if mode.is_final() { mode } else { mode.swap(input) });
+                        }
+                        processed += 1;
+                    }
+                    processed
+                })
+                .sum()
+        }
+    }
+
+    /// Sends inputs to the state machines in a [`Pool`](struct.Pool.html) from any thread.
+    /// 
+    /// Inputs sent to the same state machine are processed in the order in which they were sent. Sending an input
+    /// happens-before the `Pool::step()` call that processes it, since each shard queue is a `std::sync::mpsc` channel;
+    /// no other ordering is guaranteed between inputs sent to different shards.
+    /// 
+    pub struct Injector<Input> {
+        pool : u64,
+        senders : Vec<SyncSender<(usize, Input)>>,
+    }
+
+    impl<Input> Clone for Injector<Input> {
+        fn clone(&self) -> Self {
+            Self { pool : self.pool, senders : self.senders.clone() }
+        }
+    }
+
+    impl<Input> Injector<Input> {
+        /// Sends `input` to the state machine identified by `handle`, blocking while the queue for its shard is full.
+        /// Returns `false` if the `Pool` has been dropped, or if `handle` was issued by a different `Pool`.
+        /// 
+        pub fn send(&self, handle : Handle, input : Input) -> bool {
+            match self.sender(handle) {
+                Some(sender) => sender.send((handle.index, input)).is_ok(),
+                None => false,
+            }
+        }
+
+        /// Sends `input` to the state machine identified by `handle` without blocking. If the queue for its shard is
+        /// full, `input` is returned to the caller as `TrySendError::Full`. If the `Pool` has been dropped, or if
+        /// `handle` was issued by a different `Pool`, it is returned as `TrySendError::Disconnected`.
+        /// 
+        /// # Usage
+        /// ```
+        /// use mode::{driver::Pool, Family, Mode};
+        /// use std::sync::mpsc::TrySendError;
+        /// 
+        /// struct CounterFamily;
+        /// impl Family for CounterFamily {
+        ///     type Base = Counter;
+        ///     type Mode = Counter;
+        ///     type Input = u32;
+        ///     type Output = Counter;
+        /// }
+        /// 
+        /// struct Counter(u32);
+        /// impl Mode for Counter {
+        ///     type Family = CounterFamily;
+        ///     fn swap(self, input : u32) -> Self { Counter(self.0 + input) }
+        /// }
+        /// 
+        /// let mut small = Pool::<CounterFamily>::new(1, 1);
+        /// let mut large = Pool::<CounterFamily>::new(4, 1);
+        /// let handles : Vec<_> = (0..4).map(|_| large.insert(Counter(0))).collect();
+        /// 
+        /// // A Handle from another Pool is rejected, even if its shard doesn't exist in this one.
+        /// assert_eq!(small.injector().try_send(handles[3], 1), Err(TrySendError::Disconnected(1)));
+        /// assert!(small.get(handles[3]).is_none());
+        /// 
+        /// let injector = large.injector();
+        /// assert_eq!(injector.try_send(handles[3], 1), Ok(()));
+        /// assert_eq!(injector.try_send(handles[3], 2), Err(TrySendError::Full(2)));
+        /// ```
+        /// 
+        pub fn try_send(&self, handle : Handle, input : Input) -> Result<(), TrySendError<Input>> {
+            let sender =
+                match self.sender(handle) {
+                    Some(sender) => sender,
+                    None => return Err(TrySendError::Disconnected(input)),
+                };
+
+            sender.try_send((handle.index, input))
+                .map_err(|error| {
+                    match error {
+                        TrySendError::Full((_, input)) => TrySendError::Full(input),
+                        TrySendError::Disconnected((_, input)) => TrySendError::Disconnected(input),
+                    }
+                })
+        }
+
+        /// Returns the queue for the shard that owns the state machine identified by `handle`, or `None` if `handle`
+        /// was issued by a different `Pool`.
+        /// 
+        fn sender(&self, handle : Handle) -> Option<&SyncSender<(usize, Input)>> {
+            if handle.pool != self.pool {
+                return None;
+            }
+
+            self.senders.get(handle.shard)
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+pub use self::pool::*;
@@ -0,0 +1,22 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::time::Duration;
+
+/// Specifies the minimum amount of time for which a `Mode` must remain current before the `Automaton` will allow it to
+/// `swap()` again. This can be used to prevent an `Automaton` from oscillating rapidly between states whose transition
+/// conditions flip back and forth from one step to the next.
+/// 
+/// See [`Automaton::set_min_dwell()`](struct.Automaton.html#method.set_min_dwell) for more details.
+/// 
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+pub enum Dwell {
+    /// The current `Mode` must remain current for at least this many calls to `Automaton::next*()`.
+    Steps(u64),
+
+    /// The current `Mode` must remain current for at least this much real time.
+    Time(Duration),
+}
@@ -0,0 +1,139 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{fmt, sync::Arc, time::Instant};
+
+/// Describes a single transition from one `Mode` to another within an `Automaton`.
+/// 
+/// A `TransitionEvent` is published to every subscriber of an `Automaton` each time a call to `swap()` results in a
+/// `Mode` with a different [`tag()`](trait.Mode.html#method.tag) becoming current. Subscribers receive events over a
+/// `std::sync::mpsc` channel, and can therefore be moved to other threads and polled independently of the `Automaton`.
+/// 
+/// See [`Automaton::subscribe()`](struct.Automaton.html#method.subscribe) for more details.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct TransitionEvent {
+    /// The `tag()` of the `Mode` that was current before the transition.
+    pub from : &'static str,
+
+    /// The `tag()` of the `Mode` that became current as a result of the transition.
+    pub to : &'static str,
+
+    /// The moment at which the transition took place.
+    pub at : Instant,
+
+    /// The [`fields()`](trait.Mode.html#method.fields) of the `Mode` that became current as a result of the transition.
+    pub fields : Vec<Field>,
+
+    /// The [`path`](struct.Automaton.html#method.path) of the `Automaton` that published the event, if it has been
+    /// named.
+    pub path : Option<Arc<str>>,
+}
+
+/// A single structured field describing the state represented by a `Mode`, as returned by
+/// [`Mode::fields()`](trait.Mode.html#method.fields).
+/// 
+/// A `Field` is displayed as `name=value`, e.g. `hours_worked=4`.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct WorkerFamily;
+/// impl Family for WorkerFamily {
+///     type Base = Worker;
+///     type Mode = Worker;
+///     type Input = ();
+///     type Output = Worker;
+/// }
+/// 
+/// enum Worker { Working { hours_worked : u32 }, Eating { hours_worked : u32 } }
+/// impl Mode for Worker {
+///     type Family = WorkerFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Worker::Working { hours_worked : 3 } => Worker::Eating { hours_worked : 4 },
+///             Worker::Working { hours_worked } => Worker::Working { hours_worked : hours_worked + 1 },
+///             eating => eating,
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Worker::Working { .. } => "Working", Worker::Eating { .. } => "Eating" }
+///     }
+///     fn fields(&self) -> Vec<Field> {
+///         match self {
+///             Worker::Working { hours_worked } | Worker::Eating { hours_worked } => {
+///                 vec![Field::new("hours_worked", hours_worked)]
+///             },
+///         }
+///     }
+/// }
+/// 
+/// let mut worker = WorkerFamily::automaton_with_mode(Worker::Working { hours_worked : 0 });
+/// let events = Automaton::subscribe(&mut worker);
+/// let mut log = Vec::new();
+/// for _ in 0..4 {
+///     Automaton::next_logged(&mut worker, (), &mut log);
+/// }
+/// 
+/// let event = events.try_recv().unwrap();
+/// assert_eq!(event.to, "Eating");
+/// assert_eq!(event.fields[0].to_string(), "hours_worked=4");
+/// 
+/// assert_eq!(
+///     log.last(),
+///     Some(&Record::Transition {
+///         from : "Working".to_owned(),
+///         to : "Eating".to_owned(),
+///         fields : vec![("hours_worked".to_owned(), "4".to_owned())],
+///     }));
+/// ```
+/// 
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
+pub struct Field {
+    /// The name of the field, e.g. `hours_worked`.
+    pub name : &'static str,
+
+    /// The value of the field, converted to a `String`.
+    pub value : String,
+}
+
+impl Field {
+    /// Creates a new `Field` called `name`, with the `Display` representation of `value` as its value.
+    /// 
+    pub fn new(name : &'static str, value : impl fmt::Display) -> Self {
+        Self { name, value : value.to_string() }
+    }
+}
+
+impl fmt::Display for Field {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}={}", self.name, self.value)
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl defmt::Format for Field {
+    fn format(&self, formatter : defmt::Formatter) {
+        defmt::write!(formatter, "{=str}={=str}", self.name, self.value.as_str())
+    }
+}
+
+/// Formats the `TransitionEvent` without its timestamp, since an `Instant` has no meaning on the host that decodes the
+/// `defmt` log.
+/// 
+#[cfg(feature = "defmt")]
+impl defmt::Format for TransitionEvent {
+    fn format(&self, formatter : defmt::Formatter) {
+        defmt::write!(
+            formatter,
+            "TransitionEvent {{ from: {=str}, to: {=str}, path: {}, fields: {} }}",
+            self.from,
+            self.to,
+            self.path.as_deref(),
+            self.fields.as_slice())
+    }
+}
@@ -0,0 +1,179 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+#[cfg(feature = "serde")]
+use serde::{Deserialize, Serialize};
+use std::{error::Error, fmt};
+
+/// Identifies the hook of an `Automaton` that took the place of `swap()` for a single step.
+/// 
+/// See [`Record::Hook`](enum.Record.html#variant.Hook) for more details.
+/// 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Trigger {
+    /// The `CancellationToken` set via
+    /// [`Automaton::set_cancellation()`](struct.Automaton.html#method.set_cancellation) was cancelled.
+    Cancellation,
+
+    /// A `Future` passed into [`Automaton::transition_when()`](struct.Automaton.html#method.transition_when) resolved.
+    Awaiting,
+
+    /// A request queued via [`Automaton::request_transition()`](struct.Automaton.html#method.request_transition) was
+    /// applied.
+    Request,
+
+    /// A timeout set via [`Automaton::set_timeout()`](struct.Automaton.html#method.set_timeout) expired.
+    Timeout,
+
+    /// The stall limit set via [`Automaton::set_stall_limit()`](struct.Automaton.html#method.set_stall_limit) was
+    /// reached.
+    StallLimit,
+}
+
+/// A single entry in the event log of an `Automaton`.
+/// 
+/// See [`EventLog`](trait.EventLog.html) for more details.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(Serialize, Deserialize))]
+pub enum Record<Input> {
+    /// An `Input` was passed into the `swap()` function of the current `Mode`. `Input`s that were discarded without
+    /// reaching `swap()`, e.g. because the `Automaton` was paused, are not logged.
+    Input(Input),
+
+    /// A hook of the `Automaton` replaced the current `Mode` in place of `swap()`, and the `Input` for that step was
+    /// discarded.
+    Hook(Trigger),
+
+    /// The `Input` or `Hook` before this `Record` caused the current `Mode` to be replaced by a `Mode` with a different
+    /// `tag()`.
+    Transition {
+        /// The `tag()` of the `Mode` that was current before the transition.
+        from : String,
+
+        /// The `tag()` of the `Mode` that became current as a result of the transition.
+        to : String,
+
+        /// The name and value of each of the [`fields()`](trait.Mode.html#method.fields) of the `Mode` that became
+        /// current as a result of the transition.
+        #[cfg_attr(feature = "serde", serde(default))]
+        fields : Vec<(String, String)>,
+    },
+}
+
+/// A pluggable sink that receives a [`Record`](enum.Record.html) of every `Input` passed into an `Automaton`, and every
+/// transition that results, via [`Automaton::next_logged()`](struct.Automaton.html#method.next_logged).
+/// 
+/// Since the `Mode`s of an `Automaton` are expected to be deterministic, the current state of an `Automaton` can be
+/// reconstructed at any time by replaying the log into a fresh copy of its initial `Mode`, via
+/// [`Automaton::rehydrate()`](struct.Automaton.html#method.rehydrate) or
+/// [`Automaton::replay()`](struct.Automaton.html#method.replay). Each logged `Input` is passed into `swap()` again,
+/// and each `Hook` record forces the same hook of the replica to fire, regardless of whether its condition holds at
+/// the time, e.g. whether the timeout has expired yet. The `Transition` records provide an audit trail of every state
+/// the `Automaton` has been in, and are used to detect a replica that diverges from the original.
+/// 
+/// `EventLog` is implemented for `Vec<Record<Input>>`, which stores all `Record`s in memory. Implement this trait for
+/// other types in order to append `Record`s to a file, a database, or a message queue instead.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct OrderFamily;
+/// impl Family for OrderFamily {
+///     type Base = Order;
+///     type Mode = Order;
+///     type Input = &'static str;
+///     type Output = Order;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Order { Placed, Paid, Shipped }
+/// impl Mode for Order {
+///     type Family = OrderFamily;
+///     fn swap(self, input : &'static str) -> Self {
+///         match (self, input) {
+///             (Order::Placed, "pay") => Order::Paid,
+///             (Order::Paid, "ship") => Order::Shipped,
+///             (order, _) => order,
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Order::Placed => "Placed", Order::Paid => "Paid", Order::Shipped => "Shipped" }
+///     }
+/// }
+/// 
+/// let mut log = Vec::new();
+/// let mut order = OrderFamily::automaton_with_mode(Order::Placed);
+/// for input in vec!["pay", "refund", "ship"] {
+///     Automaton::next_logged(&mut order, input, &mut log);
+/// }
+/// assert_eq!(*order, Order::Shipped);
+/// assert_eq!(log.len(), 5);
+/// 
+/// // Rebuild the same state from the log.
+/// let replica = Automaton::<OrderFamily>::rehydrate(Order::Placed, log).unwrap();
+/// assert_eq!(*replica, Order::Shipped);
+/// ```
+/// 
+pub trait EventLog<Input> {
+    /// Appends `record` to the end of the log.
+    /// 
+    fn append(&mut self, record : Record<Input>);
+}
+
+impl<Input> EventLog<Input> for Vec<Record<Input>> {
+    fn append(&mut self, record : Record<Input>) {
+        self.push(record);
+    }
+}
+
+/// The error returned by [`Automaton::replay()`](struct.Automaton.html#method.replay) and
+/// [`Automaton::rehydrate()`](struct.Automaton.html#method.rehydrate) when a log cannot be replayed faithfully. In
+/// either case, the `Automaton` is left in the state it reached before the `Record` at `index`.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ReplayError {
+    /// The `Hook` record at `index` could not be replayed, either because the `Automaton` has no such hook for its
+    /// current `Mode`, or because the hook is a one-off closure that is not part of the log, i.e. a `Request` or an
+    /// `Awaiting` guard.
+    MissingHook {
+        /// The index of the `Record` in the log.
+        index : usize,
+
+        /// The hook that fired in the original `Automaton`.
+        trigger : Trigger,
+    },
+
+    /// The `tag()` of the current `Mode` doesn't match the state that the log says the original `Automaton` was in
+    /// before the `Record` at `index`. The `index` is the length of the log if the final state differs.
+    Diverged {
+        /// The index of the `Record` in the log.
+        index : usize,
+
+        /// The `tag()` of the `Mode` that was current in the original `Automaton`.
+        expected : String,
+
+        /// The `tag()` of the current `Mode` of the replica.
+        actual : &'static str,
+    },
+}
+
+impl fmt::Display for ReplayError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ReplayError::MissingHook { index, trigger } => {
+                write!(formatter, "Cannot replay the {:?} hook logged at index {}!", trigger, index)
+            },
+            ReplayError::Diverged { index, expected, actual } => {
+                write!(formatter, "Replay diverged at index {}: expected \"{}\", found \"{}\"!", index, expected, actual)
+            },
+        }
+    }
+}
+
+impl Error for ReplayError { }
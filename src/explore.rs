@@ -0,0 +1,484 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines a test harness that exhaustively explores the state space of a state machine with enumerable inputs.
+//! 
+//! An [`Explorer`](struct.Explorer.html) is given every `Input` that the state machine can receive, a depth bound, and
+//! any number of invariants, i.e. predicates that must hold for every `Mode` that can be reached. Starting from an
+//! initial `Mode`, it tries every possible sequence of `Input`s up to the depth bound, breadth-first, by cloning the
+//! current `Mode` and calling `Mode::swap()` on the clone directly. The resulting [`Report`](struct.Report.html)
+//! contains a counterexample for each panic and each violated invariant that was found, along with the `tag()`s of
+//! every `Mode` that was reached, and those of any expected states that were not.
+//! 
+//! Since the search is breadth-first, the first counterexample found for each failure is also one of the shortest.
+//! Only the first counterexample is recorded for each invariant, and for each distinct panic message.
+//! 
+//! Counterexamples found elsewhere, e.g. in a recorded [`EventLog`](../trait.EventLog.html) or by a property-based
+//! test, can be reduced to a minimal reproduction via
+//! [`Explorer::shrink()`](struct.Explorer.html#method.shrink), or, for arbitrary failing sequences, via the
+//! [`shrink()`](fn.shrink.html) function.
+//! 
+//! **NOTE:** Panics raised by `Mode::swap()` are caught via `std::panic::catch_unwind()`, but the panic hook still runs,
+//! so each panic will still be printed to `stderr` by default.
+//! 
+//! # Usage
+//! ```
+//! use mode::{explore::{Explorer, Failure}, Family, Mode};
+//! 
+//! struct VendingFamily;
+//! impl Family for VendingFamily {
+//!     type Base = Vending;
+//!     type Mode = Vending;
+//!     type Input = Coin;
+//!     type Output = Vending;
+//! }
+//! 
+//! #[derive(Clone, Copy, Debug, PartialEq)]
+//! enum Coin { Nickel, Dime, Refund }
+//! 
+//! #[derive(Clone, Debug, Hash, PartialEq, Eq)]
+//! enum Vending { Waiting { cents : u32 }, Vending }
+//! 
+//! impl Mode for Vending {
+//!     type Family = VendingFamily;
+//!     fn swap(self, coin : Coin) -> Self {
+//!         match (self, coin) {
+//!             (Vending::Waiting { .. }, Coin::Refund) => Vending::Waiting { cents : 0 },
+//!             (Vending::Waiting { cents }, Coin::Nickel) => Vending::Waiting { cents : cents + 5 },
+//!             (Vending::Waiting { cents }, Coin::Dime) => Vending::Waiting { cents : cents + 10 },
+//!             (vending, _) => vending,
+//!         }
+//!     }
+//!     fn tag(&self) -> &'static str {
+//!         match self { Vending::Waiting { .. } => "Waiting", Vending::Vending => "Vending" }
+//!     }
+//! }
+//! 
+//! let explorer =
+//!     Explorer::<VendingFamily>::new(vec![Coin::Nickel, Coin::Dime, Coin::Refund], 4)
+//!         .with_invariant("never holds more than 25 cents", |mode| {
+//!             !matches!(mode, Vending::Waiting { cents } if *cents > 25)
+//!         })
+//!         .expect_state("Vending")
+//!         .dedup_by(|mode| mode.clone());
+//! 
+//! let report = explorer.explore(Vending::Waiting { cents : 0 });
+//! assert!(!report.is_ok());
+//! assert_eq!(report.unreached(), ["Vending"]);
+//! 
+//! let counterexample = &report.counterexamples()[0];
+//! assert_eq!(counterexample.failure, Failure::Invariant("never holds more than 25 cents"));
+//! assert_eq!(counterexample.inputs, vec![Coin::Dime, Coin::Dime, Coin::Dime]);
+//! ```
+//! 
+
+use crate::{Family, Mode};
+use std::{
+    any::Any,
+    collections::{hash_map::DefaultHasher, BTreeSet, HashSet, VecDeque},
+    fmt,
+    hash::{Hash, Hasher},
+    panic::{self, AssertUnwindSafe},
+};
+
+type InvariantFn<F> = dyn Fn(&<F as Family>::Mode) -> bool;
+type KeyFn<F> = dyn Fn(&<F as Family>::Mode) -> u64;
+
+/// Describes why a [`Counterexample`](struct.Counterexample.html) failed.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Failure {
+    /// `Mode::swap()` panicked with the given message.
+    Panic(String),
+
+    /// The invariant with the given name returned `false` for the `Mode` that was reached.
+    Invariant(&'static str),
+}
+
+impl fmt::Display for Failure {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Failure::Panic(message) => write!(formatter, "swap() panicked: {}", message),
+            Failure::Invariant(name) => write!(formatter, "invariant \"{}\" was violated", name),
+        }
+    }
+}
+
+/// A sequence of `Input`s that causes a [`Failure`](enum.Failure.html) when passed, in order, into the initial `Mode`.
+/// 
+#[derive(Clone, Debug, PartialEq)]
+pub struct Counterexample<I> {
+    /// The `Input`s leading to the `failure`, in order.
+    pub inputs : Vec<I>,
+
+    /// The `Failure` caused by the last `Input`.
+    pub failure : Failure,
+}
+
+/// The result of a call to [`Explorer::explore()`](struct.Explorer.html#method.explore).
+/// 
+pub struct Report<F>
+    where F : Family + ?Sized
+{
+    counterexamples : Vec<Counterexample<F::Input>>,
+    reached : BTreeSet<&'static str>,
+    unreached : Vec<&'static str>,
+    transitions : usize,
+}
+
+impl<F> Report<F>
+    where F : Family + ?Sized
+{
+    /// Returns `true` if no counterexamples were found, and every expected state was reached.
+    /// 
+    pub fn is_ok(&self) -> bool {
+        self.counterexamples.is_empty() && self.unreached.is_empty()
+    }
+
+    /// Returns the shortest counterexample found for each panic message and each violated invariant, in the order in
+    /// which they were found.
+    /// 
+    pub fn counterexamples(&self) -> &[Counterexample<F::Input>] {
+        &self.counterexamples
+    }
+
+    /// Returns the `tag()`s of every `Mode` that was reached, including the initial `Mode`.
+    /// 
+    pub fn reached(&self) -> &BTreeSet<&'static str> {
+        &self.reached
+    }
+
+    /// Returns the `tag()`s of every expected state that was never reached, in the order in which they were expected.
+    /// 
+    pub fn unreached(&self) -> &[&'static str] {
+        &self.unreached
+    }
+
+    /// Returns the total number of calls to `Mode::swap()` that were made.
+    /// 
+    pub fn transitions(&self) -> usize {
+        self.transitions
+    }
+}
+
+impl<F> fmt::Debug for Report<F>
+    where
+        F : Family + ?Sized,
+        F::Input : fmt::Debug,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Report")
+            .field("counterexamples", &self.counterexamples)
+            .field("reached", &self.reached)
+            .field("unreached", &self.unreached)
+            .field("transitions", &self.transitions)
+            .finish()
+    }
+}
+
+/// Exhaustively explores every sequence of `Input`s up to a depth bound, starting from an initial `Mode`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct Explorer<F>
+    where F : Family + ?Sized
+{
+    inputs : Vec<F::Input>,
+    depth : usize,
+    invariants : Vec<(&'static str, Box<InvariantFn<F>>)>,
+    expected : Vec<&'static str>,
+    key : Option<Box<KeyFn<F>>>,
+}
+
+impl<F, M> Explorer<F>
+    where
+        F : Family<Mode = M, Output = M> + ?Sized,
+        F::Input : Clone,
+        M : Mode<Family = F> + Clone,
+{
+    /// Creates a new `Explorer` that tries every sequence of up to `depth` `Input`s taken from `inputs`.
+    /// 
+    pub fn new(inputs : Vec<F::Input>, depth : usize) -> Self {
+        Self { inputs, depth, invariants : Vec::new(), expected : Vec::new(), key : None }
+    }
+
+    /// Adds an invariant called `name`, which must return `true` for every `Mode` that can be reached.
+    /// 
+    pub fn with_invariant<P>(mut self, name : &'static str, invariant : P) -> Self
+        where P : Fn(&M) -> bool + 'static
+    {
+        self.invariants.push((name, Box::new(invariant)));
+        self
+    }
+
+    /// Adds `tag` to the set of states that are expected to be reached. Any expected state that is never reached is
+    /// listed in [`Report::unreached()`](struct.Report.html#method.unreached).
+    /// 
+    pub fn expect_state(mut self, tag : &'static str) -> Self {
+        self.expected.push(tag);
+        self
+    }
+
+    /// Treats any two `Mode`s for which `key` returns equal values as the same state, so that each state is only
+    /// explored further the first time it is reached. Without this, every sequence of `Input`s is tried, and the number
+    /// of calls to `Mode::swap()` grows exponentially with the depth.
+    /// 
+    /// **NOTE:** Keys are compared by their hashes, so two different keys whose hashes collide will be treated as the
+    /// same state.
+    /// 
+    pub fn dedup_by<K, G>(mut self, key : G) -> Self
+        where
+            K : Hash,
+            G : Fn(&M) -> K + 'static,
+    {
+        self.key =
+            Some(Box::new(move |mode| {
+                let mut hasher = DefaultHasher::new();
+                key(mode).hash(&mut hasher);
+                hasher.finish()
+            }));
+        self
+    }
+
+    /// Returns the counterexample for the first invariant that `mode` violates, if any.
+    /// 
+    fn check(&self, mode : &M, inputs : &[usize]) -> Option<Counterexample<F::Input>> {
+        self.invariants.iter()
+            .find(|(_, invariant)| !invariant(mode))
+            .map(|(name, _)| Counterexample { inputs : self.inputs_for(inputs), failure : Failure::Invariant(name) })
+    }
+
+    fn inputs_for(&self, indices : &[usize]) -> Vec<F::Input> {
+        indices.iter().map(|index| self.inputs[*index].clone()).collect()
+    }
+
+    /// Passes each of `inputs` into a clone of `initial`, in order, checking every invariant after each step, and
+    /// returns a `Counterexample` for the first panic or violated invariant, if any. The `Counterexample` only contains
+    /// the `Input`s up to and including the one that caused the `Failure`.
+    /// 
+    pub fn replay(&self, initial : &M, inputs : &[F::Input]) -> Option<Counterexample<F::Input>> {
+        let mut mode = initial.clone();
+        if let Some(counterexample) = self.check(&mode, &[]) {
+            return Some(counterexample);
+        }
+
+        for (index, input) in inputs.iter().enumerate() {
+            let input = input.clone();
+            let failure =
+                match panic::catch_unwind(AssertUnwindSafe(move || mode.swap(input))) {
+                    Ok(next) => {
+                        mode = next;
+                        match self.invariants.iter().find(|(_, invariant)| !invariant(&mode)) {
+                            Some((name, _)) => Failure::Invariant(name),
+                            None => continue,
+                        }
+                    },
+                    Err(payload) => Failure::Panic(panic_message(&*payload)),
+                };
+
+            return Some(Counterexample { inputs : inputs[..=index].to_vec(), failure });
+        }
+
+        None
+    }
+
+    /// Reduces `counterexample` to a shorter sequence of `Input`s that still causes the same `Failure` when passed into
+    /// `initial`, as checked via [`replay()`](#method.replay). First, as many `Input`s as possible are removed via
+    /// [`shrink()`](fn.shrink.html). Then, each remaining `Input` is replaced by the first `Input` passed to
+    /// [`Explorer::new()`](#method.new) that still causes the same `Failure`, if any, so that `Input`s listed earlier
+    /// are treated as simpler.
+    /// 
+    /// If `counterexample` does not cause its `Failure` in the first place, it is returned unchanged.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{explore::{Counterexample, Explorer, Failure}, Family, Mode};
+    /// 
+    /// struct TankFamily;
+    /// impl Family for TankFamily {
+    ///     type Base = Tank;
+    ///     type Mode = Tank;
+    ///     type Input = i32;
+    ///     type Output = Tank;
+    /// }
+    /// 
+    /// #[derive(Clone, Debug)]
+    /// struct Tank { level : i32 }
+    /// impl Mode for Tank {
+    ///     type Family = TankFamily;
+    ///     fn swap(self, change : i32) -> Self { Tank { level : (self.level + change).max(0) } }
+    /// }
+    /// 
+    /// let explorer =
+    ///     Explorer::<TankFamily>::new(vec![0, -1, 1, 5], 0)
+    ///         .with_invariant("does not overflow", |tank| tank.level < 10);
+    /// 
+    /// // A long trace, e.g. taken from a recorded session.
+    /// let inputs = vec![1, 0, 5, -1, 1, 0, 1, -1, 5, 1, 0, 1];
+    /// let counterexample = explorer.replay(&Tank { level : 0 }, &inputs).unwrap();
+    /// assert_eq!(counterexample.inputs.len(), 9);
+    /// 
+    /// let shrunk = explorer.shrink(&Tank { level : 0 }, counterexample);
+    /// assert_eq!(shrunk, Counterexample { inputs : vec![5, 5], failure : Failure::Invariant("does not overflow") });
+    /// ```
+    /// 
+    pub fn shrink(&self, initial : &M, counterexample : Counterexample<F::Input>) -> Counterexample<F::Input> {
+        let failure = counterexample.failure;
+        let fails = |inputs : &[F::Input]| {
+            self.replay(initial, inputs).is_some_and(|replayed| replayed.failure == failure)
+        };
+
+        if !fails(&counterexample.inputs) {
+            return Counterexample { inputs : counterexample.inputs, failure };
+        }
+
+        let mut inputs = shrink(counterexample.inputs, fails);
+        for position in 0..inputs.len() {
+            for simpler in &self.inputs {
+                let mut candidate = inputs.clone();
+                candidate[position] = simpler.clone();
+                if fails(&candidate) {
+                    inputs = candidate;
+                    break;
+                }
+            }
+        }
+
+        // Replacing an Input may cause the Failure to happen earlier, so trim the sequence again.
+        let inputs = self.replay(initial, &inputs).map(|replayed| replayed.inputs).unwrap_or(inputs);
+        Counterexample { inputs, failure }
+    }
+
+    /// Explores every sequence of `Input`s up to the depth bound, starting from `initial`, and returns a `Report` of
+    /// everything that was found. A sequence is not extended any further once it panics, violates an invariant, or
+    /// reaches a [final](../trait.Mode.html#method.is_final) `Mode`.
+    /// 
+    pub fn explore(&self, initial : M) -> Report<F> {
+        let mut counterexamples : Vec<Counterexample<F::Input>> = Vec::new();
+        let mut reached = BTreeSet::new();
+        let mut seen = HashSet::new();
+        let mut transitions = 0;
+
+        let mut record = |counterexample : Counterexample<F::Input>| {
+            if !counterexamples.iter().any(|existing| existing.failure == counterexample.failure) {
+                counterexamples.push(counterexample);
+            }
+        };
+
+        reached.insert(initial.tag());
+        if let Some(key) = &self.key {
+            seen.insert(key(&initial));
+        }
+
+        let mut pending = VecDeque::new();
+        match self.check(&initial, &[]) {
+            Some(counterexample) => record(counterexample),
+            None => pending.push_back((initial, Vec::new())),
+        }
+
+        while let Some((mode, trace)) = pending.pop_front() {
+            if trace.len() >= self.depth || mode.is_final() {
+                continue;
+            }
+
+            for (index, input) in self.inputs.iter().enumerate() {
+                let mut next_trace = trace.clone();
+                next_trace.push(index);
+                transitions += 1;
+
+                let (mode, input) = (mode.clone(), input.clone());
+                let next =
+                    match panic::catch_unwind(AssertUnwindSafe(move || mode.swap(input))) {
+                        Ok(next) => next,
+                        Err(payload) => {
+                            record(Counterexample {
+                                inputs : self.inputs_for(&next_trace),
+                                failure : Failure::Panic(panic_message(&*payload)),
+                            });
+                            continue;
+                        },
+                    };
+
+                reached.insert(next.tag());
+                if let Some(counterexample) = self.check(&next, &next_trace) {
+                    record(counterexample);
+                    continue;
+                }
+
+                if let Some(key) = &self.key {
+                    if !seen.insert(key(&next)) {
+                        continue;
+                    }
+                }
+
+                pending.push_back((next, next_trace));
+            }
+        }
+
+        let unreached = self.expected.iter().copied().filter(|tag| !reached.contains(tag)).collect();
+        Report { counterexamples, reached, unreached, transitions }
+    }
+}
+
+/// Removes as many elements from `inputs` as possible while `fails` keeps returning `true`, and returns the result.
+/// 
+/// This first tries to remove large chunks of consecutive elements, and then gradually smaller ones, down to single
+/// elements, until no single element can be removed any more. `fails` should return `true` for `inputs` itself.
+/// 
+/// # Usage
+/// ```
+/// use mode::explore::shrink;
+/// 
+/// // Fails whenever a 3 is followed, at some point, by a 7.
+/// let fails = |inputs : &[u32]| {
+///     inputs.iter().position(|input| *input == 3)
+///         .is_some_and(|three| inputs[three..].contains(&7))
+/// };
+/// 
+/// let shrunk = shrink(vec![1, 3, 4, 1, 5, 9, 2, 6, 5, 7, 5, 8], fails);
+/// assert_eq!(shrunk, vec![3, 7]);
+/// ```
+/// 
+pub fn shrink<I>(mut inputs : Vec<I>, mut fails : impl FnMut(&[I]) -> bool) -> Vec<I>
+    where I : Clone
+{
+    let mut chunk = inputs.len() / 2;
+    while chunk > 0 {
+        let mut start = 0;
+        let mut removed = false;
+        while start < inputs.len() {
+            let end = (start + chunk).min(inputs.len());
+            let candidate : Vec<I> = inputs[..start].iter().chain(&inputs[end..]).cloned().collect();
+            if fails(&candidate) {
+                inputs = candidate;
+                removed = true;
+            }
+            else {
+                start = end;
+            }
+        }
+
+        if !removed || chunk > inputs.len() {
+            chunk = (chunk / 2).min(inputs.len());
+        }
+    }
+
+    inputs
+}
+
+/// Extracts the message from the payload of a caught panic.
+/// 
+fn panic_message(payload : &(dyn Any + Send)) -> String {
+    if let Some(message) = payload.downcast_ref::<&str>() {
+        (*message).to_owned()
+    }
+    else if let Some(message) = payload.downcast_ref::<String>() {
+        message.clone()
+    }
+    else {
+        "Box<dyn Any>".to_owned()
+    }
+}
@@ -0,0 +1,276 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{modes::IntoResult, Automaton, Family};
+use std::{error::Error, fmt};
+
+/// Trait that can be implemented for the `F::Mode` type of a `Family` whose state machines produce a final result,
+/// allowing the result to be harvested once the `Automaton` has finished.
+/// 
+/// The `Outcome` can be any type, but will usually be a `Result` that distinguishes between the final `Mode`s that
+/// represent success and those that represent failure.
+/// 
+/// See [`Automaton::finish()`](struct.Automaton.html#method.finish) for more details.
+/// 
+pub trait Finish {
+    /// The type of result produced by the state machine once it has finished.
+    /// 
+    type Outcome;
+
+    /// Consumes the final `Mode` of an `Automaton`, returning its `Outcome`.
+    /// 
+    fn finish(self) -> Self::Outcome;
+}
+
+/// A shared vocabulary for describing how a state machine ended, which terminal states can map into, e.g. as the
+/// [`Finish::Outcome`](trait.Finish.html#associatedtype.Outcome) of a `Mode`.
+/// 
+/// An `Outcome` distinguishes between state machines that reached a terminal state representing success or failure,
+/// and state machines that were cancelled, i.e. stopped before they could reach any terminal state. Since `Outcome`
+/// implements [`IntoResult`](modes/trait.IntoResult.html), it can be used directly with the combinators in the
+/// [`modes`](modes/index.html) module, e.g. [`Sequence`](modes/struct.Sequence.html) and
+/// [`Race`](modes/struct.Race.html), as well as with the `Future` returned by `.await`ing an `Automaton`. A cancelled
+/// child is treated as a failure by [`IntoResult::into_result()`](modes/trait.IntoResult.html#tymethod.into_result),
+/// with `None` as the error.
+/// 
+/// See [`Automaton::into_outcome()`](struct.Automaton.html#method.into_outcome) for more details.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::IntoResult, Outcome};
+/// 
+/// let outcome : Outcome<u32, &str> = Ok(7).into();
+/// assert!(outcome.is_succeeded());
+/// assert_eq!(outcome.succeeded(), Some(7));
+/// 
+/// let outcome : Outcome<u32, &str> = Outcome::Cancelled;
+/// assert!(outcome.is_cancelled());
+/// assert_eq!(outcome.into_result(), Err(None));
+/// ```
+/// 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Outcome<T, E> {
+    /// The state machine reached a terminal state representing success, producing a value.
+    /// 
+    Succeeded(T),
+
+    /// The state machine reached a terminal state representing failure, producing an error.
+    /// 
+    Failed(E),
+
+    /// The state machine was stopped before it could reach a terminal state.
+    /// 
+    Cancelled,
+}
+
+impl<T, E> Outcome<T, E> {
+    /// Returns `true` if the `Outcome` is `Succeeded`.
+    /// 
+    pub fn is_succeeded(&self) -> bool {
+        matches!(self, Outcome::Succeeded(_))
+    }
+
+    /// Returns `true` if the `Outcome` is `Failed`.
+    /// 
+    pub fn is_failed(&self) -> bool {
+        matches!(self, Outcome::Failed(_))
+    }
+
+    /// Returns `true` if the `Outcome` is `Cancelled`.
+    /// 
+    pub fn is_cancelled(&self) -> bool {
+        matches!(self, Outcome::Cancelled)
+    }
+
+    /// Returns the value of a `Succeeded` `Outcome`, or `None` otherwise.
+    /// 
+    pub fn succeeded(self) -> Option<T> {
+        match self {
+            Outcome::Succeeded(value) => Some(value),
+            _ => None,
+        }
+    }
+
+    /// Returns the error of a `Failed` `Outcome`, or `None` otherwise.
+    /// 
+    pub fn failed(self) -> Option<E> {
+        match self {
+            Outcome::Failed(error) => Some(error),
+            _ => None,
+        }
+    }
+
+    /// Maps the value of a `Succeeded` `Outcome` via `f`, leaving any other `Outcome` untouched.
+    /// 
+    pub fn map<U>(self, f : impl FnOnce(T) -> U) -> Outcome<U, E> {
+        match self {
+            Outcome::Succeeded(value) => Outcome::Succeeded(f(value)),
+            Outcome::Failed(error) => Outcome::Failed(error),
+            Outcome::Cancelled => Outcome::Cancelled,
+        }
+    }
+
+    /// Maps the error of a `Failed` `Outcome` via `f`, leaving any other `Outcome` untouched.
+    /// 
+    pub fn map_err<U>(self, f : impl FnOnce(E) -> U) -> Outcome<T, U> {
+        match self {
+            Outcome::Succeeded(value) => Outcome::Succeeded(value),
+            Outcome::Failed(error) => Outcome::Failed(f(error)),
+            Outcome::Cancelled => Outcome::Cancelled,
+        }
+    }
+}
+
+impl<T, E> From<Result<T, E>> for Outcome<T, E> {
+    fn from(result : Result<T, E>) -> Self {
+        match result {
+            Ok(value) => Outcome::Succeeded(value),
+            Err(error) => Outcome::Failed(error),
+        }
+    }
+}
+
+impl<T, E> IntoResult for Outcome<T, E> {
+    type Value = T;
+    type Error = Option<E>;
+
+    fn into_result(self) -> Result<T, Option<E>> {
+        match self {
+            Outcome::Succeeded(value) => Ok(value),
+            Outcome::Failed(error) => Err(Some(error)),
+            Outcome::Cancelled => Err(None),
+        }
+    }
+}
+
+/// Trait that can be implemented for the `F::Mode` type of a `Family` in order to expose the value computed by the
+/// state machine once it reaches a terminal state, allowing the value to be extracted via
+/// [`Automaton::into_output()`](struct.Automaton.html#method.into_output).
+/// 
+/// Unlike [`Finish`](trait.Finish.html), which has to produce an `Outcome` for every `Mode` in the `Family`,
+/// `into_output()` only needs to return `Some` for the `Mode`s that are terminal states, i.e. those for which
+/// [`Mode::is_final()`](trait.Mode.html#method.is_final) returns `true`, and can return `None` for every other `Mode`. For a `Family`
+/// of boxed or reference-counted `Mode`s, the equivalent `FinalMode` trait in the [`boxed`](boxed/index.html),
+/// [`rc`](rc/index.html), or [`sync`](sync/index.html) module can be added as a supertrait of the `Base` type, so that
+/// the value can be extracted from a `Box<dyn Trait>` without downcasting it.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct SearchFamily;
+/// impl Family for SearchFamily {
+///     type Base = dyn Search;
+///     type Mode = Box<dyn Search>;
+///     type Input = u32;
+///     type Output = Box<dyn Search>;
+/// }
+/// 
+/// trait Search : boxed::Mode<Family = SearchFamily> + boxed::FinalMode<Output = u32> { }
+/// 
+/// struct Looking { target : u32 }
+/// impl Search for Looking { }
+/// impl boxed::FinalMode for Looking {
+///     type Output = u32;
+///     fn into_output(self : Box<Self>) -> Option<u32> { None }
+/// }
+/// impl boxed::Mode for Looking {
+///     type Family = SearchFamily;
+///     fn swap(self : Box<Self>, guess : u32) -> Box<dyn Search> {
+///         if guess == self.target { Box::new(Found { guess }) } else { self }
+///     }
+/// }
+/// 
+/// struct Found { guess : u32 }
+/// impl Search for Found { }
+/// impl boxed::FinalMode for Found {
+///     type Output = u32;
+///     fn into_output(self : Box<Self>) -> Option<u32> { Some(self.guess) }
+/// }
+/// impl boxed::Mode for Found {
+///     type Family = SearchFamily;
+///     fn swap(self : Box<Self>, _guess : u32) -> Box<dyn Search> { self }
+///     fn is_final(&self) -> bool { true }
+/// }
+/// 
+/// let mut automaton = SearchFamily::automaton_with_mode(Box::new(Looking { target : 7 }));
+/// for guess in 0..10 {
+///     Automaton::next_with_input(&mut automaton, guess);
+/// }
+/// assert_eq!(Automaton::into_output(automaton).ok(), Some(7));
+/// ```
+/// 
+pub trait FinalMode {
+    /// The type of value computed by the state machine once it reaches a terminal state.
+    /// 
+    type Output;
+
+    /// Consumes the final `Mode` of an `Automaton`, returning its `Output`. This **must** return `Some` for every `Mode`
+    /// whose [`is_final()`](trait.Mode.html#method.is_final) function returns `true`, since
+    /// [`Automaton::into_output()`](struct.Automaton.html#method.into_output) panics otherwise. It is never called on
+    /// any other `Mode` by the `Automaton`, so it can return `None` for those.
+    /// 
+    fn into_output(self) -> Option<Self::Output>
+        where Self : Sized;
+}
+
+/// The error returned by [`Automaton::finish()`](struct.Automaton.html#method.finish) and
+/// [`Automaton::into_output()`](struct.Automaton.html#method.into_output) when they are called on an `Automaton` that
+/// is not [complete](struct.Automaton.html#method.is_complete), i.e. one that has not finished yet, or was stopped
+/// while a non-final `Mode` was current. The `Automaton` can be recovered, unchanged, via
+/// [`into_inner()`](#method.into_inner).
+/// 
+pub struct FinishError<F>
+    where F : Family + ?Sized
+{
+    automaton : Automaton<F>,
+}
+
+impl<F> FinishError<F>
+    where F : Family + ?Sized
+{
+    pub(crate) fn new(automaton : Automaton<F>) -> Self {
+        Self { automaton }
+    }
+
+    /// Consumes the `FinishError`, returning the unfinished `Automaton`.
+    /// 
+    pub fn into_inner(self) -> Automaton<F> {
+        self.automaton
+    }
+}
+
+impl<F> fmt::Debug for FinishError<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("FinishError")
+            .field("phase", &Automaton::phase(&self.automaton))
+            .finish()
+    }
+}
+
+impl<F> fmt::Display for FinishError<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Cannot finish an Automaton that has not reached a final Mode!")
+    }
+}
+
+impl<F> Error for FinishError<F>
+    where F : Family + ?Sized
+{ }
+
+#[cfg(feature = "defmt")]
+impl<F> defmt::Format for FinishError<F>
+    where F : Family + ?Sized
+{
+    fn format(&self, formatter : defmt::Formatter) {
+        defmt::write!(formatter, "FinishError {{ phase: {} }}", Automaton::phase(&self.automaton))
+    }
+}
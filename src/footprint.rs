@@ -0,0 +1,89 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{fmt, iter::Sum, ops::{Add, AddAssign}};
+
+/// The amount of memory used by the current `Mode` of an `Automaton`, as returned by
+/// [`Automaton::footprint()`](struct.Automaton.html#method.footprint), or by the current `Mode`s of many `Automaton`s
+/// combined, as returned by [`Registry::footprint()`](struct.Registry.html#method.footprint).
+/// 
+/// `Footprint`s can be added together, and summed over an `Iterator`, in order to monitor the memory budget of a
+/// large population of agents.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = dyn SomeTrait;
+///     type Mode = Box<dyn SomeTrait>;
+///     type Input = ();
+///     type Output = Box<dyn SomeTrait>;
+/// }
+/// 
+/// trait SomeTrait : boxed::Mode<Family = SomeFamily> { }
+/// 
+/// struct Buffering { samples : Vec<u32> }
+/// impl SomeTrait for Buffering { }
+/// impl boxed::Mode for Buffering {
+///     type Family = SomeFamily;
+///     fn swap(self : Box<Self>, _input : ()) -> Box<dyn SomeTrait> { self }
+///     fn heap_size(&self) -> usize { self.samples.capacity() * std::mem::size_of::<u32>() }
+/// }
+/// 
+/// let automaton = SomeFamily::automaton_with_mode(Box::new(Buffering { samples : Vec::with_capacity(256) }));
+/// let footprint = Automaton::footprint(&automaton);
+/// 
+/// // The Automaton stores a Box inline, which points to the Buffering, which owns the samples.
+/// assert_eq!(footprint.inline, std::mem::size_of::<Box<dyn SomeTrait>>());
+/// assert_eq!(footprint.heap, std::mem::size_of::<Buffering>() + 1024);
+/// ```
+/// 
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Footprint {
+    /// The number of bytes used by the `Mode` stored inline within the `Automaton`, i.e. `size_of::<F::Mode>()`.
+    pub inline : usize,
+
+    /// The number of bytes of heap memory owned by the `Mode`, as reported by
+    /// [`Mode::heap_size()`](trait.Mode.html#method.heap_size). For a `Family` whose `Mode` is a pointer type, e.g.
+    /// `Box<dyn Trait>`, this includes the size of the value being pointed to.
+    pub heap : usize,
+}
+
+impl Footprint {
+    /// Returns the total number of bytes used, i.e. `inline + heap`.
+    /// 
+    pub fn total(&self) -> usize {
+        self.inline + self.heap
+    }
+}
+
+impl Add for Footprint {
+    type Output = Footprint;
+
+    fn add(self, other : Footprint) -> Footprint {
+        Footprint { inline : self.inline + other.inline, heap : self.heap + other.heap }
+    }
+}
+
+impl AddAssign for Footprint {
+    fn add_assign(&mut self, other : Footprint) {
+        *self = *self + other;
+    }
+}
+
+impl Sum for Footprint {
+    fn sum<I : Iterator<Item = Footprint>>(iter : I) -> Footprint {
+        iter.fold(Footprint::default(), Add::add)
+    }
+}
+
+impl fmt::Display for Footprint {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{} bytes ({} inline, {} heap)", self.total(), self.inline, self.heap)
+    }
+}
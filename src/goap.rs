@@ -0,0 +1,362 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines types for goal-oriented action planning (GOAP) over the `Mode`s of an `Automaton`.
+//! 
+//! In GOAP, each `Action` an agent can take is represented by a `Mode`, along with a set of preconditions that must
+//! hold in the world `State` before the `Action` can be taken, a set of effects that the `Action` has on the world
+//! `State` once it completes, and a cost. Given a starting `State` and a goal `State`, the `Planner` searches for the
+//! cheapest sequence of `Action`s that satisfies the goal, and returns it as a `Plan`. The `Plan` can then be executed
+//! using [`Automaton::next_with_plan()`](../struct.Automaton.html#method.next_with_plan), which swaps in the `Mode`
+//! for each `Action` in turn, waiting for each one to finish before moving on to the next.
+//! 
+//! # Usage
+//! ```
+//! use mode::{goap::{Action, Planner, State}, Automaton, Family, Mode};
+//! 
+//! struct ChefFamily;
+//! impl Family for ChefFamily {
+//!     type Base = Chef;
+//!     type Mode = Chef;
+//!     type Input = ();
+//!     type Output = Chef;
+//! }
+//! 
+//! #[derive(Debug, PartialEq)]
+//! enum Chef { Idle, Shopping { trips : u32 }, Cooking { minutes : u32 } }
+//! 
+//! impl Mode for Chef {
+//!     type Family = ChefFamily;
+//!     fn swap(self, _input : ()) -> Self {
+//!         match self {
+//!             Chef::Shopping { trips } => Chef::Shopping { trips : trips + 1 },
+//!             Chef::Cooking { minutes } => Chef::Cooking { minutes : minutes + 10 },
+//!             idle => idle,
+//!         }
+//!     }
+//!     fn tag(&self) -> &'static str {
+//!         match self { Chef::Idle => "Idle", Chef::Shopping { .. } => "Shop", Chef::Cooking { .. } => "Cook" }
+//!     }
+//! }
+//! 
+//! let planner =
+//!     Planner::<ChefFamily>::new()
+//!         .with_action(
+//!             Action::new("Shop", |_| Chef::Shopping { trips : 0 })
+//!                 .effect("has_ingredients", true)
+//!                 .done_when(|chef| *chef == Chef::Shopping { trips : 1 }))
+//!         .with_action(
+//!             Action::new("Cook", |_| Chef::Cooking { minutes : 0 })
+//!                 .requires("has_ingredients", true)
+//!                 .effect("dinner_ready", true)
+//!                 .done_when(|chef| matches!(chef, Chef::Cooking { minutes } if *minutes >= 30)));
+//! 
+//! let start = State::new().with("has_ingredients", false);
+//! let goal = State::new().with("dinner_ready", true);
+//! 
+//! let mut plan = planner.plan(&start, &goal).unwrap();
+//! assert_eq!(planner.names(&plan), vec!["Shop", "Cook"]);
+//! 
+//! let mut automaton = ChefFamily::automaton_with_mode(Chef::Idle);
+//! while !plan.is_complete() {
+//!     Automaton::next_with_plan(&mut automaton, &planner, &mut plan);
+//! }
+//! assert!(matches!(*automaton, Chef::Cooking { .. }));
+//! ```
+//! 
+
+use crate::Family;
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, BinaryHeap, HashSet, VecDeque},
+};
+
+/// A set of named boolean facts describing the state of the world, used to express the preconditions and effects of
+/// an `Action`, as well as the starting point and goal of a `Plan`.
+/// 
+#[derive(Clone, Debug, Default, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct State {
+    facts : BTreeMap<&'static str, bool>,
+}
+
+impl State {
+    /// Creates a new `State` with no facts.
+    /// 
+    pub fn new() -> Self {
+        Self { facts : BTreeMap::new() }
+    }
+
+    /// Sets the specified `fact` to `value`, returning `self` so that calls can be chained together.
+    /// 
+    pub fn with(mut self, fact : &'static str, value : bool) -> Self {
+        self.set(fact, value);
+        self
+    }
+
+    /// Sets the specified `fact` to `value`.
+    /// 
+    pub fn set(&mut self, fact : &'static str, value : bool) {
+        self.facts.insert(fact, value);
+    }
+
+    /// Returns the value of the specified `fact`, or `None` if it has not been set.
+    /// 
+    pub fn get(&self, fact : &'static str) -> Option<bool> {
+        self.facts.get(fact).copied()
+    }
+
+    /// Returns `true` if every fact in `other` has the same value in `self`. Facts that are not set in `self` are
+    /// treated as `false`.
+    /// 
+    pub fn satisfies(&self, other : &State) -> bool {
+        other.facts.iter()
+            .all(|(fact, value)| self.get(fact).unwrap_or(false) == *value)
+    }
+
+    /// Returns a copy of `self` with every fact in `effects` applied to it.
+    /// 
+    pub fn apply(&self, effects : &State) -> State {
+        let mut result = self.clone();
+        result.facts.extend(effects.facts.iter().map(|(fact, value)| (*fact, *value)));
+        result
+    }
+}
+
+type EnterFn<F> = dyn Fn(<F as Family>::Mode) -> <F as Family>::Mode;
+type DoneFn<F> = dyn Fn(&<F as Family>::Base) -> bool;
+
+/// A single action that can be taken by an agent, represented by a `Mode` in the `Family` `F`.
+/// 
+/// When the `Action` is started, the current `Mode` of the `Automaton` is passed into the `enter` function, and the
+/// `Mode` it returns becomes current. The `Action` is considered finished as soon as the `done_when()` predicate
+/// returns `true` for the current `Mode`. By default, an `Action` is considered finished immediately after it starts.
+/// 
+pub struct Action<F>
+    where F : Family + ?Sized
+{
+    name : &'static str,
+    cost : u32,
+    preconditions : State,
+    effects : State,
+    enter : Box<EnterFn<F>>,
+    done : Box<DoneFn<F>>,
+}
+
+impl<F> Action<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Action` called `name` with a cost of `1` and no preconditions or effects. The `enter` function
+    /// will be called with the current `Mode` in order to create the `Mode` for this `Action` when it is started.
+    /// 
+    pub fn new<E>(name : &'static str, enter : E) -> Self
+        where E : Fn(F::Mode) -> F::Mode + 'static
+    {
+        Self {
+            name,
+            cost : 1,
+            preconditions : State::new(),
+            effects : State::new(),
+            enter : Box::new(enter),
+            done : Box::new(|_| true),
+        }
+    }
+
+    /// Sets the cost of taking this `Action`. The `Planner` will always return the `Plan` with the lowest total cost.
+    /// 
+    pub fn cost(mut self, cost : u32) -> Self {
+        self.cost = cost;
+        self
+    }
+
+    /// Adds a precondition requiring `fact` to have the specified `value` before this `Action` can be taken.
+    /// 
+    pub fn requires(mut self, fact : &'static str, value : bool) -> Self {
+        self.preconditions.set(fact, value);
+        self
+    }
+
+    /// Adds an effect that sets `fact` to the specified `value` once this `Action` has been taken.
+    /// 
+    pub fn effect(mut self, fact : &'static str, value : bool) -> Self {
+        self.effects.set(fact, value);
+        self
+    }
+
+    /// Sets the predicate used to determine when this `Action` has finished, given the current `Mode`.
+    /// 
+    pub fn done_when<D>(mut self, done : D) -> Self
+        where D : Fn(&F::Base) -> bool + 'static
+    {
+        self.done = Box::new(done);
+        self
+    }
+
+    /// Returns the name of this `Action`.
+    /// 
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+}
+
+/// A sequence of `Action`s produced by a `Planner`, which can be executed by an `Automaton` via
+/// [`Automaton::next_with_plan()`](../struct.Automaton.html#method.next_with_plan).
+/// 
+#[derive(Clone, Debug)]
+pub struct Plan {
+    steps : VecDeque<usize>,
+    current : Option<usize>,
+    cost : u32,
+}
+
+impl Plan {
+    /// Returns the total cost of all `Action`s in this `Plan`.
+    /// 
+    pub fn cost(&self) -> u32 {
+        self.cost
+    }
+
+    /// Returns the number of `Action`s in this `Plan` that have not been started yet.
+    /// 
+    pub fn remaining(&self) -> usize {
+        self.steps.len()
+    }
+
+    /// Returns `true` if every `Action` in this `Plan` has been started, and the last one has finished.
+    /// 
+    pub fn is_complete(&self) -> bool {
+        self.steps.is_empty() && self.current.is_none()
+    }
+}
+
+/// Searches for the cheapest sequence of `Action`s that transforms a starting `State` into one that satisfies a goal.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct Planner<F>
+    where F : Family + ?Sized
+{
+    actions : Vec<Action<F>>,
+}
+
+impl<F> Default for Planner<F>
+    where F : Family + ?Sized
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> Planner<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Planner` with no `Action`s.
+    /// 
+    pub fn new() -> Self {
+        Self { actions : Vec::new() }
+    }
+
+    /// Adds `action` to the set of `Action`s that the `Planner` can choose from.
+    /// 
+    pub fn add_action(&mut self, action : Action<F>) {
+        self.actions.push(action);
+    }
+
+    /// Same as [`add_action()`](#method.add_action), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_action(mut self, action : Action<F>) -> Self {
+        self.add_action(action);
+        self
+    }
+
+    /// Returns the cheapest `Plan` that transforms `start` into a `State` that satisfies `goal`, or `None` if no such
+    /// `Plan` exists. If `start` already satisfies `goal`, an empty `Plan` is returned. `Plan`s whose total cost would
+    /// overflow a `u32` are never considered.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{goap::{Action, Planner, State}, Family, Mode};
+    /// 
+    /// struct SomeFamily;
+    /// impl Family for SomeFamily {
+    ///     type Base = SomeMode;
+    ///     type Mode = SomeMode;
+    ///     type Input = ();
+    ///     type Output = SomeMode;
+    /// }
+    /// 
+    /// struct SomeMode;
+    /// impl Mode for SomeMode {
+    ///     type Family = SomeFamily;
+    ///     fn swap(self, _input : ()) -> Self { self }
+    /// }
+    /// 
+    /// let planner =
+    ///     Planner::<SomeFamily>::new()
+    ///         .with_action(Action::new("Climb", |mode| mode).cost(u32::MAX).effect("halfway", true))
+    ///         .with_action(Action::new("Summit", |mode| mode).cost(1).requires("halfway", true).effect("top", true));
+    /// 
+    /// let goal = State::new().with("top", true);
+    /// assert!(planner.plan(&State::new(), &goal).is_none());
+    /// assert_eq!(planner.plan(&State::new().with("halfway", true), &goal).unwrap().cost(), 1);
+    /// ```
+    /// 
+    pub fn plan(&self, start : &State, goal : &State) -> Option<Plan> {
+        let mut open = BinaryHeap::new();
+        let mut closed = HashSet::new();
+        open.push(Reverse((0, start.clone(), Vec::new())));
+
+        while let Some(Reverse((cost, state, steps))) = open.pop() {
+            if state.satisfies(goal) {
+                return Some(Plan { steps : steps.into_iter().collect(), current : None, cost });
+            }
+
+            if !closed.insert(state.clone()) {
+                continue;
+            }
+
+            for (index, action) in self.actions.iter().enumerate() {
+                if state.satisfies(&action.preconditions) {
+                    let next = state.apply(&action.effects);
+                    if closed.contains(&next) {
+                        continue;
+                    }
+
+                    // NOTE: A Plan whose total cost doesn't fit in a u32 can never be returned, so skip it.
+                    if let Some(next_cost) = cost.checked_add(action.cost) {
+                        let mut next_steps = steps.clone();
+                        next_steps.push(index);
+                        open.push(Reverse((next_cost, next, next_steps)));
+                    }
+                }
+            }
+        }
+
+        None
+    }
+
+    /// Returns the names of all `Action`s in `plan` that have not been started yet, in order.
+    /// 
+    pub fn names(&self, plan : &Plan) -> Vec<&'static str> {
+        plan.steps.iter()
+            .map(|index| self.actions[*index].name)
+            .collect()
+    }
+
+    /// Returns `true` if `plan` has no `Action` in progress, or if the `Action` in progress has finished for `current`.
+    /// 
+    pub(crate) fn is_ready(&self, plan : &Plan, current : &F::Base) -> bool {
+        plan.current.is_none_or(|index| (self.actions[index].done)(current))
+    }
+
+    /// Marks the `Action` in progress as finished, and starts the next `Action` in `plan`, if any, returning its
+    /// `enter` function.
+    /// 
+    pub(crate) fn advance(&self, plan : &mut Plan) -> Option<&EnterFn<F>> {
+        plan.current = plan.steps.pop_front();
+        plan.current.map(|index| &*self.actions[index].enter)
+    }
+}
@@ -0,0 +1,229 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines reusable guards, i.e. predicates that decide whether the current `Mode` of an `Automaton` should be
+//! allowed to `swap()`.
+//! 
+//! A [`Guard`](struct.Guard.html) is checked against a [`Context`](struct.Context.html), which describes the current
+//! `Mode`, the `Input` that would be passed into `swap()`, and how long the current `Mode` has been current. This
+//! module provides the building blocks that come up over and over again:
+//! 
+//! - [`always()`](fn.always.html) and [`never()`](fn.never.html), which always and never pass, respectively,
+//! - [`after()`](fn.after.html), which passes once the current `Mode` has been current for some amount of time,
+//! - [`nth_step()`](fn.nth_step.html), which passes once the current `Mode` has been current for some number of
+//!   steps,
+//! - [`input_matches()`](fn.input_matches.html), which passes if the `Input` satisfies a predicate, and
+//! - [`all()`](fn.all.html), [`any()`](fn.any.html), and [`not()`](fn.not.html), which combine other `Guard`s.
+//! 
+//! A `Guard` is evaluated by [`Automaton::next_if()`](../struct.Automaton.html#method.next_if), which only calls
+//! `swap()` if the `Guard` passes.
+//! 
+//! # Usage
+//! ```
+//! use mode::{guards, Automaton, Family, Mode};
+//! 
+//! struct DoorFamily;
+//! impl Family for DoorFamily {
+//!     type Base = Door;
+//!     type Mode = Door;
+//!     type Input = char;
+//!     type Output = Door;
+//! }
+//! 
+//! #[derive(Debug, PartialEq)]
+//! enum Door { Closed, Open }
+//! impl Mode for Door {
+//!     type Family = DoorFamily;
+//!     fn swap(self, _key : char) -> Self {
+//!         match self { Door::Closed => Door::Open, Door::Open => Door::Closed }
+//!     }
+//! }
+//! 
+//! // Only open the door with the right key, and only once it has been closed for at least two steps.
+//! let guard = guards::all(vec![guards::input_matches(|key| *key == 'k'), guards::nth_step(2)]);
+//! 
+//! let mut door = DoorFamily::automaton_with_mode(Door::Closed);
+//! assert!(!Automaton::next_if(&mut door, &guard, 'x'));
+//! assert!(!Automaton::next_if(&mut door, &guard, 'k'));
+//! assert_eq!(*door, Door::Closed);
+//! 
+//! assert!(Automaton::next_if(&mut door, &guard, 'k'));
+//! assert_eq!(*door, Door::Open);
+//! ```
+//! 
+
+use crate::Family;
+use std::{fmt, time::Duration};
+
+/// Describes the situation in which a `Guard` is being checked.
+/// 
+pub struct Context<'a, F>
+    where F : Family + ?Sized
+{
+    current : &'a F::Base,
+    input : &'a F::Input,
+    steps_in_mode : u64,
+    time_in_mode : Duration,
+}
+
+impl<'a, F> Context<'a, F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Context`. This is normally done by the `Automaton`, but can be useful for testing `Guard`s in
+    /// isolation.
+    /// 
+    pub fn new(current : &'a F::Base, input : &'a F::Input, steps_in_mode : u64, time_in_mode : Duration) -> Self {
+        Self { current, input, steps_in_mode, time_in_mode }
+    }
+
+    /// Returns the current `Mode`.
+    /// 
+    pub fn current(&self) -> &'a F::Base {
+        self.current
+    }
+
+    /// Returns the `Input` that would be passed into `swap()`.
+    /// 
+    pub fn input(&self) -> &'a F::Input {
+        self.input
+    }
+
+    /// Returns the number of steps since the current `Mode` became current, as reported by
+    /// [`Automaton::steps_in_mode()`](../struct.Automaton.html#method.steps_in_mode).
+    /// 
+    pub fn steps_in_mode(&self) -> u64 {
+        self.steps_in_mode
+    }
+
+    /// Returns the amount of time since the current `Mode` became current, as reported by
+    /// [`Automaton::time_in_mode()`](../struct.Automaton.html#method.time_in_mode).
+    /// 
+    pub fn time_in_mode(&self) -> Duration {
+        self.time_in_mode
+    }
+}
+
+type CheckFn<F> = dyn Fn(&Context<F>) -> bool;
+
+/// A predicate that decides whether the current `Mode` of an `Automaton` should be allowed to `swap()`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct Guard<F>
+    where F : Family + ?Sized
+{
+    check : Box<CheckFn<F>>,
+}
+
+impl<F> Guard<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Guard` that passes whenever `check` returns `true`.
+    /// 
+    pub fn new(check : impl Fn(&Context<F>) -> bool + 'static) -> Self {
+        Self { check : Box::new(check) }
+    }
+
+    /// Returns `true` if this `Guard` passes in the specified `context`.
+    /// 
+    pub fn check(&self, context : &Context<F>) -> bool {
+        (self.check)(context)
+    }
+
+    /// Returns a `Guard` that only passes if both `self` and `other` pass. `other` is only checked if `self` passes.
+    /// 
+    pub fn and(self, other : Guard<F>) -> Guard<F>
+        where F : 'static
+    {
+        Guard::new(move |context| self.check(context) && other.check(context))
+    }
+
+    /// Returns a `Guard` that passes if either `self` or `other` passes. `other` is only checked if `self` fails.
+    /// 
+    pub fn or(self, other : Guard<F>) -> Guard<F>
+        where F : 'static
+    {
+        Guard::new(move |context| self.check(context) || other.check(context))
+    }
+}
+
+impl<F> fmt::Debug for Guard<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Guard").finish()
+    }
+}
+
+/// Returns a `Guard` that always passes.
+/// 
+pub fn always<F>() -> Guard<F>
+    where F : Family + ?Sized
+{
+    Guard::new(|_| true)
+}
+
+/// Returns a `Guard` that never passes.
+/// 
+pub fn never<F>() -> Guard<F>
+    where F : Family + ?Sized
+{
+    Guard::new(|_| false)
+}
+
+/// Returns a `Guard` that passes once the current `Mode` has been current for at least `duration`.
+/// 
+pub fn after<F>(duration : Duration) -> Guard<F>
+    where F : Family + ?Sized
+{
+    Guard::new(move |context| context.time_in_mode() >= duration)
+}
+
+/// Returns a `Guard` that passes once the current `Mode` has been current for at least `n` steps, i.e. starting with
+/// the `n`th call to one of the `Automaton::next*()` functions after the current `Mode` became current, counting from
+/// zero.
+/// 
+pub fn nth_step<F>(n : u64) -> Guard<F>
+    where F : Family + ?Sized
+{
+    Guard::new(move |context| context.steps_in_mode() >= n)
+}
+
+/// Returns a `Guard` that passes if `predicate` returns `true` for the `Input` that would be passed into `swap()`.
+/// 
+pub fn input_matches<F>(predicate : impl Fn(&F::Input) -> bool + 'static) -> Guard<F>
+    where F : Family + ?Sized
+{
+    Guard::new(move |context| predicate(context.input()))
+}
+
+/// Returns a `Guard` that only passes if every one of the `guards` passes. Guards are checked in order, stopping at the
+/// first one that fails. If `guards` is empty, the `Guard` always passes.
+/// 
+pub fn all<F>(guards : impl IntoIterator<Item = Guard<F>>) -> Guard<F>
+    where F : Family + ?Sized + 'static
+{
+    let guards : Vec<_> = guards.into_iter().collect();
+    Guard::new(move |context| guards.iter().all(|guard| guard.check(context)))
+}
+
+/// Returns a `Guard` that passes if any one of the `guards` passes. Guards are checked in order, stopping at the first
+/// one that passes. If `guards` is empty, the `Guard` never passes.
+/// 
+pub fn any<F>(guards : impl IntoIterator<Item = Guard<F>>) -> Guard<F>
+    where F : Family + ?Sized + 'static
+{
+    let guards : Vec<_> = guards.into_iter().collect();
+    Guard::new(move |context| guards.iter().any(|guard| guard.check(context)))
+}
+
+/// Returns a `Guard` that passes if `guard` fails, and vice versa.
+/// 
+pub fn not<F>(guard : Guard<F>) -> Guard<F>
+    where F : Family + ?Sized + 'static
+{
+    Guard::new(move |context| !guard.check(context))
+}
@@ -0,0 +1,99 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{
+    fmt,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+/// A queue of `Input`s that can be filled from any number of threads, and drained into an `Automaton` by a single
+/// driver thread via [`Automaton::next_with_inbox()`](struct.Automaton.html#method.next_with_inbox).
+/// 
+/// The `Inbox` itself stays with the driver thread, while the `Sender`s returned by [`sender()`](#method.sender) can be
+/// cloned and moved to other threads. The queue is a `std::sync::mpsc` channel, which provides the following ordering
+/// guarantees:
+/// 
+/// - Everything a producer thread does before sending an `Input` happens-before the driver thread receives it, so the
+///   `Input` (and any memory it points to) is fully visible to the `Mode` that the `Input` is passed into.
+/// - `Input`s sent from the same thread are received in the order in which they were sent.
+/// - `Input`s sent from different threads are received in the order in which they were enqueued, but no other
+///   ordering is guaranteed between them.
+/// 
+/// Sending never blocks, and the queue is unbounded.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::thread;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = Sum;
+///     type Mode = Sum;
+///     type Input = u32;
+///     type Output = Sum;
+/// }
+/// 
+/// struct Sum(u32);
+/// impl Mode for Sum {
+///     type Family = SomeFamily;
+///     fn swap(self, input : u32) -> Self { Sum(self.0 + input) }
+/// }
+/// 
+/// let mut automaton = SomeFamily::automaton_with_mode(Sum(0));
+/// let inbox = Inbox::new();
+/// 
+/// let producers : Vec<_> =
+///     (1..=4)
+///         .map(|value| {
+///             let sender = inbox.sender();
+///             thread::spawn(move || sender.send(value).unwrap())
+///         })
+///         .collect();
+/// 
+/// for producer in producers { producer.join().unwrap(); }
+/// 
+/// assert_eq!(Automaton::next_with_inbox(&mut automaton, &inbox), 4);
+/// assert_eq!(automaton.0, 10);
+/// ```
+/// 
+pub struct Inbox<Input> {
+    sender : Sender<Input>,
+    receiver : Receiver<Input>,
+}
+
+impl<Input> Default for Inbox<Input> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Input> Inbox<Input> {
+    /// Creates a new, empty `Inbox`.
+    /// 
+    pub fn new() -> Self {
+        let (sender, receiver) = mpsc::channel();
+        Self { sender, receiver }
+    }
+
+    /// Returns a new `Sender` that can be used to add `Input`s to this `Inbox` from any thread.
+    /// 
+    pub fn sender(&self) -> Sender<Input> {
+        self.sender.clone()
+    }
+
+    /// Removes and returns the next pending `Input`, if any, without blocking.
+    /// 
+    pub(crate) fn try_recv(&self) -> Option<Input> {
+        self.receiver.try_recv().ok()
+    }
+}
+
+impl<Input> fmt::Debug for Inbox<Input> {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Inbox").finish()
+    }
+}
@@ -0,0 +1,142 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Mode};
+
+type GuardFn<F> = dyn Fn(&<F as Family>::Input) -> bool;
+type CreateFn<F> = dyn Fn(&<F as Family>::Input) -> <F as Family>::Mode;
+
+/// An initial pseudo-state that chooses the actual initial `Mode` of an `Automaton` at start time, by evaluating a
+/// series of guarded choices against the start input.
+/// 
+/// This corresponds to a UML initial pseudo-state followed by a choice, and allows logic such as "resume where the
+/// player left off" to be expressed as part of the state machine, instead of in the code that creates it. Each choice
+/// consists of a `tag`, a `guard` that is checked against the start input, and a `create` function that builds the
+/// initial `Mode` from it. Choices are checked in the order they were added, and the first one whose `guard` passes is
+/// taken. If no `guard` passes, the `otherwise` function passed into [`new()`](#method.new) is used instead.
+/// 
+/// Once the initial `Mode` has been chosen, [`start()`](#method.start) creates the `Automaton` via
+/// [`Automaton::start_with()`](struct.Automaton.html#method.start_with), so the initial `Mode` is entered immediately
+/// and allowed to `swap()` once with the start input.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct GameFamily;
+/// impl Family for GameFamily {
+///     type Base = Game;
+///     type Mode = Game;
+///     type Input = Option<u32>;
+///     type Output = Game;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Game { Intro, Level(u32) }
+/// 
+/// impl Mode for Game {
+///     type Family = GameFamily;
+///     fn swap(self, _save : Option<u32>) -> Self { self }
+///     fn tag(&self) -> &'static str {
+///         match self { Game::Intro => "Intro", Game::Level(_) => "Level" }
+///     }
+/// }
+/// 
+/// let initial =
+///     Initial::<GameFamily>::new(|_| Game::Intro)
+///         .with_choice("Level", |save| save.is_some(), |save| Game::Level(save.unwrap()));
+/// 
+/// assert_eq!(initial.choose(&None), None);
+/// assert_eq!(initial.choose(&Some(3)), Some("Level"));
+/// assert_eq!(*initial.start(None), Game::Intro);
+/// assert_eq!(*initial.start(Some(3)), Game::Level(3));
+/// ```
+/// 
+pub struct Initial<F>
+    where F : Family + ?Sized
+{
+    choices : Vec<Choice<F>>,
+    otherwise : Box<CreateFn<F>>,
+}
+
+struct Choice<F>
+    where F : Family + ?Sized
+{
+    tag : &'static str,
+    guard : Box<GuardFn<F>>,
+    create : Box<CreateFn<F>>,
+}
+
+impl<F> Initial<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Initial` pseudo-state with no guarded choices. The `otherwise` function will be used to create
+    /// the initial `Mode` whenever no other choice is taken.
+    /// 
+    pub fn new<O>(otherwise : O) -> Self
+        where O : Fn(&F::Input) -> F::Mode + 'static
+    {
+        Self { choices : Vec::new(), otherwise : Box::new(otherwise) }
+    }
+
+    /// Adds a new guarded choice to the `Initial` pseudo-state. If `guard` passes for the start input, and no choice
+    /// that was added before it passes, `create` is used to create the initial `Mode`. The `tag` should be the same
+    /// value that the `Mode` returned by `create` will return from [`Mode::tag()`](trait.Mode.html#method.tag).
+    /// 
+    pub fn add_choice<G, C>(&mut self, tag : &'static str, guard : G, create : C)
+        where
+            G : Fn(&F::Input) -> bool + 'static,
+            C : Fn(&F::Input) -> F::Mode + 'static,
+    {
+        self.choices.push(Choice { tag, guard : Box::new(guard), create : Box::new(create) });
+    }
+
+    /// Same as [`add_choice()`](#method.add_choice), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_choice<G, C>(mut self, tag : &'static str, guard : G, create : C) -> Self
+        where
+            G : Fn(&F::Input) -> bool + 'static,
+            C : Fn(&F::Input) -> F::Mode + 'static,
+    {
+        self.add_choice(tag, guard, create);
+        self
+    }
+
+    /// Returns the `tag` of the guarded choice that would be taken for `input`, without creating a `Mode`, or `None`
+    /// if the `otherwise` function would be used instead.
+    /// 
+    pub fn choose(&self, input : &F::Input) -> Option<&'static str> {
+        self.select(input).map(|choice| choice.tag)
+    }
+
+    /// Creates the initial `Mode` for `input`, using the first guarded choice that passes, or the `otherwise` function
+    /// if none of them do.
+    /// 
+    pub fn create(&self, input : &F::Input) -> F::Mode {
+        match self.select(input) {
+            Some(choice) => (choice.create)(input),
+            None => (self.otherwise)(input),
+        }
+    }
+
+    fn select(&self, input : &F::Input) -> Option<&Choice<F>> {
+        self.choices.iter().find(|choice| (choice.guard)(input))
+    }
+}
+
+impl<F, M, Input> Initial<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Chooses the initial `Mode` for `input`, and returns a new `Automaton` that has been started with it via
+    /// [`Automaton::start_with()`](struct.Automaton.html#method.start_with).
+    /// 
+    pub fn start(&self, input : Input) -> Automaton<F> {
+        Automaton::start_with(self.create(&input), input)
+    }
+}
@@ -0,0 +1,146 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+/// Trait for tuples that can have one more element appended to the end, producing a longer tuple. This is implemented
+/// for every tuple of up to 7 elements, and is what allows an [`Inputs`](struct.Inputs.html) builder to grow one
+/// element at a time.
+/// 
+pub trait Append<T> {
+    /// The tuple that results from appending a `T` to `Self`.
+    /// 
+    type Output;
+
+    /// Appends `value` to the end of `self`.
+    /// 
+    fn append(self, value : T) -> Self::Output;
+}
+
+macro_rules! impl_append {
+    ($($name:ident),*) => {
+        impl<$($name,)* T> Append<T> for ($($name,)*) {
+            type Output = ($($name,)* T,);
+
+            #[allow(non_snake_case)]
+            fn append(self, value : T) -> Self::Output {
+                let ($($name,)*) = self;
+                ($($name,)* value,)
+            }
+        }
+    };
+}
+
+impl_append!();
+impl_append!(A);
+impl_append!(A, B);
+impl_append!(A, B, C);
+impl_append!(A, B, C, D);
+impl_append!(A, B, C, D, E);
+impl_append!(A, B, C, D, E, G);
+impl_append!(A, B, C, D, E, G, H);
+
+/// Trait for `Input` types that can be assembled from a tuple of several logical inputs, e.g. a named `struct` with one
+/// field per input, so that an `Automaton` can be stepped via
+/// [`Automaton::next_with_inputs()`](struct.Automaton.html#method.next_with_inputs).
+/// 
+/// This is implemented for every type `T` from itself, so a `Family` can also simply use the tuple as its `Input`, and
+/// destructure it in the signature of `swap()`, e.g. `fn swap(self, (delta, events) : (f32, Vec<Event>))`.
+/// 
+/// See [`Inputs`](struct.Inputs.html) for an example.
+/// 
+pub trait FromInputs<T> {
+    /// Assembles `Self` from a tuple of `inputs`.
+    /// 
+    fn from_inputs(inputs : T) -> Self;
+}
+
+impl<T> FromInputs<T> for T {
+    fn from_inputs(inputs : T) -> Self {
+        inputs
+    }
+}
+
+/// A builder for assembling a tuple of several logical inputs at the call site, one input at a time, e.g. the time
+/// that has elapsed since the last frame, followed by the events that occurred during it.
+/// 
+/// Each call to [`with()`](#method.with) appends one more input to the tuple, so the order of the calls determines the
+/// order of the elements. The finished tuple can either be retrieved via [`build()`](#method.build), or be passed into
+/// [`Automaton::next_with_inputs()`](struct.Automaton.html#method.next_with_inputs), which converts it into the
+/// `Input` of the `Family` via [`FromInputs`](trait.FromInputs.html).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// enum Event { Jump, Land }
+/// 
+/// // The Input of the Family names each logical input explicitly.
+/// struct Frame { delta : f32, events : Vec<Event> }
+/// impl FromInputs<(f32, Vec<Event>)> for Frame {
+///     fn from_inputs((delta, events) : (f32, Vec<Event>)) -> Self {
+///         Frame { delta, events }
+///     }
+/// }
+/// 
+/// struct PlayerFamily;
+/// impl Family for PlayerFamily {
+///     type Base = Player;
+///     type Mode = Player;
+///     type Input = Frame;
+///     type Output = Player;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Player { Grounded, Airborne { time : f32 } }
+/// impl Mode for Player {
+///     type Family = PlayerFamily;
+///     fn swap(self, Frame { delta, events } : Frame) -> Self {
+///         match self {
+///             Player::Grounded if events.contains(&Event::Jump) => Player::Airborne { time : delta },
+///             Player::Airborne { .. } if events.contains(&Event::Land) => Player::Grounded,
+///             Player::Airborne { time } => Player::Airborne { time : time + delta },
+///             grounded => grounded,
+///         }
+///     }
+/// }
+/// 
+/// let mut player = PlayerFamily::automaton_with_mode(Player::Grounded);
+/// Automaton::next_with_inputs(&mut player, Inputs::new().with(0.5).with(vec![Event::Jump]));
+/// Automaton::next_with_inputs(&mut player, Inputs::new().with(0.25).with(vec![]));
+/// assert_eq!(*player, Player::Airborne { time : 0.75 });
+/// 
+/// // The builder can also be used to assemble a plain tuple.
+/// assert_eq!(Inputs::new().with(1).with("two").with(3.0).build(), (1, "two", 3.0));
+/// ```
+/// 
+#[derive(Clone, Copy, Debug, Default, Eq, Hash, PartialEq)]
+pub struct Inputs<T> {
+    inputs : T,
+}
+
+impl Inputs<()> {
+    /// Creates a new `Inputs` builder without any inputs.
+    /// 
+    pub fn new() -> Self {
+        Self { inputs : () }
+    }
+}
+
+impl<T> Inputs<T> {
+    /// Appends `input` to the end of the tuple of inputs.
+    /// 
+    pub fn with<U>(self, input : U) -> Inputs<T::Output>
+        where T : Append<U>
+    {
+        Inputs { inputs : self.inputs.append(input) }
+    }
+
+    /// Returns the tuple of inputs.
+    /// 
+    pub fn build(self) -> T {
+        self.inputs
+    }
+}
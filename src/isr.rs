@@ -0,0 +1,248 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use core::{
+    fmt,
+    marker::PhantomData,
+    sync::atomic::{AtomicU32, AtomicUsize, Ordering},
+};
+
+/// Trait for `Input` types that can be packed into a single `u32`, so that they can be stored in an
+/// [`IsrQueue`](struct.IsrQueue.html) without locking or allocating.
+/// 
+/// This is implemented for `()`, `bool`, `u8`, `u16`, and `u32`. For a field-less `enum`, it can usually be implemented
+/// by casting each variant to a `u32` and matching on the value in `unpack()`.
+/// 
+pub trait Packed : Copy {
+    /// Packs `self` into a `u32`.
+    /// 
+    fn pack(self) -> u32;
+
+    /// Unpacks a value that was previously packed via [`pack()`](#tymethod.pack).
+    /// 
+    fn unpack(bits : u32) -> Self;
+}
+
+impl Packed for () {
+    fn pack(self) -> u32 { 0 }
+    fn unpack(_bits : u32) -> Self { }
+}
+
+impl Packed for bool {
+    fn pack(self) -> u32 { self as u32 }
+    fn unpack(bits : u32) -> Self { bits != 0 }
+}
+
+impl Packed for u8 {
+    fn pack(self) -> u32 { self as u32 }
+    fn unpack(bits : u32) -> Self { bits as u8 }
+}
+
+impl Packed for u16 {
+    fn pack(self) -> u32 { self as u32 }
+    fn unpack(bits : u32) -> Self { bits as u16 }
+}
+
+impl Packed for u32 {
+    fn pack(self) -> u32 { self }
+    fn unpack(bits : u32) -> Self { bits }
+}
+
+struct Slot {
+    sequence : AtomicUsize,
+    bits : AtomicU32,
+}
+
+/// A fixed-capacity queue of `Input`s that can be filled from an interrupt handler while the main loop drains it into
+/// an `Automaton` via [`Automaton::next_with_queue()`](struct.Automaton.html#method.next_with_queue).
+/// 
+/// Unlike an [`Inbox`](struct.Inbox.html), an `IsrQueue` is designed for embedded targets, e.g. motor-control firmware,
+/// and provides the following guarantees:
+/// 
+/// - **No allocation.** All `N` slots are stored inline, and [`new()`](#method.new) is a `const fn`, so the queue can
+///   be placed in a `static` and shared between an interrupt handler and the main loop without any setup.
+/// - **No locking.** [`push()`](#method.push) and [`pop()`](#method.pop) only use atomic loads, stores, and
+///   compare-and-swap operations from `core::sync::atomic`, and never wait for each other. An interrupt that preempts
+///   the main loop in the middle of a `pop()` can always complete its `push()`, and vice versa, so calling `push()`
+///   from an interrupt handler can never deadlock. Any number of producers and consumers may use the queue at once.
+/// - **No blocking.** If the queue is full, `push()` returns the `Input` to the caller immediately, so the caller can
+///   decide whether to drop it or to count the overrun.
+/// - **No `std`.** The queue only depends on `core`, and remains available when the default `std` feature is
+///   disabled, in which case the crate is `#![no_std]`. Since `Automaton` requires `std`, the main loop of a `no_std`
+///   target drains the queue via `pop()` instead of `Automaton::next_with_queue()`. The target must support atomic
+///   compare-and-swap on `usize`, e.g. ARMv7-M and later. (ARMv6-M targets such as the Cortex-M0 do not.)
+/// 
+/// `Input`s are stored as a `u32` in each slot, so the `Input` type must implement [`Packed`](trait.Packed.html).
+/// `Input`s are received in the order in which they were enqueued.
+/// 
+/// **NOTE:** `N` must be a power of two. Each slot is chosen by taking a position counter modulo `N`, and that counter
+/// wraps at `usize::MAX`. Only a power of two divides `usize::MAX + 1` evenly, so any other `N` would eventually
+/// corrupt the slot order and leave `push()` unable to make progress.
+/// 
+/// # Usage
+/// ```
+/// use mode::{IsrQueue, Packed};
+/// 
+/// #[derive(Clone, Copy, Debug, PartialEq)]
+/// enum Event { Fault, Clear }
+/// impl Packed for Event {
+///     fn pack(self) -> u32 { self as u32 }
+///     fn unpack(bits : u32) -> Self { if bits == 0 { Event::Fault } else { Event::Clear } }
+/// }
+/// 
+/// static EVENTS : IsrQueue<Event, 4> = IsrQueue::new();
+/// 
+/// // Called from the overcurrent interrupt handler.
+/// fn on_overcurrent() {
+///     let _ = EVENTS.push(Event::Fault);
+/// }
+/// 
+/// on_overcurrent();
+/// assert_eq!(EVENTS.pop(), Some(Event::Fault));
+/// assert_eq!(EVENTS.pop(), None);
+/// 
+/// // The queue rejects new Inputs once it is full.
+/// for _ in 0..4 { EVENTS.push(Event::Clear).unwrap(); }
+/// assert_eq!(EVENTS.push(Event::Clear), Err(Event::Clear));
+/// ```
+/// 
+pub struct IsrQueue<Input, const N : usize>
+    where Input : Packed
+{
+    slots : [Slot; N],
+    head : AtomicUsize,
+    tail : AtomicUsize,
+    phantom : PhantomData<fn() -> Input>,
+}
+
+impl<Input, const N : usize> IsrQueue<Input, N>
+    where Input : Packed
+{
+    /// Creates a new, empty `IsrQueue` with room for `N` `Input`s.
+    /// 
+    /// # Panics
+    /// Panics if `N` is not a power of two, including zero. When the queue is placed in a `static`, this is reported
+    /// as a compile-time error instead.
+    /// 
+    pub const fn new() -> Self {
+        assert!(N.is_power_of_two(), "The capacity of an IsrQueue must be a power of two!");
+
+        let mut slots = [const { Slot { sequence : AtomicUsize::new(0), bits : AtomicU32::new(0) } }; N];
+        let mut index = 0;
+        while index < N {
+            slots[index].sequence = AtomicUsize::new(index);
+            index += 1;
+        }
+
+        Self { slots, head : AtomicUsize::new(0), tail : AtomicUsize::new(0), phantom : PhantomData }
+    }
+
+    /// Returns the maximum number of `Input`s that the `IsrQueue` can hold.
+    /// 
+    pub const fn capacity(&self) -> usize {
+        N
+    }
+
+    /// Returns the number of `Input`s that are currently pending. Since other producers and consumers may be using the
+    /// queue at the same time, this is only a snapshot.
+    /// 
+    pub fn len(&self) -> usize {
+        let tail = self.tail.load(Ordering::Acquire);
+        let head = self.head.load(Ordering::Acquire);
+        tail.wrapping_sub(head).min(N)
+    }
+
+    /// Returns `true` if no `Input`s are currently pending.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Adds `input` to the end of the queue without blocking, locking, or allocating. This is safe to call from an
+    /// interrupt handler. If the queue is full, `input` is returned as an `Err`.
+    /// 
+    pub fn push(&self, input : Input) -> Result<(), Input> {
+        let mut position = self.tail.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[position % N];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            if sequence == position {
+                let next = position.wrapping_add(1);
+                match self.tail.compare_exchange_weak(position, next, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        slot.bits.store(input.pack(), Ordering::Relaxed);
+                        slot.sequence.store(next, Ordering::Release);
+                        return Ok(());
+                    },
+                    Err(actual) => position = actual,
+                }
+            }
+            else if (sequence.wrapping_sub(position) as isize) < 0 {
+                // The slot still holds an Input from the previous lap, so the queue is full.
+                return Err(input);
+            }
+            else {
+                position = self.tail.load(Ordering::Relaxed);
+            }
+        }
+    }
+
+    /// Removes and returns the next pending `Input`, if any, without blocking, locking, or allocating.
+    /// 
+    pub fn pop(&self) -> Option<Input> {
+        let mut position = self.head.load(Ordering::Relaxed);
+        loop {
+            let slot = &self.slots[position % N];
+            let sequence = slot.sequence.load(Ordering::Acquire);
+            let expected = position.wrapping_add(1);
+            if sequence == expected {
+                match self.head.compare_exchange_weak(position, expected, Ordering::Relaxed, Ordering::Relaxed) {
+                    Ok(_) => {
+                        let input = Input::unpack(slot.bits.load(Ordering::Relaxed));
+                        slot.sequence.store(position.wrapping_add(N), Ordering::Release);
+                        return Some(input);
+                    },
+                    Err(actual) => position = actual,
+                }
+            }
+            else if (sequence.wrapping_sub(expected) as isize) < 0 {
+                // The slot has not been written yet, so the queue is empty.
+                return None;
+            }
+            else {
+                position = self.head.load(Ordering::Relaxed);
+            }
+        }
+    }
+}
+
+impl<Input, const N : usize> Default for IsrQueue<Input, N>
+    where Input : Packed
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<Input, const N : usize> fmt::Debug for IsrQueue<Input, N>
+    where Input : Packed
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("IsrQueue")
+            .field("len", &self.len())
+            .field("capacity", &N)
+            .finish()
+    }
+}
+
+#[cfg(feature = "defmt")]
+impl<Input, const N : usize> defmt::Format for IsrQueue<Input, N>
+    where Input : Packed
+{
+    fn format(&self, formatter : defmt::Formatter) {
+        defmt::write!(formatter, "IsrQueue {{ len: {=usize}, capacity: {=usize} }}", self.len(), N)
+    }
+}
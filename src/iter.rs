@@ -0,0 +1,118 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Mode};
+
+/// Extension trait that adds the [`scan_modes()`](#method.scan_modes) combinator to every `Iterator`, so that an
+/// `Automaton` can be used as a drop-in component of an ordinary data pipeline.
+/// 
+/// For the equivalent combinator for `futures::Stream`s, see `runtime::ModeStreamExt`, which requires the `futures`
+/// feature.
+/// 
+pub trait IteratorExt : Iterator + Sized {
+    /// Maps this `Iterator` of `Input`s to an `Iterator` of `Output`s by passing each item into
+    /// [`Automaton::next_with_input_and_output()`](struct.Automaton.html#method.next_with_input_and_output) on
+    /// `automaton`, and yielding whatever `Output` is returned. The resulting `Iterator` ends when this `Iterator` ends,
+    /// or as soon as `automaton` finishes, in which case no further items are pulled from this `Iterator`.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::*;
+    /// 
+    /// struct TokenizerFamily;
+    /// impl Family for TokenizerFamily {
+    ///     type Base = Tokenizer;
+    ///     type Mode = Tokenizer;
+    ///     type Input = char;
+    ///     type Output = (Tokenizer, Option<u32>);
+    /// }
+    /// 
+    /// // Parses runs of digits into numbers, and stops at the first '.'.
+    /// enum Tokenizer { Between, Number(u32), Done }
+    /// impl Mode for Tokenizer {
+    ///     type Family = TokenizerFamily;
+    ///     fn swap(self, c : char) -> (Self, Option<u32>) {
+    ///         let number = match self { Tokenizer::Number(number) => Some(number), _ => None };
+    ///         match c.to_digit(10) {
+    ///             Some(digit) => (Tokenizer::Number(number.unwrap_or(0) * 10 + digit), None),
+    ///             None if c == '.' => (Tokenizer::Done, number),
+    ///             None => (Tokenizer::Between, number),
+    ///         }
+    ///     }
+    ///     fn is_final(&self) -> bool { matches!(self, Tokenizer::Done) }
+    /// }
+    /// 
+    /// let numbers : Vec<u32> =
+    ///     "12 7 345. 99".chars()
+    ///         .scan_modes(TokenizerFamily::automaton_with_mode(Tokenizer::Between))
+    ///         .flatten()
+    ///         .collect();
+    /// 
+    /// assert_eq!(numbers, vec![12, 7, 345]);
+    /// ```
+    /// 
+    fn scan_modes<F>(self, automaton : Automaton<F>) -> ScanModes<Self, F>
+        where F : Family<Input = Self::Item> + ?Sized
+    {
+        ScanModes { iter : self, automaton }
+    }
+}
+
+impl<I> IteratorExt for I
+    where I : Iterator
+{ }
+
+/// The `Iterator` returned by [`IteratorExt::scan_modes()`](trait.IteratorExt.html#method.scan_modes).
+/// 
+pub struct ScanModes<I, F>
+    where F : Family + ?Sized
+{
+    iter : I,
+    automaton : Automaton<F>,
+}
+
+impl<I, F> ScanModes<I, F>
+    where F : Family + ?Sized
+{
+    /// Returns an immutable reference to the wrapped `Automaton`.
+    /// 
+    pub fn automaton(&self) -> &Automaton<F> {
+        &self.automaton
+    }
+
+    /// Consumes the `ScanModes`, returning the wrapped `Iterator` and `Automaton`.
+    /// 
+    pub fn into_inner(self) -> (I, Automaton<F>) {
+        (self.iter, self.automaton)
+    }
+}
+
+impl<I, F, M, Output> Iterator for ScanModes<I, F>
+    where
+        I : Iterator,
+        F : Family<Mode = M, Input = I::Item, Output = (M, Output)> + ?Sized,
+        M : Mode<Family = F>,
+{
+    type Item = Output;
+
+    fn next(&mut self) -> Option<Output> {
+        if Automaton::is_finished(&self.automaton) {
+            return None;
+        }
+
+        let input = self.iter.next()?;
+        Some(Automaton::next_with_input_and_output(&mut self.automaton, input))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        if Automaton::is_finished(&self.automaton) {
+            (0, Some(0))
+        }
+        else {
+            (0, self.iter.size_hint().1)
+        }
+    }
+}
@@ -0,0 +1,74 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+/// Trait that allows the `F::Mode` of a `Family` to lend out a borrow of itself as the result of a transition, via
+/// [`Automaton::next_lending()`](struct.Automaton.html#method.next_lending).
+/// 
+/// A `Mode` cannot return a reference into the `Mode` it swaps in, since that `Mode` is moved into the `Automaton`
+/// after `swap()` returns. Instead, a `Family` whose `Output` is a `(Mode, Key)` tuple can implement `Lend<Key>` for
+/// its `Mode` type. After each swap, the `Automaton` passes the `Key` returned by `swap()` into
+/// [`lend()`](#tymethod.lend) on the new current `Mode`, and returns the resulting `Loan` to the caller. Since `Loan` is
+/// a generic associated type, it can borrow from the `Mode` for as long as the `Automaton` itself stays borrowed, i.e.
+/// until the next call that needs `&mut` access to the `Automaton`. This allows zero-copy response patterns, e.g. a
+/// parser returning a slice of its own buffer, that would otherwise require the response to be copied or allocated.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::ops::Range;
+/// 
+/// struct LineFamily;
+/// impl Family for LineFamily {
+///     type Base = Lines;
+///     type Mode = Lines;
+///     type Input = char;
+///     type Output = (Lines, Option<Range<usize>>);
+/// }
+/// 
+/// // Buffers incoming characters, and reports each complete line as a range of the buffer.
+/// struct Lines { buffer : String, start : usize }
+/// impl Mode for Lines {
+///     type Family = LineFamily;
+///     fn swap(mut self, c : char) -> (Self, Option<Range<usize>>) {
+///         if c == '\n' {
+///             let line = self.start..self.buffer.len();
+///             self.start = self.buffer.len();
+///             (self, Some(line))
+///         }
+///         else {
+///             self.buffer.push(c);
+///             (self, None)
+///         }
+///     }
+/// }
+/// 
+/// impl Lend<Option<Range<usize>>> for Lines {
+///     type Loan<'a> = Option<&'a str>;
+///     fn lend(&self, line : Option<Range<usize>>) -> Option<&str> {
+///         line.map(|line| &self.buffer[line])
+///     }
+/// }
+/// 
+/// let mut automaton = LineFamily::automaton_with_mode(Lines { buffer : String::new(), start : 0 });
+/// let mut lines = Vec::new();
+/// for c in "hello\nworld\n".chars() {
+///     // Each line is borrowed straight out of the buffer, and only copied here in order to keep it around.
+///     if let Some(line) = Automaton::next_lending(&mut automaton, c) {
+///         lines.push(line.to_owned());
+///     }
+/// }
+/// assert_eq!(lines, vec!["hello", "world"]);
+/// ```
+/// 
+pub trait Lend<Key> {
+    /// The type of borrow that is lent out. This may borrow from `self` for the lifetime `'a`.
+    /// 
+    type Loan<'a> where Self : 'a;
+
+    /// Returns the `Loan` described by `key`, which was returned by the `swap()` call that made this `Mode` current.
+    /// 
+    fn lend(&self, key : Key) -> Self::Loan<'_>;
+}
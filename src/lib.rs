@@ -35,14 +35,227 @@
 //! cargo run --example turing
 //! ```
 //! 
+//! # Optional features
+//! - `allocation`: Enables the [`allocation`](allocation/index.html) module, a wrapping global allocator that counts
+//!   heap allocations, and a `Middleware` that attributes them to each transition of an `Automaton`.
+//! - `cli`: Builds the `mode-cli` binary, which validates [`table`](table/index.html) definitions and renders them as
+//!   DOT, PlantUML, or SCXML, e.g. via `cargo run --features cli --bin mode-cli -- validate machine.json`.
+//! - `async-std`, `smol`: Enable the [`runtime::AsyncStd`](runtime/struct.AsyncStd.html) and
+//!   [`runtime::Smol`](runtime/struct.Smol.html) adapters, respectively, for driving an `Automaton` from async code.
+//! - `debug`: Enables the [`debug`](debug/index.html) module, a global registry of every live, named `Automaton` and
+//!   the `tag()` of its current `Mode`, which can be dumped at runtime.
+//! - `defmt`: Implements `defmt::Format` for `Automaton`, [`TransitionEvent`](struct.TransitionEvent.html),
+//!   [`Field`](struct.Field.html), [`Phase`](enum.Phase.html), [`IsrQueue`](struct.IsrQueue.html), and the errors that
+//!   can occur while stepping an `Automaton`, for cheap structured logging on embedded targets, e.g. over RTT. This
+//!   feature does not require `std`, although only the implementations for `Phase` and `IsrQueue` are available
+//!   without it.
+//! - `futures`: Enables [`runtime::InputSink`](runtime/struct.InputSink.html), which feeds `Input`s into an `Automaton`
+//!   as a `futures::Sink`, and [`runtime::ModeStreamExt`](runtime/trait.ModeStreamExt.html), which maps a
+//!   `futures::Stream` through an `Automaton`.
+//! - `nightly`: Enables [`modes::Routine`](modes/struct.Routine.html), which allows the behavior of a `Mode` to be
+//!   written as a coroutine that yields between steps. This requires a nightly Rust compiler.
+//! - `otel`: Enables the [`otel`](otel/index.html) module, which reports the state occupancy of an `Automaton` as
+//!   OpenTelemetry spans.
+//! - `rayon`: Enables `par_step_all()` on [`Batch`](struct.Batch.html) and [`Registry`](struct.Registry.html), which
+//!   step many independent state machines in parallel on the `rayon` thread pool, as well as
+//!   [`driver::Pool`](driver/struct.Pool.html), which shards a large population of state machines across the thread
+//!   pool.
+//! - `rhai`: Enables the [`script`](script/index.html) module, which allows the update and transition logic of a `Mode`
+//!   to be written in Rhai scripts.
+//! - `serde`: Enables the [`persist`](persist/index.html) module, which allows the current `Mode` of an `Automaton` to
+//!   be saved and restored with versioned migrations, and allows [`table`](table/index.html) definitions to be loaded
+//!   from JSON.
+//! - `std` (enabled by default): Enables everything except [`IsrQueue`](struct.IsrQueue.html),
+//!   [`Packed`](trait.Packed.html), and [`Phase`](enum.Phase.html), which only depend on `core`. Without it, the crate
+//!   is `#![no_std]`, so that an `IsrQueue` can be shared with interrupt handlers on targets without `std`. Every other
+//!   feature except `defmt` requires `std`.
+//! - `tokio`: Enables [`Automaton::watch()`](struct.Automaton.html#method.watch), which exposes the `tag()` of the
+//!   current `Mode` through a `tokio::sync::watch` channel, as well as the [`runtime::Tokio`](runtime/struct.Tokio.html)
+//!   adapter.
+//! - `toml`, `yaml`: Allow [`table`](table/index.html) definitions to be loaded from TOML and YAML, respectively.
+//! 
 //! # Getting started
 //! A good place to start reading would be the [`Automaton`](struct.Automaton.html) documentation, followed by
-//! [`Mode`](trait.Mode.html) and then [`Family`](trait.Family.html).
+//! [`Mode`](trait.Mode.html) and then [`Family`](trait.Family.html). The recommended way of stepping an `Automaton` is
+//! via [`automaton.step_modes()`](struct.Automaton.html#method.step_modes) and
+//! [`automaton.step_modes_with(input)`](struct.Automaton.html#method.step_modes_with).
 //! 
+#![cfg_attr(not(feature = "std"), no_std)]
+#![cfg_attr(feature = "nightly", feature(coroutine_trait))]
+
+#[cfg(feature = "std")]
+#[macro_use]
+mod macros;
+
+#[cfg(feature = "allocation")]
+pub mod allocation;
+#[cfg(feature = "std")]
+pub mod bt;
+#[cfg(feature = "std")]
+pub mod chrome;
+#[cfg(feature = "std")]
+pub mod codegen;
+#[cfg(feature = "debug")]
+pub mod debug;
+#[cfg(feature = "std")]
+pub mod dfa;
+#[cfg(feature = "std")]
+pub mod driver;
+#[cfg(feature = "std")]
+pub mod explore;
+#[cfg(feature = "std")]
+pub mod goap;
+#[cfg(feature = "std")]
+pub mod guards;
+#[cfg(feature = "std")]
+pub mod matrix;
+#[cfg(feature = "std")]
+pub mod modes;
+#[cfg(feature = "otel")]
+pub mod otel;
+#[cfg(feature = "serde")]
+pub mod persist;
+#[cfg(feature = "std")]
+pub mod runtime;
+#[cfg(feature = "rhai")]
+pub mod script;
+#[cfg(feature = "std")]
+pub mod table;
+#[cfg(feature = "std")]
+pub mod text;
+#[cfg(feature = "std")]
+pub mod verify;
+
+#[cfg(feature = "std")]
 mod automaton;
+#[cfg(feature = "std")]
+mod batch;
+#[cfg(feature = "std")]
+mod blackboard;
+#[cfg(feature = "std")]
+mod bridge;
+#[cfg(feature = "std")]
+mod cancel;
+#[cfg(feature = "std")]
+mod cow;
+#[cfg(feature = "std")]
+mod dwell;
+#[cfg(feature = "std")]
+mod event;
+#[cfg(feature = "std")]
+mod event_log;
+#[cfg(feature = "std")]
 mod family;
+#[cfg(feature = "std")]
+mod finish;
+#[cfg(feature = "std")]
+mod footprint;
+#[cfg(feature = "std")]
+mod inbox;
+#[cfg(feature = "std")]
+mod initial;
+#[cfg(feature = "std")]
+mod inputs;
+mod isr;
+#[cfg(feature = "std")]
+mod iter;
+#[cfg(feature = "std")]
+mod lend;
+#[cfg(feature = "std")]
+mod middleware;
+#[cfg(feature = "std")]
 mod mode;
+#[cfg(feature = "std")]
+mod persistent;
+mod phase;
+#[cfg(feature = "std")]
+mod pointer;
+#[cfg(feature = "std")]
+mod poisoned;
+#[cfg(feature = "std")]
+mod registry;
+#[cfg(feature = "std")]
+mod replace;
+#[cfg(feature = "std")]
+mod request;
+#[cfg(feature = "std")]
+mod slot;
+#[cfg(feature = "std")]
+mod stats;
+#[cfg(feature = "std")]
+mod swap_ref;
+#[cfg(feature = "std")]
+mod time_travel;
+#[cfg(feature = "std")]
+mod utility;
+#[cfg(feature = "std")]
+mod view;
+#[cfg(feature = "std")]
+mod watchdog;
 
+#[cfg(feature = "std")]
 pub use self::automaton::*;
+#[cfg(feature = "std")]
+pub use self::batch::*;
+#[cfg(feature = "std")]
+pub use self::blackboard::*;
+#[cfg(feature = "std")]
+pub use self::bridge::*;
+#[cfg(feature = "std")]
+pub use self::cancel::*;
+#[cfg(feature = "std")]
+pub use self::cow::*;
+#[cfg(feature = "std")]
+pub use self::dwell::*;
+#[cfg(feature = "std")]
+pub use self::event::*;
+#[cfg(feature = "std")]
+pub use self::event_log::*;
+#[cfg(feature = "std")]
 pub use self::family::*;
-pub use self::mode::*;
\ No newline at end of file
+#[cfg(feature = "std")]
+pub use self::finish::*;
+#[cfg(feature = "std")]
+pub use self::footprint::*;
+#[cfg(feature = "std")]
+pub use self::inbox::*;
+#[cfg(feature = "std")]
+pub use self::initial::*;
+#[cfg(feature = "std")]
+pub use self::inputs::*;
+pub use self::isr::*;
+#[cfg(feature = "std")]
+pub use self::iter::*;
+#[cfg(feature = "std")]
+pub use self::lend::*;
+#[cfg(feature = "std")]
+pub use self::middleware::*;
+#[cfg(feature = "std")]
+pub use self::mode::*;
+#[cfg(feature = "std")]
+pub use self::persistent::*;
+pub use self::phase::*;
+#[cfg(feature = "std")]
+pub use self::pointer::*;
+#[cfg(feature = "std")]
+pub use self::poisoned::*;
+#[cfg(feature = "std")]
+pub use self::registry::*;
+#[cfg(feature = "std")]
+pub use self::replace::*;
+#[cfg(feature = "std")]
+pub use self::request::*;
+#[cfg(feature = "std")]
+pub use self::slot::*;
+#[cfg(feature = "std")]
+pub use self::stats::*;
+#[cfg(feature = "std")]
+pub use self::swap_ref::*;
+#[cfg(feature = "std")]
+pub use self::time_travel::*;
+#[cfg(feature = "std")]
+pub use self::utility::*;
+#[cfg(feature = "std")]
+pub use self::view::*;
+#[cfg(feature = "std")]
+pub use self::watchdog::*;
\ No newline at end of file
@@ -0,0 +1,469 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+/// Generates an `enum` that wraps a set of concrete `Mode` types, allowing each state to be written as its own `struct`
+/// while still storing the current state inline in the `Automaton`, with static dispatch.
+/// 
+/// Each wrapped type must implement `Mode` for the same `Family`, whose `Mode` type should be the generated `enum`.
+/// The macro generates:
+/// 
+/// - the `enum` itself, with one single-field tuple variant per wrapped type,
+/// - a `From` impl for each wrapped type, so that `swap()` can simply return `NextState.into()`,
+/// - an `impl Mode` for the `enum` that forwards `swap()`, `tag()`, `enter()`, `exit()`, `is_final()`,
+///   `fields()`, `set_path()`, and `heap_size()` to the wrapped `Mode`, and
+/// - if a `Base` type is specified after a colon, `Borrow<Base>` and `BorrowMut<Base>` impls that allow the
+///   `Automaton` to `Deref` into the `Base` interface shared by all wrapped types. This should be the same type as
+///   the `Base` of the `Family`.
+/// 
+/// Since `tag()` is forwarded, each wrapped type keeps its own tag, which defaults to its type name.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct LightFamily;
+/// impl Family for LightFamily {
+///     type Base = dyn Light;
+///     type Mode = Signal;
+///     type Input = ();
+///     type Output = Signal;
+/// }
+/// 
+/// trait Light {
+///     fn color(&self) -> &'static str;
+/// }
+/// 
+/// struct Red;
+/// impl Light for Red { fn color(&self) -> &'static str { "red" } }
+/// impl Mode for Red {
+///     type Family = LightFamily;
+///     fn swap(self, _input : ()) -> Signal { Green { cars : 0 }.into() }
+/// }
+/// 
+/// struct Green { cars : u32 }
+/// impl Light for Green { fn color(&self) -> &'static str { "green" } }
+/// impl Mode for Green {
+///     type Family = LightFamily;
+///     fn swap(self, _input : ()) -> Signal {
+///         if self.cars < 2 { Green { cars : self.cars + 1 }.into() } else { Red.into() }
+///     }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Signal : dyn Light {
+///         Red(Red),
+///         Green(Green),
+///     }
+/// }
+/// 
+/// let mut automaton = LightFamily::automaton_with_mode(Red.into());
+/// assert_eq!(automaton.color(), "red");
+/// 
+/// Automaton::next(&mut automaton);
+/// assert_eq!(automaton.color(), "green");
+/// ```
+/// 
+#[macro_export]
+macro_rules! enum_mode {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident : $base:ty {
+            $first:ident($first_ty:ty) $(, $variant:ident($variant_ty:ty))* $(,)?
+        }
+    ) => {
+        $crate::enum_mode! {
+            $(#[$meta])*
+            $vis enum $name {
+                $first($first_ty) $(, $variant($variant_ty))*
+            }
+        }
+
+        impl ::std::borrow::Borrow<$base> for $name {
+            fn borrow(&self) -> &<<Self as $crate::Mode>::Family as $crate::Family>::Base {
+                match self {
+                    $name::$first(mode) => mode,
+                    $($name::$variant(mode) => mode,)*
+                }
+            }
+        }
+
+        impl ::std::borrow::BorrowMut<$base> for $name {
+            fn borrow_mut(&mut self) -> &mut <<Self as $crate::Mode>::Family as $crate::Family>::Base {
+                match self {
+                    $name::$first(mode) => mode,
+                    $($name::$variant(mode) => mode,)*
+                }
+            }
+        }
+    };
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $first:ident($first_ty:ty) $(, $variant:ident($variant_ty:ty))* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        $vis enum $name {
+            $first($first_ty),
+            $($variant($variant_ty),)*
+        }
+
+        impl ::std::convert::From<$first_ty> for $name {
+            fn from(mode : $first_ty) -> Self { $name::$first(mode) }
+        }
+
+        $(
+            impl ::std::convert::From<$variant_ty> for $name {
+                fn from(mode : $variant_ty) -> Self { $name::$variant(mode) }
+            }
+        )*
+
+        impl $crate::Mode for $name {
+            type Family = <$first_ty as $crate::Mode>::Family;
+
+            fn swap(self, input : <Self::Family as $crate::Family>::Input) -> <Self::Family as $crate::Family>::Output {
+                match self {
+                    $name::$first(mode) => $crate::Mode::swap(mode, input),
+                    $($name::$variant(mode) => $crate::Mode::swap(mode, input),)*
+                }
+            }
+
+            fn tag(&self) -> &'static str {
+                match self {
+                    $name::$first(mode) => $crate::Mode::tag(mode),
+                    $($name::$variant(mode) => $crate::Mode::tag(mode),)*
+                }
+            }
+
+            fn enter(&mut self) {
+                match self {
+                    $name::$first(mode) => $crate::Mode::enter(mode),
+                    $($name::$variant(mode) => $crate::Mode::enter(mode),)*
+                }
+            }
+
+            fn exit(&mut self) {
+                match self {
+                    $name::$first(mode) => $crate::Mode::exit(mode),
+                    $($name::$variant(mode) => $crate::Mode::exit(mode),)*
+                }
+            }
+
+            fn is_final(&self) -> bool {
+                match self {
+                    $name::$first(mode) => $crate::Mode::is_final(mode),
+                    $($name::$variant(mode) => $crate::Mode::is_final(mode),)*
+                }
+            }
+
+            fn fields(&self) -> ::std::vec::Vec<$crate::Field> {
+                match self {
+                    $name::$first(mode) => $crate::Mode::fields(mode),
+                    $($name::$variant(mode) => $crate::Mode::fields(mode),)*
+                }
+            }
+
+            fn set_path(&mut self, path : &str) {
+                match self {
+                    $name::$first(mode) => $crate::Mode::set_path(mode, path),
+                    $($name::$variant(mode) => $crate::Mode::set_path(mode, path),)*
+                }
+            }
+
+            fn heap_size(&self) -> usize {
+                match self {
+                    $name::$first(mode) => $crate::Mode::heap_size(mode),
+                    $($name::$variant(mode) => $crate::Mode::heap_size(mode),)*
+                }
+            }
+        }
+    };
+}
+
+/// Implements `boxed::Mode` for one or more types by forwarding `swap()` to an inherent
+/// `fn next(self, input : F::Input) -> F::Output` defined on each type, where `F` is the specified `Family`.
+/// 
+/// This removes the need to write `fn swap(self : Box<Self>, ...)` by hand, and allows the transition logic to take
+/// `self` by value, as it would with a plain `Mode`. The `Box` is unwrapped before `next()` is called, so this moves the
+/// value out of the `Box`. For very large types, implementing `boxed::Mode` by hand may therefore be preferable.
+/// 
+/// This is a declarative macro rather than a `#[derive(BoxedMode)]`, since a derive would have to live in a separate
+/// `proc-macro` crate, which `mode` does not have. Like [`enum_mode!`](macro.enum_mode.html), it is invoked once per
+/// `Family`, next to the types it applies to, and works with any type that has a suitable inherent `next()`, whether it
+/// is a `struct` or an `enum`. Generic types are supported by a second form, which takes the generic parameters and an
+/// optional `where` clause, and implements `boxed::Mode` for one generic type at a time:
+/// 
+/// ```text
+/// mode::boxed_mode!(SomeFamily { A, B, C });
+/// mode::boxed_mode!(impl<T> SomeFamily { Wait<T> } where T : Bound);
+/// ```
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = dyn SomeTrait;
+///     type Mode = Box<dyn SomeTrait>;
+///     type Input = ();
+///     type Output = Box<dyn SomeTrait>;
+/// }
+/// 
+/// trait SomeTrait : boxed::Mode<Family = SomeFamily> {
+///     fn name(&self) -> &'static str;
+/// }
+/// 
+/// struct A;
+/// impl A {
+///     fn next(self, _input : ()) -> Box<dyn SomeTrait> { Box::new(B { count : 1 }) }
+/// }
+/// impl SomeTrait for A { fn name(&self) -> &'static str { "A" } }
+/// 
+/// struct B { count : u32 }
+/// impl B {
+///     fn next(self, _input : ()) -> Box<dyn SomeTrait> {
+///         if self.count < 2 { Box::new(B { count : self.count + 1 }) } else { Box::new(Light::Red) }
+///     }
+/// }
+/// impl SomeTrait for B { fn name(&self) -> &'static str { "B" } }
+/// 
+/// // An enum works just like a struct.
+/// enum Light { Red, Green }
+/// impl Light {
+///     fn next(self, _input : ()) -> Box<dyn SomeTrait> {
+///         match self {
+///             Light::Red => Box::new(Light::Green),
+///             Light::Green => Box::new(Wait { label : "done", steps : 1 }),
+///         }
+///     }
+/// }
+/// impl SomeTrait for Light {
+///     fn name(&self) -> &'static str { match self { Light::Red => "Red", Light::Green => "Green" } }
+/// }
+/// 
+/// mode::boxed_mode!(SomeFamily { A, B, Light });
+/// 
+/// // A generic type is implemented via the second form.
+/// struct Wait<T> { label : T, steps : u32 }
+/// impl<T> Wait<T>
+///     where T : Into<&'static str> + Copy + 'static
+/// {
+///     fn next(self, _input : ()) -> Box<dyn SomeTrait> {
+///         if self.steps > 0 { Box::new(Wait { label : self.label, steps : self.steps - 1 }) } else { Box::new(A) }
+///     }
+/// }
+/// impl<T> SomeTrait for Wait<T>
+///     where T : Into<&'static str> + Copy + 'static
+/// {
+///     fn name(&self) -> &'static str { self.label.into() }
+/// }
+/// 
+/// mode::boxed_mode!(impl<T> SomeFamily { Wait<T> } where T : Into<&'static str> + Copy + 'static);
+/// 
+/// let mut automaton = SomeFamily::automaton_with_mode(Box::new(A));
+/// let mut names = Vec::new();
+/// for _ in 0..7 {
+///     Automaton::next(&mut automaton);
+///     names.push(automaton.name());
+/// }
+/// assert_eq!(names, ["B", "B", "Red", "Green", "done", "done", "A"]);
+/// ```
+/// 
+#[macro_export]
+macro_rules! boxed_mode {
+    (impl<$($param:ident),+ $(,)?> $family:ty { $mode:ty $(,)? } $(where $($bound:tt)+)?) => {
+        impl<$($param),+> $crate::boxed::Mode for $mode
+            $(where $($bound)+)?
+        {
+            type Family = $family;
+
+            fn swap(self : ::std::boxed::Box<Self>, input : <$family as $crate::Family>::Input)
+                -> <$family as $crate::Family>::Output
+            {
+                <$mode>::next(*self, input)
+            }
+        }
+    };
+    ($family:ty { $($mode:ty),+ $(,)? }) => {
+        $(
+            impl $crate::boxed::Mode for $mode {
+                type Family = $family;
+
+                fn swap(self : ::std::boxed::Box<Self>, input : <$family as $crate::Family>::Input)
+                    -> <$family as $crate::Family>::Output
+                {
+                    <$mode>::next(*self, input)
+                }
+            }
+        )+
+    };
+}
+
+/// Implements `std::borrow::Borrow<F::Base>` and `std::borrow::BorrowMut<F::Base>` for `Automaton<F>`, where `F` is
+/// the specified `Family`, so that the `Automaton` can be passed to generic functions that expect those bounds.
+/// 
+/// These `impl`s cannot be provided generically by the `mode` crate itself, since they would conflict with the blanket
+/// `impl<T> Borrow<T> for T` in the standard library. (The compiler cannot rule out that `F::Base` is the `Automaton`
+/// itself.) Since this macro is invoked with a concrete `Family`, whose `Base` must be a type defined in the same crate,
+/// no such conflict can occur. The `Mode` type of the `Family` must implement both `Borrow<F::Base>` and
+/// `BorrowMut<F::Base>`.
+/// 
+/// Note that `Automaton` already implements `AsRef<F::Base>` and `AsMut<F::Base>` for every `Family`.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::borrow::Borrow;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = Counter;
+///     type Mode = Counter;
+///     type Input = ();
+///     type Output = Counter;
+/// }
+/// 
+/// struct Counter(u32);
+/// impl Mode for Counter {
+///     type Family = SomeFamily;
+///     fn swap(self, _input : ()) -> Self { Counter(self.0 + 1) }
+/// }
+/// 
+/// mode::impl_borrow!(SomeFamily);
+/// 
+/// fn count<T : Borrow<Counter>>(counter : T) -> u32 { counter.borrow().0 }
+/// 
+/// let mut automaton = SomeFamily::automaton_with_mode(Counter(0));
+/// Automaton::next(&mut automaton);
+/// assert_eq!(count(automaton), 1);
+/// ```
+/// 
+#[macro_export]
+macro_rules! impl_borrow {
+    ($family:ty) => {
+        impl ::std::borrow::Borrow<<$family as $crate::Family>::Base> for $crate::Automaton<$family> {
+            fn borrow(&self) -> &<$family as $crate::Family>::Base {
+                self.borrow_mode()
+            }
+        }
+
+        impl ::std::borrow::BorrowMut<<$family as $crate::Family>::Base> for $crate::Automaton<$family> {
+            fn borrow_mut(&mut self) -> &mut <$family as $crate::Family>::Base {
+                self.borrow_mode_mut()
+            }
+        }
+    };
+}
+
+/// Generates an `enum` of strongly-typed newtype wrappers, e.g. for the distinct kinds of `Input` or `Output` of a
+/// `Family`, so that transition signatures name each kind explicitly instead of relying on bare tuples or primitives.
+/// 
+/// Each variant is written as a name, optionally followed by the type it wraps in parentheses. The macro generates:
+/// 
+/// - one newtype `struct` per variant, with the same name and visibility as the `enum`, and a single `pub` field
+///   containing the wrapped type, or no fields at all if no type was given,
+/// - the `enum` itself, with one single-field tuple variant per generated `struct`,
+/// - a `From` impl for each `struct`, so that a call site can simply pass `Lock(1234).into()`, and
+/// - a `TryFrom<enum>` impl for each `struct`, which returns the original `enum` as the error if it holds a different
+///   variant.
+/// 
+/// Any attributes placed on the `enum`, e.g. `#[derive(...)]`, are applied to each generated `struct` as well.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::convert::TryFrom;
+/// 
+/// mode::newtype_enum! {
+///     #[derive(Clone, Copy, Debug, PartialEq)]
+///     pub enum DoorInput {
+///         Open,
+///         Close,
+///         Lock(u32),
+///         Unlock(u32),
+///     }
+/// }
+/// 
+/// struct DoorFamily;
+/// impl Family for DoorFamily {
+///     type Base = Door;
+///     type Mode = Door;
+///     type Input = DoorInput;
+///     type Output = Door;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Door { Opened, Closed, Locked { code : u32 } }
+/// impl Mode for Door {
+///     type Family = DoorFamily;
+///     fn swap(self, input : DoorInput) -> Self {
+///         match (self, input) {
+///             (Door::Closed, DoorInput::Open(Open)) => Door::Opened,
+///             (Door::Opened, DoorInput::Close(Close)) => Door::Closed,
+///             (Door::Closed, DoorInput::Lock(Lock(code))) => Door::Locked { code },
+///             (Door::Locked { code }, DoorInput::Unlock(Unlock(attempt))) if attempt == code => Door::Closed,
+///             (door, _) => door,
+///         }
+///     }
+/// }
+/// 
+/// let mut door = DoorFamily::automaton_with_mode(Door::Closed);
+/// Automaton::next_with_input(&mut door, Lock(1234).into());
+/// Automaton::next_with_input(&mut door, Unlock(0).into());
+/// assert_eq!(*door, Door::Locked { code : 1234 });
+/// 
+/// assert_eq!(Lock::try_from(DoorInput::from(Lock(7))), Ok(Lock(7)));
+/// assert_eq!(Lock::try_from(DoorInput::Open(Open)), Err(DoorInput::Open(Open)));
+/// ```
+/// 
+#[macro_export]
+macro_rules! newtype_enum {
+    (
+        $(#[$meta:meta])*
+        $vis:vis enum $name:ident {
+            $($variant:ident $(($inner:ty))?),+ $(,)?
+        }
+    ) => {
+        $crate::newtype_enum!(@structs [$(#[$meta])*] $vis, $($variant $(($inner))?),+);
+
+        $(#[$meta])*
+        $vis enum $name {
+            $($variant($variant),)+
+        }
+
+        $(
+            impl ::std::convert::From<$variant> for $name {
+                fn from(value : $variant) -> Self { $name::$variant(value) }
+            }
+
+            impl ::std::convert::TryFrom<$name> for $variant {
+                type Error = $name;
+
+                fn try_from(value : $name) -> ::std::result::Result<Self, $name> {
+                    #[allow(unreachable_patterns)]
+                    match value {
+                        $name::$variant(value) => ::std::result::Result::Ok(value),
+                        other => ::std::result::Result::Err(other),
+                    }
+                }
+            }
+        )+
+    };
+    (@structs $attributes:tt $vis:vis, $($variant:ident $(($inner:ty))?),+) => {
+        $(
+            $crate::newtype_enum!(@struct $attributes $vis $variant $(($inner))?);
+        )+
+    };
+    (@struct [$(#[$meta:meta])*] $vis:vis $variant:ident) => {
+        $(#[$meta])*
+        $vis struct $variant;
+    };
+    (@struct [$(#[$meta:meta])*] $vis:vis $variant:ident ($inner:ty)) => {
+        $(#[$meta])*
+        $vis struct $variant(pub $inner);
+    };
+}
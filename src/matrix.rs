@@ -0,0 +1,217 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines small, fixed state machines whose transition function is a constant 2D array, for performance-critical
+//! inner loops such as protocol byte parsers.
+//! 
+//! A [`Matrix`](struct.Matrix.html) with `S` states and `E` events stores the index of the next state for every pair
+//! of current state and event. Since `Matrix::new()` is a `const fn` that checks every entry, a `Matrix` stored in a
+//! `const` or `static` is validated at compile time, and stepping it is a single table lookup, with no branching on the
+//! current state. A [`MatrixState`](struct.MatrixState.html) tracks the current state within a `&'static Matrix`, and
+//! can be stepped directly via [`MatrixState::step()`](struct.MatrixState.html#method.step), or wrapped in an
+//! `Automaton` over the [`MatrixFamily`](struct.MatrixFamily.html), whose `Input` is the index of an event.
+//! 
+//! # Usage
+//! ```
+//! use mode::{matrix::{Matrix, MatrixFamily, MatrixState}, Automaton, Family};
+//! 
+//! // Recognizes the byte sequence "OK" in a stream of bytes.
+//! const START : usize = 0;
+//! const SAW_O : usize = 1;
+//! const DONE : usize = 2;
+//! 
+//! const O : usize = 0;
+//! const K : usize = 1;
+//! const OTHER : usize = 2;
+//! 
+//! static PARSER : Matrix<3, 3> =
+//!     Matrix::new(
+//!         [
+//!             // O      K      OTHER
+//!             [SAW_O, START, START], // START
+//!             [SAW_O, DONE, START],  // SAW_O
+//!             [DONE, DONE, DONE],    // DONE
+//!         ],
+//!         ["Start", "SawO", "Done"])
+//!     .with_final(DONE);
+//! 
+//! fn event(byte : u8) -> usize {
+//!     match byte { b'O' => O, b'K' => K, _ => OTHER }
+//! }
+//! 
+//! let mut state = MatrixState::new(&PARSER, START);
+//! for byte in b"xOOK" {
+//!     state.step(event(*byte));
+//! }
+//! assert_eq!(state.index(), DONE);
+//! 
+//! let mut automaton = MatrixFamily::automaton_with_mode(MatrixState::new(&PARSER, START));
+//! for byte in b"OK" {
+//!     Automaton::next_with_input(&mut automaton, event(*byte));
+//! }
+//! assert!(Automaton::is_finished(&automaton));
+//! ```
+//! 
+
+use crate::{Family, Mode};
+use std::{fmt, marker::PhantomData};
+
+/// A constant transition table for a state machine with `S` states and `E` events.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Matrix<const S : usize, const E : usize> {
+    table : [[usize; E]; S],
+    tags : [&'static str; S],
+    finals : [bool; S],
+}
+
+impl<const S : usize, const E : usize> Matrix<S, E> {
+    /// Creates a new `Matrix` where `table[state][event]` is the index of the state that becomes current when `event`
+    /// arrives in `state`, and `tags[state]` is the name of each state, as returned by `Mode::tag()`. No state is
+    /// final.
+    /// 
+    /// # Panics
+    /// Panics if any entry in `table` is not less than `S`. If the `Matrix` is created in a `const` or `static`
+    /// context, this is a compile-time error instead.
+    /// 
+    pub const fn new(table : [[usize; E]; S], tags : [&'static str; S]) -> Self {
+        let mut state = 0;
+        while state < S {
+            let mut event = 0;
+            while event < E {
+                assert!(
+                    table[state][event] < S,
+                    "Cannot create Matrix because a transition leads to a state that does not exist!");
+                event += 1;
+            }
+            state += 1;
+        }
+
+        Self { table, tags, finals : [false; S] }
+    }
+
+    /// Marks the state at index `state` as [final](../trait.Mode.html#method.is_final).
+    /// 
+    /// # Panics
+    /// Panics if `state` is not less than `S`. If the `Matrix` is created in a `const` or `static` context, this is a
+    /// compile-time error instead.
+    /// 
+    pub const fn with_final(mut self, state : usize) -> Self {
+        assert!(state < S, "Cannot mark a state as final because it does not exist!");
+        self.finals[state] = true;
+        self
+    }
+
+    /// Returns the index of the state that becomes current when `event` arrives in `state`.
+    /// 
+    /// # Panics
+    /// Panics if `state` is not less than `S`, or if `event` is not less than `E`.
+    /// 
+    #[inline]
+    pub const fn next(&self, state : usize, event : usize) -> usize {
+        self.table[state][event]
+    }
+
+    /// Returns the name of the state at index `state`.
+    /// 
+    pub const fn tag(&self, state : usize) -> &'static str {
+        self.tags[state]
+    }
+
+    /// Returns `true` if the state at index `state` is final.
+    /// 
+    pub const fn is_final(&self, state : usize) -> bool {
+        self.finals[state]
+    }
+}
+
+/// Represents the `Family` of all `MatrixState`s with `S` states and `E` events. The `Input` is the index of an event.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct MatrixFamily<const S : usize, const E : usize> {
+    _marker : PhantomData<()>,
+}
+
+impl<const S : usize, const E : usize> Family for MatrixFamily<S, E> {
+    type Base = MatrixState<S, E>;
+    type Mode = MatrixState<S, E>;
+    type Input = usize;
+    type Output = MatrixState<S, E>;
+}
+
+/// Tracks the current state within a `&'static Matrix`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Copy)]
+pub struct MatrixState<const S : usize, const E : usize> {
+    matrix : &'static Matrix<S, E>,
+    current : usize,
+}
+
+impl<const S : usize, const E : usize> MatrixState<S, E> {
+    /// Creates a new `MatrixState` over `matrix`, whose current state is the state at index `initial`.
+    /// 
+    /// # Panics
+    /// Panics if `initial` is not less than `S`.
+    /// 
+    pub const fn new(matrix : &'static Matrix<S, E>, initial : usize) -> Self {
+        assert!(initial < S, "Cannot create MatrixState because the initial state does not exist!");
+        Self { matrix, current : initial }
+    }
+
+    /// Returns the `Matrix` that this `MatrixState` is using.
+    /// 
+    pub const fn matrix(&self) -> &'static Matrix<S, E> {
+        self.matrix
+    }
+
+    /// Returns the index of the current state.
+    /// 
+    pub const fn index(&self) -> usize {
+        self.current
+    }
+
+    /// Moves to the state that the `Matrix` says should follow the current state once `event` arrives. This does not
+    /// go through an `Automaton`, and is therefore suitable for tight inner loops.
+    /// 
+    /// # Panics
+    /// Panics if `event` is not less than `E`.
+    /// 
+    #[inline]
+    pub fn step(&mut self, event : usize) {
+        self.current = self.matrix.next(self.current, event);
+    }
+}
+
+impl<const S : usize, const E : usize> fmt::Debug for MatrixState<S, E> {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("MatrixState")
+            .field("current", &self.matrix.tag(self.current))
+            .finish()
+    }
+}
+
+impl<const S : usize, const E : usize> Mode for MatrixState<S, E> {
+    type Family = MatrixFamily<S, E>;
+
+    #[inline]
+    fn swap(mut self, event : usize) -> Self {
+        self.step(event);
+        self
+    }
+
+    fn tag(&self) -> &'static str {
+        self.matrix.tag(self.current)
+    }
+
+    fn is_final(&self) -> bool {
+        self.matrix.is_final(self.current)
+    }
+}
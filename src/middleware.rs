@@ -0,0 +1,174 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::Family;
+use std::fmt;
+
+/// The decision made by a `Middleware` before the current `Mode` of an `Automaton` is allowed to `swap()`.
+/// 
+/// The `R` parameter is the type of reason given when a swap is vetoed, which is returned to the caller of
+/// [`Automaton::next_with_pipeline()`](struct.Automaton.html#method.next_with_pipeline).
+/// 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub enum Verdict<R = ()> {
+    /// The next `Middleware` in the `Pipeline` is consulted, and, if every `Middleware` agrees, `swap()` is called.
+    Proceed,
+
+    /// `swap()` is not called, and no further `Middleware` in the `Pipeline` is consulted. The reason is returned to
+    /// the caller.
+    Veto(R),
+}
+
+/// Trait for interceptors that wrap each swap of an `Automaton`, as part of a
+/// [`Pipeline`](struct.Pipeline.html). The `R` parameter is the type of reason given when a swap is vetoed.
+/// 
+/// Before the current `Mode` is allowed to `swap()`, [`before_swap()`](#method.before_swap) is called on every
+/// `Middleware` in the `Pipeline`, in order. Each `Middleware` can observe the current `Mode`, modify the `input` that
+/// will be passed into `swap()`, or veto the swap altogether with a reason of type `R`. A vetoed swap is guaranteed to
+/// leave the current `Mode` untouched, since every `Middleware` is consulted before the current `Mode` is moved into
+/// `swap()`.
+/// Once `swap()` has been called, [`after_swap()`](#method.after_swap) is called on every `Middleware` in reverse
+/// order, so that the first `Middleware` in the `Pipeline` wraps all of the others.
+/// 
+/// See [`Automaton::next_with_pipeline()`](struct.Automaton.html#method.next_with_pipeline) for more details.
+/// 
+pub trait Middleware<F, R = ()>
+    where F : Family + ?Sized
+{
+    /// Called before the current `Mode` is allowed to `swap()`, with the `input` that will be passed into `swap()`.
+    /// 
+    /// The default implementation does nothing and returns `Verdict::Proceed`.
+    /// 
+    fn before_swap(&mut self, _current : &F::Base, _input : &mut F::Input) -> Verdict<R> {
+        Verdict::Proceed
+    }
+
+    /// Called after `swap()` has been called, with the `tag()` of the `Mode` that was current before the swap and the
+    /// `Mode` that is current now. This is called even if the `Mode` did not transition.
+    /// 
+    /// The default implementation does nothing.
+    /// 
+    fn after_swap(&mut self, _from : &'static str, _current : &F::Base) { }
+
+    /// Same as [`after_swap()`](#method.after_swap), except that the `tag()` of the `Mode` that is current now is
+    /// passed in as well, as `to`. This is what the `Pipeline` actually calls, so a `Middleware` that needs to know
+    /// the `tag()` of the current `Mode` can override this instead of `after_swap()`, even when `F::Base` is a
+    /// `dyn Trait`.
+    /// 
+    /// The default implementation calls `after_swap()`.
+    /// 
+    fn after_swap_with_tags(&mut self, from : &'static str, _to : &'static str, current : &F::Base) {
+        self.after_swap(from, current)
+    }
+}
+
+struct BeforeFn<G>(G);
+
+impl<F, R, G> Middleware<F, R> for BeforeFn<G>
+    where
+        F : Family + ?Sized,
+        G : FnMut(&F::Base, &mut F::Input) -> Verdict<R>,
+{
+    fn before_swap(&mut self, current : &F::Base, input : &mut F::Input) -> Verdict<R> {
+        (self.0)(current, input)
+    }
+}
+
+/// An ordered chain of `Middleware` that wraps each swap of an `Automaton`.
+/// 
+/// See [`Automaton::next_with_pipeline()`](struct.Automaton.html#method.next_with_pipeline) for more details.
+/// 
+pub struct Pipeline<F, R = ()>
+    where F : Family + ?Sized
+{
+    layers : Vec<Box<dyn Middleware<F, R>>>,
+}
+
+impl<F, R> Default for Pipeline<F, R>
+    where F : Family + ?Sized
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F, R> Pipeline<F, R>
+    where F : Family + ?Sized
+{
+    /// Creates a new, empty `Pipeline`.
+    /// 
+    pub fn new() -> Self {
+        Self { layers : Vec::new() }
+    }
+
+    /// Adds `layer` to the end of the `Pipeline`, so that it is consulted after every `Middleware` that was added
+    /// before it.
+    /// 
+    pub fn push(&mut self, layer : impl Middleware<F, R> + 'static) {
+        self.layers.push(Box::new(layer));
+    }
+
+    /// Same as [`push()`](#method.push), except that it consumes and returns `self`, so that calls can be chained
+    /// together.
+    /// 
+    pub fn with_layer(mut self, layer : impl Middleware<F, R> + 'static) -> Self {
+        self.push(layer);
+        self
+    }
+
+    /// Same as [`with_layer()`](#method.with_layer), except that the `Middleware` only implements `before_swap()`,
+    /// via the closure `before`.
+    /// 
+    pub fn with_fn<G>(self, before : G) -> Self
+        where
+            G : FnMut(&F::Base, &mut F::Input) -> Verdict<R> + 'static,
+            R : 'static,
+    {
+        self.with_layer(BeforeFn(before))
+    }
+
+    /// Returns the number of `Middleware` in the `Pipeline`.
+    /// 
+    pub fn len(&self) -> usize {
+        self.layers.len()
+    }
+
+    /// Returns `true` if the `Pipeline` contains no `Middleware`.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.layers.is_empty()
+    }
+
+    /// Calls `before_swap()` on every `Middleware` in order, returning the reason given by the first one that vetoes
+    /// the swap, if any.
+    /// 
+    pub(crate) fn before_swap(&mut self, current : &F::Base, input : &mut F::Input) -> Result<(), R> {
+        for layer in &mut self.layers {
+            if let Verdict::Veto(reason) = layer.before_swap(current, input) {
+                return Err(reason);
+            }
+        }
+        Ok(())
+    }
+
+    /// Calls `after_swap_with_tags()` on every `Middleware` in reverse order.
+    /// 
+    pub(crate) fn after_swap(&mut self, from : &'static str, to : &'static str, current : &F::Base) {
+        for layer in self.layers.iter_mut().rev() {
+            layer.after_swap_with_tags(from, to, current);
+        }
+    }
+}
+
+impl<F, R> fmt::Debug for Pipeline<F, R>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Pipeline")
+            .field("len", &self.layers.len())
+            .finish()
+    }
+}
@@ -4,7 +4,7 @@
 // MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
 // modified, or distributed except according to those terms.
 
-use crate::Family;
+use crate::{Family, Field};
 
 /// Trait that defines the transition behavior of a state within an `Automaton`.
 /// 
@@ -182,123 +182,492 @@ pub trait Mode {
     /// [`Automaton::next_with_output()`](struct.Automaton.html#method.next_with_output) for more details.
     /// 
     fn swap(self, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output;
-}
 
-/// Defines types that can be used to set up an `Automaton` that stores a `Box<Mode>` instead of a `Mode` in place.
-/// 
-pub mod boxed {
-    use crate::Family;
+    /// Returns a short name identifying the state represented by this `Mode`. The `Automaton` compares the `tag()` of
+    /// the current `Mode` before and after each call to `swap()` in order to detect when a transition has taken place,
+    /// e.g. in order to publish a [`TransitionEvent`](struct.TransitionEvent.html).
+    /// 
+    /// By default, this returns the name of the `Self` type. For a `Family` where every state is represented by a
+    /// single concrete type, e.g. an `enum`, this function should be overridden to return a distinct name for each
+    /// state, since otherwise no transitions will ever be detected.
+    /// 
+    /// **NOTE:** Since transitions are detected by comparing tags, swapping in a new `Mode` with the same `tag()` as
+    /// the current `Mode` is **not** considered to be a transition.
+    /// 
+    fn tag(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Called by the `Automaton` whenever this `Mode` becomes current, i.e. when the `Automaton` is started, and
+    /// immediately after any transition to a `Mode` with a different [`tag()`](#method.tag). This can be used to
+    /// perform any setup that should happen when a state begins. The default implementation does nothing.
+    /// 
+    /// See [`Automaton::start()`](struct.Automaton.html#method.start) for more details.
+    /// 
+    fn enter(&mut self) { }
+
+    /// Called by the `Automaton` on the current `Mode` immediately before tearing it down **without** giving it a chance
+    /// to `swap()`, e.g. when `Automaton::reset()` is called. This can be used to release resources or notify other
+    /// systems that the state is ending. The default implementation does nothing.
+    /// 
+    /// **NOTE:** This function is **not** called when the current `Mode` transitions out by returning another `Mode`
+    /// from `swap()`. Since `swap()` consumes the current `Mode`, any necessary cleanup can be done there instead.
+    /// 
+    fn exit(&mut self) { }
+
+    /// Returns `true` if this `Mode` is a terminal state. As soon as a `Mode` that returns `true` from this function
+    /// becomes current, the `Automaton` finishes, calling [`exit()`](#method.exit) on it and refusing to let it
+    /// `swap()` from then on. The final `Mode` can still be borrowed, e.g. in order to harvest its result. The default
+    /// implementation always returns `false`.
+    /// 
+    /// See [`Automaton::is_finished()`](struct.Automaton.html#method.is_finished) for more details.
+    /// 
+    fn is_final(&self) -> bool { false }
+
+    /// Returns a set of structured fields describing the state represented by this `Mode`, e.g. `hours_worked=4`. The
+    /// `Automaton` collects the `fields()` of every `Mode` that becomes current as the result of a transition, and
+    /// attaches them to the [`TransitionEvent`](struct.TransitionEvent.html) that is published to subscribers, as well
+    /// as to the [`Record`](enum.Record.html) of the transition passed to an [`EventLog`](trait.EventLog.html). This
+    /// allows logging and tracing integrations to report more than just the `tag()` of each state. The default
+    /// implementation returns no fields.
+    /// 
+    /// **NOTE:** This function is only called when a transition is actually being reported somewhere, so it is fine
+    /// for it to allocate.
+    /// 
+    fn fields(&self) -> Vec<Field> { Vec::new() }
+
+    /// Called by the `Automaton` with its [`path`](struct.Automaton.html#method.path) whenever this `Mode` becomes
+    /// current as the result of a transition, and whenever the path of the `Automaton` changes, as long as the
+    /// `Automaton` has been named. A `Mode` that owns nested `Automaton`s, e.g. a
+    /// [`Sequence`](modes/struct.Sequence.html), should pass `path` on to each of them via
+    /// [`Automaton::set_parent()`](struct.Automaton.html#method.set_parent), so that the path of each nested
+    /// `Automaton` includes the path of its parent, e.g. `player/weapon/reload`. The default implementation does
+    /// nothing.
+    /// 
+    fn set_path(&mut self, _path : &str) { }
 
-    /// Alternate `trait Mode` that takes a `Box<Mode>` as the `self` parameter instead of `Mode`.
+    /// Returns the number of bytes of heap memory owned by this `Mode`, e.g. the capacity of a `Vec` that it stores,
+    /// not including the size of the `Mode` itself. This is used by
+    /// [`Automaton::footprint()`](struct.Automaton.html#method.footprint) in order to report how much memory the
+    /// current `Mode` uses. The default implementation returns `0`.
     /// 
-    /// For more on how to use this `trait`, see `mode::Mode`.
+    /// The pointer types used by `dyn Trait` families, e.g. `Box<T>`, already report the size of the value that they
+    /// point to, in addition to whatever that value reports.
     /// 
-    pub trait Mode {
-        /// The `Family` type to which this `Mode` implementation belongs. In order to use the `boxed::Mode` trait, this
-        /// `Family` should be a `Box<T>` where `T : boxed::Mode`.
+    fn heap_size(&self) -> usize { 0 }
+}
+
+/// Generates a module containing an alternate `trait Mode` whose `swap()` function takes `self` wrapped in the pointer
+/// type `$pointer`, along with an alternate `trait FinalMode`, and implements `PointerMode<$pointer<T>>` and
+/// `PointerFinalMode<$pointer<T>>` for every `T` that implements them. The one `Mode` implementation for pointers is
+/// written generically over [`ModePointer`](trait.ModePointer.html), so this macro only exists because stable Rust
+/// requires a separate trait for each pointer type that is used as a `self` parameter.
+/// 
+/// The `$access` of the pointer is either `unique`, in which case `enter()`, `exit()`, and `set_path()` take
+/// `&mut self`, or `shared`, in which case they take `&self`, since the contents of the pointer may be shared.
+/// 
+macro_rules! pointer_mode {
+    (@hooks unique, $pointer:ident) => {
+        /// Called by the `Automaton` whenever this `Mode` becomes current.
         /// 
-        /// See `mode::Mode` for more details.
+        /// See `mode::Mode::enter()` for more details.
         /// 
-        type Family : Family + ?Sized;
+        fn enter(&mut self) { }
 
-        /// Will be called on the current `Mode` by `Automaton::next()` or `Automaton::next_with_output()` in order to
-        /// determine whether it wants the `Automaton` to transition to another `Mode`. Note that this `trait`'s
-        /// `swap()` function takes a `Box<Self>` instead of just `self`.
+        /// Called by the `Automaton` on the current `Mode` immediately before tearing it down without giving it a
+        /// chance to `swap()`.
         /// 
-        /// See `mode::Mode` for more details.
+        /// See `mode::Mode::exit()` for more details.
         /// 
-        fn swap(self : Box<Self>, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output;
-    }
+        fn exit(&mut self) { }
+    };
+    (@hooks shared, $pointer:ident) => {
+        #[doc = concat!(
+            "Called by the `Automaton` whenever this `Mode` becomes current. Note that this takes `&self` instead of ",
+            "`&mut self`, since the contents of ",
+            pointer_mode!(@article $pointer),
+            "` may be shared.\n\nSee `mode::Mode::enter()` for more details.")]
+        fn enter(&self) { }
 
-    impl<T, F> crate::Mode for Box<T>
-        where
-            F : Family + ?Sized,
-            T : self::Mode<Family = F> + ?Sized,
-    {
-        type Family = F;
+        #[doc = concat!(
+            "Called by the `Automaton` on the current `Mode` immediately before tearing it down without giving it a ",
+            "chance to `swap()`. Note that this takes `&self` instead of `&mut self`, since the contents of ",
+            pointer_mode!(@article $pointer),
+            "` may be shared.\n\nSee `mode::Mode::exit()` for more details.")]
+        fn exit(&self) { }
+    };
+    (@set_path unique) => {
+        /// Called by the `Automaton` with its path whenever this `Mode` becomes current.
+        /// 
+        /// See `mode::Mode::set_path()` for more details.
+        /// 
+        fn set_path(&mut self, _path : &str) { }
+    };
+    (@set_path shared) => {
+        /// Called by the `Automaton` with its path whenever this `Mode` becomes current. Note that this takes `&self`
+        /// instead of `&mut self`, since the contents of the pointer may be shared.
+        /// 
+        /// See `mode::Mode::set_path()` for more details.
+        /// 
+        fn set_path(&self, _path : &str) { }
+    };
+    (@forward unique, $pointer:ident) => {
+        fn enter(this : &mut $pointer<T>) {
+            self::Mode::enter(&mut **this)
+        }
 
-        fn swap(self, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
-            self.swap(input)
+        fn exit(this : &mut $pointer<T>) {
+            self::Mode::exit(&mut **this)
         }
-    }
+
+        fn set_path(this : &mut $pointer<T>, path : &str) {
+            self::Mode::set_path(&mut **this, path)
+        }
+    };
+    (@forward shared, $pointer:ident) => {
+        fn enter(this : &mut $pointer<T>) {
+            self::Mode::enter(&**this)
+        }
+
+        fn exit(this : &mut $pointer<T>) {
+            self::Mode::exit(&**this)
+        }
+
+        fn set_path(this : &mut $pointer<T>, path : &str) {
+            self::Mode::set_path(&**this, path)
+        }
+    };
+    (@article Box) => { "a `Box" };
+    (@article $pointer:ident) => { concat!("an `", stringify!($pointer)) };
+    (
+        $(#[$attribute:meta])*
+        pub mod $module:ident for $pointer:ident, $access:ident;
+        $($item:item)*
+    ) => {
+        #[doc = concat!(
+            "Defines types that can be used to set up an `Automaton` that stores ",
+            pointer_mode!(@article $pointer),
+            "<Mode>` instead of a `Mode` in place.")]
+        $(#[$attribute])*
+        pub mod $module {
+            use crate::{Family, Field};
+
+            $($item)*
+
+            #[doc = concat!(
+                "Alternate `trait Mode` that takes ",
+                pointer_mode!(@article $pointer),
+                "<Mode>` as the `self` parameter instead of `Mode`.\n\n",
+                "For more on how to use this `trait`, see `mode::Mode`.")]
+            pub trait Mode {
+                #[doc = concat!(
+                    "The `Family` type to which this `Mode` implementation belongs. In order to use the `",
+                    stringify!($module),
+                    "::Mode` trait, this `Family` should be ",
+                    pointer_mode!(@article $pointer),
+                    "<T>` where `T : ",
+                    stringify!($module),
+                    "::Mode`.\n\nSee `mode::Mode` for more details.")]
+                type Family : Family + ?Sized;
+
+                #[doc = concat!(
+                    "Will be called on the current `Mode` by `Automaton::next()` or `Automaton::next_with_output()` ",
+                    "in order to determine whether it wants the `Automaton` to transition to another `Mode`. Note ",
+                    "that this `trait`'s `swap()` function takes ",
+                    pointer_mode!(@article $pointer),
+                    "<Self>` instead of just `self`.\n\nSee `mode::Mode` for more details.")]
+                fn swap(self : $pointer<Self>, input : <Self::Family as Family>::Input)
+                    -> <Self::Family as Family>::Output;
+
+                #[doc = concat!(
+                    "Returns a short name identifying the state represented by this `Mode`. By default, this returns ",
+                    "the name of the concrete `Self` type, **not** the name of the `",
+                    stringify!($pointer),
+                    "` wrapping it.\n\nSee `mode::Mode::tag()` for more details.")]
+                fn tag(&self) -> &'static str {
+                    std::any::type_name::<Self>()
+                }
+
+                pointer_mode!(@hooks $access, $pointer);
+
+                /// Returns `true` if this `Mode` is a terminal state.
+                /// 
+                /// See `mode::Mode::is_final()` for more details.
+                /// 
+                fn is_final(&self) -> bool { false }
+
+                /// Returns a set of structured fields describing the state represented by this `Mode`.
+                /// 
+                /// See `mode::Mode::fields()` for more details.
+                /// 
+                fn fields(&self) -> Vec<Field> { Vec::new() }
+
+                pointer_mode!(@set_path $access);
+
+                #[doc = concat!(
+                    "Returns the number of bytes of heap memory owned by this `Mode`, not including the size of the ",
+                    "`Mode` itself, which is already reported by the `",
+                    stringify!($pointer),
+                    "` wrapping it.\n\nSee `mode::Mode::heap_size()` for more details.")]
+                fn heap_size(&self) -> usize { 0 }
+            }
+
+            #[doc = concat!(
+                "Alternate `trait FinalMode` that takes ",
+                pointer_mode!(@article $pointer),
+                "<Mode>` as the `self` parameter instead of `Mode`. This can be added as a supertrait of the `Base` ",
+                "type of a `Family`, so that the value computed by the state machine can be extracted without ",
+                "downcasting.\n\nFor more on how to use this `trait`, see `mode::FinalMode`.")]
+            pub trait FinalMode {
+                /// The type of value computed by the state machine once it reaches a terminal state.
+                /// 
+                /// See `mode::FinalMode` for more details.
+                /// 
+                type Output;
+
+                #[doc = concat!(
+                    "Consumes the final `Mode` of an `Automaton`, returning its `Output`. This **must** return `Some` ",
+                    "for every `Mode` that is a terminal state. Note that this `trait`'s `into_output()` function ",
+                    "takes ",
+                    pointer_mode!(@article $pointer),
+                    "<Self>` instead of just `self`.\n\nSee `mode::FinalMode` for more details.")]
+                fn into_output(self : $pointer<Self>) -> Option<Self::Output>;
+            }
+
+            impl<T> crate::PointerFinalMode<$pointer<T>> for T
+                where T : self::FinalMode + ?Sized
+            {
+                type Output = T::Output;
+
+                fn into_output(this : $pointer<T>) -> Option<Self::Output> {
+                    self::FinalMode::into_output(this)
+                }
+            }
+
+            impl<T> crate::PointerMode<$pointer<T>> for T
+                where T : self::Mode + ?Sized
+            {
+                type Family = T::Family;
+
+                fn swap(this : $pointer<T>, input : <Self::Family as Family>::Input)
+                    -> <Self::Family as Family>::Output
+                {
+                    self::Mode::swap(this, input)
+                }
+
+                fn tag(&self) -> &'static str {
+                    self::Mode::tag(self)
+                }
+
+                fn is_final(&self) -> bool {
+                    self::Mode::is_final(self)
+                }
+
+                fn fields(&self) -> Vec<Field> {
+                    self::Mode::fields(self)
+                }
+
+                fn heap_size(&self) -> usize {
+                    self::Mode::heap_size(self)
+                }
+
+                pointer_mode!(@forward $access, $pointer);
+            }
+        }
+    };
 }
 
-/// Defines types that can be used to set up an `Automaton` that stores an `Rc<Mode>` instead of a `Mode` in place.
-/// 
-pub mod rc {
-    use crate::Family;
-    use std::rc::Rc;
+pointer_mode! {
+    pub mod boxed for Box, unique;
 
-    /// Alternate `trait Mode` that takes an `Rc<Mode>` as the `self` parameter instead of `Mode`.
+    use std::any::Any;
+
+    /// Helper `trait` that allows a boxed `Mode` to be inspected and downcast to its concrete type, even when it is
+    /// stored as a `Box<dyn Trait>`. This is implemented automatically for every `'static` type, so all that is
+    /// necessary to make a `Family` downcastable is to add `AsAny` as a supertrait of its `Base` type. (See example
+    /// below.)
     /// 
-    /// For more on how to use this `trait`, see `mode::Mode`.
+    /// See [`Automaton::try_into_mode()`](../struct.Automaton.html#method.try_into_mode) for more details.
     /// 
-    pub trait Mode {
-        /// The `Family` type to which this `Mode` implementation belongs. In order to use the `rc::Mode` trait, this
-        /// `Family` should be an `Rc<T>` where `T : rc::Mode`.
-        /// 
-        /// See `mode::Mode` for more details.
+    /// # Usage
+    /// ```
+    /// use mode::boxed;
+    /// # use mode::Family;
+    /// #
+    /// # struct SomeFamily;
+    /// # impl Family for SomeFamily {
+    /// #     type Base = dyn SomeTrait;
+    /// #     type Mode = Box<dyn SomeTrait>;
+    /// #     type Input = ();
+    /// #     type Output = Box<dyn SomeTrait>;
+    /// # }
+    /// 
+    /// trait SomeTrait : boxed::Mode<Family = SomeFamily> + boxed::AsAny {
+    ///     // ...
+    /// }
+    /// ```
+    /// 
+    pub trait AsAny : Any {
+        /// Returns a reference to `self` as a `&dyn Any`.
         /// 
-        type Family : Family + ?Sized;
+        fn as_any(&self) -> &dyn Any;
 
-        /// Will be called on the current `Mode` by `Automaton::next()` or `Automaton::next_with_output()` in order to
-        /// determine whether it wants the `Automaton` to transition to another `Mode`. Note that this `trait`'s
-        /// `swap()` function takes an `Rc<Self>` instead of just `self`.
-        /// 
-        /// See `mode::Mode` for more details.
+        /// Converts a `Box<Self>` into a `Box<dyn Any>`, which can then be downcast to the concrete type.
         /// 
-        fn swap(self : Rc<Self>, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output;
+        fn into_any(self : Box<Self>) -> Box<dyn Any>;
     }
 
-    impl<T, F> crate::Mode for Rc<T>
-        where
-            F : Family + ?Sized,
-            T : self::Mode<Family = F> + ?Sized,
+    impl<T> AsAny for T
+        where T : Any
     {
-        type Family = F;
+        fn as_any(&self) -> &dyn Any {
+            self
+        }
 
-        fn swap(self, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
-            self.swap(input)
+        fn into_any(self : Box<Self>) -> Box<dyn Any> {
+            self
         }
     }
 }
 
-/// Defines types that can be used to set up an `Automaton` that stores an `Arc<Mode>` instead of a `Mode` in place.
-/// 
-pub mod sync {
-    use crate::Family;
-    use std::sync::Arc;
+pointer_mode! {
+    pub mod rc for Rc, shared;
+
+    use std::{collections::HashSet, hash::Hash, rc::Rc};
 
-    /// Alternate `trait Mode` that takes an `Arc<Mode>` as the `self` parameter instead of `Mode`.
+    /// Returns the contents of `this` by value, for use inside of `rc::Mode::swap()`. If no other `Rc` shares the
+    /// same `Mode` instance, it is moved out without copying it. Otherwise, it is cloned, leaving the shared instance
+    /// untouched for every other `Automaton` that uses it.
+    /// 
+    /// See [`Flyweights`](struct.Flyweights.html) for an example.
+    /// 
+    pub fn into_owned<T>(this : Rc<T>) -> T
+        where T : Clone
+    {
+        crate::into_owned(this)
+    }
+
+    /// A cache of immutable `Mode` instances that can be shared between many `Automaton`s whose `Family::Mode` is an
+    /// `Rc`, so that thousands of agents that are in equal states only keep a single instance of that state in
+    /// memory, i.e. the flyweight pattern.
+    /// 
+    /// [`get()`](#method.get) returns an `Rc` pointing to the cached instance that is equal to the `Mode` passed in,
+    /// adding that `Mode` to the cache if necessary. Since an `rc::Mode` receives an `Rc<Self>` in `swap()`, a shared
+    /// instance can transition without affecting any of the other `Automaton`s that share it, either by returning
+    /// another shared instance, or by taking ownership of its own state via [`into_owned()`](fn.into_owned.html),
+    /// which only copies the `Mode` if it is still shared.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{rc::{self, Flyweights}, Automaton, Family};
+    /// use std::rc::Rc;
     /// 
-    /// For more on how to use this `trait`, see `mode::Mode`.
+    /// struct AgentFamily;
+    /// impl Family for AgentFamily {
+    ///     type Base = dyn Agent;
+    ///     type Mode = Rc<dyn Agent>;
+    ///     type Input = ();
+    ///     type Output = Rc<dyn Agent>;
+    /// }
     /// 
-    pub trait Mode {
-        /// The `Family` type to which this `Mode` implementation belongs. In order to use the `sync::Mode` trait, this
-        /// `Family` should be an `Arc<T>` where `T : sync::Mode`.
+    /// trait Agent : rc::Mode<Family = AgentFamily> {
+    ///     fn distance(&self) -> u32;
+    /// }
+    /// 
+    /// #[derive(Clone, Eq, Hash, PartialEq)]
+    /// struct Idle;
+    /// impl Agent for Idle { fn distance(&self) -> u32 { 0 } }
+    /// impl rc::Mode for Idle {
+    ///     type Family = AgentFamily;
+    ///     fn swap(self : Rc<Self>, _input : ()) -> Rc<dyn Agent> { Rc::new(Walking { distance : 0 }) }
+    /// }
+    /// 
+    /// #[derive(Clone, Eq, Hash, PartialEq)]
+    /// struct Walking { distance : u32 }
+    /// impl Agent for Walking { fn distance(&self) -> u32 { self.distance } }
+    /// impl rc::Mode for Walking {
+    ///     type Family = AgentFamily;
+    ///     fn swap(self : Rc<Self>, _input : ()) -> Rc<dyn Agent> {
+    ///         let mut walking = rc::into_owned(self);
+    ///         walking.distance += 1;
+    ///         Rc::new(walking)
+    ///     }
+    /// }
+    /// 
+    /// // Every agent starts out sharing the same Idle instance.
+    /// let mut idle = Flyweights::new();
+    /// let mut agents : Vec<_> =
+    ///     (0..1000)
+    ///         .map(|_| AgentFamily::automaton_with_mode(idle.get(Idle)))
+    ///         .collect();
+    /// assert_eq!(idle.len(), 1);
+    /// 
+    /// // Stepping one agent leaves the shared instance untouched for all of the others.
+    /// Automaton::next(&mut agents[0]);
+    /// Automaton::next(&mut agents[0]);
+    /// assert_eq!(agents[0].distance(), 1);
+    /// assert!(agents[1..].iter().all(|agent| agent.distance() == 0));
+    /// ```
+    /// 
+    #[derive(Debug)]
+    pub struct Flyweights<T>
+        where T : Eq + Hash
+    {
+        instances : HashSet<Rc<T>>,
+    }
+
+    impl<T> Flyweights<T>
+        where T : Eq + Hash
+    {
+        /// Creates a new `Flyweights` cache with no instances.
         /// 
-        /// See `mode::Mode` for more details.
+        pub fn new() -> Self {
+            Self { instances : HashSet::new() }
+        }
+
+        /// Returns a shared `Rc` pointing to the cached instance that is equal to `mode`, adding `mode` to the cache
+        /// first if there is no such instance.
         /// 
-        type Family : Family + ?Sized;
+        pub fn get(&mut self, mode : T) -> Rc<T> {
+            if let Some(instance) = self.instances.get(&mode) {
+                return Rc::clone(instance);
+            }
+
+            let instance = Rc::new(mode);
+            self.instances.insert(Rc::clone(&instance));
+            instance
+        }
 
-        /// Will be called on the current `Mode` by `Automaton::next()` or `Automaton::next_with_output()` in order to
-        /// determine whether it wants the `Automaton` to transition to another `Mode`. Note that this `trait`'s
-        /// `swap()` function takes an `Arc<Self>` instead of just `self`.
+        /// Returns the number of distinct instances in the cache.
         /// 
-        /// See `mode::Mode` for more details.
+        pub fn len(&self) -> usize {
+            self.instances.len()
+        }
+
+        /// Returns `true` if there are no instances in the cache.
+        /// 
+        pub fn is_empty(&self) -> bool {
+            self.instances.is_empty()
+        }
+
+        /// Removes every instance from the cache that is no longer shared with any `Automaton`.
         /// 
-        fn swap(self : Arc<Self>, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output;
+        pub fn purge(&mut self) {
+            self.instances.retain(|instance| Rc::strong_count(instance) > 1);
+        }
     }
 
-    impl<T, F> crate::Mode for Arc<T>
-        where
-            F : Family + ?Sized,
-            T : self::Mode<Family = F> + ?Sized,
+    impl<T> Default for Flyweights<T>
+        where T : Eq + Hash
     {
-        type Family = F;
-
-        fn swap(self, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
-            self.swap(input)
+        fn default() -> Self {
+            Self::new()
         }
     }
-}
\ No newline at end of file
+}
+
+pointer_mode! {
+    pub mod sync for Arc, shared;
+
+    use std::sync::Arc;
+}
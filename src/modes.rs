@@ -0,0 +1,52 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines reusable `Mode` implementations for patterns that come up in many different state machines.
+//! 
+//! The types in this module are generic over the `Family` of the state machine they are used in, and are meant to be
+//! wrapped in one of the variants of a `Family::Mode` `enum`, e.g. one generated by
+//! [`enum_mode!`](../macro.enum_mode.html).
+//! 
+//! - [`Adapt`](struct.Adapt.html) runs a child state machine over a different `Family`, converting between the two via
+//!   an [`Adapter`](trait.Adapter.html).
+//! - [`All`](struct.All.html) runs several child state machines in parallel, completing once all of them have finished.
+//! - [`AsyncRoutine`](struct.AsyncRoutine.html) runs an `async` block that suspends between steps, so that long,
+//!   sequential behaviors can be written as straight-line code on stable Rust.
+//! - [`common`](common/index.html) provides generic `Idle`, `Delay`, `Immediate`, and `Failure` states.
+//! - [`Repeat`](struct.Repeat.html), [`RepeatUntil`](struct.RepeatUntil.html),
+//!   [`InvertOutcome`](struct.InvertOutcome.html), and [`LimitSteps`](struct.LimitSteps.html) wrap a child state
+//!   machine and alter when and how it completes, similar to the decorators of a behavior tree.
+//! - [`Race`](struct.Race.html) runs several child state machines in parallel, transitioning as soon as the first
+//!   one of them finishes.
+//! - [`Retrying`](struct.Retrying.html) repeats a fallible operation with exponential backoff and jitter until it
+//!   succeeds or runs out of attempts.
+//! - [`Routine`](struct.Routine.html) runs a coroutine that yields between steps, so that long, sequential behaviors
+//!   can be written as straight-line code. This is only available when the `nightly` feature is enabled.
+//! - [`Sequence`](struct.Sequence.html) runs a list of child state machines one after another, short-circuiting on
+//!   failure.
+//! 
+
+pub mod common;
+
+mod adapt;
+mod all;
+mod async_routine;
+mod decorators;
+mod race;
+mod retry;
+#[cfg(feature = "nightly")]
+mod routine;
+mod sequence;
+
+pub use self::adapt::*;
+pub use self::all::*;
+pub use self::async_routine::*;
+pub use self::decorators::*;
+pub use self::race::*;
+pub use self::retry::*;
+#[cfg(feature = "nightly")]
+pub use self::routine::*;
+pub use self::sequence::*;
@@ -0,0 +1,215 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Mode};
+use std::{
+    borrow::{Borrow, BorrowMut},
+    fmt,
+    marker::PhantomData,
+    ops::{Deref, DerefMut},
+};
+
+/// Trait for converting between the `Family` `F` of a parent state machine and the `Family` `C` of a child state
+/// machine that is run inside of it via [`Adapt`](struct.Adapt.html).
+/// 
+/// This allows a generic child state machine, e.g. one built from the other `Mode`s in this module, to be reused inside
+/// of parents with different `Input` and `Output` types, without having to write a new `Family` for each of them.
+/// 
+pub trait Adapter<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+{
+    /// Converts an `input` that was passed into the parent into the `Input` for the child.
+    /// 
+    fn input(&mut self, input : F::Input) -> C::Input;
+
+    /// Converts the `child` into the `Family::Output` of the parent, once the child has finished.
+    /// 
+    fn finish(self, child : Automaton<C>) -> F::Output;
+}
+
+/// A `Mode` that wraps an `Automaton` over some other `Family` `C`, and presents it as a `Mode` in the `Family` `F` by
+/// converting between the two via an [`Adapter`](trait.Adapter.html).
+/// 
+/// Every time the `Adapt` is swapped, the `input` is converted via `Adapter::input()` and passed into the child via
+/// [`Automaton::next_with_input()`](../struct.Automaton.html#method.next_with_input). Once the child has finished, the
+/// `Adapt` transitions to the `Family::Output` returned by `Adapter::finish()`. Until then, the `Adapt` dereferences
+/// to the `Family::Base` of the current `Mode` of the child, so that the parent can expose it through its own `Base`.
+/// 
+/// Like the other `Mode`s in this module, the `Family::Output` of `F` must implement `From<Adapt<F, C, A>>`, which is
+/// the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with an `Adapt<F, C, A>` variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::{Adapt, Adapter}, *};
+/// 
+/// // A generic countdown that is stepped with a number of ticks.
+/// struct CountdownFamily;
+/// impl Family for CountdownFamily {
+///     type Base = Countdown;
+///     type Mode = Countdown;
+///     type Input = u32;
+///     type Output = Countdown;
+/// }
+/// 
+/// struct Countdown { remaining : u32 }
+/// impl Mode for Countdown {
+///     type Family = CountdownFamily;
+///     fn swap(self, ticks : u32) -> Self { Countdown { remaining : self.remaining.saturating_sub(ticks) } }
+///     fn is_final(&self) -> bool { self.remaining == 0 }
+/// }
+/// 
+/// // A game that is stepped with the number of seconds since the last frame.
+/// struct GameFamily;
+/// impl Family for GameFamily {
+///     type Base = Game;
+///     type Mode = Game;
+///     type Input = f32;
+///     type Output = Game;
+/// }
+/// 
+/// struct Over;
+/// impl Mode for Over {
+///     type Family = GameFamily;
+///     fn swap(self, _seconds : f32) -> Game { self.into() }
+///     fn is_final(&self) -> bool { true }
+/// }
+/// 
+/// // Converts seconds into ticks at 10 ticks per second.
+/// struct Ticks;
+/// impl Adapter<GameFamily, CountdownFamily> for Ticks {
+///     fn input(&mut self, seconds : f32) -> u32 { (seconds * 10.0) as u32 }
+///     fn finish(self, _child : Automaton<CountdownFamily>) -> Game { Over.into() }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Game {
+///         Waiting(Adapt<GameFamily, CountdownFamily, Ticks>),
+///         Over(Over),
+///     }
+/// }
+/// 
+/// let countdown = CountdownFamily::automaton_with_mode(Countdown { remaining : 15 });
+/// let mut game = GameFamily::automaton_with_mode(Adapt::new(countdown, Ticks).into());
+/// 
+/// Automaton::next_with_input(&mut game, 1.0);
+/// match &*game { Game::Waiting(waiting) => assert_eq!(waiting.remaining, 5), _ => unreachable!() }
+/// 
+/// Automaton::next_with_input(&mut game, 1.0);
+/// assert!(matches!(*game, Game::Over(_)));
+/// ```
+/// 
+pub struct Adapt<F, C, A>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        A : Adapter<F, C>,
+{
+    child : Automaton<C>,
+    adapter : A,
+    family : PhantomData<fn() -> Box<F>>,
+}
+
+impl<F, C, A> Adapt<F, C, A>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        A : Adapter<F, C>,
+{
+    /// Creates a new `Adapt` that runs `child` to completion, converting to and from the parent `Family` via `adapter`.
+    /// 
+    pub fn new(child : Automaton<C>, adapter : A) -> Self {
+        Self { child, adapter, family : PhantomData }
+    }
+
+    /// Returns the child `Automaton`.
+    /// 
+    pub fn child(&self) -> &Automaton<C> {
+        &self.child
+    }
+
+    /// Returns the child `Automaton` mutably.
+    /// 
+    pub fn child_mut(&mut self) -> &mut Automaton<C> {
+        &mut self.child
+    }
+
+    /// Returns the `Adapter`.
+    /// 
+    pub fn adapter(&self) -> &A {
+        &self.adapter
+    }
+}
+
+impl<F, C, A> Deref for Adapt<F, C, A>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Borrow<C::Base>,
+        A : Adapter<F, C>,
+{
+    type Target = C::Base;
+
+    fn deref(&self) -> &C::Base {
+        self.child.borrow_mode()
+    }
+}
+
+impl<F, C, A> DerefMut for Adapt<F, C, A>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Borrow<C::Base> + BorrowMut<C::Base>,
+        A : Adapter<F, C>,
+{
+    fn deref_mut(&mut self) -> &mut C::Base {
+        self.child.borrow_mode_mut()
+    }
+}
+
+impl<F, C, A> fmt::Debug for Adapt<F, C, A>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        A : Adapter<F, C>,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Adapt")
+            .field("phase", &Automaton::phase(&self.child))
+            .field("tag", &Automaton::tag(&self.child))
+            .finish()
+    }
+}
+
+impl<F, C, A, M> Mode for Adapt<F, C, A>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Output = M> + ?Sized,
+        M : Mode<Family = C>,
+        A : Adapter<F, C>,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        let input = self.adapter.input(input);
+        Automaton::next_with_input(&mut self.child, input);
+        if Automaton::is_finished(&self.child) {
+            return self.adapter.finish(self.child);
+        }
+
+        self.into()
+    }
+
+    fn set_path(&mut self, path : &str) {
+        Automaton::set_parent(&mut self.child, Some(path));
+    }
+
+    fn heap_size(&self) -> usize {
+        Automaton::footprint(&self.child).total()
+    }
+}
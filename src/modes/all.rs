@@ -0,0 +1,241 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Finish, Mode, modes::{IntoResult, sequence::ChildOutcome}};
+use std::fmt;
+
+type CompleteFn<F, C> = dyn FnOnce(Vec<ChildOutcome<C>>) -> <F as Family>::Output + Send + Sync;
+
+/// A `Mode` that hosts several child state machines, each in its own `Automaton`, and steps all of them in parallel
+/// until every one of them has finished.
+/// 
+/// Every time the `All` is swapped, a clone of the `input` is passed into every child that has not finished yet via
+/// [`Automaton::next_with_input()`](../struct.Automaton.html#method.next_with_input). Unlike
+/// [`Sequence`](struct.Sequence.html), a child that fails does **not** cause the other children to be dropped. Once
+/// every child has finished, the `Outcome` of each child is harvested via
+/// [`Automaton::finish()`](../struct.Automaton.html#method.finish), and the `All` transitions to the `Family::Output`
+/// returned by the `on_complete` function, which receives the `Outcome`s in the order in which the children were added.
+/// The [`all_succeeded()`](fn.all_succeeded.html) helper can be used to aggregate these into a single `Result`. A child
+/// only counts as finished once a [final](../trait.Mode.html#method.is_final) `Mode` has become current, so a child
+/// that was [stopped](../struct.Automaton.html#method.stop) before that keeps the `All` from ever completing.
+/// 
+/// Since the `All` must be able to stay current, the `Family::Output` of `F` must implement `From<All<F, C>>`, which is
+/// the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with an `All<F, C>` variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::{all_succeeded, common::Failure, All}, *};
+/// 
+/// struct SubsystemFamily;
+/// impl Family for SubsystemFamily {
+///     type Base = Subsystem;
+///     type Mode = Subsystem;
+///     type Input = ();
+///     type Output = Subsystem;
+/// }
+/// 
+/// enum Subsystem { Booting { steps_left : u32 }, Ready, Broken }
+/// impl Mode for Subsystem {
+///     type Family = SubsystemFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Subsystem::Booting { steps_left : 0 } => Subsystem::Ready,
+///             Subsystem::Booting { steps_left } => Subsystem::Booting { steps_left : steps_left - 1 },
+///             other => other,
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self {
+///             Subsystem::Booting { .. } => "Booting",
+///             Subsystem::Ready => "Ready",
+///             Subsystem::Broken => "Broken",
+///         }
+///     }
+///     fn is_final(&self) -> bool { !matches!(self, Subsystem::Booting { .. }) }
+/// }
+/// 
+/// impl Finish for Subsystem {
+///     type Outcome = Result<(), &'static str>;
+///     fn finish(self) -> Self::Outcome {
+///         match self { Subsystem::Ready => Ok(()), _ => Err("subsystem broken") }
+///     }
+/// }
+/// 
+/// struct StartupFamily;
+/// impl Family for StartupFamily {
+///     type Base = Startup;
+///     type Mode = Startup;
+///     type Input = ();
+///     type Output = Startup;
+/// }
+/// 
+/// struct Running;
+/// impl Mode for Running {
+///     type Family = StartupFamily;
+///     fn swap(self, _input : ()) -> Startup { self.into() }
+///     fn is_final(&self) -> bool { true }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Startup {
+///         Starting(All<StartupFamily, SubsystemFamily>),
+///         Running(Running),
+///         Failed(Failure<StartupFamily, &'static str>),
+///     }
+/// }
+/// 
+/// let all =
+///     All::new(|outcomes| match all_succeeded(outcomes) {
+///         Ok(_) => Running.into(),
+///         Err(error) => Failure::new(error).into(),
+///     })
+///     .with_child(SubsystemFamily::automaton_with_mode(Subsystem::Booting { steps_left : 0 }))
+///     .with_child(SubsystemFamily::automaton_with_mode(Subsystem::Booting { steps_left : 2 }));
+/// 
+/// let mut startup = StartupFamily::automaton_with_mode(all.into());
+/// Automaton::next(&mut startup);
+/// assert!(matches!(&*startup, Startup::Starting(all) if all.remaining() == 1));
+/// 
+/// while !Automaton::is_finished(&startup) { Automaton::next(&mut startup); }
+/// assert!(matches!(*startup, Startup::Running(_)));
+/// 
+/// let all =
+///     All::new(|outcomes| match all_succeeded(outcomes) {
+///         Ok(_) => Running.into(),
+///         Err(error) => Failure::new(error).into(),
+///     })
+///     .with_child(SubsystemFamily::automaton_with_mode(Subsystem::Broken))
+///     .with_child(SubsystemFamily::automaton_with_mode(Subsystem::Booting { steps_left : 2 }));
+/// 
+/// let mut startup = StartupFamily::automaton_with_mode(all.into());
+/// while !Automaton::is_finished(&startup) { Automaton::next(&mut startup); }
+/// assert!(matches!(&*startup, Startup::Failed(failure) if *failure.error() == "subsystem broken"));
+/// ```
+/// 
+pub struct All<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    children : Vec<Automaton<C>>,
+    on_complete : Box<CompleteFn<F, C>>,
+}
+
+impl<F, C> All<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    /// Creates a new `All` with no children. Once every child has finished, the `All` transitions to the
+    /// `Family::Output` returned by `on_complete`.
+    /// 
+    pub fn new<G>(on_complete : G) -> Self
+        where G : FnOnce(Vec<ChildOutcome<C>>) -> F::Output + Send + Sync + 'static
+    {
+        Self { children : Vec::new(), on_complete : Box::new(on_complete) }
+    }
+
+    /// Adds `child` to the set of state machines that must finish before the `All` completes.
+    /// 
+    pub fn add_child(&mut self, child : Automaton<C>) {
+        self.children.push(child);
+    }
+
+    /// Same as [`add_child()`](#method.add_child), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_child(mut self, child : Automaton<C>) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    /// Returns the children of the `All`, in the order in which they were added.
+    /// 
+    pub fn children(&self) -> &[Automaton<C>] {
+        &self.children
+    }
+
+    /// Returns the number of children that have not reached a final `Mode` yet.
+    /// 
+    pub fn remaining(&self) -> usize {
+        self.children.iter()
+            .filter(|child| !Automaton::is_complete(child))
+            .count()
+    }
+}
+
+impl<F, C> fmt::Debug for All<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("All")
+            .field("children", &self.children.len())
+            .field("remaining", &self.remaining())
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for All<F, C>
+    where
+        F : Family + ?Sized,
+        F::Input : Clone,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+{
+    type Family = F;
+
+    fn swap(self, input : F::Input) -> F::Output {
+        let mut remaining = 0;
+        let mut children = self.children;
+        for child in children.iter_mut().filter(|child| !Automaton::is_complete(child)) {
+            Automaton::next_with_input(child, input.clone());
+            if !Automaton::is_complete(child) {
+                remaining += 1;
+            }
+        }
+
+        if remaining > 0 {
+            return Self { children, on_complete : self.on_complete }.into();
+        }
+
+        let outcomes =
+            children.into_iter()
+                .map(|child| {
+                    Automaton::finish(child)
+                        .unwrap_or_else(|_| unreachable!("A complete Automaton can always be finished!"))
+                })
+                .collect();
+        (self.on_complete)(outcomes)
+    }
+
+    fn set_path(&mut self, path : &str) {
+        for child in &mut self.children {
+            Automaton::set_parent(child, Some(path));
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.children.iter().map(|child| Automaton::footprint(child).total()).sum()
+    }
+}
+
+/// Aggregates the `Outcome`s of several child state machines, e.g. those passed to the `on_complete` function of an
+/// [`All`](struct.All.html), into a single `Result`. This returns the values of all children if every one of them
+/// succeeded, or the error of the first child that failed otherwise.
+/// 
+pub fn all_succeeded<O>(outcomes : impl IntoIterator<Item = O>) -> Result<Vec<O::Value>, O::Error>
+    where O : IntoResult
+{
+    outcomes.into_iter()
+        .map(IntoResult::into_result)
+        .collect()
+}
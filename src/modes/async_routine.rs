@@ -0,0 +1,259 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode};
+use std::{
+    fmt,
+    future::Future,
+    mem,
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Waker},
+};
+
+type BodyFuture<F> = Pin<Box<dyn Future<Output = <F as Family>::Output> + Send + Sync>>;
+type StartFn<F> = dyn FnOnce(Yielder<F>, <F as Family>::Input) -> BodyFuture<F> + Send + Sync;
+
+enum Body<F>
+    where F : Family + ?Sized
+{
+    NotStarted(Box<StartFn<F>>),
+    Running(BodyFuture<F>),
+}
+
+struct Shared<Input> {
+    input : Option<Input>,
+    yielded : Option<&'static str>,
+}
+
+/// A `Mode` whose behavior is written as an `async` block that suspends between steps, so that long, sequential
+/// behaviors can be expressed as straight-line code on stable Rust.
+/// 
+/// This is the stable equivalent of the coroutine-backed `Routine`, which requires the `nightly` feature. Instead of
+/// yielding, the body of an `AsyncRoutine` awaits [`Yielder::suspend()`](struct.Yielder.html#method.suspend), which
+/// suspends the body until the next swap, and then resolves to the `input` passed into that swap.
+/// 
+/// The body is created by passing a `Yielder` and the `input` of the first swap into the `start` function, and is
+/// polled manually with a no-op waker every time the `AsyncRoutine` is swapped, without requiring an async runtime.
+/// Each time the body suspends, the `tag` passed into `suspend()` becomes the `tag()` that the `AsyncRoutine` reports
+/// until the next swap, which allows subscribers of the `Automaton` to observe the stages of the body as transitions.
+/// Once the body returns a `Family::Output`, the `AsyncRoutine` transitions to it. If the body awaits any other
+/// `Future` that is not ready yet, the `AsyncRoutine` stays current with the same `tag()`, and the `Future` is polled
+/// again on the next swap. The `input` of that swap is discarded.
+/// 
+/// Since the `AsyncRoutine` must be able to stay current, the `Family::Output` of `F` must implement
+/// `From<AsyncRoutine<F>>`, which is the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with
+/// an `AsyncRoutine<F>` variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::AsyncRoutine, *};
+/// 
+/// struct GuardFamily;
+/// impl Family for GuardFamily {
+///     type Base = GuardMode;
+///     type Mode = GuardMode;
+///     type Input = u32;
+///     type Output = GuardMode;
+/// }
+/// 
+/// struct Alarmed;
+/// impl Mode for Alarmed {
+///     type Family = GuardFamily;
+///     fn swap(self, _noise : u32) -> GuardMode { self.into() }
+///     fn tag(&self) -> &'static str { "Alarmed" }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum GuardMode {
+///         Patrol(AsyncRoutine<GuardFamily>),
+///         Alarmed(Alarmed),
+///     }
+/// }
+/// 
+/// let patrol =
+///     AsyncRoutine::new("Patrol", |yielder, mut noise| async move {
+///         loop {
+///             for stage in ["Patrol", "Wait", "Turn"] {
+///                 if noise > 5 { return Alarmed.into(); }
+///                 noise = yielder.suspend(stage).await;
+///             }
+///         }
+///     });
+/// 
+/// let mut guard = GuardFamily::automaton_with_mode(patrol.into());
+/// let mut tags = Vec::new();
+/// for noise in [0, 1, 0, 2, 9] {
+///     Automaton::next_with_input(&mut guard, noise);
+///     tags.push(Automaton::tag(&guard));
+/// }
+/// assert_eq!(tags, vec!["Patrol", "Wait", "Turn", "Patrol", "Alarmed"]);
+/// ```
+/// 
+pub struct AsyncRoutine<F>
+    where F : Family + ?Sized
+{
+    tag : &'static str,
+    body : Body<F>,
+    shared : Arc<Mutex<Shared<F::Input>>>,
+}
+
+impl<F> AsyncRoutine<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `AsyncRoutine` whose body will be created by `start` on the first swap. Until the body suspends
+    /// for the first time, the `AsyncRoutine` reports `tag` as its `tag()`.
+    /// 
+    pub fn new<S, B>(tag : &'static str, start : S) -> Self
+        where
+            S : FnOnce(Yielder<F>, F::Input) -> B + Send + Sync + 'static,
+            B : Future<Output = F::Output> + Send + Sync + 'static,
+    {
+        Self {
+            tag,
+            body : Body::NotStarted(Box::new(move |yielder, input| Box::pin(start(yielder, input)))),
+            shared : Arc::new(Mutex::new(Shared { input : None, yielded : None })),
+        }
+    }
+
+    /// Returns `true` if the body has been started, i.e. if the `AsyncRoutine` has been swapped at least once.
+    /// 
+    pub fn is_started(&self) -> bool {
+        matches!(self.body, Body::Running(_))
+    }
+}
+
+impl<F> fmt::Debug for AsyncRoutine<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("AsyncRoutine")
+            .field("tag", &self.tag)
+            .field("started", &self.is_started())
+            .finish()
+    }
+}
+
+impl<F> Mode for AsyncRoutine<F>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        let mut future =
+            match self.body {
+                Body::NotStarted(start) => start(Yielder { shared : self.shared.clone() }, input),
+                Body::Running(future) => {
+                    lock(&self.shared).input = Some(input);
+                    future
+                },
+            };
+
+        match future.as_mut().poll(&mut Context::from_waker(Waker::noop())) {
+            Poll::Ready(output) => output,
+            Poll::Pending => {
+                let mut shared = lock(&self.shared);
+                shared.input = None;
+                if let Some(tag) = shared.yielded.take() {
+                    self.tag = tag;
+                }
+                drop(shared);
+
+                self.body = Body::Running(future);
+                self.into()
+            },
+        }
+    }
+
+    fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    fn heap_size(&self) -> usize {
+        match &self.body {
+            Body::NotStarted(start) => mem::size_of_val(&**start),
+            Body::Running(future) => mem::size_of_val(&**future),
+        }
+    }
+}
+
+/// A handle that is passed into the body of an [`AsyncRoutine`](struct.AsyncRoutine.html), and allows it to suspend
+/// until the next time the `AsyncRoutine` is swapped.
+/// 
+pub struct Yielder<F>
+    where F : Family + ?Sized
+{
+    shared : Arc<Mutex<Shared<F::Input>>>,
+}
+
+impl<F> Yielder<F>
+    where F : Family + ?Sized
+{
+    /// Returns a `Future` that suspends the body of the `AsyncRoutine` until the next swap, and then resolves to the
+    /// `input` passed into that swap. Until then, the `AsyncRoutine` reports `tag` as its `tag()`.
+    /// 
+    pub fn suspend(&self, tag : &'static str) -> Suspend<F> {
+        Suspend { shared : self.shared.clone(), tag : Some(tag) }
+    }
+}
+
+impl<F> fmt::Debug for Yielder<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Yielder").finish()
+    }
+}
+
+/// The `Future` returned by [`Yielder::suspend()`](struct.Yielder.html#method.suspend).
+/// 
+pub struct Suspend<F>
+    where F : Family + ?Sized
+{
+    shared : Arc<Mutex<Shared<F::Input>>>,
+    tag : Option<&'static str>,
+}
+
+// NOTE: Suspend never pins any of its fields, so it is safe to move it around even after it has been pinned.
+impl<F> Unpin for Suspend<F>
+    where F : Family + ?Sized
+{ }
+
+impl<F> Future for Suspend<F>
+    where F : Family + ?Sized
+{
+    type Output = F::Input;
+
+    fn poll(self : Pin<&mut Self>, _context : &mut Context<'_>) -> Poll<F::Input> {
+        let this = self.get_mut();
+        let mut shared = lock(&this.shared);
+        if let Some(tag) = this.tag.take() {
+            shared.yielded = Some(tag);
+            return Poll::Pending;
+        }
+
+        match shared.input.take() {
+            Some(input) => Poll::Ready(input),
+            None => Poll::Pending,
+        }
+    }
+}
+
+impl<F> fmt::Debug for Suspend<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Suspend")
+            .field("suspended", &self.tag.is_none())
+            .finish()
+    }
+}
+
+fn lock<Input>(shared : &Mutex<Shared<Input>>) -> std::sync::MutexGuard<'_, Shared<Input>> {
+    shared.lock().unwrap_or_else(|error| error.into_inner())
+}
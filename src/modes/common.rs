@@ -0,0 +1,261 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines generic states that show up in almost every state machine, so that they do not need to be written by hand.
+//! 
+//! Each of these `Mode`s is adapted to the `Family` of the state machine using it through `From` conversions into
+//! `Family::Output`. Every type in this module that a state machine swaps into must have a `From` conversion into its
+//! `Family::Output`, which is the case for any `enum` generated by [`enum_mode!`](../../macro.enum_mode.html) with a
+//! variant for that type.
+//! 
+//! - [`Idle`](struct.Idle.html) stays current until it is replaced from the outside.
+//! - [`Delay`](struct.Delay.html) waits for a fixed amount of time and then swaps in the next `Mode`.
+//! - [`Immediate`](struct.Immediate.html) swaps in the next `Mode` as soon as it is swapped.
+//! - [`Failure`](struct.Failure.html) is a final state holding an error.
+//! 
+//! # Usage
+//! ```
+//! use mode::{modes::common::{Delay, Failure, Idle, Immediate}, *};
+//! use std::time::Duration;
+//! 
+//! struct JobFamily;
+//! impl Family for JobFamily {
+//!     type Base = Job;
+//!     type Mode = Job;
+//!     type Input = ();
+//!     type Output = Job;
+//! }
+//! 
+//! mode::enum_mode! {
+//!     enum Job {
+//!         Idle(Idle<JobFamily>),
+//!         Starting(Immediate<Delay<Failure<JobFamily, &'static str>>>),
+//!         Waiting(Delay<Failure<JobFamily, &'static str>>),
+//!         Failed(Failure<JobFamily, &'static str>),
+//!     }
+//! }
+//! 
+//! let mut job = JobFamily::automaton_with_mode(Idle::new().into());
+//! Automaton::next(&mut job);
+//! assert!(matches!(*job, Job::Idle(_)));
+//! 
+//! let mut job =
+//!     JobFamily::automaton_with_mode(Immediate::new(Delay::new(Duration::ZERO, Failure::new("timed out"))).into());
+//! Automaton::next(&mut job);
+//! assert!(matches!(*job, Job::Waiting(_)));
+//! 
+//! Automaton::next(&mut job);
+//! assert!(Automaton::is_finished(&job));
+//! assert!(matches!(&*job, Job::Failed(failure) if *failure.error() == "timed out"));
+//! ```
+//! 
+
+use crate::{Family, Mode};
+use std::{
+    fmt,
+    marker::PhantomData,
+    time::{Duration, Instant},
+};
+
+/// A `Mode` that never swaps in another `Mode` by itself. An `Automaton` in this state stays `Idle` until the current
+/// `Mode` is replaced from the outside, e.g. via
+/// [`Automaton::request_transition()`](../../struct.Automaton.html#method.request_transition).
+/// 
+pub struct Idle<F>
+    where F : Family + ?Sized
+{
+    family : PhantomData<F>,
+}
+
+impl<F> Idle<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Idle` `Mode`.
+    /// 
+    pub fn new() -> Self {
+        Self { family : PhantomData }
+    }
+}
+
+impl<F> Default for Idle<F>
+    where F : Family + ?Sized
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<F> fmt::Debug for Idle<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.write_str("Idle")
+    }
+}
+
+impl<F> Mode for Idle<F>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+{
+    type Family = F;
+
+    fn swap(self, _input : F::Input) -> F::Output {
+        self.into()
+    }
+}
+
+/// A `Mode` that stays current for a fixed amount of time, and then swaps in the `next` `Mode`. The time starts
+/// counting when the `Delay` becomes current.
+/// 
+#[derive(Debug)]
+pub struct Delay<M> {
+    duration : Duration,
+    started : Option<Instant>,
+    next : M,
+}
+
+impl<M> Delay<M> {
+    /// Creates a new `Delay` that swaps in `next` once it has been current for at least `duration`.
+    /// 
+    pub fn new(duration : Duration, next : M) -> Self {
+        Self { duration, started : None, next }
+    }
+
+    /// Returns the amount of time that the `Delay` stays current.
+    /// 
+    pub fn duration(&self) -> Duration {
+        self.duration
+    }
+
+    /// Returns the amount of time left until the next `Mode` is swapped in, or `duration()` if the `Delay` has not
+    /// become current yet.
+    /// 
+    pub fn remaining(&self) -> Duration {
+        match self.started {
+            Some(started) => self.duration.saturating_sub(started.elapsed()),
+            None => self.duration,
+        }
+    }
+
+    /// Returns a reference to the `Mode` that will be swapped in once the `Delay` has elapsed.
+    /// 
+    pub fn next(&self) -> &M {
+        &self.next
+    }
+}
+
+impl<M> Mode for Delay<M>
+    where
+        M : Mode,
+        <M::Family as Family>::Output : From<M> + From<Self>,
+{
+    type Family = M::Family;
+
+    fn swap(mut self, _input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
+        let started = *self.started.get_or_insert_with(Instant::now);
+        if started.elapsed() >= self.duration {
+            self.next.into()
+        }
+        else {
+            self.into()
+        }
+    }
+
+    fn enter(&mut self) {
+        self.started = Some(Instant::now());
+    }
+}
+
+/// A `Mode` that swaps in the `next` `Mode` as soon as it is swapped. This is useful for states that only exist in
+/// order to run their [`enter()`](../../trait.Mode.html#method.enter) logic, or as a placeholder.
+/// 
+#[derive(Debug)]
+pub struct Immediate<M> {
+    next : M,
+}
+
+impl<M> Immediate<M> {
+    /// Creates a new `Immediate` `Mode` that swaps in `next` the next time it is swapped.
+    /// 
+    pub fn new(next : M) -> Self {
+        Self { next }
+    }
+
+    /// Returns a reference to the `Mode` that will be swapped in.
+    /// 
+    pub fn next(&self) -> &M {
+        &self.next
+    }
+}
+
+impl<M> Mode for Immediate<M>
+    where
+        M : Mode,
+        <M::Family as Family>::Output : From<M>,
+{
+    type Family = M::Family;
+
+    fn swap(self, _input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
+        self.next.into()
+    }
+}
+
+/// A final `Mode` that holds an `error` describing why the state machine failed. Once a `Failure` becomes current, the
+/// `Automaton` finishes, and the `error` can be retrieved by borrowing the current `Mode`.
+/// 
+pub struct Failure<F, E>
+    where F : Family + ?Sized
+{
+    error : E,
+    family : PhantomData<F>,
+}
+
+impl<F, E> Failure<F, E>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Failure` holding `error`.
+    /// 
+    pub fn new(error : E) -> Self {
+        Self { error, family : PhantomData }
+    }
+
+    /// Returns a reference to the error.
+    /// 
+    pub fn error(&self) -> &E {
+        &self.error
+    }
+
+    /// Consumes the `Failure`, returning the error.
+    /// 
+    pub fn into_error(self) -> E {
+        self.error
+    }
+}
+
+impl<F, E> fmt::Debug for Failure<F, E>
+    where
+        F : Family + ?Sized,
+        E : fmt::Debug,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_tuple("Failure").field(&self.error).finish()
+    }
+}
+
+impl<F, E> Mode for Failure<F, E>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+{
+    type Family = F;
+
+    fn swap(self, _input : F::Input) -> F::Output {
+        self.into()
+    }
+
+    fn is_final(&self) -> bool { true }
+}
@@ -0,0 +1,547 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{
+    Automaton, Family, Finish, Mode,
+    modes::{IntoResult, sequence::{ChildOutcome, Error, Value}},
+};
+use std::fmt;
+
+type FactoryFn<C> = dyn Fn() -> Automaton<C> + Send + Sync;
+type UntilFn<C> = dyn Fn(&ChildOutcome<C>) -> bool + Send + Sync;
+type RepeatFn<F, C> = dyn FnOnce(Result<Vec<Value<C>>, Error<C>>) -> <F as Family>::Output + Send + Sync;
+type OutcomeFn<F, C> = dyn FnOnce(ChildOutcome<C>) -> <F as Family>::Output + Send + Sync;
+type InvertFn<F, C> = dyn FnOnce(Result<Error<C>, Value<C>>) -> <F as Family>::Output + Send + Sync;
+type LimitFn<F, C> = dyn FnOnce(Option<ChildOutcome<C>>) -> <F as Family>::Output + Send + Sync;
+
+/// Creates the next run of a child via `factory`, nested under the path of its `parent`, if any.
+/// 
+fn spawn<C, G>(factory : &G, parent : Option<&str>) -> Automaton<C>
+    where
+        C : Family + ?Sized,
+        G : Fn() -> Automaton<C> + ?Sized,
+{
+    let mut child = factory();
+    if parent.is_some() {
+        Automaton::set_parent(&mut child, parent);
+    }
+    child
+}
+
+/// Harvests the `Outcome` of a child `Automaton` that has reached a final `Mode`.
+/// 
+fn harvest<C>(child : Automaton<C>) -> ChildOutcome<C>
+    where
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    Automaton::finish(child)
+        .unwrap_or_else(|_| unreachable!("A complete Automaton can always be finished!"))
+}
+
+/// A decorator `Mode` that runs a child state machine to completion a fixed number of times in a row, creating a fresh
+/// `Automaton` for each run via a `factory` function.
+/// 
+/// Every time the `Repeat` is swapped, the `input` is passed into the current run of the child via
+/// [`Automaton::next_with_input()`](../struct.Automaton.html#method.next_with_input). Each time the child finishes, its
+/// `Outcome` is classified via [`IntoResult`](trait.IntoResult.html). If the child failed, the `Repeat` transitions to
+/// the `Family::Output` returned by `on_complete` with the error. Once the child has succeeded `times` times in a row,
+/// the `Repeat` transitions to the `Family::Output` returned by `on_complete` with the values of every run, in order.
+/// 
+/// Like the other decorators in this module, the `Family::Output` of `F` must implement `From<Repeat<F, C>>`, which is
+/// the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with a `Repeat<F, C>` variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::{InvertOutcome, LimitSteps, Repeat, RepeatUntil}, *};
+/// use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+/// 
+/// struct RollFamily;
+/// impl Family for RollFamily {
+///     type Base = Roll;
+///     type Mode = Roll;
+///     type Input = ();
+///     type Output = Roll;
+/// }
+/// 
+/// // Takes one step to roll a die, which succeeds unless it comes up as a 1.
+/// enum Roll { Rolling(u32), Rolled(u32) }
+/// impl Mode for Roll {
+///     type Family = RollFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self { Roll::Rolling(value) => Roll::Rolled(value), rolled => rolled }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Roll::Rolling(_) => "Rolling", Roll::Rolled(_) => "Rolled" }
+///     }
+///     fn is_final(&self) -> bool { matches!(self, Roll::Rolled(_)) }
+/// }
+/// impl Finish for Roll {
+///     type Outcome = Result<u32, u32>;
+///     fn finish(self) -> Self::Outcome {
+///         match self { Roll::Rolled(1) => Err(1), Roll::Rolled(value) => Ok(value), _ => unreachable!() }
+///     }
+/// }
+/// 
+/// struct GameFamily;
+/// impl Family for GameFamily {
+///     type Base = Game;
+///     type Mode = Game;
+///     type Input = ();
+///     type Output = Game;
+/// }
+/// 
+/// struct Over(String);
+/// impl Mode for Over {
+///     type Family = GameFamily;
+///     fn swap(self, _input : ()) -> Game { self.into() }
+///     fn is_final(&self) -> bool { true }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Game {
+///         Repeating(Repeat<GameFamily, RollFamily>),
+///         RepeatingUntil(RepeatUntil<GameFamily, RollFamily>),
+///         Inverting(InvertOutcome<GameFamily, RollFamily>),
+///         Limiting(LimitSteps<GameFamily, RollFamily>),
+///         Over(Over),
+///     }
+/// }
+/// 
+/// fn play(game : Game) -> String {
+///     let mut automaton = GameFamily::automaton_with_mode(game);
+///     while !Automaton::is_finished(&automaton) { Automaton::next(&mut automaton); }
+///     match &*automaton { Game::Over(Over(result)) => result.clone(), _ => unreachable!() }
+/// }
+/// 
+/// // A die that counts up from 1 to 6, wrapping around.
+/// let next = Arc::new(AtomicU32::new(1));
+/// let die = move || {
+///     let value = next.fetch_add(1, Ordering::SeqCst) % 6 + 1;
+///     RollFamily::automaton_with_mode(Roll::Rolling(value))
+/// };
+/// 
+/// let game = Repeat::new(3, die.clone(), |result| Over(format!("{:?}", result)).into());
+/// assert_eq!(play(game.into()), "Ok([2, 3, 4])");
+/// 
+/// let game = RepeatUntil::new(die.clone(), |outcome| *outcome == Err(1), |_| Over("snake eyes".into()).into());
+/// assert_eq!(play(game.into()), "snake eyes");
+/// 
+/// let game = InvertOutcome::new(die(), |result| Over(format!("{:?}", result)).into());
+/// assert_eq!(play(game.into()), "Err(2)");
+/// 
+/// let slow = RollFamily::automaton_with_mode(Roll::Rolling(6));
+/// let game = LimitSteps::new(slow, 0, |outcome| Over(format!("{:?}", outcome)).into());
+/// assert_eq!(play(game.into()), "None");
+/// ```
+/// 
+pub struct Repeat<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    times : usize,
+    factory : Box<FactoryFn<C>>,
+    current : Option<Automaton<C>>,
+    parent : Option<String>,
+    values : Vec<Value<C>>,
+    on_complete : Box<RepeatFn<F, C>>,
+}
+
+impl<F, C> Repeat<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    /// Creates a new `Repeat` that runs the child `Automaton` returned by `factory` to completion `times` times in a
+    /// row, and then transitions to the `Family::Output` returned by `on_complete`.
+    /// 
+    pub fn new<G, H>(times : usize, factory : G, on_complete : H) -> Self
+        where
+            G : Fn() -> Automaton<C> + Send + Sync + 'static,
+            H : FnOnce(Result<Vec<Value<C>>, Error<C>>) -> F::Output + Send + Sync + 'static,
+    {
+        Self {
+            times,
+            factory : Box::new(factory),
+            current : None,
+            parent : None,
+            values : Vec::new(),
+            on_complete : Box::new(on_complete),
+        }
+    }
+
+    /// Returns the current run of the child, or `None` if the next run has not been started yet.
+    /// 
+    pub fn current(&self) -> Option<&Automaton<C>> {
+        self.current.as_ref()
+    }
+
+    /// Returns the number of runs that have succeeded so far.
+    /// 
+    pub fn completed(&self) -> usize {
+        self.values.len()
+    }
+}
+
+impl<F, C> fmt::Debug for Repeat<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Repeat")
+            .field("times", &self.times)
+            .field("completed", &self.values.len())
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for Repeat<F, C>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+        M::Outcome : IntoResult,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        if self.values.len() >= self.times {
+            return (self.on_complete)(Ok(self.values));
+        }
+
+        let (factory, parent) = (&self.factory, self.parent.as_deref());
+        let child = self.current.get_or_insert_with(|| spawn(&**factory, parent));
+        Automaton::next_with_input(child, input);
+        if Automaton::is_complete(child) {
+            match harvest(self.current.take().unwrap()).into_result() {
+                Ok(value) => self.values.push(value),
+                Err(error) => return (self.on_complete)(Err(error)),
+            }
+
+            if self.values.len() >= self.times {
+                return (self.on_complete)(Ok(self.values));
+            }
+        }
+
+        self.into()
+    }
+
+    fn set_path(&mut self, path : &str) {
+        self.parent = Some(path.to_owned());
+        if let Some(child) = self.current.as_mut() {
+            Automaton::set_parent(child, Some(path));
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.current.as_ref().map_or(0, |child| Automaton::footprint(child).total())
+    }
+}
+
+/// A decorator `Mode` that runs a child state machine to completion over and over again, creating a fresh `Automaton`
+/// for each run via a `factory` function, until the `Outcome` of a run satisfies the `until` predicate. The `Mode` then
+/// transitions to the `Family::Output` returned by `on_complete` with that `Outcome`.
+/// 
+/// See [`Repeat`](struct.Repeat.html) for an example.
+/// 
+pub struct RepeatUntil<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    factory : Box<FactoryFn<C>>,
+    current : Option<Automaton<C>>,
+    parent : Option<String>,
+    runs : usize,
+    until : Box<UntilFn<C>>,
+    on_complete : Box<OutcomeFn<F, C>>,
+}
+
+impl<F, C> RepeatUntil<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    /// Creates a new `RepeatUntil` that runs the child `Automaton` returned by `factory` to completion until `until`
+    /// returns `true` for its `Outcome`, and then transitions to the `Family::Output` returned by `on_complete`.
+    /// 
+    pub fn new<G, U, H>(factory : G, until : U, on_complete : H) -> Self
+        where
+            G : Fn() -> Automaton<C> + Send + Sync + 'static,
+            U : Fn(&ChildOutcome<C>) -> bool + Send + Sync + 'static,
+            H : FnOnce(ChildOutcome<C>) -> F::Output + Send + Sync + 'static,
+    {
+        Self {
+            factory : Box::new(factory),
+            current : None,
+            parent : None,
+            runs : 0,
+            until : Box::new(until),
+            on_complete : Box::new(on_complete),
+        }
+    }
+
+    /// Returns the current run of the child, or `None` if the next run has not been started yet.
+    /// 
+    pub fn current(&self) -> Option<&Automaton<C>> {
+        self.current.as_ref()
+    }
+
+    /// Returns the number of runs that have finished so far.
+    /// 
+    pub fn runs(&self) -> usize {
+        self.runs
+    }
+}
+
+impl<F, C> fmt::Debug for RepeatUntil<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("RepeatUntil")
+            .field("runs", &self.runs)
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for RepeatUntil<F, C>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        let (factory, parent) = (&self.factory, self.parent.as_deref());
+        let child = self.current.get_or_insert_with(|| spawn(&**factory, parent));
+        Automaton::next_with_input(child, input);
+        if Automaton::is_complete(child) {
+            self.runs += 1;
+            let outcome = harvest(self.current.take().unwrap());
+            if (self.until)(&outcome) {
+                return (self.on_complete)(outcome);
+            }
+        }
+
+        self.into()
+    }
+
+    fn set_path(&mut self, path : &str) {
+        self.parent = Some(path.to_owned());
+        if let Some(child) = self.current.as_mut() {
+            Automaton::set_parent(child, Some(path));
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.current.as_ref().map_or(0, |child| Automaton::footprint(child).total())
+    }
+}
+
+/// A decorator `Mode` that runs a child state machine to completion and swaps the success and failure cases of its
+/// `Outcome`, as classified via [`IntoResult`](trait.IntoResult.html). Once the child has finished, the `Mode`
+/// transitions to the `Family::Output` returned by `on_complete` with the error of the child as an `Ok`, or the value
+/// of the child as an `Err`.
+/// 
+/// See [`Repeat`](struct.Repeat.html) for an example.
+/// 
+pub struct InvertOutcome<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    child : Automaton<C>,
+    on_complete : Box<InvertFn<F, C>>,
+}
+
+impl<F, C> InvertOutcome<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    /// Creates a new `InvertOutcome` that runs `child` to completion, and then transitions to the `Family::Output`
+    /// returned by `on_complete` with the inverted `Outcome`.
+    /// 
+    pub fn new<H>(child : Automaton<C>, on_complete : H) -> Self
+        where H : FnOnce(Result<Error<C>, Value<C>>) -> F::Output + Send + Sync + 'static
+    {
+        Self { child, on_complete : Box::new(on_complete) }
+    }
+
+    /// Returns the child `Automaton`.
+    /// 
+    pub fn child(&self) -> &Automaton<C> {
+        &self.child
+    }
+}
+
+impl<F, C> fmt::Debug for InvertOutcome<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("InvertOutcome")
+            .field("phase", &Automaton::phase(&self.child))
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for InvertOutcome<F, C>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+        M::Outcome : IntoResult,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        Automaton::next_with_input(&mut self.child, input);
+        if !Automaton::is_complete(&self.child) {
+            return self.into();
+        }
+
+        let inverted =
+            match harvest(self.child).into_result() {
+                Ok(value) => Err(value),
+                Err(error) => Ok(error),
+            };
+        (self.on_complete)(inverted)
+    }
+
+    fn set_path(&mut self, path : &str) {
+        Automaton::set_parent(&mut self.child, Some(path));
+    }
+
+    fn heap_size(&self) -> usize {
+        Automaton::footprint(&self.child).total()
+    }
+}
+
+/// A decorator `Mode` that gives a child state machine a limited number of steps in which to finish.
+/// 
+/// Once the child has finished, the `Mode` transitions to the `Family::Output` returned by `on_complete` with the
+/// `Outcome` of the child. If the child has been stepped `limit` times without finishing, as reported by
+/// [`Automaton::steps()`](../struct.Automaton.html#method.steps), it is stopped via
+/// [`Automaton::stop()`](../struct.Automaton.html#method.stop), which calls
+/// [`Mode::exit()`](../trait.Mode.html#method.exit) on its current `Mode`, and the `Mode` transitions to the
+/// `Family::Output` returned by `on_complete` with `None` instead.
+/// 
+/// See [`Repeat`](struct.Repeat.html) for an example.
+/// 
+pub struct LimitSteps<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    child : Automaton<C>,
+    limit : u64,
+    on_complete : Box<LimitFn<F, C>>,
+}
+
+impl<F, C> LimitSteps<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    /// Creates a new `LimitSteps` that allows `child` to be stepped at most `limit` times before giving up, and then
+    /// transitions to the `Family::Output` returned by `on_complete`.
+    /// 
+    pub fn new<H>(child : Automaton<C>, limit : u64, on_complete : H) -> Self
+        where H : FnOnce(Option<ChildOutcome<C>>) -> F::Output + Send + Sync + 'static
+    {
+        Self { child, limit, on_complete : Box::new(on_complete) }
+    }
+
+    /// Returns the child `Automaton`.
+    /// 
+    pub fn child(&self) -> &Automaton<C> {
+        &self.child
+    }
+
+    /// Returns the maximum number of times the child can be stepped.
+    /// 
+    pub fn limit(&self) -> u64 {
+        self.limit
+    }
+}
+
+impl<F, C> fmt::Debug for LimitSteps<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("LimitSteps")
+            .field("limit", &self.limit)
+            .field("steps", &Automaton::steps(&self.child))
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for LimitSteps<F, C>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        if Automaton::steps(&self.child) < self.limit {
+            Automaton::next_with_input(&mut self.child, input);
+        }
+
+        if Automaton::is_complete(&self.child) {
+            return (self.on_complete)(Some(harvest(self.child)));
+        }
+
+        if Automaton::steps(&self.child) >= self.limit {
+            if Automaton::is_started(&self.child) {
+                Automaton::stop(&mut self.child);
+            }
+            return (self.on_complete)(None);
+        }
+
+        self.into()
+    }
+
+    fn set_path(&mut self, path : &str) {
+        Automaton::set_parent(&mut self.child, Some(path));
+    }
+
+    fn heap_size(&self) -> usize {
+        Automaton::footprint(&self.child).total()
+    }
+}
@@ -0,0 +1,245 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Finish, Mode, modes::sequence::ChildOutcome};
+use std::fmt;
+
+type WinnerFn<F, C> = dyn FnOnce(usize, ChildOutcome<C>) -> <F as Family>::Output + Send + Sync;
+type CancelledFn<F> = dyn FnOnce() -> <F as Family>::Output + Send + Sync;
+
+/// A `Mode` that hosts several child state machines, each in its own `Automaton`, and transitions as soon as the first
+/// one of them finishes.
+/// 
+/// Every time the `Race` is swapped, a clone of the `input` is passed into each child via
+/// [`Automaton::next_with_input()`](../struct.Automaton.html#method.next_with_input), in the order in which the
+/// children were added. As soon as one child reaches a final `Mode`, the remaining children are not stepped, and the
+/// `Outcome` of the winner is harvested via [`Automaton::finish()`](../struct.Automaton.html#method.finish). Every
+/// other child that has been started is then stopped via [`Automaton::stop()`](../struct.Automaton.html#method.stop),
+/// which calls [`Mode::exit()`](../trait.Mode.html#method.exit) on its current `Mode`, and dropped. Finally, the `Race`
+/// transitions to the `Family::Output` returned by the `on_winner` function, which receives the index of the winning
+/// child along with its `Outcome`. If several children would finish during the same swap, the child that was added
+/// first wins.
+/// 
+/// The children can use [`Outcome`](../enum.Outcome.html) as their `Finish::Outcome`, so that `on_winner` can tell
+/// whether the winner succeeded, failed, or was cancelled, e.g. because a
+/// [`CancellationToken`](../struct.CancellationToken.html) routed it into a final cancellation state. A child that was
+/// [stopped](../struct.Automaton.html#method.stop) before reaching a final `Mode` can never win. Once none of the
+/// children can win anymore, because every one of them was stopped before reaching a final `Mode`, the `Race`
+/// transitions to the `Family::Output` returned by the `on_cancelled` function instead. A `Race` always starts out with
+/// at least one child, which is passed into [`new()`](#method.new).
+/// 
+/// Since the `Race` must be able to stay current, the `Family::Output` of `F` must implement `From<Race<F, C>>`, which
+/// is the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with a `Race<F, C>` variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::Race, *};
+/// use std::sync::{atomic::{AtomicU32, Ordering}, Arc};
+/// 
+/// struct WaitFamily;
+/// impl Family for WaitFamily {
+///     type Base = Wait;
+///     type Mode = Wait;
+///     type Input = ();
+///     type Output = Wait;
+/// }
+/// 
+/// enum Wait { Waiting { name : &'static str, steps_left : u32, exits : Arc<AtomicU32> }, Done(&'static str) }
+/// impl Mode for Wait {
+///     type Family = WaitFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Wait::Waiting { name, steps_left : 0, .. } => Wait::Done(name),
+///             Wait::Waiting { name, steps_left, exits } => Wait::Waiting { name, steps_left : steps_left - 1, exits },
+///             done => done,
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Wait::Waiting { .. } => "Waiting", Wait::Done(_) => "Done" }
+///     }
+///     fn exit(&mut self) {
+///         if let Wait::Waiting { exits, .. } = self { exits.fetch_add(1, Ordering::SeqCst); }
+///     }
+///     fn is_final(&self) -> bool { matches!(self, Wait::Done(_)) }
+/// }
+/// 
+/// impl Finish for Wait {
+///     type Outcome = &'static str;
+///     fn finish(self) -> &'static str {
+///         match self { Wait::Done(name) => name, _ => unreachable!() }
+///     }
+/// }
+/// 
+/// struct RequestFamily;
+/// impl Family for RequestFamily {
+///     type Base = Request;
+///     type Mode = Request;
+///     type Input = ();
+///     type Output = Request;
+/// }
+/// 
+/// struct Resolved(&'static str);
+/// impl Mode for Resolved {
+///     type Family = RequestFamily;
+///     fn swap(self, _input : ()) -> Request { self.into() }
+///     fn is_final(&self) -> bool { true }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Request {
+///         Pending(Race<RequestFamily, WaitFamily>),
+///         Resolved(Resolved),
+///     }
+/// }
+/// 
+/// let exits = Arc::new(AtomicU32::new(0));
+/// let wait = |name, steps_left| {
+///     WaitFamily::automaton_with_mode(Wait::Waiting { name, steps_left, exits : exits.clone() })
+/// };
+/// 
+/// let race =
+///     Race::new(wait("timeout", 5), |_index, winner| Resolved(winner).into(), || Resolved("cancelled").into())
+///         .with_child(wait("reply", 2))
+///         .with_child(wait("user input", 3));
+/// 
+/// let mut request = RequestFamily::automaton_with_mode(race.into());
+/// while !Automaton::is_finished(&request) { Automaton::next(&mut request); }
+/// 
+/// assert!(matches!(*request, Request::Resolved(Resolved("reply"))));
+/// assert_eq!(exits.load(Ordering::SeqCst), 2);
+/// 
+/// // A Race whose children were all stopped before finishing can never be won.
+/// let mut stopped = wait("reply", 2);
+/// Automaton::stop(&mut stopped);
+/// 
+/// let race = Race::new(stopped, |_index, winner| Resolved(winner).into(), || Resolved("cancelled").into());
+/// let mut request = RequestFamily::automaton_with_mode(race.into());
+/// Automaton::next(&mut request);
+/// assert!(matches!(*request, Request::Resolved(Resolved("cancelled"))));
+/// ```
+/// 
+pub struct Race<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    children : Vec<Automaton<C>>,
+    on_winner : Box<WinnerFn<F, C>>,
+    on_cancelled : Box<CancelledFn<F>>,
+}
+
+impl<F, C> Race<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    /// Creates a new `Race` with `first` as its only child. As soon as one child finishes, the `Race` transitions to
+    /// the `Family::Output` returned by `on_winner`. If every child is stopped before reaching a final `Mode`, the
+    /// `Race` transitions to the `Family::Output` returned by `on_cancelled` instead.
+    /// 
+    pub fn new<G, H>(first : Automaton<C>, on_winner : G, on_cancelled : H) -> Self
+        where
+            G : FnOnce(usize, ChildOutcome<C>) -> F::Output + Send + Sync + 'static,
+            H : FnOnce() -> F::Output + Send + Sync + 'static,
+    {
+        Self { children : vec![first], on_winner : Box::new(on_winner), on_cancelled : Box::new(on_cancelled) }
+    }
+
+    /// Adds `child` to the `Race`. The index passed to `on_winner` is the number of children that were added before
+    /// it.
+    /// 
+    pub fn add_child(&mut self, child : Automaton<C>) {
+        self.children.push(child);
+    }
+
+    /// Same as [`add_child()`](#method.add_child), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_child(mut self, child : Automaton<C>) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    /// Returns the children of the `Race`, in the order in which they were added.
+    /// 
+    pub fn children(&self) -> &[Automaton<C>] {
+        &self.children
+    }
+}
+
+impl<F, C> fmt::Debug for Race<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Race")
+            .field("children", &self.children.len())
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for Race<F, C>
+    where
+        F : Family + ?Sized,
+        F::Input : Clone,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        let mut winner = None;
+        let mut contenders = 0;
+        for (index, child) in self.children.iter_mut().enumerate() {
+            if !Automaton::is_finished(child) {
+                Automaton::next_with_input(child, input.clone());
+            }
+
+            if Automaton::is_complete(child) {
+                winner = Some(index);
+                break;
+            }
+
+            if !Automaton::is_finished(child) {
+                contenders += 1;
+            }
+        }
+
+        let index =
+            match winner {
+                Some(index) => index,
+                None if contenders == 0 => return (self.on_cancelled)(),
+                None => return self.into(),
+            };
+
+        let child = self.children.remove(index);
+        for loser in &mut self.children {
+            if Automaton::is_started(loser) {
+                Automaton::stop(loser);
+            }
+        }
+
+        let outcome =
+            Automaton::finish(child)
+                .unwrap_or_else(|_| unreachable!("A complete Automaton can always be finished!"));
+        (self.on_winner)(index, outcome)
+    }
+
+    fn set_path(&mut self, path : &str) {
+        for child in &mut self.children {
+            Automaton::set_parent(child, Some(path));
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.children.iter().map(|child| Automaton::footprint(child).total()).sum()
+    }
+}
@@ -0,0 +1,279 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode};
+use std::{
+    collections::hash_map::RandomState,
+    hash::BuildHasher,
+    time::{Duration, Instant},
+};
+
+/// Describes how long a [`Retrying`](struct.Retrying.html) `Mode` waits between attempts, and how many attempts it
+/// makes before giving up.
+/// 
+/// After the `n`th failed attempt, the delay before the next attempt is `initial * multiplier^(n - 1)`, capped at
+/// `max_delay`. If `jitter` is greater than zero, each delay is then scaled by a random factor between `1 - jitter` and
+/// `1`, so that many state machines retrying against the same service do not all retry at the same time.
+/// 
+/// By default, the `multiplier` is `2.0`, the `max_delay` is one minute, there is no `jitter`, and at most `5` attempts
+/// are made.
+/// 
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Backoff {
+    initial : Duration,
+    max_delay : Duration,
+    multiplier : f64,
+    jitter : f64,
+    max_attempts : u32,
+}
+
+impl Backoff {
+    /// Creates a new `Backoff` that waits for `initial` after the first failed attempt.
+    /// 
+    pub fn new(initial : Duration) -> Self {
+        Self {
+            initial,
+            max_delay : Duration::from_secs(60),
+            multiplier : 2.0,
+            jitter : 0.0,
+            max_attempts : 5,
+        }
+    }
+
+    /// Sets the factor by which the delay grows after each failed attempt. This is clamped to be at least `1.0` and
+    /// finite, and a `NaN` multiplier is treated as `1.0`.
+    /// 
+    pub fn with_multiplier(mut self, multiplier : f64) -> Self {
+        self.multiplier = if multiplier.is_nan() { 1.0 } else { multiplier.clamp(1.0, f64::MAX) };
+        self
+    }
+
+    /// Sets the longest delay that will ever be waited between two attempts, before `jitter` is applied.
+    /// 
+    pub fn with_max_delay(mut self, max_delay : Duration) -> Self {
+        self.max_delay = max_delay;
+        self
+    }
+
+    /// Sets the fraction of each delay that is randomized. This is clamped to the range `0.0..=1.0`.
+    /// 
+    pub fn with_jitter(mut self, jitter : f64) -> Self {
+        self.jitter = jitter.clamp(0.0, 1.0);
+        self
+    }
+
+    /// Sets the total number of attempts that will be made before giving up, including the first one. A value of `0`
+    /// is treated as `1`.
+    /// 
+    pub fn with_max_attempts(mut self, max_attempts : u32) -> Self {
+        self.max_attempts = max_attempts.max(1);
+        self
+    }
+
+    /// Returns the total number of attempts that will be made before giving up.
+    /// 
+    pub fn max_attempts(&self) -> u32 {
+        self.max_attempts
+    }
+
+    /// Returns the delay before the next attempt after `failures` failed attempts, **without** `jitter` applied.
+    /// 
+    pub fn delay(&self, failures : u32) -> Duration {
+        if failures == 0 {
+            return Duration::ZERO;
+        }
+
+        let factor = self.multiplier.powi(failures.saturating_sub(1).min(i32::MAX as u32) as i32);
+        let delay = self.initial.as_secs_f64() * factor;
+        if delay.is_finite() && delay < self.max_delay.as_secs_f64() {
+            Duration::from_secs_f64(delay.max(0.0))
+        }
+        else {
+            self.max_delay
+        }
+    }
+
+    /// Returns the delay before the next attempt after `failures` failed attempts, with a random amount of `jitter`
+    /// applied.
+    /// 
+    pub fn jittered_delay(&self, failures : u32) -> Duration {
+        let delay = self.delay(failures);
+        if self.jitter == 0.0 {
+            return delay;
+        }
+
+        let random = (RandomState::new().hash_one(failures) >> 11) as f64 / (1u64 << 53) as f64;
+        delay.mul_f64(1.0 - self.jitter * random)
+    }
+}
+
+/// A fallible operation that can be repeated by a [`Retrying`](struct.Retrying.html) `Mode`.
+/// 
+pub trait Fallible : Sized {
+    /// The `Family` of the state machine in which the operation is retried.
+    /// 
+    type Family : Family + ?Sized;
+
+    /// The type of error returned by a failed attempt.
+    /// 
+    type Error;
+
+    /// Makes a single attempt at the operation. If this returns `Ok`, the `Retrying` `Mode` transitions to the
+    /// `Family::Output` that was returned, e.g. a success state. Otherwise, another attempt is scheduled, or, if there
+    /// are no attempts left, [`give_up()`](#method.give_up) is called.
+    /// 
+    fn attempt(&mut self, input : <Self::Family as Family>::Input)
+        -> Result<<Self::Family as Family>::Output, Self::Error>;
+
+    /// Called with the error returned by the last attempt and the total number of attempts made, once there are no
+    /// attempts left. The `Retrying` `Mode` transitions to the `Family::Output` that is returned, e.g. a failure state.
+    /// 
+    fn give_up(self, error : Self::Error, attempts : u32) -> <Self::Family as Family>::Output;
+}
+
+/// A `Mode` that repeats a [`Fallible`](trait.Fallible.html) operation according to a [`Backoff`](struct.Backoff.html)
+/// until it either succeeds or runs out of attempts.
+/// 
+/// Every time `swap()` is called, the `Retrying` `Mode` checks whether the delay since the last failed attempt has
+/// elapsed. If it has, another attempt is made. If the delay has not elapsed yet, the `input` is dropped and the
+/// `Retrying` `Mode` stays current. Since `Retrying<M>` must be able to stay current, the `Family::Output` of `M` must
+/// implement `From<Retrying<M>>`, which is the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html)
+/// with a `Retrying<M>` variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::{Backoff, Fallible, Retrying}, *};
+/// use std::time::Duration;
+/// 
+/// struct NetFamily;
+/// impl Family for NetFamily {
+///     type Base = Net;
+///     type Mode = Net;
+///     type Input = ();
+///     type Output = Net;
+/// }
+/// 
+/// struct Connect { refusals : u32 }
+/// impl Fallible for Connect {
+///     type Family = NetFamily;
+///     type Error = &'static str;
+///     fn attempt(&mut self, _input : ()) -> Result<Net, &'static str> {
+///         if self.refusals == 0 { return Ok(Connected.into()); }
+///         self.refusals -= 1;
+///         Err("connection refused")
+///     }
+///     fn give_up(self, error : &'static str, _attempts : u32) -> Net { Failed(error).into() }
+/// }
+/// 
+/// struct Connected;
+/// impl Mode for Connected {
+///     type Family = NetFamily;
+///     fn swap(self, _input : ()) -> Net { self.into() }
+/// }
+/// 
+/// struct Failed(&'static str);
+/// impl Mode for Failed {
+///     type Family = NetFamily;
+///     fn swap(self, _input : ()) -> Net { self.into() }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Net {
+///         Connecting(Retrying<Connect>),
+///         Connected(Connected),
+///         Failed(Failed),
+///     }
+/// }
+/// 
+/// let backoff = Backoff::new(Duration::ZERO).with_max_attempts(3);
+/// 
+/// let mut net = NetFamily::automaton_with_mode(Retrying::new(Connect { refusals : 2 }, backoff).into());
+/// for _ in 0..3 { Automaton::next(&mut net); }
+/// assert!(matches!(*net, Net::Connected(_)));
+/// 
+/// let mut net = NetFamily::automaton_with_mode(Retrying::new(Connect { refusals : 5 }, backoff).into());
+/// for _ in 0..3 { Automaton::next(&mut net); }
+/// assert!(matches!(*net, Net::Failed(Failed("connection refused"))));
+/// ```
+/// 
+#[derive(Debug)]
+pub struct Retrying<M> {
+    operation : M,
+    backoff : Backoff,
+    failures : u32,
+    waiting : Option<(Instant, Duration)>,
+}
+
+impl<M> Retrying<M> {
+    /// Creates a new `Retrying` `Mode` that makes its first attempt at `operation` as soon as it is swapped, and then
+    /// retries according to `backoff`.
+    /// 
+    pub fn new(operation : M, backoff : Backoff) -> Self {
+        Self { operation, backoff, failures : 0, waiting : None }
+    }
+
+    /// Returns a reference to the operation being retried.
+    /// 
+    pub fn operation(&self) -> &M {
+        &self.operation
+    }
+
+    /// Returns a mutable reference to the operation being retried.
+    /// 
+    pub fn operation_mut(&mut self) -> &mut M {
+        &mut self.operation
+    }
+
+    /// Returns the `Backoff` used to schedule attempts.
+    /// 
+    pub fn backoff(&self) -> &Backoff {
+        &self.backoff
+    }
+
+    /// Returns the number of attempts that have failed so far.
+    /// 
+    pub fn failures(&self) -> u32 {
+        self.failures
+    }
+
+    /// Returns the amount of time left until the next attempt can be made, or `Duration::ZERO` if it can be made
+    /// immediately.
+    /// 
+    pub fn time_until_attempt(&self) -> Duration {
+        self.waiting
+            .map(|(failed_at, delay)| delay.saturating_sub(failed_at.elapsed()))
+            .unwrap_or(Duration::ZERO)
+    }
+}
+
+impl<M> Mode for Retrying<M>
+    where
+        M : Fallible,
+        <M::Family as Family>::Output : From<Self>,
+{
+    type Family = M::Family;
+
+    fn swap(mut self, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
+        if self.time_until_attempt() > Duration::ZERO {
+            return self.into();
+        }
+
+        match self.operation.attempt(input) {
+            Ok(output) => output,
+            Err(error) => {
+                self.failures += 1;
+                if self.failures >= self.backoff.max_attempts {
+                    let attempts = self.failures;
+                    return self.operation.give_up(error, attempts);
+                }
+
+                // NOTE: The delay is kept apart from the Instant, since a long max_delay could overflow an Instant.
+                self.waiting = Some((Instant::now(), self.backoff.jittered_delay(self.failures)));
+                self.into()
+            },
+        }
+    }
+}
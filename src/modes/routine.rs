@@ -0,0 +1,135 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode};
+use std::{
+    fmt,
+    mem,
+    ops::{Coroutine, CoroutineState},
+    pin::Pin,
+};
+
+type CoroutineFn<F> =
+    dyn Coroutine<<F as Family>::Input, Yield = &'static str, Return = <F as Family>::Output> + Send + Sync;
+
+/// A `Mode` whose behavior is written as a coroutine that yields between steps, so that long, sequential behaviors
+/// can be expressed as straight-line code instead of as a separate state for every stage.
+/// 
+/// Every time the `Routine` is swapped, the coroutine is resumed with the `input`, and runs until it either yields or
+/// returns. Each time the coroutine yields, it produces the `tag()` that the `Routine` should report until the next
+/// swap, which allows subscribers of the `Automaton` to observe the stages of the coroutine as transitions, e.g.
+/// `"Patrol"` → `"Wait"` → `"Turn"`. Once the coroutine returns a `Family::Output`, the `Routine` transitions to it.
+/// 
+/// Since the `Routine` must be able to stay current, the `Family::Output` of `F` must implement `From<Routine<F>>`,
+/// which is the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with a `Routine<F>` variant.
+/// 
+/// **NOTE:** This type is only available when the `nightly` feature is enabled, since it relies on the unstable
+/// `coroutine_trait` feature of the Rust compiler.
+/// 
+/// For an equivalent that works on stable Rust, see [`AsyncRoutine`](struct.AsyncRoutine.html).
+/// 
+/// # Usage
+/// ```
+/// #![feature(coroutines, stmt_expr_attributes)]
+/// use mode::{modes::Routine, *};
+/// 
+/// struct GuardFamily;
+/// impl Family for GuardFamily {
+///     type Base = GuardMode;
+///     type Mode = GuardMode;
+///     type Input = u32;
+///     type Output = GuardMode;
+/// }
+/// 
+/// struct Alarmed;
+/// impl Mode for Alarmed {
+///     type Family = GuardFamily;
+///     fn swap(self, _noise : u32) -> GuardMode { self.into() }
+///     fn tag(&self) -> &'static str { "Alarmed" }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum GuardMode {
+///         Routine(Routine<GuardFamily>),
+///         Alarmed(Alarmed),
+///     }
+/// }
+/// 
+/// let patrol =
+///     Routine::new("Patrol", #[coroutine] |mut noise : u32| {
+///         loop {
+///             for stage in ["Patrol", "Wait", "Turn"] {
+///                 if noise > 5 { return Alarmed.into(); }
+///                 noise = yield stage;
+///             }
+///         }
+///     });
+/// 
+/// let mut guard = GuardFamily::automaton_with_mode(patrol.into());
+/// let mut tags = Vec::new();
+/// for noise in [0, 1, 0, 2, 9] {
+///     Automaton::next_with_input(&mut guard, noise);
+///     tags.push(Automaton::tag(&guard));
+/// }
+/// assert_eq!(tags, vec!["Patrol", "Wait", "Turn", "Patrol", "Alarmed"]);
+/// ```
+/// 
+pub struct Routine<F>
+    where F : Family + ?Sized
+{
+    tag : &'static str,
+    coroutine : Pin<Box<CoroutineFn<F>>>,
+    size : usize,
+}
+
+impl<F> Routine<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Routine` that runs `coroutine`. Until the coroutine yields for the first time, the `Routine`
+    /// reports `tag` as its `tag()`.
+    /// 
+    pub fn new<G>(tag : &'static str, coroutine : G) -> Self
+        where G : Coroutine<F::Input, Yield = &'static str, Return = F::Output> + Send + Sync + 'static
+    {
+        Self { tag, coroutine : Box::pin(coroutine), size : mem::size_of::<G>() }
+    }
+}
+
+impl<F> fmt::Debug for Routine<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Routine")
+            .field("tag", &self.tag)
+            .finish()
+    }
+}
+
+impl<F> Mode for Routine<F>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        match self.coroutine.as_mut().resume(input) {
+            CoroutineState::Yielded(tag) => {
+                self.tag = tag;
+                self.into()
+            },
+            CoroutineState::Complete(output) => output,
+        }
+    }
+
+    fn tag(&self) -> &'static str {
+        self.tag
+    }
+
+    fn heap_size(&self) -> usize {
+        self.size
+    }
+}
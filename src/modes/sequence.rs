@@ -0,0 +1,275 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Finish, Mode};
+use std::{collections::VecDeque, fmt};
+
+/// Trait that classifies the [`Finish::Outcome`](../trait.Finish.html#associatedtype.Outcome) of a child state machine
+/// as either a success or a failure, so that combinators like [`Sequence`](struct.Sequence.html) know when to
+/// short-circuit.
+/// 
+pub trait IntoResult {
+    /// The value produced by a child state machine that succeeded.
+    /// 
+    type Value;
+
+    /// The error produced by a child state machine that failed.
+    /// 
+    type Error;
+
+    /// Converts the outcome into a `Result`.
+    /// 
+    fn into_result(self) -> Result<Self::Value, Self::Error>;
+}
+
+impl<T, E> IntoResult for Result<T, E> {
+    type Value = T;
+    type Error = E;
+
+    fn into_result(self) -> Self { self }
+}
+
+pub(crate) type ChildOutcome<C> = <<C as Family>::Mode as Finish>::Outcome;
+pub(crate) type Value<C> = <<<C as Family>::Mode as Finish>::Outcome as IntoResult>::Value;
+pub(crate) type Error<C> = <<<C as Family>::Mode as Finish>::Outcome as IntoResult>::Error;
+
+type SuccessFn<F, C> = dyn FnOnce(Vec<Value<C>>) -> <F as Family>::Output + Send + Sync;
+type FailureFn<F, C> = dyn FnOnce(Error<C>) -> <F as Family>::Output + Send + Sync;
+
+/// A `Mode` that runs a list of child state machines one after another, each in its own `Automaton`.
+/// 
+/// Every time the `Sequence` is swapped, the `input` is passed into the first child that has not finished yet via
+/// [`Automaton::next_with_input()`](../struct.Automaton.html#method.next_with_input). As soon as that child finishes,
+/// its `Outcome` is harvested via [`Automaton::finish()`](../struct.Automaton.html#method.finish) and classified via
+/// [`IntoResult`](trait.IntoResult.html). If the child failed, the `Sequence` short-circuits, transitioning to the
+/// `Family::Output` returned by the `on_failure` function, and the remaining children are dropped without being
+/// started. Otherwise, the next child is started on the following swap. Once every child has succeeded, the `Sequence`
+/// transitions to the `Family::Output` returned by the `on_success` function, which receives the values produced by
+/// the children, in order. A child only counts as finished once a [final](../trait.Mode.html#method.is_final) `Mode`
+/// has become current, so a child that was [stopped](../struct.Automaton.html#method.stop) before that stalls the
+/// `Sequence`.
+/// 
+/// The children can use [`Outcome`](../enum.Outcome.html) as their `Finish::Outcome`, in which case a child that was
+/// cancelled, i.e. stopped before reaching a terminal state, is passed into `on_failure` with `None` as its error.
+/// 
+/// Since the `Sequence` must be able to stay current, the `Family::Output` of `F` must implement `From<Sequence<F, C>>`,
+/// which is the case for any `enum` generated by [`enum_mode!`](../macro.enum_mode.html) with a `Sequence<F, C>`
+/// variant.
+/// 
+/// # Usage
+/// ```
+/// use mode::{modes::{common::Failure, Sequence}, *};
+/// 
+/// struct ShotFamily;
+/// impl Family for ShotFamily {
+///     type Base = Shot;
+///     type Mode = Shot;
+///     type Input = ();
+///     type Output = Shot;
+/// }
+/// 
+/// enum Shot { Playing { frames : u32, length : u32 }, Done(u32), Cut(&'static str) }
+/// impl Mode for Shot {
+///     type Family = ShotFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self {
+///             Shot::Playing { length : 0, .. } => Shot::Cut("empty shot"),
+///             Shot::Playing { frames, length } if frames + 1 == length => Shot::Done(length),
+///             Shot::Playing { frames, length } => Shot::Playing { frames : frames + 1, length },
+///             other => other,
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Shot::Playing { .. } => "Playing", Shot::Done(_) => "Done", Shot::Cut(_) => "Cut" }
+///     }
+///     fn is_final(&self) -> bool { !matches!(self, Shot::Playing { .. }) }
+/// }
+/// 
+/// impl Finish for Shot {
+///     type Outcome = Result<u32, &'static str>;
+///     fn finish(self) -> Self::Outcome {
+///         match self { Shot::Done(length) => Ok(length), Shot::Cut(reason) => Err(reason), _ => unreachable!() }
+///     }
+/// }
+/// 
+/// struct CutsceneFamily;
+/// impl Family for CutsceneFamily {
+///     type Base = Cutscene;
+///     type Mode = Cutscene;
+///     type Input = ();
+///     type Output = Cutscene;
+/// }
+/// 
+/// struct Finished(Vec<u32>);
+/// impl Mode for Finished {
+///     type Family = CutsceneFamily;
+///     fn swap(self, _input : ()) -> Cutscene { self.into() }
+///     fn is_final(&self) -> bool { true }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum Cutscene {
+///         Playing(Sequence<CutsceneFamily, ShotFamily>),
+///         Finished(Finished),
+///         Aborted(Failure<CutsceneFamily, &'static str>),
+///     }
+/// }
+/// 
+/// fn shot(length : u32) -> Automaton<ShotFamily> {
+///     ShotFamily::automaton_with_mode(Shot::Playing { frames : 0, length })
+/// }
+/// 
+/// fn cutscene(lengths : &[u32]) -> Automaton<CutsceneFamily> {
+///     let mut sequence =
+///         Sequence::new(
+///             |lengths| Finished(lengths).into(),
+///             |reason| Failure::new(reason).into());
+///     for length in lengths {
+///         sequence.add_child(shot(*length));
+///     }
+///     CutsceneFamily::automaton_with_mode(sequence.into())
+/// }
+/// 
+/// let mut automaton = cutscene(&[2, 1]);
+/// while !Automaton::is_finished(&automaton) { Automaton::next(&mut automaton); }
+/// assert!(matches!(&*automaton, Cutscene::Finished(Finished(lengths)) if *lengths == vec![2, 1]));
+/// 
+/// let mut automaton = cutscene(&[1, 0, 3]);
+/// while !Automaton::is_finished(&automaton) { Automaton::next(&mut automaton); }
+/// assert!(matches!(&*automaton, Cutscene::Aborted(failure) if *failure.error() == "empty shot"));
+/// ```
+/// 
+pub struct Sequence<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    children : VecDeque<Automaton<C>>,
+    values : Vec<Value<C>>,
+    on_success : Box<SuccessFn<F, C>>,
+    on_failure : Box<FailureFn<F, C>>,
+}
+
+impl<F, C> Sequence<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    /// Creates a new `Sequence` with no children. Once every child has succeeded, the `Sequence` transitions to the
+    /// `Family::Output` returned by `on_success`. If a child fails, the `Sequence` transitions to the `Family::Output`
+    /// returned by `on_failure` instead.
+    /// 
+    pub fn new<S, E>(on_success : S, on_failure : E) -> Self
+        where
+            S : FnOnce(Vec<Value<C>>) -> F::Output + Send + Sync + 'static,
+            E : FnOnce(Error<C>) -> F::Output + Send + Sync + 'static,
+    {
+        Self {
+            children : VecDeque::new(),
+            values : Vec::new(),
+            on_success : Box::new(on_success),
+            on_failure : Box::new(on_failure),
+        }
+    }
+
+    /// Adds `child` to the end of the `Sequence`.
+    /// 
+    pub fn add_child(&mut self, child : Automaton<C>) {
+        self.children.push_back(child);
+    }
+
+    /// Same as [`add_child()`](#method.add_child), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_child(mut self, child : Automaton<C>) -> Self {
+        self.add_child(child);
+        self
+    }
+
+    /// Returns the child that is currently running, or `None` if the `Sequence` is empty.
+    /// 
+    pub fn current(&self) -> Option<&Automaton<C>> {
+        self.children.front()
+    }
+
+    /// Returns the number of children that have not finished yet, including the one that is currently running.
+    /// 
+    pub fn remaining(&self) -> usize {
+        self.children.len()
+    }
+
+    /// Returns the values produced by the children that have already succeeded, in order.
+    /// 
+    pub fn values(&self) -> &[Value<C>] {
+        &self.values
+    }
+}
+
+impl<F, C> fmt::Debug for Sequence<F, C>
+    where
+        F : Family + ?Sized,
+        C : Family + ?Sized,
+        C::Mode : Finish,
+        <C::Mode as Finish>::Outcome : IntoResult,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Sequence")
+            .field("remaining", &self.children.len())
+            .field("succeeded", &self.values.len())
+            .finish()
+    }
+}
+
+impl<F, C, M> Mode for Sequence<F, C>
+    where
+        F : Family + ?Sized,
+        F::Output : From<Self>,
+        C : Family<Mode = M, Input = F::Input, Output = M> + ?Sized,
+        M : Mode<Family = C> + Finish,
+        M::Outcome : IntoResult,
+{
+    type Family = F;
+
+    fn swap(mut self, input : F::Input) -> F::Output {
+        let child =
+            match self.children.front_mut() {
+                Some(child) => child,
+                None => return (self.on_success)(self.values),
+            };
+
+        Automaton::next_with_input(child, input);
+        if Automaton::is_complete(child) {
+            let child = self.children.pop_front().unwrap();
+            let outcome =
+                Automaton::finish(child)
+                    .unwrap_or_else(|_| unreachable!("A complete Automaton can always be finished!"));
+            match outcome.into_result() {
+                Ok(value) => self.values.push(value),
+                Err(error) => return (self.on_failure)(error),
+            }
+
+            if self.children.is_empty() {
+                return (self.on_success)(self.values);
+            }
+        }
+
+        self.into()
+    }
+
+    fn set_path(&mut self, path : &str) {
+        for child in &mut self.children {
+            Automaton::set_parent(child, Some(path));
+        }
+    }
+
+    fn heap_size(&self) -> usize {
+        self.children.iter().map(|child| Automaton::footprint(child).total()).sum()
+    }
+}
@@ -0,0 +1,198 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines a recorder that reports the behavior of one or more `Automaton`s as OpenTelemetry spans.
+//! 
+//! A [`SpanRecorder`](struct.SpanRecorder.html) emits one span per state occupancy, i.e. a span that starts when an
+//! `Automaton` enters a `Mode` and ends when it leaves it, named after the [`tag()`](../trait.Mode.html#method.tag) of
+//! the `Mode`. Each span carries the name of the `Automaton` as the `mode.automaton` attribute, the `tag()` as the
+//! `mode.state` attribute, the [`path`](../struct.Automaton.html#method.path) of the `Automaton` as the `mode.path`
+//! attribute if it has been named, and one attribute for each of the [`fields()`](../trait.Mode.html#method.fields) of
+//! the `Mode`. Every transition is also recorded as a `mode.transition` event on the span being left, with `mode.from`
+//! and `mode.to` attributes, as well as the `fields()` of the `Mode` being entered.
+//! 
+//! Transitions are collected from [`TransitionEvent`](../struct.TransitionEvent.html)s, so the timestamps of each span
+//! reflect the time at which the transition actually took place, even though spans are only reported once
+//! [`poll()`](struct.SpanRecorder.html#method.poll) is called.
+//! 
+//! **NOTE:** This module is only available when the `otel` feature is enabled.
+//! 
+//! # Usage
+//! ```
+//! use mode::{otel::SpanRecorder, Automaton, Family, Mode};
+//! use opentelemetry::trace::noop::NoopTracer;
+//! 
+//! struct DoorFamily;
+//! impl Family for DoorFamily {
+//!     type Base = Door;
+//!     type Mode = Door;
+//!     type Input = ();
+//!     type Output = Door;
+//! }
+//! 
+//! enum Door { Open, Closed }
+//! impl Mode for Door {
+//!     type Family = DoorFamily;
+//!     fn swap(self, _input : ()) -> Self {
+//!         match self { Door::Open => Door::Closed, Door::Closed => Door::Open }
+//!     }
+//!     fn tag(&self) -> &'static str {
+//!         match self { Door::Open => "Open", Door::Closed => "Closed" }
+//!     }
+//! }
+//! 
+//! // In a real service, this would be a tracer from the configured TracerProvider, e.g. via
+//! // opentelemetry::global::tracer("mode").
+//! let mut recorder = SpanRecorder::new(NoopTracer::new());
+//! let mut door = DoorFamily::automaton_with_mode(Door::Closed);
+//! let track = recorder.attach("front_door", &mut door);
+//! 
+//! Automaton::next(&mut door);
+//! recorder.poll();
+//! assert_eq!(recorder.state(track), Some("Open"));
+//! 
+//! // Ends the spans for every state that is still occupied.
+//! recorder.end_all();
+//! assert_eq!(recorder.state(track), None);
+//! ```
+//! 
+
+use crate::{Automaton, Family, Field, TransitionEvent};
+use opentelemetry::{
+    trace::{Span, Tracer},
+    KeyValue,
+};
+use std::{
+    fmt,
+    sync::mpsc::Receiver,
+    time::{Instant, SystemTime},
+};
+
+struct Track<S> {
+    name : String,
+    current : Option<(&'static str, S)>,
+    events : Receiver<TransitionEvent>,
+}
+
+/// Reports the state occupancy of any number of `Automaton`s as OpenTelemetry spans created by a `Tracer`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct SpanRecorder<T>
+    where T : Tracer
+{
+    tracer : T,
+    tracks : Vec<Track<T::Span>>,
+}
+
+impl<T> SpanRecorder<T>
+    where T : Tracer
+{
+    /// Creates a new `SpanRecorder` that creates spans via `tracer`.
+    /// 
+    pub fn new(tracer : T) -> Self {
+        Self { tracer, tracks : Vec::new() }
+    }
+
+    /// Returns the `Tracer` used to create spans.
+    /// 
+    pub fn tracer(&self) -> &T {
+        &self.tracer
+    }
+
+    /// Subscribes to the `TransitionEvent`s of `automaton`, which is reported under `name`, and returns the index of the
+    /// track that was created for it. A span for the current `Mode` of `automaton` is started immediately.
+    /// 
+    pub fn attach<F>(&mut self, name : impl Into<String>, automaton : &mut Automaton<F>) -> usize
+        where F : Family + ?Sized
+    {
+        let name = name.into();
+        let tag = Automaton::tag(automaton);
+        let span = self.start(&name, Automaton::path(automaton), tag, &Automaton::fields(automaton), SystemTime::now());
+        self.tracks.push(Track { name, current : Some((tag, span)), events : Automaton::subscribe(automaton) });
+        self.tracks.len() - 1
+    }
+
+    /// Returns the `tag()` of the state that the `Automaton` for the track with index `track` is currently in, as far
+    /// as the `SpanRecorder` knows, or `None` if its spans have been ended.
+    /// 
+    /// # Panics
+    /// Panics if there is no track with index `track`.
+    /// 
+    pub fn state(&self, track : usize) -> Option<&'static str> {
+        self.tracks[track].current.as_ref().map(|(tag, _)| *tag)
+    }
+
+    /// Collects every pending `TransitionEvent` from each attached `Automaton`, ending the span for every state that
+    /// was left, and starting a span for every state that was entered.
+    /// 
+    pub fn poll(&mut self) {
+        for index in 0..self.tracks.len() {
+            let events : Vec<TransitionEvent> = self.tracks[index].events.try_iter().collect();
+            for event in events {
+                let at = system_time(event.at);
+                if let Some((_, mut span)) = self.tracks[index].current.take() {
+                    let mut attributes =
+                        vec![KeyValue::new("mode.from", event.from), KeyValue::new("mode.to", event.to)];
+                    attributes.extend(event.fields.iter().map(attribute));
+                    span.add_event_with_timestamp("mode.transition", at, attributes);
+                    span.end_with_timestamp(at);
+                }
+
+                let span = self.start(&self.tracks[index].name, event.path.as_deref(), event.to, &event.fields, at);
+                self.tracks[index].current = Some((event.to, span));
+            }
+        }
+    }
+
+    /// Collects every pending `TransitionEvent` via [`poll()`](#method.poll), and then ends the span for the current
+    /// state of every attached `Automaton`. Transitions that happen afterwards start new spans the next time `poll()`
+    /// is called.
+    /// 
+    pub fn end_all(&mut self) {
+        self.poll();
+        for track in &mut self.tracks {
+            if let Some((_, mut span)) = track.current.take() {
+                span.end();
+            }
+        }
+    }
+
+    fn start(&self, automaton : &str, path : Option<&str>, tag : &'static str, fields : &[Field], at : SystemTime)
+        -> T::Span
+    {
+        let mut attributes =
+            vec![KeyValue::new("mode.automaton", automaton.to_owned()), KeyValue::new("mode.state", tag)];
+        attributes.extend(path.map(|path| KeyValue::new("mode.path", path.to_owned())));
+        attributes.extend(fields.iter().map(attribute));
+        self.tracer.span_builder(tag)
+            .with_start_time(at)
+            .with_attributes(attributes)
+            .start(&self.tracer)
+    }
+}
+
+impl<T> fmt::Debug for SpanRecorder<T>
+    where T : Tracer
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("SpanRecorder")
+            .field("tracks", &self.tracks.iter().map(|track| &track.name).collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Converts `field` into an attribute with the same name and value.
+/// 
+fn attribute(field : &Field) -> KeyValue {
+    KeyValue::new(field.name, field.value.clone())
+}
+
+/// Converts `at` into the equivalent `SystemTime`, assuming that it is in the past.
+/// 
+fn system_time(at : Instant) -> SystemTime {
+    SystemTime::now() - Instant::now().saturating_duration_since(at)
+}
@@ -0,0 +1,459 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines types for persisting the current `Mode` of an `Automaton`, and restoring it later, possibly from a snapshot
+//! taken by an older version of the program.
+//! 
+//! A [`Snapshot`](struct.Snapshot.html) records the `tag()` of the current `Mode`, the schema version of the `Mode`
+//! type at the time the `Snapshot` was taken, and the serialized state of the `Mode` itself. A `Snapshot` implements
+//! `Serialize` and `Deserialize`, so it can be written out in any self-describing `serde` format, e.g. JSON or YAML.
+//! 
+//! Every `Mode` type that can be persisted declares its current schema version by implementing
+//! [`Versioned`](trait.Versioned.html). Whenever the serialized format of the `Mode` changes, the version should be
+//! incremented, and a migration function should be registered with [`Migrations`](struct.Migrations.html) that upgrades
+//! the serialized state from the previous version. When a `Snapshot` from an older version is restored, every
+//! migration between the version of the `Snapshot` and the current version is run, in order, before the `Mode` is
+//! deserialized.
+//! 
+//! If a `Snapshot` records when its `Mode` became current, restoring it also re-arms the clock of the `Automaton`, so
+//! that time-based behavior, e.g. a [minimum dwell time](../enum.Dwell.html), continues across a restart as if the
+//! program had never stopped.
+//! 
+//! Two `Snapshot`s can also be compared via [`Snapshot::diff()`](struct.Snapshot.html#method.diff), which reports
+//! whether the current `Mode` changed and which fields of the serialized state differ.
+//! 
+//! **NOTE:** This module is only available when the `serde` feature is enabled.
+//! 
+//! # Usage
+//! ```
+//! use mode::{persist::{Migrations, Snapshot, Versioned}, Automaton, Family, Mode};
+//! use serde::{Deserialize, Serialize};
+//! use serde_json::json;
+//! 
+//! struct DoorFamily;
+//! impl Family for DoorFamily {
+//!     type Base = Door;
+//!     type Mode = Door;
+//!     type Input = ();
+//!     type Output = Door;
+//! }
+//! 
+//! // Version 1 of Door was `{ "open" : bool }`. Version 2 replaced it with an angle.
+//! #[derive(Debug, PartialEq, Serialize, Deserialize)]
+//! struct Door { angle : u32 }
+//! 
+//! impl Versioned for Door { const VERSION : u32 = 2; }
+//! 
+//! impl Mode for Door {
+//!     type Family = DoorFamily;
+//!     fn swap(self, _input : ()) -> Self { self }
+//! }
+//! 
+//! let migrations =
+//!     Migrations::new()
+//!         .with_migration(1, |state| {
+//!             let open = state["open"].as_bool().unwrap_or(false);
+//!             Ok(json!({ "angle" : if open { 90 } else { 0 } }))
+//!         });
+//! 
+//! // A Snapshot written by version 1 of the program.
+//! let old : Snapshot = serde_json::from_str(r#"{ "version" : 1, "tag" : "Door", "state" : { "open" : true } }"#).unwrap();
+//! 
+//! let automaton = Automaton::<DoorFamily>::restore(old, &migrations).unwrap();
+//! assert_eq!(*automaton, Door { angle : 90 });
+//! 
+//! let new = Automaton::snapshot(&automaton).unwrap();
+//! assert_eq!(new.version, 2);
+//! ```
+//! 
+
+use crate::{Automaton, Family, Mode};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use serde_json::Value;
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    error::Error,
+    fmt,
+    time::SystemTime,
+};
+
+/// Trait that declares the current schema version of a persistable `Mode` type.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub trait Versioned {
+    /// The current schema version of the serialized form of this type. This should be incremented every time the
+    /// serialized form changes in a way that is not backwards compatible.
+    /// 
+    const VERSION : u32;
+}
+
+/// A serializable record of the current `Mode` of an `Automaton`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Snapshot {
+    /// The schema version of the `Mode` type at the time the `Snapshot` was taken.
+    pub version : u32,
+
+    /// The `tag()` of the `Mode` that was current at the time the `Snapshot` was taken.
+    pub tag : String,
+
+    /// The serialized state of the `Mode` itself.
+    pub state : Value,
+
+    /// The wall-clock time at which the current `Mode` became current, if recorded. This is used to carry
+    /// time-based behavior, e.g. a minimum dwell time, across a restart.
+    #[serde(default)]
+    pub entered_at : Option<SystemTime>,
+}
+
+impl Snapshot {
+    /// Compares this `Snapshot` against `other`, reporting whether the current `Mode` changed, and which fields of the
+    /// serialized state differ. This can be used to find out where two replicas of the same state machine diverged. The
+    /// `entered_at` times of the two `Snapshot`s are not compared.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::persist::Snapshot;
+    /// use serde_json::json;
+    /// 
+    /// let snapshot = |tag : &str, speed : u32| {
+    ///     Snapshot { version : 1, tag : tag.into(), state : json!({ "speed" : speed, "target" : [1, 2] }), entered_at : None }
+    /// };
+    /// 
+    /// let left = snapshot("Walking", 2);
+    /// let right = snapshot("Running", 5);
+    /// 
+    /// let diff = left.diff(&right);
+    /// assert_eq!(diff.tag, Some(("Walking".to_owned(), "Running".to_owned())));
+    /// assert_eq!(diff.fields.len(), 1);
+    /// assert_eq!(diff.fields[0].path, "/speed");
+    /// assert_eq!(diff.fields[0].left, Some(json!(2)));
+    /// assert_eq!(diff.fields[0].right, Some(json!(5)));
+    /// ```
+    /// 
+    pub fn diff(&self, other : &Snapshot) -> SnapshotDiff {
+        let mut fields = Vec::new();
+        diff_values(String::new(), Some(&self.state), Some(&other.state), &mut fields);
+
+        SnapshotDiff {
+            tag : if self.tag != other.tag { Some((self.tag.clone(), other.tag.clone())) } else { None },
+            version : if self.version != other.version { Some((self.version, other.version)) } else { None },
+            fields,
+        }
+    }
+}
+
+/// Recursively compares `left` and `right`, appending a `FieldDiff` to `fields` for every leaf value that differs.
+/// Paths are written as JSON pointers.
+/// 
+fn diff_values(path : String, left : Option<&Value>, right : Option<&Value>, fields : &mut Vec<FieldDiff>) {
+    match (left, right) {
+        (Some(Value::Object(left)), Some(Value::Object(right))) => {
+            let keys : BTreeSet<&String> = left.keys().chain(right.keys()).collect();
+            for key in keys {
+                let escaped = key.replace('~', "~0").replace('/', "~1");
+                diff_values(format!("{}/{}", path, escaped), left.get(key), right.get(key), fields);
+            }
+        },
+        (Some(Value::Array(left)), Some(Value::Array(right))) => {
+            for index in 0..left.len().max(right.len()) {
+                diff_values(format!("{}/{}", path, index), left.get(index), right.get(index), fields);
+            }
+        },
+        (left, right) => {
+            if left != right {
+                fields.push(FieldDiff { path, left : left.cloned(), right : right.cloned() });
+            }
+        },
+    }
+}
+
+/// The differences between two `Snapshot`s, as returned by [`Snapshot::diff()`](struct.Snapshot.html#method.diff).
+/// 
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct SnapshotDiff {
+    /// The `tag()` of the current `Mode` in each `Snapshot`, if they differ.
+    pub tag : Option<(String, String)>,
+
+    /// The schema version of each `Snapshot`, if they differ.
+    pub version : Option<(u32, u32)>,
+
+    /// Every field of the serialized state that differs between the two `Snapshot`s, in order of their paths.
+    pub fields : Vec<FieldDiff>,
+}
+
+impl SnapshotDiff {
+    /// Returns `true` if the two `Snapshot`s were identical.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.tag.is_none() && self.version.is_none() && self.fields.is_empty()
+    }
+}
+
+impl fmt::Display for SnapshotDiff {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        if let Some((left, right)) = &self.tag {
+            writeln!(formatter, "tag: {} => {}", left, right)?;
+        }
+
+        if let Some((left, right)) = &self.version {
+            writeln!(formatter, "version: {} => {}", left, right)?;
+        }
+
+        for field in &self.fields {
+            writeln!(formatter, "{}", field)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single field of the serialized state that differs between two `Snapshot`s.
+/// 
+#[derive(Clone, Debug, PartialEq)]
+pub struct FieldDiff {
+    /// The location of the field within the serialized state, as a JSON pointer, e.g. `/target/0`. The root of the
+    /// serialized state is represented by an empty path.
+    pub path : String,
+
+    /// The value of the field in the left `Snapshot`, or `None` if it is not present.
+    pub left : Option<Value>,
+
+    /// The value of the field in the right `Snapshot`, or `None` if it is not present.
+    pub right : Option<Value>,
+}
+
+impl fmt::Display for FieldDiff {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        let describe = |value : &Option<Value>| value.as_ref().map_or("<missing>".to_owned(), Value::to_string);
+        write!(formatter, "{}: {} => {}", self.path, describe(&self.left), describe(&self.right))
+    }
+}
+
+/// An error that occurred while persisting or restoring an `Automaton`.
+/// 
+#[derive(Debug)]
+pub enum PersistError {
+    /// The `Mode` could not be serialized or deserialized.
+    Serde(serde_json::Error),
+
+    /// No migration was registered to upgrade the serialized state from the specified version.
+    MissingMigration(u32),
+
+    /// The `Snapshot` was taken by a newer version of the program than the one restoring it.
+    Unsupported {
+        /// The version of the `Snapshot`.
+        found : u32,
+
+        /// The current version of the `Mode` type.
+        current : u32,
+    },
+
+    /// A migration function failed with the specified message.
+    Migration(String),
+}
+
+impl fmt::Display for PersistError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PersistError::Serde(error) => write!(formatter, "Failed to (de)serialize Mode: {}", error),
+            PersistError::MissingMigration(version) => {
+                write!(formatter, "No migration registered from version {}!", version)
+            },
+            PersistError::Unsupported { found, current } => {
+                write!(formatter, "Cannot restore a version {} snapshot with a version {} Mode!", found, current)
+            },
+            PersistError::Migration(message) => write!(formatter, "Migration failed: {}", message),
+        }
+    }
+}
+
+impl Error for PersistError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            PersistError::Serde(error) => Some(error),
+            _ => None,
+        }
+    }
+}
+
+impl From<serde_json::Error> for PersistError {
+    fn from(error : serde_json::Error) -> Self {
+        PersistError::Serde(error)
+    }
+}
+
+type MigrationFn = dyn Fn(Value) -> Result<Value, String> + Send + Sync;
+
+/// A set of migration functions, each of which upgrades the serialized state of a `Mode` from one schema version to
+/// the next.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Default)]
+pub struct Migrations {
+    steps : BTreeMap<u32, Box<MigrationFn>>,
+}
+
+impl Migrations {
+    /// Creates a new, empty set of `Migrations`.
+    /// 
+    pub fn new() -> Self {
+        Self { steps : BTreeMap::new() }
+    }
+
+    /// Registers `migration` as the function that upgrades serialized state from version `from` to version `from + 1`.
+    /// If another migration was already registered for `from`, it is replaced.
+    /// 
+    pub fn add_migration<M>(&mut self, from : u32, migration : M)
+        where M : Fn(Value) -> Result<Value, String> + Send + Sync + 'static
+    {
+        self.steps.insert(from, Box::new(migration));
+    }
+
+    /// Same as [`add_migration()`](#method.add_migration), except that it consumes and returns `self`, so that calls
+    /// can be chained together.
+    /// 
+    pub fn with_migration<M>(mut self, from : u32, migration : M) -> Self
+        where M : Fn(Value) -> Result<Value, String> + Send + Sync + 'static
+    {
+        self.add_migration(from, migration);
+        self
+    }
+
+    /// Runs every migration needed to upgrade the state in `snapshot` to version `current`, returning the upgraded
+    /// state.
+    /// 
+    pub fn migrate(&self, snapshot : Snapshot, current : u32) -> Result<Value, PersistError> {
+        if snapshot.version > current {
+            return Err(PersistError::Unsupported { found : snapshot.version, current });
+        }
+
+        let mut state = snapshot.state;
+        for version in snapshot.version..current {
+            let migration = self.steps.get(&version).ok_or(PersistError::MissingMigration(version))?;
+            state = migration(state).map_err(PersistError::Migration)?;
+        }
+
+        Ok(state)
+    }
+}
+
+impl fmt::Debug for Migrations {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Migrations")
+            .field("from", &self.steps.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+impl<F> Automaton<F>
+    where
+        F : Family + ?Sized,
+        F::Mode : Serialize + Versioned,
+{
+    /// Returns a [`Snapshot`](persist/struct.Snapshot.html) of the current `Mode`, which can be used to recreate the
+    /// `Automaton` later via [`Automaton::restore()`](#method.restore). Only the current `Mode` is recorded. Other state,
+    /// e.g. the `Blackboard` and step counters, is not.
+    /// 
+    /// The time at which the current `Mode` became current is only recorded if the `Automaton` has been started, so an
+    /// `Automaton` that is restored from a `Snapshot` of an unstarted `Automaton` has not been started either.
+    /// 
+    /// **NOTE:** This function is only available when the `serde` feature is enabled.
+    /// 
+    /// # Usage
+    /// ```
+    /// use mode::{persist::{Migrations, Versioned}, Automaton, Family, Mode};
+    /// use serde::{Deserialize, Serialize};
+    /// 
+    /// struct LampFamily;
+    /// impl Family for LampFamily {
+    ///     type Base = Lamp;
+    ///     type Mode = Lamp;
+    ///     type Input = ();
+    ///     type Output = Lamp;
+    /// }
+    /// 
+    /// #[derive(Debug, PartialEq, Serialize, Deserialize)]
+    /// struct Lamp { lit : bool }
+    /// 
+    /// impl Versioned for Lamp { const VERSION : u32 = 1; }
+    /// 
+    /// impl Mode for Lamp {
+    ///     type Family = LampFamily;
+    ///     fn swap(self, _input : ()) -> Self { Lamp { lit : !self.lit } }
+    /// }
+    /// 
+    /// // An Automaton that was never started is restored without being started.
+    /// let idle = Automaton::<LampFamily>::with_mode(Lamp { lit : false });
+    /// let snapshot = Automaton::snapshot(&idle).unwrap();
+    /// assert_eq!(snapshot.entered_at, None);
+    /// 
+    /// let restored = Automaton::<LampFamily>::restore(snapshot, &Migrations::new()).unwrap();
+    /// assert!(!Automaton::is_started(&restored));
+    /// assert_eq!(*restored, Lamp { lit : false });
+    /// 
+    /// // A started Automaton records when its Mode became current, and is started again when it is restored.
+    /// let mut running = Automaton::<LampFamily>::with_mode(Lamp { lit : false });
+    /// Automaton::next(&mut running);
+    /// let snapshot = Automaton::snapshot(&running).unwrap();
+    /// assert!(snapshot.entered_at.is_some());
+    /// 
+    /// let restored = Automaton::<LampFamily>::restore(snapshot, &Migrations::new()).unwrap();
+    /// assert!(Automaton::is_started(&restored));
+    /// assert_eq!(*restored, Lamp { lit : true });
+    /// ```
+    /// 
+    pub fn snapshot(this : &Self) -> Result<Snapshot, PersistError> {
+        let mode = this.borrow_current();
+
+        let entered_at =
+            if Self::is_started(this) { SystemTime::now().checked_sub(Self::time_in_mode(this)) }
+            else { None };
+
+        Ok(Snapshot {
+            version : F::Mode::VERSION,
+            tag : mode.tag().to_owned(),
+            state : serde_json::to_value(mode)?,
+            entered_at,
+        })
+    }
+}
+
+impl<F> Automaton<F>
+    where
+        F : Family + ?Sized,
+        F::Mode : DeserializeOwned + Versioned,
+{
+    /// Creates a new `Automaton` whose initial `Mode` is deserialized from `snapshot`, after running any `migrations`
+    /// that are necessary to upgrade it to the current version of the `Mode` type.
+    /// 
+    /// If `snapshot` recorded when its `Mode` became current, the restored `Automaton` is started immediately, and its
+    /// clock is re-armed so that `Automaton::time_in_mode()` includes all wall-clock time that has passed since then,
+    /// including while the program was not running. This allows time-based behavior, such as a minimum dwell time, to
+    /// survive a restart. Otherwise, the restored `Automaton` has not been started yet.
+    /// 
+    /// **NOTE:** This function is only available when the `serde` feature is enabled.
+    /// 
+    /// See the [`persist` module documentation](persist/index.html) for more details.
+    /// 
+    pub fn restore(snapshot : Snapshot, migrations : &Migrations) -> Result<Self, PersistError> {
+        let entered_at = snapshot.entered_at;
+        let state = migrations.migrate(snapshot, F::Mode::VERSION)?;
+        let mut automaton = Self::with_mode(serde_json::from_value(state)?);
+
+        if let Some(entered_at) = entered_at {
+            // If the clock went backwards, treat the Mode as having just become current.
+            let elapsed = SystemTime::now().duration_since(entered_at).unwrap_or_default();
+            Self::resume_after(&mut automaton, elapsed);
+        }
+
+        Ok(automaton)
+    }
+}
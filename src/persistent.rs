@@ -0,0 +1,219 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Field, Mode};
+use std::{borrow::Borrow, fmt, ops::Deref, sync::Arc};
+
+/// Trait for the `Family::Output` of a `Family`, which splits it into the next `Mode` and the value returned alongside
+/// it, if any. This is implemented both for `M` itself, in which case `()` is returned alongside it, and for `(M, T)`
+/// tuples, in which case the `T` is returned, so that [`Persistent::step()`](struct.Persistent.html#method.step) can
+/// be used with either kind of `Family`.
+/// 
+pub trait SplitOutput<M> {
+    /// The type of the value that is returned alongside the next `Mode`.
+    /// 
+    type Value;
+
+    /// Splits `self` into the next `Mode` and the value returned alongside it.
+    /// 
+    fn split(self) -> (M, Self::Value);
+}
+
+impl<M> SplitOutput<M> for M {
+    type Value = ();
+
+    fn split(self) -> (M, ()) {
+        (self, ())
+    }
+}
+
+impl<M, T> SplitOutput<M> for (M, T) {
+    type Value = T;
+
+    fn split(self) -> (M, T) {
+        self
+    }
+}
+
+/// An immutable, persistent state machine value, for Elm-style architectures in which every step produces a new
+/// version of the state machine instead of mutating it in place.
+/// 
+/// Stepping a `Persistent` via [`step()`](#method.step) consumes it and returns the next version, along with any value
+/// returned by `Mode::swap()`. Cloning a `Persistent` is cheap, since the current `Mode` is shared between the clones
+/// via an `Arc`, so every historical version can be retained simply by cloning it before it is stepped. When a version
+/// that is still shared with a clone is stepped, its `Mode` is cloned first, so that `swap()` can take it by value. To
+/// avoid deep-copying large state payloads whenever that happens, they can be wrapped in a
+/// [`CowState`](struct.CowState.html), which is then shared structurally between all versions that didn't change it.
+/// 
+/// Unlike an `Automaton`, a `Persistent` has no lifecycle, and doesn't support subscribers, timeouts, or any of the
+/// other features that require mutable bookkeeping. It only calls `Mode::enter()` on the initial `Mode` when the
+/// `Persistent` is created, and on each new `Mode` whose `tag()` differs from that of the previous one. Just as with an
+/// `Automaton`, once a [final](trait.Mode.html#method.is_final) `Mode` becomes current, `step()` no longer calls
+/// `swap()`. A `Persistent` can be turned into an `Automaton` at any time via
+/// [`into_automaton()`](#method.into_automaton).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct CounterFamily;
+/// impl Family for CounterFamily {
+///     type Base = Counter;
+///     type Mode = Counter;
+///     type Input = u32;
+///     type Output = (Counter, bool);
+/// }
+/// 
+/// #[derive(Clone, Debug, PartialEq)]
+/// struct Counter { total : u32 }
+/// impl Mode for Counter {
+///     type Family = CounterFamily;
+///     fn swap(self, amount : u32) -> (Self, bool) {
+///         let total = self.total + amount;
+///         (Counter { total }, total > 5)
+///     }
+/// }
+/// 
+/// let mut history = vec![Persistent::<CounterFamily>::new(Counter { total : 0 })];
+/// for amount in 1..=3 {
+///     let (next, over) = history.last().unwrap().clone().step(amount);
+///     assert_eq!(over, Some(next.total > 5));
+///     history.push(next);
+/// }
+/// 
+/// let totals : Vec<_> = history.iter().map(|version| version.total).collect();
+/// assert_eq!(totals, vec![0, 1, 3, 6]);
+/// assert_eq!(history[3].steps(), 3);
+/// ```
+/// 
+pub struct Persistent<F>
+    where F : Family + ?Sized
+{
+    mode : Arc<F::Mode>,
+    steps : u64,
+}
+
+impl<F> Persistent<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Persistent` with `mode` as its current `Mode`, calling
+    /// [`Mode::enter()`](trait.Mode.html#method.enter) on it.
+    /// 
+    pub fn new(mut mode : F::Mode) -> Self {
+        mode.enter();
+        Self { mode : Arc::new(mode), steps : 0 }
+    }
+
+    /// Returns the number of times the `Persistent` has been stepped since it was created.
+    /// 
+    pub fn steps(&self) -> u64 {
+        self.steps
+    }
+
+    /// Returns the `tag()` of the current `Mode`.
+    /// 
+    pub fn tag(&self) -> &'static str {
+        self.mode.tag()
+    }
+
+    /// Returns `true` if the current `Mode` is [final](trait.Mode.html#method.is_final), in which case
+    /// [`step()`](#method.step) no longer calls `swap()`.
+    /// 
+    pub fn is_final(&self) -> bool {
+        self.mode.is_final()
+    }
+
+    /// Returns the [`fields()`](trait.Mode.html#method.fields) of the current `Mode`.
+    /// 
+    pub fn fields(&self) -> Vec<Field> {
+        self.mode.fields()
+    }
+
+    /// Returns `true` if `this` and `other` share the same `Mode` instance, e.g. because one is a clone of the other.
+    /// 
+    pub fn ptr_eq(this : &Self, other : &Self) -> bool {
+        Arc::ptr_eq(&this.mode, &other.mode)
+    }
+
+    /// Returns the current `Mode` by value, cloning it only if it is still shared with another version.
+    /// 
+    pub fn into_mode(self) -> F::Mode
+        where F::Mode : Clone
+    {
+        Arc::try_unwrap(self.mode).unwrap_or_else(|mode| (*mode).clone())
+    }
+
+    /// Converts the `Persistent` into an `Automaton` with the same current `Mode`, cloning the `Mode` only if it is
+    /// still shared with another version. Since the current `Mode` has already been entered, the `Automaton` is
+    /// returned already [started](struct.Automaton.html#method.start), and `Mode::enter()` is not called on it again.
+    /// 
+    pub fn into_automaton(self) -> Automaton<F>
+        where F::Mode : Clone
+    {
+        Automaton::with_entered_mode(self.into_mode())
+    }
+}
+
+impl<F, M> Persistent<F>
+    where
+        F : Family<Mode = M> + ?Sized,
+        M : Mode<Family = F> + Clone,
+        F::Output : SplitOutput<M>,
+{
+    /// Consumes the `Persistent` and passes `input` into the `swap()` function of the current `Mode`, returning the
+    /// next version of the `Persistent`, along with the value returned alongside the next `Mode`. If the current `Mode`
+    /// is [final](#method.is_final), `swap()` is not called, and the `Persistent` is returned unchanged, along with
+    /// `None`.
+    /// 
+    pub fn step(self, input : F::Input) -> (Self, Option<<F::Output as SplitOutput<M>>::Value>) {
+        if self.is_final() {
+            return (self, None);
+        }
+
+        let steps = self.steps + 1;
+        let current = self.into_mode();
+        let from = current.tag();
+        let (mut next, value) = current.swap(input).split();
+        if next.tag() != from {
+            next.enter();
+        }
+
+        (Self { mode : Arc::new(next), steps }, Some(value))
+    }
+}
+
+impl<F> Clone for Persistent<F>
+    where F : Family + ?Sized
+{
+    fn clone(&self) -> Self {
+        Self { mode : Arc::clone(&self.mode), steps : self.steps }
+    }
+}
+
+impl<F> Deref for Persistent<F>
+    where
+        F : Family + ?Sized,
+        F::Mode : Borrow<F::Base>,
+{
+    type Target = F::Base;
+
+    /// Returns an immutable reference to the current `Mode` as a `&F::Base`.
+    /// 
+    fn deref(&self) -> &F::Base {
+        (*self.mode).borrow()
+    }
+}
+
+impl<F> fmt::Debug for Persistent<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Persistent")
+            .field("tag", &self.tag())
+            .field("steps", &self.steps)
+            .finish()
+    }
+}
@@ -0,0 +1,28 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+/// Represents the lifecycle phase of an `Automaton`.
+/// 
+/// Every `Automaton` begins in the `Idle` phase. Calling `Automaton::start()` (or any of the `Automaton::next*()`
+/// functions, which start the `Automaton` implicitly) runs the [`Mode::enter()`](trait.Mode.html#method.enter) hook of
+/// the initial `Mode` and moves the `Automaton` into the `Running` phase. Once the `Automaton` is stopped, or a
+/// [final](trait.Mode.html#method.is_final) `Mode` becomes current, it moves into the `Finished` phase, and will no
+/// longer allow the current `Mode` to `swap()`.
+/// 
+/// See [`Automaton::phase()`](struct.Automaton.html#method.phase) for more details.
+/// 
+#[derive(Copy, Clone, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub enum Phase {
+    /// The `Automaton` has been created, but the initial `Mode` has not been entered yet.
+    Idle,
+
+    /// The initial `Mode` has been entered, and the `Automaton` is allowing the current `Mode` to `swap()`.
+    Running,
+
+    /// The `Automaton` has finished, and will no longer allow the current `Mode` to `swap()`.
+    Finished,
+}
@@ -0,0 +1,283 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Field, FinalMode, Mode};
+use std::{mem, ops::Deref, rc::Rc, sync::Arc};
+
+/// Trait for smart pointer types that can be used as the `Family::Mode` of a `dyn Trait` `Family`, e.g. `Box<T>`,
+/// `Rc<T>`, or `Arc<T>`.
+/// 
+/// A `ModePointer` describes the operations that differ between pointer types, i.e. whether the value it points to can
+/// be moved out of it, and whether it can be accessed mutably. Together with [`PointerMode`](trait.PointerMode.html),
+/// this allows the one `Mode` implementation for pointers to be written generically, so that a new kind of pointer,
+/// e.g. a `SmallBox`, only requires an implementation of this trait, without another module of forwarding code.
+/// 
+/// See [`PointerMode`](trait.PointerMode.html) for an example.
+/// 
+pub trait ModePointer : Deref + Sized {
+    /// Moves the value out of the pointer if no other pointer shares it, or returns the pointer unchanged otherwise.
+    /// 
+    fn try_unwrap(this : Self) -> Result<Self::Target, Self>
+        where Self::Target : Sized;
+
+    /// Returns a mutable reference to the value if no other pointer shares it, or `None` otherwise.
+    /// 
+    fn get_mut(this : &mut Self) -> Option<&mut Self::Target>;
+}
+
+impl<T> ModePointer for Box<T>
+    where T : ?Sized
+{
+    fn try_unwrap(this : Self) -> Result<T, Self>
+        where T : Sized
+    {
+        Ok(*this)
+    }
+
+    fn get_mut(this : &mut Self) -> Option<&mut T> {
+        Some(&mut **this)
+    }
+}
+
+impl<T> ModePointer for Rc<T>
+    where T : ?Sized
+{
+    fn try_unwrap(this : Self) -> Result<T, Self>
+        where T : Sized
+    {
+        Rc::try_unwrap(this)
+    }
+
+    fn get_mut(this : &mut Self) -> Option<&mut T> {
+        Rc::get_mut(this)
+    }
+}
+
+impl<T> ModePointer for Arc<T>
+    where T : ?Sized
+{
+    fn try_unwrap(this : Self) -> Result<T, Self>
+        where T : Sized
+    {
+        Arc::try_unwrap(this)
+    }
+
+    fn get_mut(this : &mut Self) -> Option<&mut T> {
+        Arc::get_mut(this)
+    }
+}
+
+/// Returns the value that `this` points to, moving it out of the pointer without copying it if no other pointer
+/// shares it, and cloning it otherwise, which leaves the shared value untouched for everyone else.
+/// 
+pub fn into_owned<P>(this : P) -> P::Target
+    where
+        P : ModePointer,
+        P::Target : Clone,
+{
+    P::try_unwrap(this).unwrap_or_else(|shared| (*shared).clone())
+}
+
+/// Trait for the value that a [`ModePointer`](trait.ModePointer.html) `P` points to, which defines the transition
+/// behavior of a state whose `Family::Mode` is `P`. Every `P` whose target implements `PointerMode<P>` implements
+/// [`Mode`](trait.Mode.html), forwarding each call to its target, and reporting the size of the target in addition to
+/// its `heap_size()`.
+/// 
+/// The [`boxed`](boxed/index.html), [`rc`](rc/index.html), and [`sync`](sync/index.html) modules each define an
+/// alternate `trait Mode` whose `swap()` function takes `self` wrapped in the corresponding pointer type, and which
+/// implements `PointerMode` for every type that implements it, so that `PointerMode` rarely needs to be implemented
+/// directly. Since stable Rust doesn't allow a `self` parameter to be wrapped in an arbitrary pointer type, the
+/// functions of this trait take the pointer as a regular parameter named `this` instead.
+/// 
+/// # Usage
+/// ```
+/// use mode::{Automaton, Family, ModePointer, PointerMode};
+/// use std::{ops::Deref, rc::Rc};
+/// 
+/// // A custom pointer type that shares its contents, just like an Rc.
+/// struct Shared<T : ?Sized>(Rc<T>);
+/// impl<T : ?Sized> Deref for Shared<T> {
+///     type Target = T;
+///     fn deref(&self) -> &T { &self.0 }
+/// }
+/// impl<T : ?Sized> ModePointer for Shared<T> {
+///     fn try_unwrap(this : Self) -> Result<T, Self> where T : Sized { Rc::try_unwrap(this.0).map_err(Shared) }
+///     fn get_mut(this : &mut Self) -> Option<&mut T> { Rc::get_mut(&mut this.0) }
+/// }
+/// 
+/// struct LightFamily;
+/// impl Family for LightFamily {
+///     type Base = dyn Light;
+///     type Mode = Shared<dyn Light>;
+///     type Input = ();
+///     type Output = Shared<dyn Light>;
+/// }
+/// 
+/// trait Light {
+///     fn toggle(self : Rc<Self>) -> Shared<dyn Light>;
+///     fn is_on(&self) -> bool;
+/// }
+/// 
+/// impl PointerMode<Shared<dyn Light>> for dyn Light {
+///     type Family = LightFamily;
+///     fn swap(this : Shared<dyn Light>, _input : ()) -> Shared<dyn Light> { this.0.toggle() }
+///     fn tag(&self) -> &'static str { if self.is_on() { "On" } else { "Off" } }
+/// }
+/// 
+/// struct On;
+/// impl Light for On {
+///     fn toggle(self : Rc<Self>) -> Shared<dyn Light> { Shared(Rc::new(Off)) }
+///     fn is_on(&self) -> bool { true }
+/// }
+/// 
+/// struct Off;
+/// impl Light for Off {
+///     fn toggle(self : Rc<Self>) -> Shared<dyn Light> { Shared(Rc::new(On)) }
+///     fn is_on(&self) -> bool { false }
+/// }
+/// 
+/// let mut light = LightFamily::automaton_with_mode(Shared(Rc::new(Off)));
+/// assert_eq!(Automaton::tag(&light), "Off");
+/// 
+/// Automaton::next(&mut light);
+/// assert_eq!(Automaton::tag(&light), "On");
+/// ```
+/// 
+pub trait PointerMode<P> {
+    /// The `Family` type to which this `Mode` implementation belongs.
+    /// 
+    /// See [`Mode::Family`](trait.Mode.html#associatedtype.Family) for more details.
+    /// 
+    type Family : Family + ?Sized;
+
+    /// Consumes the pointer `this`, returning the next `Family::Output`.
+    /// 
+    /// See [`Mode::swap()`](trait.Mode.html#tymethod.swap) for more details.
+    /// 
+    fn swap(this : P, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output;
+
+    /// Returns a short name identifying the state represented by this `Mode`. By default, this returns the name of the
+    /// `Self` type, **not** the name of the pointer wrapping it.
+    /// 
+    /// See [`Mode::tag()`](trait.Mode.html#method.tag) for more details.
+    /// 
+    fn tag(&self) -> &'static str {
+        std::any::type_name::<Self>()
+    }
+
+    /// Called by the `Automaton` whenever this `Mode` becomes current. The pointer is passed in as-is, so that pointer
+    /// types that share their contents can decide whether to access them mutably, e.g. via
+    /// [`ModePointer::get_mut()`](trait.ModePointer.html#tymethod.get_mut).
+    /// 
+    /// See [`Mode::enter()`](trait.Mode.html#method.enter) for more details.
+    /// 
+    fn enter(_this : &mut P) { }
+
+    /// Called by the `Automaton` on the current `Mode` immediately before tearing it down without giving it a chance to
+    /// `swap()`.
+    /// 
+    /// See [`Mode::exit()`](trait.Mode.html#method.exit) for more details.
+    /// 
+    fn exit(_this : &mut P) { }
+
+    /// Returns `true` if this `Mode` is a terminal state.
+    /// 
+    /// See [`Mode::is_final()`](trait.Mode.html#method.is_final) for more details.
+    /// 
+    fn is_final(&self) -> bool { false }
+
+    /// Returns a set of structured fields describing the state represented by this `Mode`.
+    /// 
+    /// See [`Mode::fields()`](trait.Mode.html#method.fields) for more details.
+    /// 
+    fn fields(&self) -> Vec<Field> { Vec::new() }
+
+    /// Called by the `Automaton` with its path whenever this `Mode` becomes current.
+    /// 
+    /// See [`Mode::set_path()`](trait.Mode.html#method.set_path) for more details.
+    /// 
+    fn set_path(_this : &mut P, _path : &str) { }
+
+    /// Returns the number of bytes of heap memory owned by this `Mode`, not including the size of the `Mode` itself,
+    /// which is already reported by the pointer wrapping it.
+    /// 
+    /// See [`Mode::heap_size()`](trait.Mode.html#method.heap_size) for more details.
+    /// 
+    fn heap_size(&self) -> usize { 0 }
+}
+
+impl<P> Mode for P
+    where
+        P : ModePointer,
+        P::Target : PointerMode<P>,
+{
+    type Family = <P::Target as PointerMode<P>>::Family;
+
+    fn swap(self, input : <Self::Family as Family>::Input) -> <Self::Family as Family>::Output {
+        <P::Target as PointerMode<P>>::swap(self, input)
+    }
+
+    fn tag(&self) -> &'static str {
+        PointerMode::tag(&**self)
+    }
+
+    fn enter(&mut self) {
+        <P::Target as PointerMode<P>>::enter(self)
+    }
+
+    fn exit(&mut self) {
+        <P::Target as PointerMode<P>>::exit(self)
+    }
+
+    fn is_final(&self) -> bool {
+        PointerMode::is_final(&**self)
+    }
+
+    fn fields(&self) -> Vec<Field> {
+        PointerMode::fields(&**self)
+    }
+
+    fn set_path(&mut self, path : &str) {
+        <P::Target as PointerMode<P>>::set_path(self, path)
+    }
+
+    fn heap_size(&self) -> usize {
+        mem::size_of_val(&**self) + PointerMode::heap_size(&**self)
+    }
+}
+
+/// The equivalent of [`FinalMode`](trait.FinalMode.html) for the value that a
+/// [`ModePointer`](trait.ModePointer.html) `P` points to. Every `P` whose target implements `PointerFinalMode<P>`
+/// implements `FinalMode`, forwarding to its target.
+/// 
+/// The alternate `trait FinalMode` of each of the [`boxed`](boxed/index.html), [`rc`](rc/index.html), and
+/// [`sync`](sync/index.html) modules implements this for every type that implements it.
+/// 
+pub trait PointerFinalMode<P> {
+    /// The type of value computed by the state machine once it reaches a terminal state.
+    /// 
+    /// See [`FinalMode::Output`](trait.FinalMode.html#associatedtype.Output) for more details.
+    /// 
+    type Output;
+
+    /// Consumes the pointer `this` to the final `Mode` of an `Automaton`, returning its `Output`.
+    /// 
+    /// See [`FinalMode::into_output()`](trait.FinalMode.html#method.into_output) for more details.
+    /// 
+    fn into_output(this : P) -> Option<Self::Output>;
+}
+
+impl<P> FinalMode for P
+    where
+        P : ModePointer,
+        P::Target : PointerFinalMode<P>,
+{
+    type Output = <P::Target as PointerFinalMode<P>>::Output;
+
+    fn into_output(self) -> Option<Self::Output> {
+        <P::Target as PointerFinalMode<P>>::into_output(self)
+    }
+}
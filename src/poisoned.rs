@@ -0,0 +1,64 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{error::Error, fmt};
+
+/// The error returned by the `try_` accessors of an `Automaton` that no longer has a current `Mode`.
+/// 
+/// The current `Mode` is moved out of the `Automaton` for the duration of each swap. If user code called during the
+/// swap panics, e.g. `Mode::swap()` itself, and the panic is caught via `std::panic::catch_unwind()`, the `Automaton`
+/// is left without a current `Mode`, and is said to be poisoned. Every other way of using the `Automaton` leaves it
+/// with a current `Mode`, so `Poisoned` is never returned unless a panic was caught.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::panic::{self, AssertUnwindSafe};
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = Fragile;
+///     type Mode = Fragile;
+///     type Input = ();
+///     type Output = Fragile;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// struct Fragile(u32);
+/// impl Mode for Fragile {
+///     type Family = SomeFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         if self.0 == 1 { panic!("Broken!"); }
+///         Fragile(self.0 + 1)
+///     }
+/// }
+/// 
+/// let mut automaton = SomeFamily::automaton_with_mode(Fragile(0));
+/// assert_eq!(Automaton::try_next(&mut automaton), Ok(()));
+/// assert_eq!(automaton.try_borrow_mode(), Ok(&Fragile(1)));
+/// 
+/// # let hook = panic::take_hook();
+/// # panic::set_hook(Box::new(|_| { }));
+/// let result = panic::catch_unwind(AssertUnwindSafe(|| Automaton::next(&mut automaton)));
+/// # panic::set_hook(hook);
+/// assert!(result.is_err());
+/// 
+/// assert!(Automaton::is_poisoned(&automaton));
+/// assert_eq!(automaton.try_borrow_mode(), Err(Poisoned));
+/// assert_eq!(Automaton::try_next(&mut automaton), Err(Poisoned));
+/// ```
+/// 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Poisoned;
+
+impl fmt::Display for Poisoned {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Cannot access the current Mode because a previous swap panicked!")
+    }
+}
+
+impl Error for Poisoned { }
@@ -0,0 +1,310 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, Footprint, Mode, TransitionEvent};
+use std::{
+    collections::HashMap,
+    fmt,
+    hash::Hash,
+    sync::mpsc::{self, Receiver, Sender},
+};
+
+struct Entry<F>
+    where F : Family + ?Sized
+{
+    automaton : Automaton<F>,
+    events : Receiver<TransitionEvent>,
+}
+
+/// Manages a collection of `Automaton`s within the same `Family`, each identified by a unique key of type `K`, e.g. one
+/// state machine per connection ID.
+/// 
+/// All `Automaton`s in the `Registry` can be stepped at once via [`step_all()`](#method.step_all), or individually via
+/// [`step()`](#method.step). The `Registry` also collects the [`TransitionEvent`](struct.TransitionEvent.html)s
+/// published by each `Automaton` and forwards them, along with the key of the `Automaton` that transitioned, to every
+/// `Receiver` returned by [`subscribe()`](#method.subscribe). Events are forwarded every time the `Registry` steps an
+/// `Automaton`, as well as by [`flush_events()`](#method.flush_events).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct ConnectionFamily;
+/// impl Family for ConnectionFamily {
+///     type Base = Connection;
+///     type Mode = Connection;
+///     type Input = ();
+///     type Output = Connection;
+/// }
+/// 
+/// enum Connection { Handshake, Open }
+/// impl Mode for Connection {
+///     type Family = ConnectionFamily;
+///     fn swap(self, _input : ()) -> Self { Connection::Open }
+///     fn tag(&self) -> &'static str {
+///         match self { Connection::Handshake => "Handshake", Connection::Open => "Open" }
+///     }
+/// }
+/// 
+/// let mut registry = Registry::new();
+/// let events = registry.subscribe();
+/// 
+/// registry.insert(1, ConnectionFamily::automaton_with_mode(Connection::Handshake));
+/// registry.insert(2, ConnectionFamily::automaton_with_mode(Connection::Open));
+/// registry.step_all();
+/// 
+/// let (id, event) = events.try_recv().unwrap();
+/// assert_eq!((id, event.from, event.to), (1, "Handshake", "Open"));
+/// assert!(events.try_recv().is_err());
+/// ```
+/// 
+pub struct Registry<K, F>
+    where F : Family + ?Sized
+{
+    entries : HashMap<K, Entry<F>>,
+    subscribers : Vec<Sender<(K, TransitionEvent)>>,
+}
+
+impl<K, F> Default for Registry<K, F>
+    where
+        K : Eq + Hash,
+        F : Family + ?Sized,
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<K, F> Registry<K, F>
+    where
+        K : Eq + Hash,
+        F : Family + ?Sized,
+{
+    /// Creates a new, empty `Registry`.
+    /// 
+    pub fn new() -> Self {
+        Self { entries : HashMap::new(), subscribers : Vec::new() }
+    }
+
+    /// Adds `automaton` to the `Registry` under `key`. If another `Automaton` was already registered under `key`, it
+    /// is replaced and returned.
+    /// 
+    pub fn insert(&mut self, key : K, mut automaton : Automaton<F>) -> Option<Automaton<F>> {
+        let events = Automaton::subscribe(&mut automaton);
+        self.entries.insert(key, Entry { automaton, events })
+            .map(|entry| entry.automaton)
+    }
+
+    /// Removes the `Automaton` registered under `key` from the `Registry` and returns it, if any. Any pending events
+    /// from it are discarded.
+    /// 
+    pub fn remove(&mut self, key : &K) -> Option<Automaton<F>> {
+        self.entries.remove(key)
+            .map(|entry| entry.automaton)
+    }
+
+    /// Returns an immutable reference to the `Automaton` registered under `key`, if any.
+    /// 
+    pub fn get(&self, key : &K) -> Option<&Automaton<F>> {
+        self.entries.get(key)
+            .map(|entry| &entry.automaton)
+    }
+
+    /// Returns a mutable reference to the `Automaton` registered under `key`, if any.
+    /// 
+    pub fn get_mut(&mut self, key : &K) -> Option<&mut Automaton<F>> {
+        self.entries.get_mut(key)
+            .map(|entry| &mut entry.automaton)
+    }
+
+    /// Returns `true` if an `Automaton` is registered under `key`.
+    /// 
+    pub fn contains_key(&self, key : &K) -> bool {
+        self.entries.contains_key(key)
+    }
+
+    /// Returns the number of `Automaton`s in the `Registry`.
+    /// 
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    /// Returns `true` if the `Registry` contains no `Automaton`s.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Releases any spare capacity left behind by `Automaton`s and subscribers that have been removed from the
+    /// `Registry`, so that memory usage stays bounded over long sessions. See
+    /// [`Batch::compact()`](struct.Batch.html#method.compact) for when to call this.
+    /// 
+    pub fn compact(&mut self) {
+        self.entries.shrink_to_fit();
+        self.subscribers.shrink_to_fit();
+    }
+
+    /// Returns an iterator over the keys of all `Automaton`s in the `Registry`, in arbitrary order.
+    /// 
+    pub fn keys(&self) -> impl Iterator<Item = &K> + '_ {
+        self.entries.keys()
+    }
+
+    /// Returns an iterator over every key and `Automaton` in the `Registry`, in arbitrary order.
+    /// 
+    pub fn iter(&self) -> impl Iterator<Item = (&K, &Automaton<F>)> + '_ {
+        self.entries.iter()
+            .map(|(key, entry)| (key, &entry.automaton))
+    }
+
+    /// Returns an iterator over every key and a mutable reference to each `Automaton` in the `Registry`, in arbitrary
+    /// order.
+    /// 
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (&K, &mut Automaton<F>)> + '_ {
+        self.entries.iter_mut()
+            .map(|(key, entry)| (key, &mut entry.automaton))
+    }
+
+    /// Returns the combined [`footprint()`](struct.Automaton.html#method.footprint) of the current `Mode`s of every
+    /// `Automaton` in the `Registry`.
+    /// 
+    pub fn footprint(&self) -> Footprint {
+        self.iter().map(|(_, automaton)| Automaton::footprint(automaton)).sum()
+    }
+}
+
+impl<K, F> Registry<K, F>
+    where
+        K : Clone + Eq + Hash,
+        F : Family + ?Sized,
+{
+    /// Returns a `Receiver` that will be sent the key of the `Automaton` along with a copy of every
+    /// [`TransitionEvent`](struct.TransitionEvent.html) published by any `Automaton` in the `Registry`. Dropping the
+    /// `Receiver` will automatically unsubscribe it.
+    /// 
+    pub fn subscribe(&mut self) -> Receiver<(K, TransitionEvent)> {
+        let (sender, receiver) = mpsc::channel();
+        self.subscribers.push(sender);
+        receiver
+    }
+
+    /// Forwards all pending `TransitionEvent`s from every `Automaton` in the `Registry` to all subscribers. This only
+    /// needs to be called if `Automaton`s are stepped outside of the `Registry`, e.g. via `get_mut()`.
+    /// 
+    pub fn flush_events(&mut self) {
+        let subscribers = &mut self.subscribers;
+        for (key, entry) in self.entries.iter() {
+            Self::forward(subscribers, key, entry);
+        }
+    }
+
+    fn forward(subscribers : &mut Vec<Sender<(K, TransitionEvent)>>, key : &K, entry : &Entry<F>) {
+        for event in entry.events.try_iter() {
+            subscribers.retain(|subscriber| subscriber.send((key.clone(), event.clone())).is_ok());
+        }
+    }
+}
+
+impl<K, F, M> Registry<K, F>
+    where
+        K : Clone + Eq + Hash,
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Calls `Automaton::next()` on the `Automaton` registered under `key`, returning `false` if there is no such
+    /// `Automaton`.
+    /// 
+    pub fn step(&mut self, key : &K) -> bool {
+        match self.entries.get_mut(key) {
+            Some(entry) => {
+                Automaton::next(&mut entry.automaton);
+                Self::forward(&mut self.subscribers, key, entry);
+                true
+            },
+            None => false,
+        }
+    }
+
+    /// Calls `Automaton::next()` on every `Automaton` in the `Registry`, in arbitrary order.
+    /// 
+    pub fn step_all(&mut self) {
+        let subscribers = &mut self.subscribers;
+        for (key, entry) in self.entries.iter_mut() {
+            Automaton::next(&mut entry.automaton);
+            Self::forward(subscribers, key, entry);
+        }
+    }
+}
+
+impl<K, F, M, Input> Registry<K, F>
+    where
+        K : Clone + Eq + Hash,
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Calls `Automaton::next_with_input()` on every `Automaton` in the `Registry`, in arbitrary order, passing in the
+    /// `Input` returned by calling `input` with the key of each `Automaton`.
+    /// 
+    pub fn step_all_with_input(&mut self, mut input : impl FnMut(&K) -> Input) {
+        let subscribers = &mut self.subscribers;
+        for (key, entry) in self.entries.iter_mut() {
+            Automaton::next_with_input(&mut entry.automaton, input(key));
+            Self::forward(subscribers, key, entry);
+        }
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, F, M, Input> Registry<K, F>
+    where
+        K : Clone + Eq + Hash + Send + Sync,
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F> + Send,
+{
+    /// Same as [`step_all_with_input()`](#method.step_all_with_input), except that the `Automaton`s in the `Registry`
+    /// are stepped in parallel on the `rayon` thread pool. Since `input` may be called from multiple threads at once, it
+    /// must be `Sync`. Once every `Automaton` has been stepped, all pending `TransitionEvent`s are forwarded to
+    /// subscribers from the calling thread.
+    /// 
+    /// **NOTE:** This function is only available when the `rayon` feature is enabled.
+    /// 
+    pub fn par_step_all_with_input(&mut self, input : impl Fn(&K) -> Input + Sync) {
+        use rayon::prelude::*;
+
+        self.entries.par_iter_mut()
+            .for_each(|(key, entry)| Automaton::next_with_input(&mut entry.automaton, input(key)));
+        self.flush_events();
+    }
+}
+
+#[cfg(feature = "rayon")]
+impl<K, F, M> Registry<K, F>
+    where
+        K : Clone + Eq + Hash + Send + Sync,
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F> + Send,
+{
+    /// Same as [`step_all()`](#method.step_all), except that the `Automaton`s in the `Registry` are stepped in parallel
+    /// on the `rayon` thread pool.
+    /// 
+    /// **NOTE:** This function is only available when the `rayon` feature is enabled.
+    /// 
+    pub fn par_step_all(&mut self) {
+        self.par_step_all_with_input(|_| ());
+    }
+}
+
+impl<K, F> fmt::Debug for Registry<K, F>
+    where
+        K : fmt::Debug,
+        F : Family + ?Sized,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Registry")
+            .field("keys", &self.entries.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
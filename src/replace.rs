@@ -0,0 +1,64 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::mem;
+
+/// Replaces the value behind `slot` with the result of passing its current value into `f`, without the caller having
+/// to construct a temporary dummy state or wrap the value in an `Option` in order to move it out.
+/// 
+/// This makes it possible to write a transition for a concrete `struct` or `enum` `Mode` directly against a `&mut`
+/// reference, moving state out of the old value and into the new one, e.g. from inside a `&mut self` method or a
+/// field of a larger type. If `f` panics, `slot` is left holding the value returned by `fallback`, so that it is never
+/// observed in a moved-from state, even if the panic is caught further up the stack. `fallback` is called before `f`,
+/// and should be cheap and must not panic.
+/// 
+/// # Usage
+/// ```
+/// use mode::replace_with;
+/// use std::panic::{self, AssertUnwindSafe};
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Door { Closed { name : String }, Open { name : String }, Broken }
+/// 
+/// let mut door = Door::Closed { name : "Front".into() };
+/// replace_with(&mut door, || Door::Broken, |door| match door {
+///     Door::Closed { name } => Door::Open { name },
+///     other => other,
+/// });
+/// assert_eq!(door, Door::Open { name : "Front".into() });
+/// 
+/// let result = panic::catch_unwind(AssertUnwindSafe(|| {
+///     replace_with(&mut door, || Door::Broken, |_| panic!("The hinge snapped!"));
+/// }));
+/// assert!(result.is_err());
+/// assert_eq!(door, Door::Broken);
+/// ```
+/// 
+pub fn replace_with<T>(slot : &mut T, fallback : impl FnOnce() -> T, f : impl FnOnce(T) -> T) {
+    replace_with_and_return(slot, fallback, |value| (f(value), ()))
+}
+
+/// Same as [`replace_with()`](fn.replace_with.html), except that `T::default()` is used as the fallback value.
+/// 
+pub fn replace_with_or_default<T>(slot : &mut T, f : impl FnOnce(T) -> T)
+    where T : Default
+{
+    replace_with(slot, T::default, f)
+}
+
+/// Same as [`replace_with()`](fn.replace_with.html), except that `f` also returns an extra value alongside the new
+/// value for `slot`, which is passed back to the caller. This is useful for `Mode`s whose `swap()` function returns a
+/// `(Mode, T)` tuple as its `Output`.
+/// 
+pub fn replace_with_and_return<T, R>(slot : &mut T, fallback : impl FnOnce() -> T, f : impl FnOnce(T) -> (T, R))
+    -> R
+{
+    // NOTE: If f() panics, the fallback value stays in place, so it's impossible to observe a moved-from slot.
+    let current = mem::replace(slot, fallback());
+    let (next, result) = f(current);
+    *slot = next;
+    result
+}
@@ -0,0 +1,153 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::Family;
+use std::fmt;
+
+type BuildFn<F> = dyn FnOnce(<F as Family>::Mode) -> <F as Family>::Mode + Send + Sync;
+type RejectFn = dyn FnOnce() + Send + Sync;
+
+/// A transition that was requested from outside of an `Automaton` via
+/// [`Automaton::request_transition()`](struct.Automaton.html#method.request_transition), and is waiting to be applied.
+/// 
+/// Whenever the `Automaton` is next stepped, the pending request with the highest priority wins, and its builder is
+/// called with the current `Mode` in order to create the `Mode` that becomes current. Every other pending request is
+/// rejected, and its [`on_rejected()`](#method.on_rejected) callback, if any, is called.
+/// 
+pub struct TransitionRequest<F>
+    where F : Family + ?Sized
+{
+    priority : i32,
+    build : Box<BuildFn<F>>,
+    on_rejected : Option<Box<RejectFn>>,
+}
+
+impl<F> TransitionRequest<F>
+    where F : Family + ?Sized
+{
+    pub(crate) fn new(priority : i32, build : Box<BuildFn<F>>) -> Self {
+        Self { priority, build, on_rejected : None }
+    }
+
+    /// Returns the priority of this request. When several requests are pending, the one with the highest priority wins,
+    /// with ties being broken in favor of the request that was made first.
+    /// 
+    pub fn priority(&self) -> i32 {
+        self.priority
+    }
+
+    /// Sets the callback that will be called if this request loses to another request with a higher priority, or if the
+    /// `Automaton` finishes before the request can be applied.
+    /// 
+    pub fn on_rejected(&mut self, on_rejected : impl FnOnce() + Send + Sync + 'static) -> &mut Self {
+        self.on_rejected = Some(Box::new(on_rejected));
+        self
+    }
+
+    /// Consumes the request, calling its `on_rejected()` callback, if any.
+    /// 
+    pub(crate) fn reject(self) {
+        if let Some(on_rejected) = self.on_rejected {
+            on_rejected();
+        }
+    }
+
+    /// Consumes the request, returning its builder.
+    /// 
+    pub(crate) fn into_builder(self) -> Box<BuildFn<F>> {
+        self.build
+    }
+}
+
+impl<F> fmt::Debug for TransitionRequest<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("TransitionRequest")
+            .field("priority", &self.priority)
+            .finish()
+    }
+}
+
+/// Trait for `Mode`s that can be created from the current `Mode` of an `Automaton` over the `Family` `F`, plus some
+/// extra `Args`, when they are requested via [`Automaton::request()`](struct.Automaton.html#method.request).
+/// 
+/// Since the current `Mode` is passed in by value, state can be moved out of it and into the new `Mode`, just as with
+/// `Mode::swap()`, even though the transition was initiated from outside of the `Automaton`. The new `Mode` is then
+/// converted into `F::Mode` via `Into`, e.g. using one of the `From` impls generated by
+/// [`enum_mode!`](macro.enum_mode.html).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct HeroFamily;
+/// impl Family for HeroFamily {
+///     type Base = dyn Hero;
+///     type Mode = State;
+///     type Input = ();
+///     type Output = State;
+/// }
+/// 
+/// trait Hero {
+///     fn inventory(&self) -> &[&'static str];
+///     fn is_stunned(&self) -> bool { false }
+/// }
+/// 
+/// struct Exploring { inventory : Vec<&'static str> }
+/// impl Hero for Exploring { fn inventory(&self) -> &[&'static str] { &self.inventory } }
+/// impl Mode for Exploring {
+///     type Family = HeroFamily;
+///     fn swap(self, _input : ()) -> State { self.into() }
+/// }
+/// 
+/// struct Stunned { inventory : Vec<&'static str>, turns : u32 }
+/// impl Hero for Stunned {
+///     fn inventory(&self) -> &[&'static str] { &self.inventory }
+///     fn is_stunned(&self) -> bool { true }
+/// }
+/// impl Mode for Stunned {
+///     type Family = HeroFamily;
+///     fn swap(self, _input : ()) -> State {
+///         if self.turns > 1 { Stunned { turns : self.turns - 1, ..self }.into() }
+///         else { Exploring { inventory : self.inventory }.into() }
+///     }
+/// }
+/// 
+/// mode::enum_mode! {
+///     enum State : dyn Hero {
+///         Exploring(Exploring),
+///         Stunned(Stunned),
+///     }
+/// }
+/// 
+/// impl EnterFrom<HeroFamily, u32> for Stunned {
+///     fn enter_from(current : State, turns : u32) -> Self {
+///         // Move the inventory into the new Mode instead of cloning it.
+///         let inventory =
+///             match current {
+///                 State::Exploring(Exploring { inventory }) | State::Stunned(Stunned { inventory, .. }) => inventory,
+///             };
+///         Stunned { inventory, turns }
+///     }
+/// }
+/// 
+/// let mut hero = HeroFamily::automaton_with_mode(Exploring { inventory : vec!["sword"] }.into());
+/// Automaton::request::<Stunned, _>(&mut hero, 0, 2);
+/// 
+/// Automaton::next(&mut hero);
+/// assert!(hero.is_stunned());
+/// assert_eq!(hero.inventory(), ["sword"]);
+/// ```
+/// 
+pub trait EnterFrom<F, Args = ()> : Into<<F as Family>::Mode>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Mode` from the `current` `Mode` of an `Automaton` and the `args` that were passed into
+    /// `Automaton::request()`.
+    /// 
+    fn enter_from(current : F::Mode, args : Args) -> Self;
+}
@@ -0,0 +1,776 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines an executor-agnostic interface for driving an `Automaton` from async code.
+//! 
+//! The async helpers in this crate never depend on a particular async runtime directly. Instead, they are written
+//! against the [`Runtime`](trait.Runtime.html) trait, which abstracts over spawning tasks and waiting on timers. Adapters
+//! for several popular runtimes are provided behind feature flags:
+//! 
+//! - [`Threads`](struct.Threads.html), which needs no async executor and is always available,
+//! - [`Tokio`](struct.Tokio.html), behind the `tokio` feature,
+//! - [`AsyncStd`](struct.AsyncStd.html), behind the `async-std` feature, and
+//! - [`Smol`](struct.Smol.html), behind the `smol` feature.
+//! 
+//! Any other runtime can be supported by implementing `Runtime` for it.
+//! 
+//! In addition, any `Automaton` whose `Mode`s implement [`Finish`](../trait.Finish.html) can be `.await`ed directly,
+//! resolving with the `Outcome` of its final `Mode` once it finishes. See [`Completion`](struct.Completion.html) for
+//! more details. When the `futures` feature is enabled, [`InputSink`](struct.InputSink.html) also allows `Input`s to be
+//! fed into an `Automaton` as a `futures::Sink`, and [`ModeStreamExt`](trait.ModeStreamExt.html) allows a
+//! `futures::Stream` of `Input`s to be mapped through an `Automaton`.
+//! 
+//! # Usage
+//! ```
+//! use mode::{runtime::{self, BoxFuture, Runtime}, Automaton, Family, Mode};
+//! use std::{future::Future, task::{Context, Poll, Waker}, time::Duration};
+//! 
+//! struct CountdownFamily;
+//! impl Family for CountdownFamily {
+//!     type Base = Countdown;
+//!     type Mode = Countdown;
+//!     type Input = ();
+//!     type Output = Countdown;
+//! }
+//! 
+//! struct Countdown(u32);
+//! impl Mode for Countdown {
+//!     type Family = CountdownFamily;
+//!     fn swap(self, _input : ()) -> Self { Countdown(self.0 - 1) }
+//!     fn is_final(&self) -> bool { self.0 == 0 }
+//! }
+//! 
+//! // A trivial Runtime whose timers complete immediately.
+//! struct Immediate;
+//! impl Runtime for Immediate {
+//!     fn spawn(&self, _future : BoxFuture) { unimplemented!() }
+//!     fn sleep(&self, _duration : Duration) -> BoxFuture { Box::pin(async { }) }
+//! }
+//! 
+//! let mut automaton = CountdownFamily::automaton_with_mode(Countdown(3));
+//! let mut ticking = Box::pin(runtime::tick_every(&Immediate, &mut automaton, Duration::from_millis(10)));
+//! 
+//! let mut context = Context::from_waker(Waker::noop());
+//! assert_eq!(ticking.as_mut().poll(&mut context), Poll::Ready(3));
+//! drop(ticking);
+//! 
+//! assert!(Automaton::is_finished(&automaton));
+//! ```
+//! 
+
+use crate::{Automaton, Family, Finish, FinishError, Mode};
+use std::{
+    fmt,
+    future::{Future, IntoFuture},
+    pin::Pin,
+    sync::{Arc, Mutex},
+    task::{Context, Poll, Wake, Waker},
+    thread,
+    time::{Duration, Instant},
+};
+
+#[cfg(feature = "futures")]
+use std::{collections::VecDeque, error::Error};
+
+/// A boxed, type-erased `Future` that can be sent to another thread.
+/// 
+pub type BoxFuture = Pin<Box<dyn Future<Output = ()> + Send + 'static>>;
+
+/// Abstracts over the services that the async helpers in this crate need from an async runtime.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub trait Runtime {
+    /// Spawns `future` as a new, detached task on the runtime.
+    /// 
+    fn spawn(&self, future : BoxFuture);
+
+    /// Returns a `Future` that completes once `duration` has elapsed.
+    /// 
+    fn sleep(&self, duration : Duration) -> BoxFuture;
+}
+
+/// Steps `automaton` once every `period` by calling `Automaton::next()`, waiting on the timers provided by `runtime`
+/// between steps, until the `Automaton` [finishes](../struct.Automaton.html#method.is_finished). Resolves to the number
+/// of steps that were taken.
+/// 
+pub async fn tick_every<R, F, M>(runtime : &R, automaton : &mut Automaton<F>, period : Duration) -> u64
+    where
+        R : Runtime + ?Sized,
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    let mut steps = 0;
+    while !Automaton::is_finished(automaton) {
+        runtime.sleep(period).await;
+        Automaton::next(automaton);
+        steps += 1;
+    }
+    steps
+}
+
+/// Moves `automaton` into a new task spawned on `runtime`, which steps it once every `period` as in
+/// [`tick_every()`](fn.tick_every.html). Once the `Automaton` finishes, it is passed into `on_finish`, e.g. so that its
+/// final result can be harvested.
+/// 
+pub fn spawn_every<R, F, M>(
+    runtime : &R,
+    mut automaton : Automaton<F>,
+    period : Duration,
+    on_finish : impl FnOnce(Automaton<F>) + Send + 'static)
+    where
+        R : Runtime + Clone + Send + Sync + 'static,
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized + 'static,
+        M : Mode<Family = F> + Send,
+{
+    let task_runtime = runtime.clone();
+    runtime.spawn(Box::pin(async move {
+        tick_every(&task_runtime, &mut automaton, period).await;
+        on_finish(automaton);
+    }));
+}
+
+/// A `Future` that steps an `Automaton` until it finishes, and then resolves with the `Outcome` of its final `Mode`.
+/// 
+/// Every time the `Completion` is polled, it calls `Automaton::next()` repeatedly, until the `Automaton` finishes or has
+/// to wait for something, so it is meant for state machines that run to completion on their own, e.g. a protocol
+/// handshake, which can then simply be `.await`ed from async code. (An `Automaton` that waits on external events should
+/// be fed via an [`InputSink`](struct.InputSink.html) or [`tick_every()`](fn.tick_every.html) instead, since a
+/// `Completion` has no way of knowing when those events arrive.) A `Completion` is usually created by `.await`ing an
+/// `Automaton` directly, via `IntoFuture`. If the final `Mode` uses [`Outcome`](../enum.Outcome.html) as its
+/// `Finish::Outcome`, the `Completion` resolves with the same `Outcome` that the combinators in the
+/// [`modes`](../modes/index.html) module consume.
+/// 
+/// The `Completion` resolves with `Ok` once the `Automaton` is [complete](../struct.Automaton.html#method.is_complete).
+/// If the `Automaton` is instead [stopped](../struct.Automaton.html#method.stop) while a non-final `Mode` is current,
+/// it resolves with a [`FinishError`](../struct.FinishError.html), from which the `Automaton` can be recovered.
+/// 
+/// While the `Automaton` is [paused](../struct.Automaton.html#method.pause), the `Completion` does not wake its task
+/// until the `Automaton` is resumed. While a [minimum dwell time](../struct.Automaton.html#method.set_min_dwell) holds
+/// the current `Mode` in place, the `Completion` waits on a single timer from its [`Runtime`](trait.Runtime.html) that
+/// expires once the minimum dwell time has elapsed, or once the timeout for the current `Mode` expires, if that comes
+/// first. The timer is only replaced if that point in time changes. In the meantime, the `Automaton` is not stepped, so
+/// e.g. a [`CancellationToken`](../struct.CancellationToken.html) that is cancelled during that time is only applied
+/// once the timer expires. A `Completion` created via `IntoFuture` or [`new()`](#method.new) uses the
+/// [`Threads`](struct.Threads.html) runtime, and one created via [`with_runtime()`](#method.with_runtime) uses the
+/// `Runtime` that is passed in.
+/// 
+/// # Usage
+/// ```
+/// use mode::{runtime::{Completion, Threads}, *};
+/// use std::{future::Future, pin::pin, task::{Context, Poll, Waker}, thread, time::Duration};
+/// 
+/// struct HandshakeFamily;
+/// impl Family for HandshakeFamily {
+///     type Base = Handshake;
+///     type Mode = Handshake;
+///     type Input = ();
+///     type Output = Handshake;
+/// }
+/// 
+/// enum Handshake { Hello, Ack, Established }
+/// impl Mode for Handshake {
+///     type Family = HandshakeFamily;
+///     fn swap(self, _input : ()) -> Self {
+///         match self { Handshake::Hello => Handshake::Ack, _ => Handshake::Established }
+///     }
+///     fn is_final(&self) -> bool { matches!(self, Handshake::Established) }
+/// }
+/// 
+/// impl Finish for Handshake {
+///     type Outcome = &'static str;
+///     fn finish(self) -> &'static str { "connected" }
+/// }
+/// 
+/// let automaton = HandshakeFamily::automaton_with_mode(Handshake::Hello);
+/// let mut handshake = pin!(async move { automaton.await });
+/// 
+/// let mut context = Context::from_waker(Waker::noop());
+/// assert!(matches!(handshake.as_mut().poll(&mut context), Poll::Ready(Ok("connected"))));
+/// 
+/// // While a minimum dwell time holds the Automaton in place, the Completion waits on a timer from its Runtime.
+/// let mut held = HandshakeFamily::automaton_with_mode(Handshake::Hello);
+/// Automaton::set_min_dwell(&mut held, Some(Dwell::Time(Duration::from_millis(10))));
+/// Automaton::start(&mut held);
+/// let mut handshake = pin!(Completion::with_runtime(held, Threads));
+/// assert!(handshake.as_mut().poll(&mut context).is_pending());
+/// 
+/// thread::sleep(Duration::from_millis(30));
+/// assert!(matches!(handshake.as_mut().poll(&mut context), Poll::Ready(Ok("connected"))));
+/// 
+/// // An Automaton that is stopped before reaching a final Mode resolves with an error instead.
+/// let mut stopped = HandshakeFamily::automaton_with_mode(Handshake::Hello);
+/// Automaton::stop(&mut stopped);
+/// let mut handshake = pin!(async move { stopped.await });
+/// assert!(matches!(handshake.as_mut().poll(&mut context), Poll::Ready(Err(_))));
+/// ```
+/// 
+pub struct Completion<F, R = Threads>
+    where F : Family + ?Sized
+{
+    automaton : Option<Automaton<F>>,
+    runtime : R,
+    timer : Option<(Instant, BoxFuture)>,
+}
+
+impl<F> Completion<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Completion` that steps `automaton` until it finishes, waiting on timers from the
+    /// [`Threads`](struct.Threads.html) runtime.
+    /// 
+    pub fn new(automaton : Automaton<F>) -> Self {
+        Self::with_runtime(automaton, Threads)
+    }
+}
+
+impl<F, R> Completion<F, R>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Completion` that steps `automaton` until it finishes, waiting on timers from `runtime`.
+    /// 
+    pub fn with_runtime(automaton : Automaton<F>, runtime : R) -> Self {
+        Self { automaton : Some(automaton), runtime, timer : None }
+    }
+}
+
+// NOTE: Neither the Automaton nor the Runtime is ever pinned, and the timer is pinned on the heap, so it is safe to move
+// all of them around even after the Completion has been pinned.
+impl<F, R> Unpin for Completion<F, R>
+    where F : Family + ?Sized
+{ }
+
+impl<F, M, R> Future for Completion<F, R>
+    where
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F> + Finish,
+        R : Runtime,
+{
+    type Output = Result<M::Outcome, FinishError<F>>;
+
+    fn poll(self : Pin<&mut Self>, context : &mut Context<'_>) -> Poll<Self::Output> {
+        let this = self.get_mut();
+        loop {
+            let automaton =
+                this.automaton.as_mut()
+                    .expect("Cannot poll a Completion after it has already resolved!");
+
+            if !Automaton::is_finished(automaton) {
+                Automaton::next(automaton);
+            }
+
+            if Automaton::is_finished(automaton) {
+                this.timer = None;
+                return Poll::Ready(Automaton::finish(this.automaton.take().unwrap()));
+            }
+
+            if Automaton::is_paused(automaton) {
+                this.timer = None;
+                Automaton::wake_on_resume(automaton, context.waker());
+                return Poll::Pending;
+            }
+
+            let release_at =
+                match Automaton::release_at(automaton) {
+                    Some(release_at) => release_at,
+                    None => {
+                        this.timer = None;
+                        continue;
+                    },
+                };
+
+            // Only re-arm the timer if the point in time at which the current Mode is released has changed.
+            if this.timer.as_ref().map(|(armed_for, _)| *armed_for) != Some(release_at) {
+                let delay = release_at.saturating_duration_since(Instant::now());
+                this.timer = Some((release_at, this.runtime.sleep(delay)));
+            }
+
+            let (_, timer) = this.timer.as_mut().unwrap();
+            match timer.as_mut().poll(context) {
+                Poll::Ready(()) => this.timer = None,
+                Poll::Pending => return Poll::Pending,
+            }
+        }
+    }
+}
+
+impl<F, R> fmt::Debug for Completion<F, R>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Completion")
+            .field("resolved", &self.automaton.is_none())
+            .field("waiting", &self.timer.is_some())
+            .finish()
+    }
+}
+
+impl<F, M> IntoFuture for Automaton<F>
+    where
+        F : Family<Mode = M, Input = (), Output = M> + ?Sized,
+        M : Mode<Family = F> + Finish,
+{
+    type Output = Result<M::Outcome, FinishError<F>>;
+    type IntoFuture = Completion<F>;
+
+    fn into_future(self) -> Completion<F> {
+        Completion::new(self)
+    }
+}
+
+/// The error returned by [`InputSink`](struct.InputSink.html) when an `Input` is sent to an `Automaton` that has
+/// already finished.
+/// 
+/// **NOTE:** This type is only available when the `futures` feature is enabled.
+/// 
+#[cfg(feature = "futures")]
+#[derive(Copy, Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "defmt", derive(defmt::Format))]
+pub struct Finished;
+
+#[cfg(feature = "futures")]
+impl fmt::Display for Finished {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "Cannot send an Input to an Automaton that has already finished!")
+    }
+}
+
+#[cfg(feature = "futures")]
+impl Error for Finished { }
+
+/// A `futures::Sink` that accepts `Input`s for an `Automaton` and passes each of them into
+/// `Automaton::next_with_input()` as they arrive.
+/// 
+/// `Input`s are buffered in an internal queue holding up to `capacity` items, and are drained into the `Automaton`
+/// whenever the `InputSink` is flushed, or whenever room is needed for a new `Input`. While the `Automaton` is paused,
+/// the queue cannot be drained, so once it is full, the `InputSink` applies backpressure by reporting that it is not
+/// ready to accept more `Input`s, and does not wake its task again until the `Automaton` is resumed, e.g. via
+/// [`automaton_mut()`](#method.automaton_mut). Once the `Automaton` finishes, any remaining `Input`s are discarded and
+/// sending further `Input`s fails with [`Finished`](struct.Finished.html).
+/// 
+/// **NOTE:** This type is only available when the `futures` feature is enabled.
+/// 
+/// # Usage
+/// ```
+/// use futures::{executor::block_on, stream, SinkExt, StreamExt};
+/// use mode::{runtime::InputSink, Automaton, Family, Mode};
+/// 
+/// struct SumFamily;
+/// impl Family for SumFamily {
+///     type Base = Sum;
+///     type Mode = Sum;
+///     type Input = u32;
+///     type Output = Sum;
+/// }
+/// 
+/// struct Sum(u32);
+/// impl Mode for Sum {
+///     type Family = SumFamily;
+///     fn swap(self, input : u32) -> Self { Sum(self.0 + input) }
+/// }
+/// 
+/// let mut sink = InputSink::new(SumFamily::automaton_with_mode(Sum(0)), 2);
+/// block_on(stream::iter(1..=4).map(Ok).forward(&mut sink)).unwrap();
+/// assert_eq!(sink.automaton().0, 10);
+/// ```
+/// 
+#[cfg(feature = "futures")]
+pub struct InputSink<F>
+    where F : Family + ?Sized
+{
+    automaton : Automaton<F>,
+    queue : VecDeque<F::Input>,
+    capacity : usize,
+}
+
+#[cfg(feature = "futures")]
+impl<F> InputSink<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `InputSink` that feeds `Input`s into `automaton`, buffering up to `capacity` of them at a time.
+    /// 
+    /// # Panics
+    /// Panics if `capacity` is zero.
+    /// 
+    pub fn new(automaton : Automaton<F>, capacity : usize) -> Self {
+        assert!(capacity > 0, "InputSink capacity must be greater than zero!");
+        Self { automaton, queue : VecDeque::with_capacity(capacity), capacity }
+    }
+
+    /// Returns an immutable reference to the wrapped `Automaton`.
+    /// 
+    pub fn automaton(&self) -> &Automaton<F> {
+        &self.automaton
+    }
+
+    /// Returns a mutable reference to the wrapped `Automaton`.
+    /// 
+    pub fn automaton_mut(&mut self) -> &mut Automaton<F> {
+        &mut self.automaton
+    }
+
+    /// Consumes the `InputSink`, returning the wrapped `Automaton`. Any `Input`s that have not been flushed yet are
+    /// discarded.
+    /// 
+    pub fn into_inner(self) -> Automaton<F> {
+        self.automaton
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<F, M, Input> InputSink<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Drains as many queued `Input`s into the `Automaton` as possible, returning `Err(Finished)` if the `Automaton` has
+    /// finished.
+    /// 
+    fn drain(&mut self) -> Result<(), Finished> {
+        while !Automaton::is_paused(&self.automaton) && !Automaton::is_finished(&self.automaton) {
+            match self.queue.pop_front() {
+                Some(input) => Automaton::next_with_input(&mut self.automaton, input),
+                None => break,
+            }
+        }
+
+        if Automaton::is_finished(&self.automaton) {
+            self.queue.clear();
+            Err(Finished)
+        }
+        else {
+            Ok(())
+        }
+    }
+}
+
+// NOTE: The Automaton and queued Inputs are never pinned, so it is safe to move them around.
+#[cfg(feature = "futures")]
+impl<F> Unpin for InputSink<F>
+    where F : Family + ?Sized
+{ }
+
+#[cfg(feature = "futures")]
+impl<F, M, Input> futures_sink::Sink<Input> for InputSink<F>
+    where
+        F : Family<Mode = M, Input = Input, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    type Error = Finished;
+
+    fn poll_ready(self : Pin<&mut Self>, context : &mut Context<'_>) -> Poll<Result<(), Finished>> {
+        let this = self.get_mut();
+        if this.queue.len() < this.capacity {
+            return Poll::Ready(Ok(()));
+        }
+
+        this.drain()?;
+
+        if this.queue.len() < this.capacity {
+            Poll::Ready(Ok(()))
+        }
+        else {
+            // The Automaton is paused, so check back once it is resumed.
+            Automaton::wake_on_resume(&mut this.automaton, context.waker());
+            Poll::Pending
+        }
+    }
+
+    fn start_send(self : Pin<&mut Self>, input : Input) -> Result<(), Finished> {
+        let this = self.get_mut();
+        if Automaton::is_finished(&this.automaton) {
+            return Err(Finished);
+        }
+
+        this.queue.push_back(input);
+        Ok(())
+    }
+
+    fn poll_flush(self : Pin<&mut Self>, context : &mut Context<'_>) -> Poll<Result<(), Finished>> {
+        let this = self.get_mut();
+        this.drain()?;
+
+        if this.queue.is_empty() {
+            Poll::Ready(Ok(()))
+        }
+        else {
+            // The Automaton is paused, so check back once it is resumed.
+            Automaton::wake_on_resume(&mut this.automaton, context.waker());
+            Poll::Pending
+        }
+    }
+
+    fn poll_close(self : Pin<&mut Self>, context : &mut Context<'_>) -> Poll<Result<(), Finished>> {
+        self.poll_flush(context)
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<F> fmt::Debug for InputSink<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("InputSink")
+            .field("queued", &self.queue.len())
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// Extension trait that adds the [`scan_modes()`](#method.scan_modes) combinator to every `futures::Stream`. For the
+/// equivalent combinator for ordinary `Iterator`s, see [`IteratorExt`](../trait.IteratorExt.html).
+/// 
+/// **NOTE:** This trait is only available when the `futures` feature is enabled.
+/// 
+#[cfg(feature = "futures")]
+pub trait ModeStreamExt : futures_core::Stream + Sized {
+    /// Maps this `Stream` of `Input`s to a `Stream` of `Output`s by passing each item into
+    /// `Automaton::next_with_input_and_output()` on `automaton`, and yielding whatever `Output` is returned. The
+    /// resulting `Stream` ends when this `Stream` ends, or as soon as `automaton` finishes.
+    /// 
+    /// # Usage
+    /// ```
+    /// use futures::{executor::block_on, stream, StreamExt};
+    /// use mode::{runtime::ModeStreamExt, Family, Mode};
+    /// 
+    /// struct DecoderFamily;
+    /// impl Family for DecoderFamily {
+    ///     type Base = Decoder;
+    ///     type Mode = Decoder;
+    ///     type Input = u8;
+    ///     type Output = (Decoder, Option<u16>);
+    /// }
+    /// 
+    /// // Decodes a stream of bytes into big-endian u16s.
+    /// enum Decoder { High, Low(u8) }
+    /// impl Mode for Decoder {
+    ///     type Family = DecoderFamily;
+    ///     fn swap(self, byte : u8) -> (Self, Option<u16>) {
+    ///         match self {
+    ///             Decoder::High => (Decoder::Low(byte), None),
+    ///             Decoder::Low(high) => (Decoder::High, Some(u16::from_be_bytes([high, byte]))),
+    ///         }
+    ///     }
+    /// }
+    /// 
+    /// let bytes = stream::iter(vec![0x01, 0x02, 0x03, 0x04]);
+    /// let words : Vec<_> =
+    ///     block_on(
+    ///         bytes.scan_modes(DecoderFamily::automaton_with_mode(Decoder::High))
+    ///             .filter_map(|word| async move { word })
+    ///             .collect());
+    /// 
+    /// assert_eq!(words, vec![0x0102, 0x0304]);
+    /// ```
+    /// 
+    fn scan_modes<F>(self, automaton : Automaton<F>) -> ScanModes<Self, F>
+        where F : Family<Input = Self::Item> + ?Sized
+    {
+        ScanModes { stream : self, automaton }
+    }
+}
+
+#[cfg(feature = "futures")]
+impl<S> ModeStreamExt for S
+    where S : futures_core::Stream
+{ }
+
+/// The `Stream` returned by [`ModeStreamExt::scan_modes()`](trait.ModeStreamExt.html#method.scan_modes).
+/// 
+/// **NOTE:** This type is only available when the `futures` feature is enabled.
+/// 
+#[cfg(feature = "futures")]
+pub struct ScanModes<S, F>
+    where F : Family + ?Sized
+{
+    stream : S,
+    automaton : Automaton<F>,
+}
+
+#[cfg(feature = "futures")]
+impl<S, F> ScanModes<S, F>
+    where F : Family + ?Sized
+{
+    /// Returns an immutable reference to the wrapped `Automaton`.
+    /// 
+    pub fn automaton(&self) -> &Automaton<F> {
+        &self.automaton
+    }
+
+    /// Consumes the `ScanModes`, returning the wrapped `Stream` and `Automaton`.
+    /// 
+    pub fn into_inner(self) -> (S, Automaton<F>) {
+        (self.stream, self.automaton)
+    }
+}
+
+// NOTE: The Automaton is never pinned, and the wrapped Stream is only ever polled while it is Unpin.
+#[cfg(feature = "futures")]
+impl<S, F> Unpin for ScanModes<S, F>
+    where
+        S : Unpin,
+        F : Family + ?Sized,
+{ }
+
+#[cfg(feature = "futures")]
+impl<S, F, M, Output> futures_core::Stream for ScanModes<S, F>
+    where
+        S : futures_core::Stream + Unpin,
+        F : Family<Mode = M, Input = S::Item, Output = (M, Output)> + ?Sized,
+        M : Mode<Family = F>,
+{
+    type Item = Output;
+
+    fn poll_next(self : Pin<&mut Self>, context : &mut Context<'_>) -> Poll<Option<Output>> {
+        let this = self.get_mut();
+        if Automaton::is_finished(&this.automaton) {
+            return Poll::Ready(None);
+        }
+
+        match Pin::new(&mut this.stream).poll_next(context) {
+            Poll::Ready(Some(input)) => {
+                Poll::Ready(Some(Automaton::next_with_input_and_output(&mut this.automaton, input)))
+            },
+            Poll::Ready(None) => Poll::Ready(None),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+}
+
+/// A `Runtime` that does not need an async executor at all, and is always available. Every task spawned on it runs on a
+/// new thread, which blocks until the task completes, and every timer is backed by a new thread that sleeps until the
+/// timer expires. This is the `Runtime` used by a [`Completion`](struct.Completion.html) that is created by `.await`ing
+/// an `Automaton` directly. Since threads are comparatively expensive, one of the other `Runtime`s should be preferred
+/// when an async executor is available.
+/// 
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Threads;
+
+impl Runtime for Threads {
+    fn spawn(&self, mut future : BoxFuture) {
+        thread::spawn(move || {
+            let waker = Waker::from(Arc::new(Unparker(thread::current())));
+            let mut context = Context::from_waker(&waker);
+            while future.as_mut().poll(&mut context).is_pending() {
+                thread::park();
+            }
+        });
+    }
+
+    fn sleep(&self, duration : Duration) -> BoxFuture {
+        Box::pin(Sleep { deadline : Instant::now().checked_add(duration), waker : None })
+    }
+}
+
+/// Wakes a task spawned on the [`Threads`](struct.Threads.html) runtime by unparking the thread that runs it.
+/// 
+struct Unparker(thread::Thread);
+
+impl Wake for Unparker {
+    fn wake(self : Arc<Self>) {
+        self.0.unpark();
+    }
+}
+
+/// A timer created by the [`Threads`](struct.Threads.html) runtime. The first time that it is polled before `deadline`,
+/// it spawns a single thread that sleeps until `deadline`, and then wakes whichever `Waker` was registered last.
+/// 
+struct Sleep {
+    deadline : Option<Instant>,
+    waker : Option<Arc<Mutex<Waker>>>,
+}
+
+impl Future for Sleep {
+    type Output = ();
+
+    fn poll(self : Pin<&mut Self>, context : &mut Context<'_>) -> Poll<()> {
+        let this = self.get_mut();
+
+        // A deadline that is too far in the future to be represented is never reached.
+        let deadline =
+            match this.deadline {
+                Some(deadline) => deadline,
+                None => return Poll::Pending,
+            };
+
+        if Instant::now() >= deadline {
+            return Poll::Ready(());
+        }
+
+        match &this.waker {
+            Some(waker) => waker.lock().unwrap_or_else(|error| error.into_inner()).clone_from(context.waker()),
+            None => {
+                let waker = Arc::new(Mutex::new(context.waker().clone()));
+                this.waker = Some(waker.clone());
+                thread::spawn(move || {
+                    thread::sleep(deadline.saturating_duration_since(Instant::now()));
+                    waker.lock().unwrap_or_else(|error| error.into_inner()).wake_by_ref();
+                });
+            },
+        }
+
+        Poll::Pending
+    }
+}
+
+/// A `Runtime` that spawns tasks and creates timers on the ambient `tokio` runtime. The functions of this `Runtime` must
+/// be called from within the context of a `tokio` runtime.
+/// 
+/// **NOTE:** This type is only available when the `tokio` feature is enabled.
+/// 
+#[cfg(feature = "tokio")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Tokio;
+
+#[cfg(feature = "tokio")]
+impl Runtime for Tokio {
+    fn spawn(&self, future : BoxFuture) {
+        tokio::spawn(future);
+    }
+
+    fn sleep(&self, duration : Duration) -> BoxFuture {
+        Box::pin(tokio::time::sleep(duration))
+    }
+}
+
+/// A `Runtime` that spawns tasks and creates timers via `async-std`.
+/// 
+/// **NOTE:** This type is only available when the `async-std` feature is enabled.
+/// 
+#[cfg(feature = "async-std")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct AsyncStd;
+
+#[cfg(feature = "async-std")]
+impl Runtime for AsyncStd {
+    fn spawn(&self, future : BoxFuture) {
+        async_std::task::spawn(future);
+    }
+
+    fn sleep(&self, duration : Duration) -> BoxFuture {
+        Box::pin(async_std::task::sleep(duration))
+    }
+}
+
+/// A `Runtime` that spawns tasks on the global `smol` executor and creates timers via `smol::Timer`.
+/// 
+/// **NOTE:** This type is only available when the `smol` feature is enabled.
+/// 
+#[cfg(feature = "smol")]
+#[derive(Copy, Clone, Debug, Default)]
+pub struct Smol;
+
+#[cfg(feature = "smol")]
+impl Runtime for Smol {
+    fn spawn(&self, future : BoxFuture) {
+        smol::spawn(future).detach();
+    }
+
+    fn sleep(&self, duration : Duration) -> BoxFuture {
+        let timer = smol::Timer::after(duration);
+        Box::pin(async move { timer.await; })
+    }
+}
@@ -0,0 +1,346 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines `Mode`s whose update and transition logic is written in [Rhai](https://rhai.rs) scripts, so that new states
+//! can be authored and tweaked without recompiling.
+//! 
+//! A [`Scripts`](struct.Scripts.html) collection maps the name of each state to a script. Each script is compiled
+//! once, when it is added, and then run every time the state is current and the `Automaton` is stepped via
+//! [`Automaton::next_with_blackboard()`](../struct.Automaton.html#method.next_with_blackboard). When run, the script
+//! can read and write the [`Variables`](struct.Variables.html) stored in the `Blackboard` of the `Automaton`, which are
+//! exposed to it as an object map called `blackboard`. The value of the last expression in the script decides what
+//! happens next:
+//! 
+//! - If it is the name of a state, e.g. `"Flee"`, that state becomes current.
+//! - If it is `()`, e.g. because the script ends in a statement, the current state stays current.
+//! 
+//! Any error raised while running the script, including returning the name of a state that does not exist, leaves
+//! the current state in place, and can be inspected via [`Scripted::error()`](struct.Scripted.html#method.error).
+//! 
+//! Rust functions can be made available to scripts by registering them with the
+//! [`Engine`](https://docs.rs/rhai/1/rhai/struct.Engine.html) returned by
+//! [`Scripts::engine_mut()`](struct.Scripts.html#method.engine_mut) before any scripts are added.
+//! 
+//! **NOTE:** This module is only available when the `rhai` feature is enabled.
+//! 
+//! # Usage
+//! ```
+//! use mode::{script::{ScriptFamily, Scripted, Scripts, Variables}, Automaton, Family};
+//! 
+//! let mut scripts = Scripts::new();
+//! scripts.add_state("Patrol", r#"
+//!     blackboard.steps += 1;
+//!     if blackboard.health < 30 { "Flee" }
+//! "#).unwrap();
+//! scripts.add_state("Flee", r#"
+//!     blackboard.health += 10;
+//!     if blackboard.health >= 50 { "Patrol" }
+//! "#).unwrap();
+//! 
+//! let mut guard = ScriptFamily::automaton_with_mode(Scripted::new(scripts, "Patrol").unwrap());
+//! 
+//! let mut variables = Variables::new();
+//! variables.set("health", 100_i64);
+//! variables.set("steps", 0_i64);
+//! Automaton::blackboard_mut(&mut guard).insert(variables);
+//! 
+//! Automaton::next_with_blackboard(&mut guard);
+//! assert_eq!(guard.state(), "Patrol");
+//! 
+//! Automaton::blackboard_mut(&mut guard).get_mut::<Variables>().unwrap().set("health", 20_i64);
+//! Automaton::next_with_blackboard(&mut guard);
+//! assert_eq!(guard.state(), "Flee");
+//! 
+//! let steps = Automaton::blackboard(&guard).get::<Variables>().unwrap().get::<i64>("steps");
+//! assert_eq!(steps, Some(2));
+//! ```
+//! 
+
+use crate::{table::intern, Blackboard, Family, Mode};
+use rhai::{Dynamic, Engine, Map, Scope, AST};
+use std::{collections::HashMap, error::Error, fmt, sync::Arc};
+
+/// The name of the variable through which scripts can access the `Variables` in the `Blackboard`.
+/// 
+const BLACKBOARD : &str = "blackboard";
+
+/// A set of dynamically-typed variables, stored in the `Blackboard` of an `Automaton` in order to share them with
+/// scripts.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug, Default)]
+pub struct Variables {
+    map : Map,
+}
+
+impl Variables {
+    /// Creates a new, empty set of `Variables`.
+    /// 
+    pub fn new() -> Self {
+        Self { map : Map::new() }
+    }
+
+    /// Sets the variable called `name` to `value`, returning the previous value, if any.
+    /// 
+    pub fn set(&mut self, name : &str, value : impl Into<Dynamic>) -> Option<Dynamic> {
+        self.map.insert(name.into(), value.into())
+    }
+
+    /// Returns the value of the variable called `name`, if it exists and has type `T`.
+    /// 
+    pub fn get<T>(&self, name : &str) -> Option<T>
+        where T : Clone + 'static
+    {
+        self.map.get(name).and_then(|value| value.clone().try_cast::<T>())
+    }
+
+    /// Removes the variable called `name`, returning its value, if any.
+    /// 
+    pub fn remove(&mut self, name : &str) -> Option<Dynamic> {
+        self.map.remove(name)
+    }
+
+    /// Returns the underlying Rhai object map.
+    /// 
+    pub fn as_map(&self) -> &Map {
+        &self.map
+    }
+
+    /// Returns the underlying Rhai object map mutably.
+    /// 
+    pub fn as_map_mut(&mut self) -> &mut Map {
+        &mut self.map
+    }
+}
+
+/// An error raised while compiling or running a script.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum ScriptError {
+    /// The script for the named state could not be compiled.
+    Compile {
+        /// The name of the state.
+        state : String,
+
+        /// A description of the error.
+        message : String,
+    },
+
+    /// The script for the named state raised an error while running.
+    Runtime {
+        /// The name of the state.
+        state : String,
+
+        /// A description of the error.
+        message : String,
+    },
+
+    /// A state referred to by name does not exist.
+    UnknownState(String),
+}
+
+impl fmt::Display for ScriptError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ScriptError::Compile { state, message } => {
+                write!(formatter, "Failed to compile the script for state \"{}\": {}", state, message)
+            },
+            ScriptError::Runtime { state, message } => {
+                write!(formatter, "The script for state \"{}\" failed: {}", state, message)
+            },
+            ScriptError::UnknownState(name) => write!(formatter, "State \"{}\" does not exist!", name),
+        }
+    }
+}
+
+impl Error for ScriptError { }
+
+/// A collection of named states, each of which is defined by a compiled script.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct Scripts {
+    engine : Engine,
+    states : HashMap<&'static str, AST>,
+}
+
+impl Default for Scripts {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Scripts {
+    /// Creates a new, empty collection of `Scripts` with a default Rhai `Engine`.
+    /// 
+    pub fn new() -> Self {
+        Self::with_engine(Engine::new())
+    }
+
+    /// Creates a new, empty collection of `Scripts` that will be compiled and run by `engine`.
+    /// 
+    pub fn with_engine(engine : Engine) -> Self {
+        Self { engine, states : HashMap::new() }
+    }
+
+    /// Returns the Rhai `Engine` used to compile and run scripts, e.g. in order to register Rust functions with it.
+    /// 
+    pub fn engine_mut(&mut self) -> &mut Engine {
+        &mut self.engine
+    }
+
+    /// Compiles `source` and adds it as the script for the state called `name`, replacing any previous script for that
+    /// state.
+    /// 
+    pub fn add_state(&mut self, name : &str, source : &str) -> Result<(), ScriptError> {
+        let ast =
+            self.engine.compile(source)
+                .map_err(|error| ScriptError::Compile { state : name.to_owned(), message : error.to_string() })?;
+        self.states.insert(intern(name), ast);
+        Ok(())
+    }
+
+    /// Same as [`add_state()`](#method.add_state), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_state(mut self, name : &str, source : &str) -> Result<Self, ScriptError> {
+        self.add_state(name, source)?;
+        Ok(self)
+    }
+
+    /// Returns `true` if a state called `name` has been added.
+    /// 
+    pub fn contains(&self, name : &str) -> bool {
+        self.states.contains_key(name)
+    }
+
+    /// Runs the script for `state`, returning the name of the next state, if the script asked for a transition.
+    /// 
+    fn run(&self, state : &'static str, variables : Variables) -> (Result<Option<&'static str>, ScriptError>, Variables) {
+        let mut scope = Scope::new();
+        scope.push(BLACKBOARD, variables.map);
+
+        let result = self.engine.eval_ast_with_scope::<Dynamic>(&mut scope, &self.states[state]);
+        let variables = Variables { map : scope.get_value::<Map>(BLACKBOARD).unwrap_or_default() };
+
+        let result =
+            result
+                .map_err(|error| ScriptError::Runtime { state : state.to_owned(), message : error.to_string() })
+                .and_then(|value| {
+                    if value.is_unit() {
+                        return Ok(None);
+                    }
+
+                    let name =
+                        value.into_string()
+                            .map_err(|found| ScriptError::Runtime {
+                                state : state.to_owned(),
+                                message : format!("Expected the name of a state or (), but found {}", found),
+                            })?;
+
+                    self.states.get_key_value(name.as_str())
+                        .map(|(name, _)| Some(*name))
+                        .ok_or(ScriptError::UnknownState(name))
+                });
+
+        (result, variables)
+    }
+}
+
+impl fmt::Debug for Scripts {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Scripts")
+            .field("states", &self.states.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Represents the `Family` of all scripted `Mode`s. The `Blackboard` is passed into each script, so that the
+/// `Automaton` should be stepped via
+/// [`Automaton::next_with_blackboard()`](../struct.Automaton.html#method.next_with_blackboard).
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct ScriptFamily;
+
+impl Family for ScriptFamily {
+    type Base = Scripted;
+    type Mode = Scripted;
+    type Input = Blackboard;
+    type Output = (Scripted, Blackboard);
+}
+
+/// A `Mode` that runs the script for the current state every time it is swapped.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug)]
+pub struct Scripted {
+    scripts : Arc<Scripts>,
+    current : &'static str,
+    error : Option<ScriptError>,
+}
+
+impl Scripted {
+    /// Creates a new `Scripted` `Mode` whose current state is the state called `initial` in `scripts`.
+    /// 
+    pub fn new(scripts : Scripts, initial : &str) -> Result<Self, ScriptError> {
+        Self::with_shared(Arc::new(scripts), initial)
+    }
+
+    /// Same as [`new()`](#method.new), except that the `Scripts` can be shared between several `Scripted` `Mode`s.
+    /// 
+    pub fn with_shared(scripts : Arc<Scripts>, initial : &str) -> Result<Self, ScriptError> {
+        let current =
+            scripts.states.get_key_value(initial)
+                .map(|(name, _)| *name)
+                .ok_or_else(|| ScriptError::UnknownState(initial.to_owned()))?;
+        Ok(Self { scripts, current, error : None })
+    }
+
+    /// Returns the name of the current state.
+    /// 
+    pub fn state(&self) -> &'static str {
+        self.current
+    }
+
+    /// Returns the `Scripts` that define every state.
+    /// 
+    pub fn scripts(&self) -> &Arc<Scripts> {
+        &self.scripts
+    }
+
+    /// Returns the error raised the last time the script for the current state was run, if any.
+    /// 
+    pub fn error(&self) -> Option<&ScriptError> {
+        self.error.as_ref()
+    }
+}
+
+impl Mode for Scripted {
+    type Family = ScriptFamily;
+
+    fn swap(mut self, mut blackboard : Blackboard) -> (Self, Blackboard) {
+        let variables = blackboard.remove::<Variables>().unwrap_or_default();
+        let (result, variables) = self.scripts.run(self.current, variables);
+        blackboard.insert(variables);
+
+        match result {
+            Ok(next) => {
+                self.current = next.unwrap_or(self.current);
+                self.error = None;
+            },
+            Err(error) => self.error = Some(error),
+        }
+
+        (self, blackboard)
+    }
+
+    fn tag(&self) -> &'static str {
+        self.current
+    }
+}
@@ -0,0 +1,183 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode, Poisoned};
+use std::{
+    fmt,
+    ops::{Deref, DerefMut},
+};
+
+/// A lightweight container for a concrete `enum` or `struct` `Mode` that allows it to be transitioned in place, by
+/// consuming the current value and moving its state into the next one, without adding a placeholder variant to the
+/// `enum` or implementing `Default` for it.
+/// 
+/// A `ModeSlot` is useful when a `Mode` is embedded in some larger type, e.g. as a field of a game entity, where a full
+/// `Automaton` would be overkill, and where transitioning through a `&mut` reference would otherwise require a dummy
+/// `Transitioning` variant to swap in while the old value is being consumed. The current value is moved out of the
+/// `ModeSlot` for the duration of each transition. As with `Automaton`, if the transition function panics and the panic
+/// is caught, the `ModeSlot` is left [poisoned](struct.Poisoned.html), and every further attempt to access it panics,
+/// except for the `try_` accessors, which return `Err(Poisoned)` instead.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct LightFamily;
+/// impl Family for LightFamily {
+///     type Base = Light;
+///     type Mode = Light;
+///     type Input = u32;
+///     type Output = Light;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Light { Off { hours : u32 }, On { hours : u32 } }
+/// 
+/// impl Mode for Light {
+///     type Family = LightFamily;
+///     fn swap(self, elapsed : u32) -> Self {
+///         match self {
+///             Light::Off { hours } => Light::On { hours },
+///             Light::On { hours } => Light::Off { hours : hours + elapsed },
+///         }
+///     }
+/// }
+/// 
+/// struct Lamp { light : ModeSlot<Light> }
+/// 
+/// let mut lamp = Lamp { light : ModeSlot::new(Light::Off { hours : 0 }) };
+/// lamp.light.swap(0);
+/// assert_eq!(*lamp.light, Light::On { hours : 0 });
+/// lamp.light.swap(3);
+/// assert_eq!(*lamp.light, Light::Off { hours : 3 });
+/// 
+/// lamp.light.transition(|light| match light {
+///     Light::Off { hours } => Light::Off { hours : hours * 2 },
+///     on => on,
+/// });
+/// assert_eq!(lamp.light.into_inner(), Light::Off { hours : 6 });
+/// ```
+/// 
+pub struct ModeSlot<M> {
+    mode : Option<M>,
+}
+
+impl<M> ModeSlot<M> {
+    /// Creates a new `ModeSlot` with `mode` as its current value.
+    /// 
+    pub fn new(mode : M) -> Self {
+        Self { mode : Some(mode) }
+    }
+
+    /// Consumes the current value by passing it into `f`, and replaces it with the value that `f` returns.
+    /// 
+    /// # Panics
+    /// Panics if the `ModeSlot` is [poisoned](struct.Poisoned.html). If `f` panics, the `ModeSlot` is left poisoned.
+    /// 
+    pub fn transition(&mut self, f : impl FnOnce(M) -> M) {
+        self.transition_and_return(|mode| (f(mode), ()))
+    }
+
+    /// Same as [`transition()`](#method.transition), except that `f` also returns an extra value alongside the next
+    /// value, which is passed back to the caller.
+    /// 
+    /// # Panics
+    /// Panics if the `ModeSlot` is [poisoned](struct.Poisoned.html). If `f` panics, the `ModeSlot` is left poisoned.
+    /// 
+    pub fn transition_and_return<R>(&mut self, f : impl FnOnce(M) -> (M, R)) -> R {
+        let mode = self.mode.take().expect("Cannot transition a ModeSlot that panicked during a previous transition!");
+        let (next, result) = f(mode);
+        self.mode = Some(next);
+        result
+    }
+
+    /// Same as [`transition()`](#method.transition), except that it returns `Err(Poisoned)` instead of panicking if
+    /// the `ModeSlot` is [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn try_transition(&mut self, f : impl FnOnce(M) -> M) -> Result<(), Poisoned> {
+        if self.is_poisoned() {
+            return Err(Poisoned);
+        }
+        self.transition(f);
+        Ok(())
+    }
+
+    /// Returns `true` if a previous transition panicked, leaving the `ModeSlot` without a current value.
+    /// 
+    pub fn is_poisoned(&self) -> bool {
+        self.mode.is_none()
+    }
+
+    /// Returns an immutable reference to the current value, or `Err(Poisoned)` if the `ModeSlot` is
+    /// [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn try_get(&self) -> Result<&M, Poisoned> {
+        self.mode.as_ref().ok_or(Poisoned)
+    }
+
+    /// Returns a mutable reference to the current value, or `Err(Poisoned)` if the `ModeSlot` is
+    /// [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn try_get_mut(&mut self) -> Result<&mut M, Poisoned> {
+        self.mode.as_mut().ok_or(Poisoned)
+    }
+
+    /// Consumes the `ModeSlot`, returning its current value.
+    /// 
+    /// # Panics
+    /// Panics if the `ModeSlot` is [poisoned](struct.Poisoned.html).
+    /// 
+    pub fn into_inner(self) -> M {
+        self.mode.expect("Cannot take the value of a ModeSlot that panicked during a previous transition!")
+    }
+}
+
+impl<F, M> ModeSlot<M>
+    where
+        F : Family<Mode = M, Output = M> + ?Sized,
+        M : Mode<Family = F>,
+{
+    /// Transitions the current value by calling [`Mode::swap()`](trait.Mode.html#tymethod.swap) on it with `input`.
+    /// 
+    /// # Panics
+    /// Panics if the `ModeSlot` is [poisoned](struct.Poisoned.html). If `swap()` panics, the `ModeSlot` is left
+    /// poisoned.
+    /// 
+    pub fn swap(&mut self, input : F::Input) {
+        self.transition(|mode| mode.swap(input))
+    }
+}
+
+impl<M> From<M> for ModeSlot<M> {
+    fn from(mode : M) -> Self {
+        Self::new(mode)
+    }
+}
+
+impl<M> Deref for ModeSlot<M> {
+    type Target = M;
+
+    fn deref(&self) -> &M {
+        self.mode.as_ref().expect("Cannot access a ModeSlot that panicked during a previous transition!")
+    }
+}
+
+impl<M> DerefMut for ModeSlot<M> {
+    fn deref_mut(&mut self) -> &mut M {
+        self.mode.as_mut().expect("Cannot access a ModeSlot that panicked during a previous transition!")
+    }
+}
+
+impl<M> fmt::Debug for ModeSlot<M>
+    where M : fmt::Debug
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match &self.mode {
+            Some(mode) => formatter.debug_tuple("ModeSlot").field(mode).finish(),
+            None => formatter.debug_tuple("ModeSlot").field(&Poisoned).finish(),
+        }
+    }
+}
@@ -0,0 +1,350 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Automaton, Family, TransitionEvent};
+use std::{
+    collections::BTreeMap,
+    fmt,
+    sync::mpsc::Receiver,
+    time::{Duration, Instant},
+};
+
+/// The number of linear sub-buckets per power of two in a `Histogram`, which bounds the relative error of each
+/// recorded duration to `1 / SUB_BUCKETS`.
+/// 
+const SUB_BUCKETS : u64 = 16;
+const SUB_BUCKET_BITS : u32 = 4;
+
+/// A histogram of durations with HDR-style buckets, i.e. buckets that grow exponentially in size, each split into 16
+/// linear sub-buckets, so that durations from a microsecond up to many days can be recorded with a relative error of at
+/// most 1/16th.
+/// 
+/// Each bucket holds a `u64` count, and buckets are only allocated up to the one holding the longest duration recorded
+/// so far. A `Histogram` therefore takes about 2 KB on the heap once a duration of one second has been recorded, about
+/// 4 KB for durations of up to a day, and at most about 8 KB. Keep this in mind when keeping a `Histogram` for every
+/// `Mode` of every `Automaton` in a large population.
+/// 
+/// Durations are recorded with microsecond resolution. Percentiles are reported as the upper bound of the bucket in
+/// which they fall, clamped to the largest duration that was recorded, so they never under-report.
+/// 
+/// # Usage
+/// ```
+/// use mode::Histogram;
+/// use std::time::Duration;
+/// 
+/// let mut histogram = Histogram::new();
+/// for millis in 1..=100 {
+///     histogram.record(Duration::from_millis(millis));
+/// }
+/// 
+/// assert_eq!(histogram.count(), 100);
+/// assert_eq!(histogram.max(), Some(Duration::from_millis(100)));
+/// 
+/// // Percentiles are accurate to within 1/16th.
+/// let p95 = histogram.p95().unwrap().as_secs_f64() * 1000.0;
+/// assert!(p95 >= 95.0 && p95 <= 95.0 * 17.0 / 16.0);
+/// ```
+/// 
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+pub struct Histogram {
+    counts : Vec<u64>,
+    count : u64,
+    sum : u128,
+    min : u64,
+    max : u64,
+}
+
+impl Histogram {
+    /// Creates a new, empty `Histogram`.
+    /// 
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the index of the bucket containing `micros`.
+    /// 
+    fn bucket(micros : u64) -> usize {
+        if micros < SUB_BUCKETS {
+            return micros as usize;
+        }
+
+        let exponent = 63 - micros.leading_zeros();
+        let sub_bucket = (micros >> (exponent - SUB_BUCKET_BITS)) & (SUB_BUCKETS - 1);
+        (SUB_BUCKETS + (exponent - SUB_BUCKET_BITS) as u64 * SUB_BUCKETS + sub_bucket) as usize
+    }
+
+    /// Returns the largest number of microseconds that falls into the bucket at `index`.
+    /// 
+    fn upper_bound(index : usize) -> u64 {
+        let index = index as u64;
+        if index < SUB_BUCKETS {
+            return index;
+        }
+
+        let shift = (index - SUB_BUCKETS) / SUB_BUCKETS;
+        let sub_bucket = (index - SUB_BUCKETS) % SUB_BUCKETS;
+        let lower = (SUB_BUCKETS + sub_bucket) << shift;
+        lower.saturating_add((1u64 << shift) - 1)
+    }
+
+    /// Records a single `duration`.
+    /// 
+    pub fn record(&mut self, duration : Duration) {
+        let micros = duration.as_micros().min(u64::MAX as u128) as u64;
+        let bucket = Self::bucket(micros);
+        if bucket >= self.counts.len() {
+            self.counts.resize(bucket + 1, 0);
+        }
+
+        self.counts[bucket] += 1;
+        self.min = if self.count == 0 { micros } else { self.min.min(micros) };
+        self.max = self.max.max(micros);
+        self.count += 1;
+        self.sum += micros as u128;
+    }
+
+    /// Returns the number of durations that have been recorded.
+    /// 
+    pub fn count(&self) -> u64 {
+        self.count
+    }
+
+    /// Returns `true` if no durations have been recorded.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.count == 0
+    }
+
+    /// Returns the shortest duration that has been recorded, or `None` if the `Histogram` is empty.
+    /// 
+    pub fn min(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros(self.min))
+    }
+
+    /// Returns the longest duration that has been recorded, or `None` if the `Histogram` is empty.
+    /// 
+    pub fn max(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros(self.max))
+    }
+
+    /// Returns the mean of every duration that has been recorded, or `None` if the `Histogram` is empty.
+    /// 
+    pub fn mean(&self) -> Option<Duration> {
+        (self.count > 0).then(|| Duration::from_micros((self.sum / self.count as u128) as u64))
+    }
+
+    /// Returns the duration below which `percentile` percent of the recorded durations fall, e.g. `99.0` for the 99th
+    /// percentile, or `None` if the `Histogram` is empty. `percentile` is clamped to the range `0.0..=100.0`.
+    /// 
+    pub fn percentile(&self, percentile : f64) -> Option<Duration> {
+        if self.count == 0 {
+            return None;
+        }
+
+        let rank = ((percentile.clamp(0.0, 100.0) / 100.0 * self.count as f64).ceil() as u64).max(1);
+        let mut seen = 0;
+        for (index, count) in self.counts.iter().enumerate() {
+            seen += count;
+            if seen >= rank {
+                return Some(Duration::from_micros(Self::upper_bound(index).clamp(self.min, self.max)));
+            }
+        }
+
+        Some(Duration::from_micros(self.max))
+    }
+
+    /// Returns the median of the recorded durations. Same as `percentile(50.0)`.
+    /// 
+    pub fn p50(&self) -> Option<Duration> {
+        self.percentile(50.0)
+    }
+
+    /// Returns the 95th percentile of the recorded durations. Same as `percentile(95.0)`.
+    /// 
+    pub fn p95(&self) -> Option<Duration> {
+        self.percentile(95.0)
+    }
+
+    /// Returns the 99th percentile of the recorded durations. Same as `percentile(99.0)`.
+    /// 
+    pub fn p99(&self) -> Option<Duration> {
+        self.percentile(99.0)
+    }
+
+    /// Adds every duration recorded in `other` to `self`.
+    /// 
+    pub fn merge(&mut self, other : &Histogram) {
+        if other.count == 0 {
+            return;
+        }
+
+        if other.counts.len() > self.counts.len() {
+            self.counts.resize(other.counts.len(), 0);
+        }
+
+        for (count, other) in self.counts.iter_mut().zip(&other.counts) {
+            *count += other;
+        }
+
+        self.min = if self.count == 0 { other.min } else { self.min.min(other.min) };
+        self.max = self.max.max(other.max);
+        self.count += other.count;
+        self.sum += other.sum;
+    }
+}
+
+/// Collects a [`Histogram`](struct.Histogram.html) of dwell times, i.e. how long each state remained current, per
+/// `tag()`, from the [`TransitionEvent`](struct.TransitionEvent.html)s of one or more `Automaton`s, and reports their
+/// percentiles, e.g. so that service operators can alert when a connection spends too long in `Reconnecting`.
+/// 
+/// An `Automaton` can either be attached directly via [`attach()`](#method.attach), in which case the `DwellStats`
+/// subscribes to it and collects its events whenever [`poll()`](#method.poll) is called, or its events can be passed in
+/// by hand via [`record()`](#method.record). Only completed stays are recorded, i.e. a dwell time is recorded for a
+/// state once it is left. The time spent in the current state so far is available via
+/// [`Automaton::time_in_mode()`](struct.Automaton.html#method.time_in_mode).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::time::{Duration, Instant};
+/// 
+/// let mut stats = DwellStats::new();
+/// let start = Instant::now();
+/// let event = |from, to, millis| {
+///     TransitionEvent { from, to, at : start + Duration::from_millis(millis), fields : Vec::new(), path : None }
+/// };
+/// 
+/// let track = stats.add_track(start);
+/// for (index, millis) in [10, 20, 40, 50, 200, 210].iter().enumerate() {
+///     let (from, to) = if index % 2 == 0 { ("Connected", "Reconnecting") } else { ("Reconnecting", "Connected") };
+///     stats.record(track, &event(from, to, *millis));
+/// }
+/// 
+/// let reconnecting = stats.histogram("Reconnecting").unwrap();
+/// assert_eq!(reconnecting.count(), 3);
+/// assert_eq!(reconnecting.max(), Some(Duration::from_millis(10)));
+/// 
+/// // Connected was current from 0-10ms, 20-40ms, and 50-200ms.
+/// assert_eq!(stats.histogram("Connected").unwrap().max(), Some(Duration::from_millis(150)));
+/// assert!(stats.report().contains("Connected: count=3"));
+/// ```
+/// 
+#[derive(Debug, Default)]
+pub struct DwellStats {
+    histograms : BTreeMap<&'static str, Histogram>,
+    tracks : Vec<DwellTrack>,
+}
+
+#[derive(Debug)]
+struct DwellTrack {
+    entered_at : Instant,
+    events : Option<Receiver<TransitionEvent>>,
+}
+
+impl DwellStats {
+    /// Creates a new `DwellStats` without any tracks or recorded dwell times.
+    /// 
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a new track, on which the first state is treated as having been entered at `entered_at`, and returns its
+    /// index. Use this together with [`record()`](#method.record) in order to record `TransitionEvent`s by hand.
+    /// 
+    pub fn add_track(&mut self, entered_at : Instant) -> usize {
+        self.tracks.push(DwellTrack { entered_at, events : None });
+        self.tracks.len() - 1
+    }
+
+    /// Adds a new track for `automaton`, subscribes to its `TransitionEvent`s, and returns the index of the track. The
+    /// current `Mode` of `automaton` is treated as having been entered `time_in_mode()` ago. Transitions are only
+    /// collected once [`poll()`](#method.poll) is called.
+    /// 
+    pub fn attach<F>(&mut self, automaton : &mut Automaton<F>) -> usize
+        where F : Family + ?Sized
+    {
+        let now = Instant::now();
+        let entered_at = now.checked_sub(Automaton::time_in_mode(automaton)).unwrap_or(now);
+        let track = self.add_track(entered_at);
+        self.tracks[track].events = Some(Automaton::subscribe(automaton));
+        track
+    }
+
+    /// Records `event` on the track with index `track`, adding the time since the previous transition on that track to
+    /// the `Histogram` for the state being left.
+    /// 
+    /// # Panics
+    /// Panics if there is no track with index `track`.
+    /// 
+    pub fn record(&mut self, track : usize, event : &TransitionEvent) {
+        let track = &mut self.tracks[track];
+        let dwell = event.at.saturating_duration_since(track.entered_at);
+        track.entered_at = event.at;
+        self.histograms.entry(event.from).or_default().record(dwell);
+    }
+
+    /// Collects every pending `TransitionEvent` from each `Automaton` that was attached via
+    /// [`attach()`](#method.attach).
+    /// 
+    pub fn poll(&mut self) {
+        for index in 0..self.tracks.len() {
+            let events : Vec<TransitionEvent> =
+                match &self.tracks[index].events {
+                    Some(events) => events.try_iter().collect(),
+                    None => continue,
+                };
+
+            for event in &events {
+                self.record(index, event);
+            }
+        }
+    }
+
+    /// Returns the `Histogram` of dwell times for the state with the specified `tag`, or `None` if that state has
+    /// never been left.
+    /// 
+    pub fn histogram(&self, tag : &str) -> Option<&Histogram> {
+        self.histograms.get(tag)
+    }
+
+    /// Returns an iterator over the `tag()` and `Histogram` of every state that has been left at least once, ordered
+    /// by `tag()`.
+    /// 
+    pub fn histograms(&self) -> impl Iterator<Item = (&'static str, &Histogram)> + '_ {
+        self.histograms.iter().map(|(tag, histogram)| (*tag, histogram))
+    }
+
+    /// Returns a human-readable report containing one line per state, ordered by `tag()`, with the number of recorded
+    /// dwell times and their p50, p95, p99, and maximum, e.g. `Reconnecting: count=3 p50=10ms p95=10ms p99=10ms
+    /// max=10ms`.
+    /// 
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        for (tag, histogram) in &self.histograms {
+            report.push_str(&format!("{}: {}\n", tag, Summary(histogram)));
+        }
+        report
+    }
+}
+
+/// Formats the count and percentiles of a `Histogram` for `DwellStats::report()`.
+/// 
+struct Summary<'a>(&'a Histogram);
+
+impl fmt::Display for Summary<'_> {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        let histogram = self.0;
+        let zero = Duration::from_secs(0);
+        write!(
+            formatter,
+            "count={} p50={:?} p95={:?} p99={:?} max={:?}",
+            histogram.count(),
+            histogram.p50().unwrap_or(zero),
+            histogram.p95().unwrap_or(zero),
+            histogram.p99().unwrap_or(zero),
+            histogram.max().unwrap_or(zero))
+    }
+}
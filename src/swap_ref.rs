@@ -0,0 +1,73 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode};
+
+/// Trait for `Mode`s that can swap while borrowing a context mutably, instead of taking ownership of an `Input`.
+/// 
+/// `Mode::swap()` takes its `input` by value, which means that large contexts, e.g. the state of a game world or the
+/// tape of a Turing machine, have to be moved into and back out of the `Mode` on every step, or cloned. A `Mode` that
+/// implements `SwapRef` can instead be stepped via
+/// [`Automaton::next_with_ref()`](struct.Automaton.html#method.next_with_ref), which passes an `&mut Self::Context` into
+/// [`swap_ref()`](#tymethod.swap_ref). The `Family` of such a `Mode` would typically have `()` as its `Input`, with
+/// `Mode::swap()` simply returning `self`.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct MachineFamily;
+/// impl Family for MachineFamily {
+///     type Base = Machine;
+///     type Mode = Machine;
+///     type Input = ();
+///     type Output = Machine;
+/// }
+/// 
+/// struct Tape { cells : Vec<bool>, head : usize }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Machine { Writing, Halted }
+/// 
+/// impl Mode for Machine {
+///     type Family = MachineFamily;
+///     fn swap(self, _input : ()) -> Self { self }
+/// }
+/// 
+/// impl SwapRef for Machine {
+///     type Context = Tape;
+///     fn swap_ref(self, tape : &mut Tape) -> Self {
+///         match self {
+///             Machine::Writing if tape.head < tape.cells.len() => {
+///                 tape.cells[tape.head] = true;
+///                 tape.head += 1;
+///                 Machine::Writing
+///             },
+///             _ => Machine::Halted,
+///         }
+///     }
+/// }
+/// 
+/// let mut tape = Tape { cells : vec![false; 2], head : 0 };
+/// let mut machine = MachineFamily::automaton_with_mode(Machine::Writing);
+/// for _ in 0..3 {
+///     Automaton::next_with_ref(&mut machine, &mut tape);
+/// }
+/// 
+/// assert_eq!(*machine, Machine::Halted);
+/// assert_eq!(tape.cells, vec![true, true]);
+/// ```
+/// 
+pub trait SwapRef : Mode {
+    /// The type of context that is borrowed mutably by `swap_ref()`.
+    /// 
+    type Context : ?Sized;
+
+    /// Same as [`Mode::swap()`](trait.Mode.html#tymethod.swap), except that `context` is borrowed mutably instead of
+    /// being passed in by value.
+    /// 
+    fn swap_ref(self, context : &mut Self::Context) -> <Self::Family as Family>::Output;
+}
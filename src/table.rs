@@ -0,0 +1,888 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines data-driven state machines, whose states and transitions are described by a table at runtime instead of
+//! by Rust types.
+//! 
+//! A [`Definition`](struct.Definition.html) lists the named states of a state machine, which of them is initial, which
+//! of them are final, and a set of transitions, each of which moves the state machine from one state to another when
+//! a named event arrives. Once validated, a `Definition` can be turned into a [`Machine`](struct.Machine.html), which
+//! implements `Mode` for the [`TableFamily`](struct.TableFamily.html), and can therefore be driven by an `Automaton`
+//! like any other `Mode`. Each event is passed in as the `Input` of `Automaton::next_with_input()`, and the name of
+//! the current state is reported as its `tag()`.
+//! 
+//! # Hot reloading
+//! The `Definition` used by a `Machine` can be replaced at runtime via [`Machine::reload()`](struct.Machine.html#method.reload),
+//! e.g. in order to live-tune AI behavior during a playtest. The new `Definition` is validated first, and the current
+//! state is mapped onto the new `Definition` by name. If either step fails, the `Machine` is left unchanged.
+//! 
+//! # Guards
+//! A transition can name a guard, i.e. a predicate registered in Rust via [`Guards`](struct.Guards.html). A guarded
+//! transition is only taken if its guard returns `true` for the current `Machine` and the event that arrived. Several
+//! transitions from the same state can be triggered by the same event, as long as their guards differ, in which case
+//! the first transition whose guard passes is taken. A transition without a guard always passes, but it is only taken
+//! if none of the guarded transitions for the same state and event pass, no matter where it appears in the
+//! `Definition`.
+//! 
+//! # Loading definitions
+//! When the `serde` feature is enabled, a `Definition` can be loaded from JSON via
+//! [`Loader::load_json()`](struct.Loader.html#method.load_json). The `yaml` and `toml` features add
+//! [`Loader::load_yaml()`](struct.Loader.html#method.load_yaml) and [`Loader::load_toml()`](struct.Loader.html#method.load_toml),
+//! respectively. Every format follows the same schema, shown here in JSON:
+//! 
+//! ```json
+//! {
+//!     "initial": "Idle",
+//!     "states": [
+//!         { "name": "Idle" },
+//!         { "name": "Done", "is_final": true }
+//!     ],
+//!     "transitions": [
+//!         { "from": "Idle", "event": "go", "to": "Done", "guard": "is_ready" }
+//!     ]
+//! }
+//! ```
+//! 
+//! Both `is_final` and `guard` are optional. A `Loader` holds the `Guards` that loaded `Definition`s may refer to, and
+//! rejects any `Definition` that names a guard it does not know about.
+//! 
+//! # Usage
+//! ```
+//! use mode::{table::{Definition, Machine, TableFamily}, Automaton, Family};
+//! 
+//! let definition =
+//!     Definition::new("Idle")
+//!         .with_state("Idle")
+//!         .with_state("Chasing")
+//!         .with_transition("Idle", "see_player", "Chasing")
+//!         .with_transition("Chasing", "lose_player", "Idle");
+//! 
+//! let mut guard = TableFamily::automaton_with_mode(Machine::new(definition).unwrap());
+//! Automaton::next_with_input(&mut guard, "see_player".to_owned());
+//! assert_eq!(guard.state(), "Chasing");
+//! 
+//! // Tune the behavior without dropping the Machine. The current state is kept.
+//! let tuned =
+//!     Definition::new("Idle")
+//!         .with_state("Idle")
+//!         .with_state("Chasing")
+//!         .with_state("Searching")
+//!         .with_transition("Idle", "see_player", "Chasing")
+//!         .with_transition("Chasing", "lose_player", "Searching")
+//!         .with_transition("Searching", "timeout", "Idle");
+//! 
+//! guard.reload(tuned).unwrap();
+//! assert_eq!(guard.state(), "Chasing");
+//! 
+//! Automaton::next_with_input(&mut guard, "lose_player".to_owned());
+//! assert_eq!(guard.state(), "Searching");
+//! ```
+//! 
+
+use crate::{Automaton, Family, Mode};
+use std::{
+    collections::{HashMap, HashSet},
+    error::Error,
+    fmt,
+    io,
+    sync::{Arc, Mutex},
+};
+
+/// A single named state within a [`Definition`](struct.Definition.html).
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StateDef {
+    /// The unique name of the state.
+    pub name : String,
+
+    /// Whether the state is [final](../trait.Mode.html#method.is_final).
+    #[cfg_attr(feature = "serde", serde(default))]
+    pub is_final : bool,
+}
+
+/// A single transition within a [`Definition`](struct.Definition.html).
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct TransitionDef {
+    /// The name of the state in which this transition applies.
+    pub from : String,
+
+    /// The name of the event that triggers this transition.
+    pub event : String,
+
+    /// The name of the state that becomes current once this transition is taken.
+    pub to : String,
+
+    /// The name of the guard that must pass in order for this transition to be taken, if any.
+    #[cfg_attr(feature = "serde", serde(default, skip_serializing_if = "Option::is_none"))]
+    pub guard : Option<String>,
+}
+
+impl TransitionDef {
+    /// Returns the label used for this transition when rendering a diagram.
+    /// 
+    fn label(&self) -> String {
+        match &self.guard {
+            Some(guard) => format!("{} [{}]", self.event, guard),
+            None => self.event.clone(),
+        }
+    }
+}
+
+/// Describes the states and transitions of a data-driven state machine.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug, Default, Eq, PartialEq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Definition {
+    /// The name of the initial state.
+    pub initial : String,
+
+    /// Every state in the state machine.
+    pub states : Vec<StateDef>,
+
+    /// Every transition in the state machine.
+    pub transitions : Vec<TransitionDef>,
+}
+
+impl Definition {
+    /// Creates a new `Definition` with no states or transitions, whose initial state will be called `initial`.
+    /// 
+    pub fn new(initial : impl Into<String>) -> Self {
+        Self { initial : initial.into(), states : Vec::new(), transitions : Vec::new() }
+    }
+
+    /// Adds a new, non-final state called `name`.
+    /// 
+    pub fn with_state(mut self, name : impl Into<String>) -> Self {
+        self.states.push(StateDef { name : name.into(), is_final : false });
+        self
+    }
+
+    /// Adds a new, final state called `name`.
+    /// 
+    pub fn with_final_state(mut self, name : impl Into<String>) -> Self {
+        self.states.push(StateDef { name : name.into(), is_final : true });
+        self
+    }
+
+    /// Adds a new transition from the state called `from` to the state called `to`, which is taken when `event`
+    /// arrives.
+    /// 
+    pub fn with_transition(mut self, from : impl Into<String>, event : impl Into<String>, to : impl Into<String>)
+        -> Self
+    {
+        self.transitions.push(TransitionDef { from : from.into(), event : event.into(), to : to.into(), guard : None });
+        self
+    }
+
+    /// Same as [`with_transition()`](#method.with_transition), except that the transition is only taken if the guard
+    /// called `guard` passes.
+    /// 
+    pub fn with_guarded_transition(
+        mut self,
+        from : impl Into<String>,
+        event : impl Into<String>,
+        to : impl Into<String>,
+        guard : impl Into<String>)
+        -> Self
+    {
+        self.transitions.push(TransitionDef {
+            from : from.into(),
+            event : event.into(),
+            to : to.into(),
+            guard : Some(guard.into()),
+        });
+        self
+    }
+
+    /// Checks that this `Definition` is well-formed, i.e. that every state has a unique name, that the initial state
+    /// exists, that every transition refers to states that exist, and that no two transitions from the same state are
+    /// triggered by the same event with the same guard. Guard names are not checked, since they are only known once
+    /// the `Definition` is combined with a set of [`Guards`](struct.Guards.html).
+    /// 
+    pub fn validate(&self) -> Result<(), DefinitionError> {
+        Compiled::new(self.clone(), None).map(|_| ())
+    }
+
+    /// Same as [`validate()`](#method.validate), except that every guard named by a transition must also exist in
+    /// `guards`.
+    /// 
+    pub fn validate_with(&self, guards : &Guards) -> Result<(), DefinitionError> {
+        Compiled::new(self.clone(), Some(guards)).map(|_| ())
+    }
+
+    /// Parses a `Definition` from a JSON string, following the schema described in the
+    /// [module-level documentation](index.html#loading-definitions). The `Definition` is not validated.
+    /// 
+    /// **NOTE:** This function is only available when the `serde` feature is enabled.
+    /// 
+    #[cfg(feature = "serde")]
+    pub fn from_json(json : &str) -> Result<Self, LoadError> {
+        serde_json::from_str(json).map_err(|error| LoadError::Parse(error.to_string()))
+    }
+
+    /// Parses a `Definition` from a YAML string, following the schema described in the
+    /// [module-level documentation](index.html#loading-definitions). The `Definition` is not validated.
+    /// 
+    /// **NOTE:** This function is only available when the `yaml` feature is enabled.
+    /// 
+    #[cfg(feature = "yaml")]
+    pub fn from_yaml(yaml : &str) -> Result<Self, LoadError> {
+        serde_yaml::from_str(yaml).map_err(|error| LoadError::Parse(error.to_string()))
+    }
+
+    /// Parses a `Definition` from a TOML string, following the schema described in the
+    /// [module-level documentation](index.html#loading-definitions). The `Definition` is not validated.
+    /// 
+    /// **NOTE:** This function is only available when the `toml` feature is enabled.
+    /// 
+    #[cfg(feature = "toml")]
+    pub fn from_toml(toml : &str) -> Result<Self, LoadError> {
+        toml::from_str(toml).map_err(|error| LoadError::Parse(error.to_string()))
+    }
+
+    /// Reads a `Definition` from the file at `path`, choosing the format by its extension: `.json` files are parsed
+    /// via [`from_json()`](#method.from_json), `.yaml` and `.yml` files via [`from_yaml()`](#method.from_yaml), and
+    /// `.toml` files via [`from_toml()`](#method.from_toml), provided that the corresponding feature is enabled. The
+    /// `Definition` is not validated.
+    /// 
+    /// **NOTE:** This function is only available when the `serde` feature is enabled.
+    /// 
+    #[cfg(feature = "serde")]
+    pub fn from_file(path : impl AsRef<std::path::Path>) -> Result<Self, LoadError> {
+        let path = path.as_ref();
+        let contents = std::fs::read_to_string(path)?;
+        match path.extension().and_then(|extension| extension.to_str()) {
+            Some("json") => Self::from_json(&contents),
+            #[cfg(feature = "yaml")]
+            Some("yaml") | Some("yml") => Self::from_yaml(&contents),
+            #[cfg(feature = "toml")]
+            Some("toml") => Self::from_toml(&contents),
+            _ => Err(LoadError::Parse(format!("Unsupported file extension for {}", path.display()))),
+        }
+    }
+
+    /// Returns the names of all states that can never become current, because no chain of transitions leads to them
+    /// from the initial state. Guards are assumed to be able to pass.
+    /// 
+    /// ```
+    /// use mode::table::Definition;
+    /// 
+    /// let definition =
+    ///     Definition::new("A")
+    ///         .with_state("A")
+    ///         .with_state("B")
+    ///         .with_state("C")
+    ///         .with_transition("A", "go", "B")
+    ///         .with_transition("C", "go", "A");
+    /// 
+    /// assert_eq!(definition.unreachable_states(), vec!["C"]);
+    /// assert_eq!(definition.dead_states(), vec!["B"]);
+    /// ```
+    /// 
+    pub fn unreachable_states(&self) -> Vec<&str> {
+        let mut reached = HashSet::new();
+        let mut pending = vec![self.initial.as_str()];
+        while let Some(name) = pending.pop() {
+            if reached.insert(name) {
+                pending.extend(
+                    self.transitions.iter()
+                        .filter(|transition| transition.from == name)
+                        .map(|transition| transition.to.as_str()));
+            }
+        }
+
+        self.states.iter()
+            .map(|state| state.name.as_str())
+            .filter(|name| !reached.contains(name))
+            .collect()
+    }
+
+    /// Returns the names of all states that are not final, but have no transitions out of them, so that a `Machine`
+    /// that enters them can never leave.
+    /// 
+    pub fn dead_states(&self) -> Vec<&str> {
+        self.states.iter()
+            .filter(|state| !state.is_final && !self.transitions.iter().any(|transition| transition.from == state.name))
+            .map(|state| state.name.as_str())
+            .collect()
+    }
+
+    /// Renders this `Definition` as a [Graphviz](https://graphviz.org) DOT graph. Final states are drawn with a double
+    /// border, and each guard is shown in square brackets after the name of its event.
+    /// 
+    pub fn to_dot(&self) -> String {
+        let mut dot = String::from("digraph {\n    __initial [shape=point];\n");
+        dot.push_str(&format!("    __initial -> {:?};\n", self.initial));
+        for state in &self.states {
+            let shape = if state.is_final { "doublecircle" } else { "circle" };
+            dot.push_str(&format!("    {:?} [shape={}];\n", state.name, shape));
+        }
+        for transition in &self.transitions {
+            dot.push_str(&format!(
+                "    {:?} -> {:?} [label={:?}];\n",
+                transition.from,
+                transition.to,
+                transition.label()));
+        }
+        dot.push_str("}\n");
+        dot
+    }
+
+    /// Renders this `Definition` as a [PlantUML](https://plantuml.com) state diagram. Each guard is shown in square
+    /// brackets after the name of its event.
+    /// 
+    pub fn to_plantuml(&self) -> String {
+        let mut uml = String::from("@startuml\n");
+        let ids : HashMap<&str, String> =
+            self.states.iter()
+                .enumerate()
+                .map(|(index, state)| (state.name.as_str(), format!("S{}", index)))
+                .collect();
+        let id = |name : &str| ids.get(name).cloned().unwrap_or_else(|| name.to_owned());
+
+        for state in &self.states {
+            uml.push_str(&format!("state {:?} as {}\n", state.name, id(&state.name)));
+        }
+        uml.push_str(&format!("[*] --> {}\n", id(&self.initial)));
+        for transition in &self.transitions {
+            uml.push_str(&format!("{} --> {} : {}\n", id(&transition.from), id(&transition.to), transition.label()));
+        }
+        for state in self.states.iter().filter(|state| state.is_final) {
+            uml.push_str(&format!("{} --> [*]\n", id(&state.name)));
+        }
+        uml.push_str("@enduml\n");
+        uml
+    }
+
+    /// Writes this `Definition` as an [SCXML](https://www.w3.org/TR/scxml/) document, so that it can be exchanged
+    /// with XState and other statechart tools. Each guard is written as the `cond` attribute of its transition. Since
+    /// a `Machine` stops as soon as it enters a final state, final states are written as `<final>` elements, and any
+    /// transitions out of them are omitted.
+    /// 
+    /// ```
+    /// use mode::table::Definition;
+    /// 
+    /// let definition =
+    ///     Definition::new("Idle")
+    ///         .with_state("Idle")
+    ///         .with_final_state("Done")
+    ///         .with_guarded_transition("Idle", "finish", "Done", "is_ready");
+    /// 
+    /// assert_eq!(definition.to_scxml(), concat!(
+    ///     "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n",
+    ///     "<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" initial=\"Idle\">\n",
+    ///     "  <state id=\"Idle\">\n",
+    ///     "    <transition event=\"finish\" target=\"Done\" cond=\"is_ready\"/>\n",
+    ///     "  </state>\n",
+    ///     "  <final id=\"Done\"/>\n",
+    ///     "</scxml>\n"));
+    /// ```
+    /// 
+    pub fn to_scxml(&self) -> String {
+        let mut scxml = String::new();
+        scxml.push_str("<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n");
+        scxml.push_str(&format!(
+            "<scxml xmlns=\"http://www.w3.org/2005/07/scxml\" version=\"1.0\" initial=\"{}\">\n",
+            escape_xml(&self.initial)));
+
+        for state in &self.states {
+            let id = escape_xml(&state.name);
+            if state.is_final {
+                scxml.push_str(&format!("  <final id=\"{}\"/>\n", id));
+                continue;
+            }
+
+            let mut transitions = self.transitions.iter().filter(|transition| transition.from == state.name).peekable();
+            if transitions.peek().is_none() {
+                scxml.push_str(&format!("  <state id=\"{}\"/>\n", id));
+                continue;
+            }
+
+            scxml.push_str(&format!("  <state id=\"{}\">\n", id));
+            for transition in transitions {
+                scxml.push_str(&format!(
+                    "    <transition event=\"{}\" target=\"{}\"",
+                    escape_xml(&transition.event),
+                    escape_xml(&transition.to)));
+                if let Some(guard) = &transition.guard {
+                    scxml.push_str(&format!(" cond=\"{}\"", escape_xml(guard)));
+                }
+                scxml.push_str("/>\n");
+            }
+            scxml.push_str("  </state>\n");
+        }
+
+        scxml.push_str("</scxml>\n");
+        scxml
+    }
+}
+
+/// Escapes `text` so that it can be used as XML text or as the value of an XML attribute.
+/// 
+pub(crate) fn escape_xml(text : &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        match c {
+            '&' => escaped.push_str("&amp;"),
+            '<' => escaped.push_str("&lt;"),
+            '>' => escaped.push_str("&gt;"),
+            '"' => escaped.push_str("&quot;"),
+            '\'' => escaped.push_str("&apos;"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// An error describing why a `Definition` could not be used.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum DefinitionError {
+    /// Two or more states have the same name.
+    DuplicateState(String),
+
+    /// A state referred to by name does not exist.
+    UnknownState(String),
+
+    /// A guard referred to by name has not been registered.
+    UnknownGuard(String),
+
+    /// More than one transition from the same state is triggered by the same event with the same guard.
+    AmbiguousTransition {
+        /// The name of the state.
+        from : String,
+
+        /// The name of the event.
+        event : String,
+    },
+}
+
+impl fmt::Display for DefinitionError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            DefinitionError::DuplicateState(name) => write!(formatter, "State \"{}\" is defined more than once!", name),
+            DefinitionError::UnknownState(name) => write!(formatter, "State \"{}\" does not exist!", name),
+            DefinitionError::UnknownGuard(name) => write!(formatter, "Guard \"{}\" has not been registered!", name),
+            DefinitionError::AmbiguousTransition { from, event } => {
+                write!(formatter, "State \"{}\" has more than one transition on event \"{}\"!", from, event)
+            },
+        }
+    }
+}
+
+impl Error for DefinitionError { }
+
+type GuardFn = dyn Fn(&Machine, &str) -> bool + Send + Sync;
+
+/// A set of named predicates that can be referred to by the transitions of a `Definition`.
+/// 
+/// Each guard is called with the current `Machine` and the name of the event that arrived, and returns `true` if the
+/// transition should be taken. Guards that depend on the state of the outside world can capture it, e.g. via an
+/// `Arc<AtomicBool>`.
+/// 
+/// See the [module-level documentation](index.html#guards) for more details.
+/// 
+#[derive(Clone, Default)]
+pub struct Guards {
+    guards : HashMap<String, Arc<GuardFn>>,
+}
+
+impl Guards {
+    /// Creates a new, empty set of `Guards`.
+    /// 
+    pub fn new() -> Self {
+        Self { guards : HashMap::new() }
+    }
+
+    /// Registers `guard` under `name`, replacing any guard previously registered under the same name.
+    /// 
+    pub fn add_guard<G>(&mut self, name : impl Into<String>, guard : G)
+        where G : Fn(&Machine, &str) -> bool + Send + Sync + 'static
+    {
+        self.guards.insert(name.into(), Arc::new(guard));
+    }
+
+    /// Same as [`add_guard()`](#method.add_guard), except that it consumes and returns `self`, so that calls can be
+    /// chained together.
+    /// 
+    pub fn with_guard<G>(mut self, name : impl Into<String>, guard : G) -> Self
+        where G : Fn(&Machine, &str) -> bool + Send + Sync + 'static
+    {
+        self.add_guard(name, guard);
+        self
+    }
+
+    /// Returns `true` if a guard has been registered under `name`.
+    /// 
+    pub fn contains(&self, name : &str) -> bool {
+        self.guards.contains_key(name)
+    }
+}
+
+impl fmt::Debug for Guards {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Guards")
+            .field("names", &self.guards.keys().collect::<Vec<_>>())
+            .finish()
+    }
+}
+
+/// Returns a `&'static str` with the same contents as `name`. Each distinct name is only ever allocated once, so that
+/// reloading the same `Definition` repeatedly does not leak memory.
+/// 
+pub(crate) fn intern(name : &str) -> &'static str {
+    static NAMES : Mutex<Option<HashSet<&'static str>>> = Mutex::new(None);
+
+    let mut names = NAMES.lock().unwrap_or_else(|error| error.into_inner());
+    let names = names.get_or_insert_with(HashSet::new);
+    match names.get(name) {
+        Some(interned) => interned,
+        None => {
+            let interned : &'static str = Box::leak(name.to_owned().into_boxed_str());
+            names.insert(interned);
+            interned
+        },
+    }
+}
+
+/// A validated `Definition`, indexed for fast lookup.
+/// 
+#[derive(Debug)]
+struct Compiled {
+    definition : Definition,
+    names : Vec<&'static str>,
+    indices : HashMap<String, usize>,
+    edges : HashMap<(usize, String), Vec<Edge>>,
+}
+
+#[derive(Debug)]
+struct Edge {
+    to : usize,
+    guard : Option<String>,
+}
+
+impl Compiled {
+    fn new(definition : Definition, guards : Option<&Guards>) -> Result<Self, DefinitionError> {
+        let mut indices = HashMap::new();
+        for (index, state) in definition.states.iter().enumerate() {
+            if indices.insert(state.name.clone(), index).is_some() {
+                return Err(DefinitionError::DuplicateState(state.name.clone()));
+            }
+        }
+
+        let find = |name : &String| indices.get(name).copied().ok_or_else(|| DefinitionError::UnknownState(name.clone()));
+        find(&definition.initial)?;
+
+        let mut edges : HashMap<_, Vec<Edge>> = HashMap::new();
+        for transition in &definition.transitions {
+            let from = find(&transition.from)?;
+            let to = find(&transition.to)?;
+
+            if let (Some(guards), Some(guard)) = (guards, &transition.guard) {
+                if !guards.contains(guard) {
+                    return Err(DefinitionError::UnknownGuard(guard.clone()));
+                }
+            }
+
+            let edges = edges.entry((from, transition.event.clone())).or_default();
+            if edges.iter().any(|edge| edge.guard == transition.guard) {
+                return Err(DefinitionError::AmbiguousTransition {
+                    from : transition.from.clone(),
+                    event : transition.event.clone(),
+                });
+            }
+
+            // NOTE: The unguarded edge, if any, is always kept last, so that it is only taken if no guard passes.
+            let index =
+                match transition.guard {
+                    Some(_) => edges.iter().position(|edge| edge.guard.is_none()).unwrap_or(edges.len()),
+                    None => edges.len(),
+                };
+            edges.insert(index, Edge { to, guard : transition.guard.clone() });
+        }
+
+        let names = definition.states.iter().map(|state| intern(&state.name)).collect();
+        Ok(Self { definition, names, indices, edges })
+    }
+}
+
+/// Represents the `Family` of all data-driven state machines. Events are passed in as `String`s.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub struct TableFamily;
+
+impl Family for TableFamily {
+    type Base = Machine;
+    type Mode = Machine;
+    type Input = String;
+    type Output = Machine;
+}
+
+/// A data-driven state machine that tracks the current state within a `Definition`.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+#[derive(Clone, Debug)]
+pub struct Machine {
+    compiled : Arc<Compiled>,
+    guards : Guards,
+    current : usize,
+}
+
+impl Machine {
+    /// Validates `definition` and creates a new `Machine` whose current state is the initial state of `definition`.
+    /// 
+    pub fn new(definition : Definition) -> Result<Self, DefinitionError> {
+        Self::with_guards(definition, Guards::new())
+    }
+
+    /// Same as [`new()`](#method.new), except that the transitions in `definition` can refer to any of the `guards`.
+    /// 
+    pub fn with_guards(definition : Definition, guards : Guards) -> Result<Self, DefinitionError> {
+        let compiled = Compiled::new(definition, Some(&guards))?;
+        let current = compiled.indices[&compiled.definition.initial];
+        Ok(Self { compiled : Arc::new(compiled), guards, current })
+    }
+
+    /// Returns the `Definition` that this `Machine` is currently using.
+    /// 
+    pub fn definition(&self) -> &Definition {
+        &self.compiled.definition
+    }
+
+    /// Returns the `Guards` that the `Definition` of this `Machine` can refer to.
+    /// 
+    pub fn guards(&self) -> &Guards {
+        &self.guards
+    }
+
+    /// Returns the name of the current state.
+    /// 
+    pub fn state(&self) -> &'static str {
+        self.compiled.names[self.current]
+    }
+
+    /// Returns `true` if the current state has a transition that would be taken if `event` arrived now.
+    /// 
+    pub fn accepts(&self, event : &str) -> bool {
+        self.target(event).is_some()
+    }
+
+    /// Replaces the `Definition` used by this `Machine` with `definition`, keeping the current state by name. If
+    /// `definition` is not valid, or does not contain a state with the same name as the current state, an error is
+    /// returned and the `Machine` is left unchanged.
+    /// 
+    pub fn reload(&mut self, definition : Definition) -> Result<(), DefinitionError> {
+        let compiled = Compiled::new(definition, Some(&self.guards))?;
+        let current =
+            compiled.indices.get(self.state())
+                .copied()
+                .ok_or_else(|| DefinitionError::UnknownState(self.state().to_owned()))?;
+
+        self.compiled = Arc::new(compiled);
+        self.current = current;
+        Ok(())
+    }
+
+    /// Returns the index of the state that would become current if `event` arrived now, if any.
+    /// 
+    fn target(&self, event : &str) -> Option<usize> {
+        self.compiled.edges.get(&(self.current, event.to_owned()))?
+            .iter()
+            .find(|edge| {
+                edge.guard.as_ref()
+                    .is_none_or(|guard| (self.guards.guards[guard])(self, event))
+            })
+            .map(|edge| edge.to)
+    }
+}
+
+impl Mode for Machine {
+    type Family = TableFamily;
+
+    fn swap(mut self, event : String) -> Self {
+        if let Some(to) = self.target(&event) {
+            self.current = to;
+        }
+        self
+    }
+
+    fn tag(&self) -> &'static str {
+        self.state()
+    }
+
+    fn is_final(&self) -> bool {
+        self.compiled.definition.states[self.current].is_final
+    }
+}
+
+/// An error describing why a `Definition` could not be loaded.
+/// 
+#[derive(Debug)]
+pub enum LoadError {
+    /// The input could not be read.
+    Io(io::Error),
+
+    /// The input could not be parsed according to the schema described in the
+    /// [module-level documentation](index.html#loading-definitions).
+    Parse(String),
+
+    /// The input was parsed, but the resulting `Definition` is not valid.
+    Definition(DefinitionError),
+}
+
+impl fmt::Display for LoadError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            LoadError::Io(error) => write!(formatter, "Failed to read the definition: {}", error),
+            LoadError::Parse(message) => write!(formatter, "Failed to parse the definition: {}", message),
+            LoadError::Definition(error) => write!(formatter, "Invalid definition: {}", error),
+        }
+    }
+}
+
+impl Error for LoadError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            LoadError::Io(error) => Some(error),
+            LoadError::Parse(_) => None,
+            LoadError::Definition(error) => Some(error),
+        }
+    }
+}
+
+impl From<io::Error> for LoadError {
+    fn from(error : io::Error) -> Self {
+        LoadError::Io(error)
+    }
+}
+
+impl From<DefinitionError> for LoadError {
+    fn from(error : DefinitionError) -> Self {
+        LoadError::Definition(error)
+    }
+}
+
+/// Constructs `Automaton`s over the `TableFamily` from `Definition`s, which may be loaded from configuration files.
+/// 
+/// See the [module-level documentation](index.html#loading-definitions) for more details.
+/// 
+/// # Usage
+/// ```
+/// # #[cfg(feature = "serde")] {
+/// use mode::{table::{Guards, Loader}, Automaton};
+/// use std::sync::{atomic::{AtomicBool, Ordering}, Arc};
+/// 
+/// let door_unlocked = Arc::new(AtomicBool::new(false));
+/// let unlocked = door_unlocked.clone();
+/// 
+/// let loader = Loader::new(Guards::new().with_guard("is_unlocked", move |_, _| unlocked.load(Ordering::Relaxed)));
+/// let mut door = loader.load_json(r#"{
+///     "initial": "Closed",
+///     "states": [{ "name": "Closed" }, { "name": "Open" }],
+///     "transitions": [
+///         { "from": "Closed", "event": "push", "to": "Open", "guard": "is_unlocked" },
+///         { "from": "Open", "event": "pull", "to": "Closed" }
+///     ]
+/// }"#).unwrap();
+/// 
+/// Automaton::next_with_input(&mut door, "push".to_owned());
+/// assert_eq!(door.state(), "Closed");
+/// 
+/// door_unlocked.store(true, Ordering::Relaxed);
+/// Automaton::next_with_input(&mut door, "push".to_owned());
+/// assert_eq!(door.state(), "Open");
+/// 
+/// assert!(loader.load_json(r#"{
+///     "initial": "Closed",
+///     "states": [{ "name": "Closed" }],
+///     "transitions": [{ "from": "Closed", "event": "push", "to": "Closed", "guard": "is_missing" }]
+/// }"#).is_err());
+/// # }
+/// ```
+/// 
+#[derive(Clone, Debug, Default)]
+pub struct Loader {
+    guards : Guards,
+}
+
+impl Loader {
+    /// Creates a new `Loader` for `Definition`s that can refer to any of the `guards`.
+    /// 
+    pub fn new(guards : Guards) -> Self {
+        Self { guards }
+    }
+
+    /// Returns the `Guards` that loaded `Definition`s can refer to.
+    /// 
+    pub fn guards(&self) -> &Guards {
+        &self.guards
+    }
+
+    /// Validates `definition` and returns a new `Automaton` whose current `Mode` is a `Machine` in the initial state of
+    /// `definition`.
+    /// 
+    pub fn load(&self, definition : Definition) -> Result<Automaton<TableFamily>, LoadError> {
+        let machine = Machine::with_guards(definition, self.guards.clone())?;
+        Ok(TableFamily::automaton_with_mode(machine))
+    }
+
+    /// Parses a `Definition` from a JSON string and passes it to [`load()`](#method.load).
+    /// 
+    /// **NOTE:** This function is only available when the `serde` feature is enabled.
+    /// 
+    #[cfg(feature = "serde")]
+    pub fn load_json(&self, json : &str) -> Result<Automaton<TableFamily>, LoadError> {
+        self.load(Definition::from_json(json)?)
+    }
+
+    /// Parses a `Definition` from a YAML string and passes it to [`load()`](#method.load).
+    /// 
+    /// **NOTE:** This function is only available when the `yaml` feature is enabled.
+    /// 
+    #[cfg(feature = "yaml")]
+    pub fn load_yaml(&self, yaml : &str) -> Result<Automaton<TableFamily>, LoadError> {
+        self.load(Definition::from_yaml(yaml)?)
+    }
+
+    /// Parses a `Definition` from a TOML string and passes it to [`load()`](#method.load).
+    /// 
+    /// **NOTE:** This function is only available when the `toml` feature is enabled.
+    /// 
+    /// ```
+    /// # #[cfg(feature = "toml")] {
+    /// use mode::table::Loader;
+    /// 
+    /// let automaton = Loader::default().load_toml(r#"
+    ///     initial = "Idle"
+    /// 
+    ///     [[states]]
+    ///     name = "Idle"
+    /// 
+    ///     [[states]]
+    ///     name = "Done"
+    ///     is_final = true
+    /// 
+    ///     [[transitions]]
+    ///     from = "Idle"
+    ///     event = "finish"
+    ///     to = "Done"
+    /// "#).unwrap();
+    /// assert_eq!(automaton.state(), "Idle");
+    /// # }
+    /// ```
+    /// 
+    #[cfg(feature = "toml")]
+    pub fn load_toml(&self, toml : &str) -> Result<Automaton<TableFamily>, LoadError> {
+        self.load(Definition::from_toml(toml)?)
+    }
+}
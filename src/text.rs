@@ -0,0 +1,484 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines helpers for `Family`s whose `Input` is a stream of `char`s or `u8`s, which make this crate a practical base
+//! for hand-written lexers.
+//! 
+//! A lexer written as a state machine usually needs the same three things, regardless of the language it tokenizes:
+//! 
+//! - [`Position`](struct.Position.html), which tracks the byte offset, line, and column of each symbol, so that
+//!   tokens and errors can be reported where they occurred,
+//! - [`Lookahead`](struct.Lookahead.html), which wraps an `Iterator` of symbols, allows any number of upcoming symbols
+//!   to be peeked at, and turns each symbol into a [`Located`](struct.Located.html) `Input` that carries its
+//!   `Position` and the symbol after it, and
+//! - [`Lexeme`](struct.Lexeme.html), which accumulates the text of the token that is currently being scanned, and
+//!   emits it as a [`Token`](struct.Token.html) with a kind and a [`Span`](struct.Span.html) once it is complete.
+//! 
+//! All of these work with both `char` and `u8` symbols, via the [`Symbol`](trait.Symbol.html) trait. Since a
+//! `Lookahead` is an ordinary `Iterator`, it can be mapped through a lexer `Automaton` via
+//! [`IteratorExt::scan_modes()`](../trait.IteratorExt.html#method.scan_modes).
+//! 
+//! # Usage
+//! ```
+//! use mode::{text::{Lexeme, Located, Lookahead, Token}, Family, IteratorExt, Mode};
+//! 
+//! #[derive(Clone, Copy, Debug, PartialEq)]
+//! enum Kind { Word, Number }
+//! 
+//! struct LexerFamily;
+//! impl Family for LexerFamily {
+//!     type Base = Lexer;
+//!     type Mode = Lexer;
+//!     type Input = Located<char>;
+//!     type Output = (Lexer, Option<Token<Kind>>);
+//! }
+//! 
+//! enum Lexer { Between, Word(Lexeme<char>), Number(Lexeme<char>) }
+//! impl Mode for Lexer {
+//!     type Family = LexerFamily;
+//!     fn swap(self, input : Located<char>) -> (Self, Option<Token<Kind>>) {
+//!         let (kind, mut lexeme) =
+//!             match self {
+//!                 Lexer::Word(lexeme) => (Kind::Word, lexeme),
+//!                 Lexer::Number(lexeme) => (Kind::Number, lexeme),
+//!                 Lexer::Between if input.symbol.is_alphabetic() => (Kind::Word, Lexeme::new()),
+//!                 Lexer::Between if input.symbol.is_ascii_digit() => (Kind::Number, Lexeme::new()),
+//!                 Lexer::Between => return (Lexer::Between, None),
+//!             };
+//!         lexeme.push(&input);
+//! 
+//!         // The lookahead allows each token to be emitted on its last symbol, instead of on the one after it.
+//!         let continues =
+//!             input.next.is_some_and(|next| match kind {
+//!                 Kind::Word => next.is_alphanumeric(),
+//!                 Kind::Number => next.is_ascii_digit(),
+//!             });
+//!         match (continues, kind) {
+//!             (true, Kind::Word) => (Lexer::Word(lexeme), None),
+//!             (true, Kind::Number) => (Lexer::Number(lexeme), None),
+//!             (false, _) => (Lexer::Between, Some(lexeme.emit(kind))),
+//!         }
+//!     }
+//! }
+//! 
+//! let tokens : Vec<_> =
+//!     Lookahead::new("let x\n  = 42".chars())
+//!         .scan_modes(LexerFamily::automaton_with_mode(Lexer::Between))
+//!         .flatten()
+//!         .collect();
+//! 
+//! let texts : Vec<_> = tokens.iter().map(|token| token.text.as_str()).collect();
+//! assert_eq!(texts, vec!["let", "x", "42"]);
+//! assert_eq!(tokens[1].span.start.to_string(), "1:5");
+//! assert_eq!(tokens[2].kind, Kind::Number);
+//! assert_eq!(tokens[2].span.start.to_string(), "2:5");
+//! assert_eq!(tokens[2].span.len(), 2);
+//! ```
+//! 
+
+use std::{collections::VecDeque, fmt, mem};
+
+/// Trait for the symbols that a lexer can consume, i.e. `char` and `u8`.
+/// 
+pub trait Symbol : Copy {
+    /// The type that the text of a [`Token`](struct.Token.html) made out of these symbols is stored as, i.e. `String`
+    /// for `char` and `Vec<u8>` for `u8`.
+    /// 
+    type Text : Clone + fmt::Debug + Default + Eq + Extend<Self>;
+
+    /// Returns `true` if this symbol ends a line, i.e. if it is a line feed.
+    /// 
+    fn is_newline(self) -> bool;
+
+    /// Returns the number of bytes that this symbol occupies in the input, e.g. the length of a `char` encoded as
+    /// UTF-8.
+    /// 
+    fn width(self) -> usize;
+}
+
+impl Symbol for char {
+    type Text = String;
+
+    fn is_newline(self) -> bool {
+        self == '\n'
+    }
+
+    fn width(self) -> usize {
+        self.len_utf8()
+    }
+}
+
+impl Symbol for u8 {
+    type Text = Vec<u8>;
+
+    fn is_newline(self) -> bool {
+        self == b'\n'
+    }
+
+    fn width(self) -> usize {
+        1
+    }
+}
+
+/// A position within some text, consisting of a byte offset and a line and column number.
+/// 
+/// Lines and columns are counted from `1`, and columns are counted in symbols, so each `char` advances the column by
+/// one, regardless of how many bytes it occupies. A `Position` is displayed as `line:column`, which is the format that
+/// most editors and compilers use for diagnostics.
+/// 
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct Position {
+    /// The number of bytes that precede this `Position`.
+    pub offset : usize,
+
+    /// The line number of this `Position`, starting at `1`.
+    pub line : usize,
+
+    /// The column number of this `Position` within its line, starting at `1`.
+    pub column : usize,
+}
+
+impl Position {
+    /// Returns the `Position` of the first symbol of the text, i.e. offset `0` at line `1`, column `1`.
+    /// 
+    pub fn new() -> Self {
+        Self { offset : 0, line : 1, column : 1 }
+    }
+
+    /// Advances this `Position` past `symbol`, moving it to the beginning of the next line if `symbol` is a newline.
+    /// 
+    pub fn advance<S>(&mut self, symbol : S)
+        where S : Symbol
+    {
+        self.offset += symbol.width();
+        if symbol.is_newline() {
+            self.line += 1;
+            self.column = 1;
+        }
+        else {
+            self.column += 1;
+        }
+    }
+
+    /// Same as [`advance()`](#method.advance), except that it consumes and returns `self`.
+    /// 
+    pub fn advanced<S>(mut self, symbol : S) -> Self
+        where S : Symbol
+    {
+        self.advance(symbol);
+        self
+    }
+}
+
+impl Default for Position {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Display for Position {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}:{}", self.line, self.column)
+    }
+}
+
+/// A range of text between two [`Position`](struct.Position.html)s, where `start` is inclusive and `end` is exclusive.
+/// 
+#[derive(Clone, Copy, Debug, Eq, Hash, PartialEq)]
+pub struct Span {
+    /// The `Position` of the first symbol in the `Span`.
+    pub start : Position,
+
+    /// The `Position` immediately after the last symbol in the `Span`.
+    pub end : Position,
+}
+
+impl Span {
+    /// Returns an empty `Span` that starts and ends at `position`.
+    /// 
+    pub fn at(position : Position) -> Self {
+        Self { start : position, end : position }
+    }
+
+    /// Returns the number of bytes covered by the `Span`.
+    /// 
+    pub fn len(&self) -> usize {
+        self.end.offset - self.start.offset
+    }
+
+    /// Returns `true` if the `Span` doesn't cover any symbols.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+}
+
+impl fmt::Display for Span {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}-{}", self.start, self.end)
+    }
+}
+
+/// A single symbol produced by a [`Lookahead`](struct.Lookahead.html), along with the `Position` at which it occurred
+/// and the symbol after it, if any. This is meant to be used as the `Input` of a lexer `Family`, so that each `Mode`
+/// can decide whether the current token continues without having to buffer any symbols itself.
+/// 
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct Located<S> {
+    /// The symbol itself.
+    pub symbol : S,
+
+    /// The `Position` of the symbol.
+    pub position : Position,
+
+    /// The symbol after this one, or `None` if this is the last symbol.
+    pub next : Option<S>,
+}
+
+impl<S> Located<S>
+    where S : Symbol
+{
+    /// Returns the `Position` immediately after the symbol.
+    /// 
+    pub fn end(&self) -> Position {
+        self.position.advanced(self.symbol)
+    }
+
+    /// Returns `true` if this is the last symbol of the text.
+    /// 
+    pub fn is_last(&self) -> bool {
+        self.next.is_none()
+    }
+}
+
+/// Wraps an `Iterator` of [`Symbol`](trait.Symbol.html)s, tracking the [`Position`](struct.Position.html) of each one,
+/// and buffering as many upcoming symbols as necessary to allow them to be peeked at.
+/// 
+/// A `Lookahead` is itself an `Iterator` over [`Located`](struct.Located.html) symbols, each of which carries the
+/// symbol after it, so that a lexer `Automaton` can be driven directly by it. For lexers that need to look further
+/// ahead, [`peek_nth()`](#method.peek_nth) can buffer any number of upcoming symbols.
+/// 
+/// # Usage
+/// ```
+/// use mode::text::{Lookahead, Position};
+/// 
+/// let mut bytes = Lookahead::new(b"a\nbc".iter().copied());
+/// assert_eq!(bytes.peek(), Some(b'a'));
+/// assert_eq!(bytes.peek_nth(2), Some(b'b'));
+/// 
+/// let a = bytes.next().unwrap();
+/// assert_eq!((a.symbol, a.next), (b'a', Some(b'\n')));
+/// assert_eq!(a.position, Position::new());
+/// 
+/// assert_eq!(bytes.next_if(|byte| byte == b'\n').map(|newline| newline.symbol), Some(b'\n'));
+/// assert_eq!(bytes.position().to_string(), "2:1");
+/// assert_eq!(bytes.next_if(|byte| byte == b'x'), None);
+/// assert_eq!(bytes.map(|byte| byte.symbol).collect::<Vec<_>>(), b"bc".to_vec());
+/// ```
+/// 
+pub struct Lookahead<I>
+    where I : Iterator
+{
+    iter : I,
+    buffer : VecDeque<I::Item>,
+    position : Position,
+}
+
+impl<I, S> Lookahead<I>
+    where
+        I : Iterator<Item = S>,
+        S : Symbol,
+{
+    /// Creates a new `Lookahead` over `iter`, starting at [`Position::new()`](struct.Position.html#method.new).
+    /// 
+    pub fn new(iter : I) -> Self {
+        Self::starting_at(iter, Position::new())
+    }
+
+    /// Creates a new `Lookahead` over `iter`, whose first symbol is located at `position`. This is useful when `iter`
+    /// only covers part of some larger text, e.g. a single line.
+    /// 
+    pub fn starting_at(iter : I, position : Position) -> Self {
+        Self { iter, buffer : VecDeque::new(), position }
+    }
+
+    /// Returns the `Position` of the next symbol, or the `Position` at the end of the text if there are no symbols
+    /// left.
+    /// 
+    pub fn position(&self) -> Position {
+        self.position
+    }
+
+    /// Returns the next symbol without consuming it, or `None` if there are no symbols left.
+    /// 
+    pub fn peek(&mut self) -> Option<S> {
+        self.peek_nth(0)
+    }
+
+    /// Returns the symbol `n` symbols after the next one without consuming any symbols, or `None` if there are not
+    /// enough symbols left. `peek_nth(0)` is equivalent to [`peek()`](#method.peek).
+    /// 
+    pub fn peek_nth(&mut self, n : usize) -> Option<S> {
+        while self.buffer.len() <= n {
+            self.buffer.push_back(self.iter.next()?);
+        }
+        self.buffer.get(n).copied()
+    }
+
+    /// Consumes and returns the next symbol, but only if it satisfies `predicate`.
+    /// 
+    pub fn next_if(&mut self, predicate : impl FnOnce(S) -> bool) -> Option<Located<S>> {
+        match self.peek() {
+            Some(symbol) if predicate(symbol) => self.next(),
+            _ => None,
+        }
+    }
+
+    /// Returns `true` if there are no symbols left.
+    /// 
+    pub fn is_at_end(&mut self) -> bool {
+        self.peek().is_none()
+    }
+}
+
+impl<I, S> Iterator for Lookahead<I>
+    where
+        I : Iterator<Item = S>,
+        S : Symbol,
+{
+    type Item = Located<S>;
+
+    fn next(&mut self) -> Option<Located<S>> {
+        let symbol = self.buffer.pop_front().or_else(|| self.iter.next())?;
+        let position = self.position;
+        self.position.advance(symbol);
+        Some(Located { symbol, position, next : self.peek() })
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let (lower, upper) = self.iter.size_hint();
+        let buffered = self.buffer.len();
+        (lower.saturating_add(buffered), upper.and_then(|upper| upper.checked_add(buffered)))
+    }
+}
+
+impl<I> fmt::Debug for Lookahead<I>
+    where
+        I : Iterator,
+        I::Item : fmt::Debug,
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Lookahead")
+            .field("buffer", &self.buffer)
+            .field("position", &self.position)
+            .finish()
+    }
+}
+
+/// A token emitted by a lexer, consisting of a `kind`, the `text` of the token, and the `Span` it was found at.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Token<K, S = char>
+    where S : Symbol
+{
+    /// What kind of token this is, typically an `enum` defined by the lexer.
+    pub kind : K,
+
+    /// The symbols that make up the token.
+    pub text : S::Text,
+
+    /// Where the token was found.
+    pub span : Span,
+}
+
+/// Accumulates the text of the token that a lexer is currently scanning, along with the `Span` it covers, until the
+/// token is complete and can be [emitted](#method.emit) as a [`Token`](struct.Token.html).
+/// 
+/// Since a `Lexeme` is an ordinary value, it can be moved from the current `Mode` into the next one as part of a
+/// transition, e.g. from a mode that scans the integer part of a number into one that scans its fraction.
+/// 
+/// See the [module documentation](index.html) for an example.
+/// 
+pub struct Lexeme<S>
+    where S : Symbol
+{
+    text : S::Text,
+    span : Span,
+}
+
+impl<S> Lexeme<S>
+    where S : Symbol
+{
+    /// Creates a new, empty `Lexeme`. The `Span` of the `Lexeme` will start at the first symbol that is pushed into it.
+    /// 
+    pub fn new() -> Self {
+        Self { text : S::Text::default(), span : Span::at(Position::new()) }
+    }
+
+    /// Appends `located` to the end of the `Lexeme`, extending its `Span` past it. If the `Lexeme` is empty, its
+    /// `Span` is moved to start at `located`.
+    /// 
+    pub fn push(&mut self, located : &Located<S>) {
+        if self.is_empty() {
+            self.span = Span::at(located.position);
+        }
+        self.text.extend(Some(located.symbol));
+        self.span.end = located.end();
+    }
+
+    /// Returns the text that has been accumulated so far.
+    /// 
+    pub fn text(&self) -> &S::Text {
+        &self.text
+    }
+
+    /// Returns the `Span` covered by the text that has been accumulated so far.
+    /// 
+    pub fn span(&self) -> Span {
+        self.span
+    }
+
+    /// Returns `true` if no symbols have been pushed into the `Lexeme` since it was created, emitted, or cleared.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.span.is_empty()
+    }
+
+    /// Discards the text that has been accumulated so far, leaving the `Lexeme` empty.
+    /// 
+    pub fn clear(&mut self) {
+        self.text = S::Text::default();
+        self.span = Span::at(self.span.end);
+    }
+
+    /// Returns the text that has been accumulated so far as a `Token` of the specified `kind`, leaving the `Lexeme`
+    /// empty, so that it can be reused for the next token.
+    /// 
+    pub fn emit<K>(&mut self, kind : K) -> Token<K, S> {
+        let span = self.span;
+        self.span = Span::at(span.end);
+        Token { kind, text : mem::take(&mut self.text), span }
+    }
+}
+
+impl<S> Default for Lexeme<S>
+    where S : Symbol
+{
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl<S> fmt::Debug for Lexeme<S>
+    where S : Symbol
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Lexeme")
+            .field("text", &self.text)
+            .field("span", &self.span)
+            .finish()
+    }
+}
@@ -0,0 +1,294 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::{Family, Mode, Persistent, SplitOutput};
+use std::{collections::VecDeque, fmt};
+
+/// A time-travel debugger for a state machine, which retains the last `capacity` versions of a
+/// [`Persistent`](struct.Persistent.html) state machine, so that it can be stepped backwards and forwards through its
+/// history, and any two retained versions can be compared via [`diff()`](#method.diff).
+/// 
+/// Calling [`step()`](#method.step) steps the version at the cursor and appends the result to the history, moving the
+/// cursor onto it. If the cursor had been moved [`back()`](#method.back) beforehand, every version after it is
+/// discarded first, just like the redo history of an editor. Once more than `capacity` versions have been retained,
+/// the oldest version is discarded. Since the versions share their `Mode`s structurally, as described in the
+/// `Persistent` docs, retaining many of them is cheap.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct GuardFamily;
+/// impl Family for GuardFamily {
+///     type Base = Guard;
+///     type Mode = Guard;
+///     type Input = u32;
+///     type Output = Guard;
+/// }
+/// 
+/// #[derive(Clone, Debug, PartialEq)]
+/// enum Guard { Patrol { health : u32 }, Flee { health : u32 } }
+/// impl Mode for Guard {
+///     type Family = GuardFamily;
+///     fn swap(self, damage : u32) -> Self {
+///         match self {
+///             Guard::Patrol { health } if health <= damage + 50 => Guard::Flee { health : health - damage },
+///             Guard::Patrol { health } => Guard::Patrol { health : health - damage },
+///             Guard::Flee { health } => Guard::Flee { health : health.saturating_sub(damage) },
+///         }
+///     }
+///     fn tag(&self) -> &'static str {
+///         match self { Guard::Patrol { .. } => "Patrol", Guard::Flee { .. } => "Flee" }
+///     }
+///     fn fields(&self) -> Vec<Field> {
+///         match self { Guard::Patrol { health } | Guard::Flee { health } => vec![Field::new("health", health)] }
+///     }
+/// }
+/// 
+/// let mut debugger = TimeTravel::<GuardFamily>::new(Persistent::new(Guard::Patrol { health : 100 }), 10);
+/// for damage in [20, 20, 20] {
+///     debugger.step(damage);
+/// }
+/// assert_eq!(**debugger.current(), Guard::Flee { health : 40 });
+/// 
+/// // Step back to find the transition.
+/// assert!(debugger.back());
+/// assert_eq!(**debugger.current(), Guard::Patrol { health : 60 });
+/// 
+/// let diff = debugger.diff(2, 3).unwrap();
+/// assert_eq!(diff.to_string(), "Patrol -> Flee (1 step)\n  health: 60 -> 40");
+/// 
+/// // Stepping from an earlier version discards the versions after it.
+/// debugger.step(0);
+/// assert_eq!(debugger.len(), 4);
+/// assert_eq!(**debugger.current(), Guard::Patrol { health : 60 });
+/// ```
+/// 
+pub struct TimeTravel<F>
+    where F : Family + ?Sized
+{
+    history : VecDeque<Persistent<F>>,
+    cursor : usize,
+    capacity : usize,
+}
+
+impl<F> TimeTravel<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `TimeTravel` debugger that starts from `initial`, and retains at most `capacity` versions.
+    /// 
+    /// # Panics
+    /// If `capacity` is `0`.
+    /// 
+    pub fn new(initial : Persistent<F>, capacity : usize) -> Self {
+        assert!(capacity > 0, "A TimeTravel debugger must retain at least one version!");
+        let mut history = VecDeque::with_capacity(capacity);
+        history.push_back(initial);
+        Self { history, cursor : 0, capacity }
+    }
+
+    /// Returns the maximum number of versions that are retained.
+    /// 
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of versions that are currently retained.
+    /// 
+    pub fn len(&self) -> usize {
+        self.history.len()
+    }
+
+    /// Always returns `false`, since a `TimeTravel` debugger always retains at least the current version.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.history.is_empty()
+    }
+
+    /// Returns the index of the version at the cursor, where `0` is the oldest version that is still retained.
+    /// 
+    pub fn cursor(&self) -> usize {
+        self.cursor
+    }
+
+    /// Returns the version at the cursor.
+    /// 
+    pub fn current(&self) -> &Persistent<F> {
+        &self.history[self.cursor]
+    }
+
+    /// Returns the version at `index`, where `0` is the oldest version that is still retained, or `None` if there is
+    /// no such version.
+    /// 
+    pub fn get(&self, index : usize) -> Option<&Persistent<F>> {
+        self.history.get(index)
+    }
+
+    /// Moves the cursor back by one version, returning `false` if it was already at the oldest retained version.
+    /// 
+    pub fn back(&mut self) -> bool {
+        if self.cursor == 0 {
+            return false;
+        }
+
+        self.cursor -= 1;
+        true
+    }
+
+    /// Moves the cursor forward by one version, returning `false` if it was already at the newest version.
+    /// 
+    pub fn forward(&mut self) -> bool {
+        if self.cursor + 1 >= self.history.len() {
+            return false;
+        }
+
+        self.cursor += 1;
+        true
+    }
+
+    /// Moves the cursor to the version at `index`, returning `false` and leaving the cursor where it is if there is no
+    /// such version.
+    /// 
+    pub fn seek(&mut self, index : usize) -> bool {
+        if index >= self.history.len() {
+            return false;
+        }
+
+        self.cursor = index;
+        true
+    }
+
+    /// Compares the versions at indices `from` and `to`, returning `None` if either of them is not retained.
+    /// 
+    pub fn diff(&self, from : usize, to : usize) -> Option<Diff> {
+        let (from, to) = (self.history.get(from)?, self.history.get(to)?);
+
+        let (before, after) = (from.fields(), to.fields());
+        let mut changes = Vec::new();
+        for field in &before {
+            let value = after.iter().find(|other| other.name == field.name).map(|other| other.value.clone());
+            if value.as_ref() != Some(&field.value) {
+                changes.push(Change { name : field.name, before : Some(field.value.clone()), after : value });
+            }
+        }
+
+        for field in &after {
+            if !before.iter().any(|other| other.name == field.name) {
+                changes.push(Change { name : field.name, before : None, after : Some(field.value.clone()) });
+            }
+        }
+
+        Some(Diff {
+            from : from.tag(),
+            to : to.tag(),
+            steps : to.steps() as i64 - from.steps() as i64,
+            changes,
+        })
+    }
+}
+
+impl<F, M> TimeTravel<F>
+    where
+        F : Family<Mode = M> + ?Sized,
+        M : Mode<Family = F> + Clone,
+        F::Output : SplitOutput<M>,
+{
+    /// Steps the version at the cursor with `input`, discarding every version after the cursor, and appends the
+    /// result to the history, moving the cursor onto it. Returns the value returned alongside the next `Mode`. If the
+    /// `Mode` of the version at the cursor is [final](struct.Persistent.html#method.is_final), it cannot be stepped, so
+    /// the history is left unchanged, and `None` is returned instead.
+    /// 
+    pub fn step(&mut self, input : F::Input) -> Option<<F::Output as SplitOutput<M>>::Value> {
+        if self.history[self.cursor].is_final() {
+            return None;
+        }
+
+        self.history.truncate(self.cursor + 1);
+        let (next, value) = self.history[self.cursor].clone().step(input);
+        if self.history.len() >= self.capacity {
+            self.history.pop_front();
+        }
+
+        self.history.push_back(next);
+        self.cursor = self.history.len() - 1;
+        value
+    }
+}
+
+impl<F> fmt::Debug for TimeTravel<F>
+    where F : Family + ?Sized
+{
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("TimeTravel")
+            .field("len", &self.history.len())
+            .field("cursor", &self.cursor)
+            .field("capacity", &self.capacity)
+            .finish()
+    }
+}
+
+/// The differences between two versions of a state machine, as returned by
+/// [`TimeTravel::diff()`](struct.TimeTravel.html#method.diff).
+/// 
+/// Versions are compared by the `tag()` of their `Mode`s, the number of steps between them, and the
+/// [`fields()`](trait.Mode.html#method.fields) of their `Mode`s.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Diff {
+    /// The `tag()` of the `Mode` of the first version.
+    pub from : &'static str,
+
+    /// The `tag()` of the `Mode` of the second version.
+    pub to : &'static str,
+
+    /// The number of steps from the first version to the second, which is negative if the second version is older.
+    pub steps : i64,
+
+    /// Every field that differs between the `Mode`s of the two versions.
+    pub changes : Vec<Change>,
+}
+
+impl Diff {
+    /// Returns `true` if both versions have the same `tag()` and the same fields.
+    /// 
+    pub fn is_empty(&self) -> bool {
+        self.from == self.to && self.changes.is_empty()
+    }
+}
+
+impl fmt::Display for Diff {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        let plural = if self.steps.abs() == 1 { "" } else { "s" };
+        write!(formatter, "{} -> {} ({} step{})", self.from, self.to, self.steps, plural)?;
+        for change in &self.changes {
+            write!(formatter, "\n  {}", change)?;
+        }
+
+        Ok(())
+    }
+}
+
+/// A single field that differs between two versions of a state machine, as part of a [`Diff`](struct.Diff.html).
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Change {
+    /// The name of the field.
+    pub name : &'static str,
+
+    /// The value of the field in the first version, or `None` if the field was not present.
+    pub before : Option<String>,
+
+    /// The value of the field in the second version, or `None` if the field was not present.
+    pub after : Option<String>,
+}
+
+impl fmt::Display for Change {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        let before = self.before.as_deref().unwrap_or("(none)");
+        let after = self.after.as_deref().unwrap_or("(none)");
+        write!(formatter, "{}: {} -> {}", self.name, before, after)
+    }
+}
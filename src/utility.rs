@@ -0,0 +1,157 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use crate::Family;
+
+/// A utility-based selection layer that can be used to choose the next `Mode` for an `Automaton` by scoring a set of
+/// candidate `Mode`s against the current `Mode`.
+/// 
+/// Each candidate consists of a `tag`, a `score` function, and an `enter` function. Every time
+/// [`Automaton::next_with_utility()`](struct.Automaton.html#method.next_with_utility) is called, the `score` function
+/// of each candidate is called with the current `Mode` as an `&F::Base`. If the highest score is strictly greater than
+/// the `threshold` of the `Utility`, the current `Mode` is passed by value into the `enter` function of the winning
+/// candidate, and the `Mode` it returns becomes current. Since the current `Mode` is consumed, it is still possible to
+/// move state out of it and into the `Mode` being created, just as with `Mode::swap()`. If no candidate scores above
+/// the `threshold`, or if the winning candidate has the same `tag` as the current `Mode`, the current `Mode` is allowed
+/// to `swap()` as usual.
+/// 
+/// Ties are broken in favor of the candidate that was added first.
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// 
+/// struct GuardFamily;
+/// impl Family for GuardFamily {
+///     type Base = Guard;
+///     type Mode = Guard;
+///     type Input = ();
+///     type Output = Guard;
+/// }
+/// 
+/// #[derive(Debug, PartialEq)]
+/// enum Guard { Patrol { health : u32 }, Flee { health : u32 } }
+/// 
+/// impl Guard {
+///     fn health(&self) -> u32 {
+///         match self { Guard::Patrol { health } | Guard::Flee { health } => *health }
+///     }
+/// }
+/// 
+/// impl Mode for Guard {
+///     type Family = GuardFamily;
+///     fn swap(self, _input : ()) -> Self { self }
+///     fn tag(&self) -> &'static str {
+///         match self { Guard::Patrol { .. } => "Patrol", Guard::Flee { .. } => "Flee" }
+///     }
+/// }
+/// 
+/// let utility =
+///     Utility::<GuardFamily>::new(0.5)
+///         .with_candidate("Flee",
+///             |guard| 1.0 - guard.health() as f32 / 100.0,
+///             |guard| Guard::Flee { health : guard.health() });
+/// 
+/// let mut automaton = GuardFamily::automaton_with_mode(Guard::Patrol { health : 90 });
+/// Automaton::next_with_utility(&mut automaton, &utility);
+/// assert_eq!(*automaton, Guard::Patrol { health : 90 });
+/// 
+/// *automaton = Guard::Patrol { health : 20 };
+/// Automaton::next_with_utility(&mut automaton, &utility);
+/// assert_eq!(*automaton, Guard::Flee { health : 20 });
+/// ```
+/// 
+pub struct Utility<F>
+    where F : Family + ?Sized
+{
+    threshold : f32,
+    candidates : Vec<Candidate<F>>,
+}
+
+type ScoreFn<F> = dyn Fn(&<F as Family>::Base) -> f32;
+type EnterFn<F> = dyn Fn(<F as Family>::Mode) -> <F as Family>::Mode;
+
+struct Candidate<F>
+    where F : Family + ?Sized
+{
+    tag : &'static str,
+    score : Box<ScoreFn<F>>,
+    enter : Box<EnterFn<F>>,
+}
+
+impl<F> Utility<F>
+    where F : Family + ?Sized
+{
+    /// Creates a new `Utility` with no candidates. A candidate must score strictly higher than `threshold` in order to
+    /// be selected.
+    /// 
+    pub fn new(threshold : f32) -> Self {
+        Self { threshold, candidates : Vec::new() }
+    }
+
+    /// Returns the minimum score that a candidate must exceed in order to be selected.
+    /// 
+    pub fn threshold(&self) -> f32 {
+        self.threshold
+    }
+
+    /// Sets the minimum score that a candidate must exceed in order to be selected.
+    /// 
+    pub fn set_threshold(&mut self, threshold : f32) {
+        self.threshold = threshold;
+    }
+
+    /// Adds a new candidate `Mode` to the `Utility`. The `tag` should be the same value that the `Mode` returned by
+    /// `enter` will return from [`Mode::tag()`](trait.Mode.html#method.tag).
+    /// 
+    pub fn add_candidate<S, E>(&mut self, tag : &'static str, score : S, enter : E)
+        where
+            S : Fn(&F::Base) -> f32 + 'static,
+            E : Fn(F::Mode) -> F::Mode + 'static,
+    {
+        self.candidates.push(Candidate { tag, score : Box::new(score), enter : Box::new(enter) });
+    }
+
+    /// Same as [`add_candidate()`](#method.add_candidate), except that it consumes and returns `self`, so that calls
+    /// can be chained together.
+    /// 
+    pub fn with_candidate<S, E>(mut self, tag : &'static str, score : S, enter : E) -> Self
+        where
+            S : Fn(&F::Base) -> f32 + 'static,
+            E : Fn(F::Mode) -> F::Mode + 'static,
+    {
+        self.add_candidate(tag, score, enter);
+        self
+    }
+
+    /// Scores every candidate against `current`, returning the `tag` and score of the highest-scoring candidate if its
+    /// score exceeds the `threshold`.
+    /// 
+    pub fn best(&self, current : &F::Base) -> Option<(&'static str, f32)> {
+        self.select(current)
+            .map(|(candidate, score)| (candidate.tag, score))
+    }
+
+    fn select(&self, current : &F::Base) -> Option<(&Candidate<F>, f32)> {
+        let mut best : Option<(&Candidate<F>, f32)> = None;
+
+        for candidate in &self.candidates {
+            let score = (candidate.score)(current);
+            if score > self.threshold && best.is_none_or(|(_, best_score)| score > best_score) {
+                best = Some((candidate, score));
+            }
+        }
+
+        best
+    }
+
+    /// Selects the winning candidate for `current`, returning its `tag` and `enter` function.
+    /// 
+    pub(crate) fn winner(&self, current : &F::Base) -> Option<(&'static str, &EnterFn<F>)> {
+        self.select(current)
+            .map(|(candidate, _)| (candidate.tag, &*candidate.enter))
+    }
+}
@@ -0,0 +1,357 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+//! Defines a lightweight model checker for temporal properties over the state graph of a
+//! [`table`](../table/index.html) definition.
+//! 
+//! A [`Property`](enum.Property.html) describes something that must hold along every possible run of a state machine,
+//! e.g. that the `"Closed"` state can always eventually be reached, no matter which state the machine is in, or that
+//! the `"Paid"` state never follows the `"Cancelled"` state. [`check()`](fn.check.html) explores every state reachable
+//! from the initial state of a [`Definition`](../table/struct.Definition.html), and, if the property does not hold,
+//! returns the shortest [`Trace`](struct.Trace.html) of events that demonstrates the violation.
+//! 
+//! Since a [`Machine`](../table/struct.Machine.html) halts as soon as it enters a final state, transitions leaving a
+//! final state are never explored.
+//! 
+//! **NOTE:** Guards cannot be evaluated without running the state machine, so every guarded transition is assumed to be
+//! takeable. This over-approximates the runs of the state machine, which is only sound for safety properties, i.e.
+//! [`Property::Never`](enum.Property.html#variant.Never) and
+//! [`Property::NeverFollows`](enum.Property.html#variant.NeverFollows): if one of them holds, it holds regardless of
+//! what the guards return, although a reported counterexample may depend on a guard passing when it never would in
+//! practice. Whether a state can always be reached does depend on the guards, so
+//! [`Property::AlwaysReachable`](enum.Property.html#variant.AlwaysReachable) is rejected with
+//! [`CheckError::Unsupported`](enum.CheckError.html#variant.Unsupported) for any `Definition` with a guarded
+//! transition.
+//! 
+//! ```
+//! use mode::{table::Definition, verify::{check, CheckError, Property}};
+//! 
+//! // Closed can only be reached if the is_paid guard ever passes.
+//! let order =
+//!     Definition::new("Open")
+//!         .with_state("Open")
+//!         .with_state("Reopened")
+//!         .with_final_state("Closed")
+//!         .with_guarded_transition("Open", "close", "Closed", "is_paid")
+//!         .with_transition("Closed", "reopen", "Reopened");
+//! 
+//! let property = Property::always_reachable("Closed");
+//! assert_eq!(check(&order, &property), Err(CheckError::Unsupported(property)));
+//! 
+//! // Safety properties can still be checked. Since Closed is final, Reopened can never be reached.
+//! assert!(check(&order, &Property::never("Reopened")).is_ok());
+//! assert!(check(&order, &Property::never("Closed")).is_err());
+//! ```
+//! 
+//! # Usage
+//! ```
+//! use mode::{table::Definition, verify::{check, Property}};
+//! 
+//! let order =
+//!     Definition::new("Open")
+//!         .with_state("Open")
+//!         .with_state("Cancelled")
+//!         .with_state("Paid")
+//!         .with_final_state("Closed")
+//!         .with_transition("Open", "pay", "Paid")
+//!         .with_transition("Open", "cancel", "Cancelled")
+//!         .with_transition("Paid", "ship", "Closed")
+//!         .with_transition("Cancelled", "archive", "Closed")
+//!         .with_transition("Cancelled", "retry_payment", "Paid");
+//! 
+//! assert!(check(&order, &Property::always_reachable("Closed")).is_ok());
+//! 
+//! let error = check(&order, &Property::never_follows("Paid", "Cancelled")).unwrap_err();
+//! let trace = error.trace().unwrap();
+//! assert_eq!(trace.events(), ["cancel", "retry_payment"]);
+//! assert_eq!(trace.to_string(), "Open --cancel--> Cancelled --retry_payment--> Paid");
+//! ```
+//! 
+
+use crate::table::{Definition, DefinitionError};
+use std::{
+    collections::{HashMap, VecDeque},
+    error::Error,
+    fmt,
+};
+
+/// A temporal property over the states of a [`Definition`](../table/struct.Definition.html), which can be checked via
+/// [`check()`](fn.check.html).
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum Property {
+    /// From every state that is reachable from the initial state, the named state can eventually be reached.
+    AlwaysReachable(String),
+
+    /// The named state is never reached.
+    Never(String),
+
+    /// Once the state named `earlier` has been reached, the state named `later` is never reached afterwards.
+    NeverFollows {
+        /// The state that must not be reached after `earlier`.
+        later : String,
+
+        /// The state after which `later` must not be reached.
+        earlier : String,
+    },
+}
+
+impl Property {
+    /// Creates a `Property` stating that `state` can always eventually be reached, no matter which state the state
+    /// machine is in.
+    /// 
+    pub fn always_reachable(state : impl Into<String>) -> Self {
+        Property::AlwaysReachable(state.into())
+    }
+
+    /// Creates a `Property` stating that `state` is never reached.
+    /// 
+    pub fn never(state : impl Into<String>) -> Self {
+        Property::Never(state.into())
+    }
+
+    /// Creates a `Property` stating that `later` is never reached once `earlier` has been reached.
+    /// 
+    pub fn never_follows(later : impl Into<String>, earlier : impl Into<String>) -> Self {
+        Property::NeverFollows { later : later.into(), earlier : earlier.into() }
+    }
+}
+
+impl fmt::Display for Property {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            Property::AlwaysReachable(state) => write!(formatter, "\"{}\" is always eventually reachable", state),
+            Property::Never(state) => write!(formatter, "\"{}\" is never reached", state),
+            Property::NeverFollows { later, earlier } => {
+                write!(formatter, "\"{}\" never follows \"{}\"", later, earlier)
+            },
+        }
+    }
+}
+
+/// A sequence of transitions starting at the initial state of a `Definition`, used as a counterexample when a
+/// `Property` does not hold.
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub struct Trace {
+    states : Vec<String>,
+    events : Vec<String>,
+}
+
+impl Trace {
+    /// Returns the names of every state visited along the `Trace`, starting with the initial state.
+    /// 
+    pub fn states(&self) -> &[String] {
+        &self.states
+    }
+
+    /// Returns the names of the events that cause each transition along the `Trace`, in order.
+    /// 
+    pub fn events(&self) -> &[String] {
+        &self.events
+    }
+}
+
+impl fmt::Display for Trace {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        write!(formatter, "{}", self.states[0])?;
+        for (event, state) in self.events.iter().zip(&self.states[1..]) {
+            write!(formatter, " --{}--> {}", event, state)?;
+        }
+        Ok(())
+    }
+}
+
+/// An error returned by [`check()`](fn.check.html).
+/// 
+#[derive(Clone, Debug, Eq, PartialEq)]
+pub enum CheckError {
+    /// The `Definition` is not well-formed, or the `Property` names a state that does not exist.
+    Definition(DefinitionError),
+
+    /// The `Property` cannot be checked soundly for the `Definition`, because its outcome depends on what the guards of
+    /// the `Definition` return. This is the case for `Property::AlwaysReachable` whenever any transition is guarded.
+    Unsupported(Property),
+
+    /// The `Property` does not hold. The `trace` leads from the initial state to a state that demonstrates the
+    /// violation. For [`Property::AlwaysReachable`](enum.Property.html#variant.AlwaysReachable), this is a state from
+    /// which the target can no longer be reached.
+    Violated {
+        /// The `Property` that does not hold.
+        property : Property,
+
+        /// The shortest counterexample.
+        trace : Trace,
+    },
+}
+
+impl CheckError {
+    /// Returns the counterexample if the `Property` does not hold.
+    /// 
+    pub fn trace(&self) -> Option<&Trace> {
+        match self {
+            CheckError::Violated { trace, .. } => Some(trace),
+            CheckError::Definition(_) | CheckError::Unsupported(_) => None,
+        }
+    }
+}
+
+impl fmt::Display for CheckError {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            CheckError::Definition(error) => write!(formatter, "{}", error),
+            CheckError::Unsupported(property) => {
+                write!(formatter, "Property {} cannot be checked without evaluating guards!", property)
+            },
+            CheckError::Violated { property, trace } => {
+                write!(formatter, "Property {} does not hold! Counterexample: {}", property, trace)
+            },
+        }
+    }
+}
+
+impl Error for CheckError {
+    fn source(&self) -> Option<&(dyn Error + 'static)> {
+        match self {
+            CheckError::Definition(error) => Some(error),
+            CheckError::Unsupported(_) | CheckError::Violated { .. } => None,
+        }
+    }
+}
+
+impl From<DefinitionError> for CheckError {
+    fn from(error : DefinitionError) -> Self {
+        CheckError::Definition(error)
+    }
+}
+
+type Node = (usize, bool);
+type Parents<'a> = HashMap<Node, Option<(Node, &'a str)>>;
+
+/// The state graph of a `Definition`, with states identified by index.
+/// 
+struct Graph<'a> {
+    definition : &'a Definition,
+    edges : Vec<Vec<(&'a str, usize)>>,
+    initial : usize,
+}
+
+impl<'a> Graph<'a> {
+    fn new(definition : &'a Definition) -> Result<Self, DefinitionError> {
+        definition.validate()?;
+
+        let mut edges = vec![Vec::new(); definition.states.len()];
+        for transition in &definition.transitions {
+            let from = Self::find(definition, &transition.from)?;
+            let to = Self::find(definition, &transition.to)?;
+
+            // NOTE: A Machine halts as soon as it enters a final state, so it can never leave one.
+            if !definition.states[from].is_final {
+                edges[from].push((transition.event.as_str(), to));
+            }
+        }
+
+        let initial = Self::find(definition, &definition.initial)?;
+        Ok(Self { definition, edges, initial })
+    }
+
+    fn find(definition : &Definition, name : &str) -> Result<usize, DefinitionError> {
+        definition.states.iter()
+            .position(|state| state.name == name)
+            .ok_or_else(|| DefinitionError::UnknownState(name.to_owned()))
+    }
+
+    /// Searches breadth-first from the initial state over nodes made up of a state index and a flag, which starts out
+    /// `false` and is updated by `update` with the flag and index of the state being left along each transition, and
+    /// returns the shortest `Trace` to a node for which `found` returns `true`.
+    /// 
+    fn search(&self, update : impl Fn(bool, usize) -> bool, found : impl Fn(usize, bool) -> bool) -> Option<Trace> {
+        let start = (self.initial, false);
+        let mut parents : Parents = HashMap::new();
+        let mut pending = VecDeque::new();
+        parents.insert(start, None);
+        pending.push_back(start);
+
+        while let Some(node) = pending.pop_front() {
+            if found(node.0, node.1) {
+                return Some(self.trace(&parents, node));
+            }
+
+            for (event, to) in &self.edges[node.0] {
+                let next = (*to, update(node.1, node.0));
+                parents.entry(next).or_insert_with(|| {
+                    pending.push_back(next);
+                    Some((node, *event))
+                });
+            }
+        }
+
+        None
+    }
+
+    fn trace(&self, parents : &Parents, end : Node) -> Trace {
+        let mut states = vec![self.definition.states[end.0].name.clone()];
+        let mut events = Vec::new();
+        let mut node = end;
+        while let Some(Some((parent, event))) = parents.get(&node) {
+            events.push((*event).to_owned());
+            states.push(self.definition.states[parent.0].name.clone());
+            node = *parent;
+        }
+
+        states.reverse();
+        events.reverse();
+        Trace { states, events }
+    }
+}
+
+/// Checks whether `property` holds for every run of a state machine described by `definition`, which is validated
+/// first. Returns [`CheckError::Violated`](enum.CheckError.html#variant.Violated) with the shortest counterexample if
+/// it does not.
+/// 
+/// See the [module-level documentation](index.html) for more details.
+/// 
+pub fn check(definition : &Definition, property : &Property) -> Result<(), CheckError> {
+    let graph = Graph::new(definition)?;
+    let violation =
+        match property {
+            Property::AlwaysReachable(target) => {
+                let target = Graph::find(definition, target)?;
+                if definition.transitions.iter().any(|transition| transition.guard.is_some()) {
+                    return Err(CheckError::Unsupported(property.clone()));
+                }
+
+                // Find every state from which the target can be reached by walking the graph backwards.
+                let mut reaches = vec![false; definition.states.len()];
+                let mut pending = vec![target];
+                reaches[target] = true;
+                while let Some(state) = pending.pop() {
+                    for (from, edges) in graph.edges.iter().enumerate() {
+                        if !reaches[from] && edges.iter().any(|(_, to)| *to == state) {
+                            reaches[from] = true;
+                            pending.push(from);
+                        }
+                    }
+                }
+
+                graph.search(|_, _| false, |state, _| !reaches[state])
+            },
+            Property::Never(target) => {
+                let target = Graph::find(definition, target)?;
+                graph.search(|_, _| false, |state, _| state == target)
+            },
+            Property::NeverFollows { later, earlier } => {
+                let later = Graph::find(definition, later)?;
+                let earlier = Graph::find(definition, earlier)?;
+                graph.search(|seen, from| seen || from == earlier, |state, seen| seen && state == later)
+            },
+        };
+
+    match violation {
+        Some(trace) => Err(CheckError::Violated { property : property.clone(), trace }),
+        None => Ok(()),
+    }
+}
@@ -0,0 +1,26 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+/// Trait that can be implemented for the `F::Mode` type of a `Family` in order to expose the current `Mode` through an
+/// interface other than `F::Base`, e.g. a separate `dyn Render` trait for read-only rendering access alongside a
+/// `dyn Update` trait for mutable simulation access. A `Family` can implement `View<V>` for any number of `V` types.
+/// 
+/// **NOTE:** When `V` is a trait object, the lifetime of the returned reference usually needs to be spelled out in the
+/// `impl`, e.g. `fn view(&self) -> &(dyn Render + 'static)`, since `View<dyn Render>` refers to `dyn Render + 'static`.
+/// 
+/// See [`Automaton::as_view()`](struct.Automaton.html#method.as_view) for more details.
+/// 
+pub trait View<V>
+    where V : ?Sized
+{
+    /// Returns an immutable reference to `self` as a `&V`.
+    /// 
+    fn view(&self) -> &V;
+
+    /// Returns a mutable reference to `self` as a `&mut V`.
+    /// 
+    fn view_mut(&mut self) -> &mut V;
+}
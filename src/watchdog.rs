@@ -0,0 +1,115 @@
+// Copyright 2019 Andrew Thomas Christensen
+//
+// Licensed under the Apache License, Version 2.0, <LICENSE-APACHE or http://www.apache.org/licenses/LICENSE-2.0> or the
+// MIT license <LICENSE-MIT or http://opensource.org/licenses/MIT>, at your option. This file may not be copied,
+// modified, or distributed except according to those terms.
+
+use std::{
+    fmt,
+    sync::{Arc, Mutex},
+    time::{Duration, Instant},
+};
+
+/// Detects when an `Automaton` has not been stepped within a configured interval, e.g. because the loop driving it is
+/// stuck.
+/// 
+/// A `Watchdog` is attached to an `Automaton` via
+/// [`Automaton::attach_watchdog()`](struct.Automaton.html#method.attach_watchdog), after which every call to one of the
+/// `Automaton::next*()` functions records a heartbeat, even if the `Automaton` is paused. Since a stalled `Automaton`
+/// cannot detect that it is stalled by itself, the `Watchdog` is meant to be cloned and checked from somewhere else,
+/// e.g. a supervisor thread, via [`check()`](#method.check). All clones of a `Watchdog` share the same heartbeat.
+/// 
+/// In order to detect an `Automaton` that is being stepped, but is stuck in the same `Mode`, see
+/// [`Automaton::set_stall_limit()`](struct.Automaton.html#method.set_stall_limit).
+/// 
+/// # Usage
+/// ```
+/// use mode::*;
+/// use std::time::Duration;
+/// 
+/// struct SomeFamily;
+/// impl Family for SomeFamily {
+///     type Base = SomeMode;
+///     type Mode = SomeMode;
+///     type Input = ();
+///     type Output = SomeMode;
+/// }
+/// 
+/// struct SomeMode;
+/// impl Mode for SomeMode {
+///     type Family = SomeFamily;
+///     fn swap(self, _input : ()) -> Self { self }
+/// }
+/// 
+/// let watchdog = Watchdog::new(Duration::from_millis(10));
+/// let mut automaton = SomeFamily::automaton_with_mode(SomeMode);
+/// Automaton::attach_watchdog(&mut automaton, &watchdog);
+/// 
+/// Automaton::next(&mut automaton);
+/// assert!(!watchdog.check(|_| panic!("Not stalled yet!")));
+/// 
+/// std::thread::sleep(Duration::from_millis(20));
+/// let mut stalled_for = None;
+/// assert!(watchdog.check(|elapsed| stalled_for = Some(elapsed)));
+/// assert!(stalled_for.unwrap() >= Duration::from_millis(10));
+/// ```
+/// 
+#[derive(Clone)]
+pub struct Watchdog {
+    interval : Duration,
+    heartbeat : Arc<Mutex<Instant>>,
+}
+
+impl Watchdog {
+    /// Creates a new `Watchdog` that considers an `Automaton` stalled if it has not been stepped for `interval`. The
+    /// interval starts counting immediately, even if no `Automaton` has been attached yet.
+    /// 
+    pub fn new(interval : Duration) -> Self {
+        Self { interval, heartbeat : Arc::new(Mutex::new(Instant::now())) }
+    }
+
+    /// Returns the interval after which an `Automaton` that has not been stepped is considered stalled.
+    /// 
+    pub fn interval(&self) -> Duration {
+        self.interval
+    }
+
+    /// Returns the amount of time since the attached `Automaton` was last stepped.
+    /// 
+    pub fn since_last_step(&self) -> Duration {
+        self.heartbeat.lock().unwrap_or_else(|error| error.into_inner()).elapsed()
+    }
+
+    /// Returns `true` if the attached `Automaton` has not been stepped within the interval.
+    /// 
+    pub fn is_stalled(&self) -> bool {
+        self.since_last_step() >= self.interval
+    }
+
+    /// Calls `on_stall` with the amount of time since the attached `Automaton` was last stepped, if it is stalled.
+    /// Returns `true` if the `Automaton` is stalled.
+    /// 
+    pub fn check(&self, on_stall : impl FnOnce(Duration)) -> bool {
+        let elapsed = self.since_last_step();
+        let stalled = elapsed >= self.interval;
+        if stalled {
+            on_stall(elapsed);
+        }
+        stalled
+    }
+
+    /// Records a heartbeat. This is called by the `Automaton` every time it is stepped.
+    /// 
+    pub(crate) fn beat(&self) {
+        *self.heartbeat.lock().unwrap_or_else(|error| error.into_inner()) = Instant::now();
+    }
+}
+
+impl fmt::Debug for Watchdog {
+    fn fmt(&self, formatter : &mut fmt::Formatter) -> fmt::Result {
+        formatter.debug_struct("Watchdog")
+            .field("interval", &self.interval)
+            .field("since_last_step", &self.since_last_step())
+            .finish()
+    }
+}